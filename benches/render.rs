@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
+use minecraft_map_tool::stitch::{stitch_maps, Area};
+use minecraft_map_tool::synthetic::synthetic_maps;
+use minecraft_map_tool::ReadMap;
+use std::path::Path;
+
+fn render(c: &mut Criterion) {
+    let palette = generate_palette(&BASE_COLORS_2699);
+    let maps = synthetic_maps(Path::new("world"));
+
+    let mut group = c.benchmark_group("render");
+    group.throughput(Throughput::Elements(maps.len() as u64));
+    group.bench_function("make_image", |b| {
+        b.iter(|| {
+            for map_item in &maps {
+                black_box(map_item.make_image(black_box(&palette)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn stitch(c: &mut Criterion) {
+    let palette = generate_palette(&BASE_COLORS_2699);
+    let world = tempfile::tempdir().unwrap();
+    let data = world.path().join("data");
+    std::fs::create_dir_all(&data).unwrap();
+    let maps = synthetic_maps(world.path());
+    for map_item in maps.iter().take(16) {
+        map_item.write().unwrap();
+    }
+    let area = Area::of_map(&maps[0].data).union(&Area::of_map(&maps[15].data));
+
+    c.bench_function("stitch_maps", |b| {
+        b.iter(|| {
+            let maps = ReadMap::from_paths(maps.iter().take(16).map(|m| m.file.clone()).collect());
+            black_box(stitch_maps(maps, &area, &palette, || {}).unwrap());
+        })
+    });
+}
+
+criterion_group!(benches, render, stitch);
+criterion_main!(benches);
@@ -0,0 +1,10 @@
+//! Reads map files from arbitrary bytes, which must fail cleanly instead of panicking
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minecraft_map_tool::MapItem;
+use std::path::Path;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = MapItem::read_from_reader(data, Path::new("map_0.dat"));
+});
@@ -0,0 +1,8 @@
+//! Writes arbitrary map items and checks that they are read back the same
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use minecraft_map_tool::fuzzing::check_round_trip;
+use minecraft_map_tool::MapItem;
+
+fuzz_target!(|map_item: MapItem| check_round_trip(&map_item));
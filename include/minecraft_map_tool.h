@@ -0,0 +1,64 @@
+/*
+ * C API of minecraft_map_tool
+ *
+ * The library is built with the ffi feature, see src/ffi.rs for the documentation of each
+ * function. Keep this file in sync with src/ffi.rs.
+ */
+
+#ifndef MINECRAFT_MAP_TOOL_H
+#define MINECRAFT_MAP_TOOL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Number of bytes in an RGBA image of a map */
+#define MMT_RGBA_SIZE (128 * 128 * 4)
+
+/* Map item handle, released with mmt_free */
+typedef struct MmtMap MmtMap;
+
+/* Basic information about a map */
+typedef struct MmtMapInfo {
+    /* How zoomed out the map is, from 0 to 4 */
+    int8_t scale;
+
+    /* 1 if the map is locked in a cartography table */
+    int8_t locked;
+
+    /* Center of the map by X */
+    int32_t x_center;
+
+    /* Center of the map by Z */
+    int32_t z_center;
+
+    /* Data version of the Minecraft version that saved the map */
+    int32_t data_version;
+
+    /* Number of banner markers */
+    uint32_t banner_count;
+} MmtMapInfo;
+
+/* Error message of the last failed call in this thread, or NULL */
+const char *mmt_last_error(void);
+
+/* Reads the map file at the UTF-8 path, returns NULL if the file could not be read */
+MmtMap *mmt_read_map(const char *path);
+
+/* Releases the map read with mmt_read_map, NULL is ignored */
+void mmt_free(MmtMap *map);
+
+/* Fills the info of the map, returns 0 on success and -1 on failure */
+int32_t mmt_map_info(const MmtMap *map, MmtMapInfo *info);
+
+/* Renders the map as 128x128 RGBA pixels to the buffer, returns 0 on success and -1 on failure */
+int32_t mmt_render_rgba(const MmtMap *map, uint8_t *buffer, size_t length);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MINECRAFT_MAP_TOOL_H */
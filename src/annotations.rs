@@ -0,0 +1,404 @@
+//! Drawing annotations on stitched images
+//!
+//! Routes, borders and land claims are not on the maps themselves. Annotations are labeled
+//! points, lines, rectangles and polygons in world coordinates, read from a JSON file like
+//!
+//! ```json
+//! [
+//!     {"type": "point", "x": 120, "z": -40, "label": "Spawn"},
+//!     {"type": "line", "points": [[0, 0], [200, 0], [200, 150]], "color": "gold", "width": 3},
+//!     {"type": "rectangle", "left": -50, "top": -50, "right": 50, "bottom": 50,
+//!      "label": "Claim", "color": "#3366ff", "fill": true, "dimension": "The Nether"},
+//!     {"type": "polygon", "points": [[0, 0], [80, 20], [40, 90]], "fill": true, "opacity": 0.5}
+//! ]
+//! ```
+//!
+//! Colors are text color names or hex colors, see [parse_color]. Annotations with a
+//! dimension are only drawn on the images of that dimension.
+
+use crate::error::{Error, Result};
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::legend::blend;
+use crate::text::parse_color;
+use crate::transform::MapTransform;
+use image::{Rgba, RgbaImage};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Color of the annotations without a color
+const DEFAULT_COLOR: [u8; 3] = [0xff, 0x55, 0x55];
+
+/// Opacity of the filled shapes without an opacity
+const FILL_OPACITY: f32 = 0.25;
+
+/// Color of the shadow behind the labels
+const LABEL_SHADOW: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// A labeled shape drawn on stitched images
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Annotation {
+    #[serde(flatten)]
+    pub shape: Shape,
+
+    /// Text drawn next to the shape
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// Color name, like `red`, or a hex color like `#ff8800`
+    #[serde(default)]
+    pub color: Option<String>,
+
+    /// Width of the lines in pixels
+    #[serde(default = "default_width")]
+    pub width: u32,
+
+    /// Fill rectangles and polygons with a see-through color
+    #[serde(default)]
+    pub fill: bool,
+
+    /// Opacity of the fill from 0.0 to 1.0, 0.25 if not given
+    #[serde(default)]
+    pub opacity: Option<f32>,
+
+    /// Only draw on images of this dimension, compared like the stitch --dimension option
+    #[serde(default)]
+    pub dimension: Option<String>,
+}
+
+impl Annotation {
+    /// Tests if the annotation is drawn on the images of the *dimension*
+    pub fn is_in(&self, dimension: &str) -> bool {
+        match &self.dimension {
+            Some(own) => own.eq_ignore_ascii_case(dimension),
+            None => true,
+        }
+    }
+
+    /// Color of the annotation
+    pub fn rgba(&self) -> Rgba<u8> {
+        let [r, g, b] = self
+            .color
+            .as_deref()
+            .and_then(parse_color)
+            .unwrap_or(DEFAULT_COLOR);
+        Rgba([r, g, b, 255])
+    }
+
+    /// Color for filling the shape
+    fn fill_rgba(&self) -> Rgba<u8> {
+        let opacity = self.opacity.unwrap_or(FILL_OPACITY).clamp(0.0, 1.0);
+        let [r, g, b, _] = self.rgba().0;
+        Rgba([r, g, b, (opacity * 255.0).round() as u8])
+    }
+}
+
+fn default_width() -> u32 {
+    1
+}
+
+/// Shape and position of an annotation in world coordinates
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Shape {
+    /// A marker at a block
+    Point { x: i32, z: i32 },
+
+    /// Line through the points, given as `[x, z]` pairs
+    Line { points: Vec<[i32; 2]> },
+
+    /// Rectangle including the blocks on its edges
+    Rectangle {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    },
+
+    /// Area inside the points, given as `[x, z]` pairs, without the areas inside the holes
+    Polygon {
+        points: Vec<[i32; 2]>,
+        #[serde(default)]
+        holes: Vec<Vec<[i32; 2]>>,
+    },
+}
+
+/// Reads the annotations from the JSON *file*
+pub fn read_annotations(file: &Path) -> Result<Vec<Annotation>> {
+    parse_annotations(&fs::read_to_string(file)?)
+}
+
+/// Parses the annotations from the JSON *text*
+pub fn parse_annotations(text: &str) -> Result<Vec<Annotation>> {
+    let annotations: Vec<Annotation> = serde_json::from_str(text)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let unknown_color = annotations
+        .iter()
+        .filter_map(|annotation| annotation.color.as_deref())
+        .any(|color| parse_color(color).is_none());
+    if unknown_color {
+        return Err(Error::map_item_error(
+            "Unknown annotation color, expected a name like dark_red or a hex color like #ff8800",
+        ));
+    }
+    Ok(annotations)
+}
+
+/// Draws the *annotations* on the *image* that shows the world with the *transform*
+///
+/// Each font pixel of the labels is *scale* image pixels wide. Shapes outside the image are
+/// cut at its edges.
+pub fn draw_annotations(
+    image: &mut RgbaImage,
+    transform: &MapTransform,
+    annotations: &[Annotation],
+    scale: u32,
+) {
+    for annotation in annotations {
+        let color = annotation.rgba();
+        let width = annotation.width.max(1);
+        let label_at = match &annotation.shape {
+            Shape::Point { x, z } => {
+                let (x, y) = transform.pixel_at(*x, *z);
+                let radius = (2 * scale + width / 2) as i32;
+                fill_rect(image, x - radius, y - radius, x + radius, y + radius, color);
+                (x + radius + 2 * scale as i32, y)
+            }
+            Shape::Line { points } => {
+                let pixels: Vec<(i32, i32)> = points
+                    .iter()
+                    .map(|[x, z]| transform.pixel_at(*x, *z))
+                    .collect();
+                for pair in pixels.windows(2) {
+                    draw_line(image, pair[0], pair[1], width, color);
+                }
+                if let [only] = pixels[..] {
+                    draw_line(image, only, only, width, color);
+                }
+                match pixels.first() {
+                    Some(&(x, y)) => (x + (width + 2 * scale) as i32, y),
+                    None => continue,
+                }
+            }
+            Shape::Rectangle {
+                left,
+                top,
+                right,
+                bottom,
+            } => {
+                let corner = transform.pixel_at(*left.min(right), *top.min(bottom));
+                let (right, bottom) = transform.pixel_at(*left.max(right), *top.max(bottom));
+                let (left, top) = corner;
+                if annotation.fill {
+                    let fill = annotation.fill_rgba();
+                    for y in top.max(0)..=bottom.min(image.height() as i32 - 1) {
+                        for x in left.max(0)..=right.min(image.width() as i32 - 1) {
+                            let pixel = image.get_pixel_mut(x as u32, y as u32);
+                            *pixel = blend(*pixel, fill);
+                        }
+                    }
+                }
+                let corners = [(left, top), (right, top), (right, bottom), (left, bottom)];
+                for index in 0..corners.len() {
+                    let next = corners[(index + 1) % corners.len()];
+                    draw_line(image, corners[index], next, width, color);
+                }
+                let inset = (width + scale + 1) as i32;
+                let half_text = (GLYPH_HEIGHT * scale / 2) as i32;
+                (left + inset, top + inset + half_text)
+            }
+            Shape::Polygon { points, holes } => {
+                let rings: Vec<&[[i32; 2]]> = std::iter::once(points.as_slice())
+                    .chain(holes.iter().map(Vec::as_slice))
+                    .collect();
+                if annotation.fill {
+                    fill_polygon(image, transform, &rings, annotation.fill_rgba());
+                }
+                for ring in &rings {
+                    let pixels: Vec<(i32, i32)> = ring
+                        .iter()
+                        .map(|[x, z]| transform.pixel_at(*x, *z))
+                        .collect();
+                    for index in 0..pixels.len() {
+                        let next = pixels[(index + 1) % pixels.len()];
+                        draw_line(image, pixels[index], next, width, color);
+                    }
+                }
+                // The label is centered in the bounding box of the outline
+                let (Some(left), Some(right)) = (
+                    points.iter().map(|[x, _]| *x).min(),
+                    points.iter().map(|[x, _]| *x).max(),
+                ) else {
+                    continue;
+                };
+                let top = points.iter().map(|[_, z]| *z).min().unwrap_or_default();
+                let bottom = points.iter().map(|[_, z]| *z).max().unwrap_or_default();
+                let (x, y) =
+                    transform.pixel_at(left + (right - left) / 2, top + (bottom - top) / 2);
+                let half_label = match &annotation.label {
+                    Some(label) => (text_width(label, scale) / 2) as i32,
+                    None => 0,
+                };
+                (x - half_label, y)
+            }
+        };
+        if let Some(label) = &annotation.label {
+            draw_label(image, label_at, label, scale, color);
+        }
+    }
+}
+
+/// Draws the *label* with a shadow, starting at *at* and centered on it vertically
+fn draw_label(image: &mut RgbaImage, at: (i32, i32), label: &str, scale: u32, color: Rgba<u8>) {
+    let x = at.0.max(0) as u32;
+    let y = (at.1 - (GLYPH_HEIGHT * scale / 2) as i32).max(0) as u32;
+    let max_width = image.width().saturating_sub(x);
+    draw_text(image, x + 1, y + 1, max_width, scale, label, LABEL_SHADOW);
+    draw_text(image, x, y, max_width, scale, label, color);
+}
+
+/// Blends the *color* over the pixels whose centers are inside the *rings*
+///
+/// A pixel is inside when a line from it crosses the rings an odd number of times, so the
+/// rings after the first one cut holes into it.
+fn fill_polygon(
+    image: &mut RgbaImage,
+    transform: &MapTransform,
+    rings: &[&[[i32; 2]]],
+    color: Rgba<u8>,
+) {
+    let edges: Vec<((f64, f64), (f64, f64))> = rings
+        .iter()
+        .flat_map(|ring| {
+            let points: Vec<(f64, f64)> = ring
+                .iter()
+                .map(|[x, z]| transform.position(*x as f64, *z as f64))
+                .collect();
+            (0..points.len())
+                .map(|index| (points[index], points[(index + 1) % points.len()]))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let mut crossings = Vec::new();
+    for y in 0..image.height() {
+        let center_y = y as f64 + 0.5;
+        crossings.clear();
+        for &((x1, y1), (x2, y2)) in &edges {
+            if (y1 <= center_y) != (y2 <= center_y) {
+                crossings.push(x1 + (center_y - y1) / (y2 - y1) * (x2 - x1));
+            }
+        }
+        crossings.sort_by(f64::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            // Pixels with their centers between the crossings
+            let start = (pair[0] - 0.5).ceil().max(0.0);
+            let end = (pair[1] - 0.5).ceil().min(image.width() as f64);
+            for x in start as u32..end.max(start) as u32 {
+                let pixel = image.get_pixel_mut(x, y);
+                *pixel = blend(*pixel, color);
+            }
+        }
+    }
+}
+
+/// Draws a line from *start* to *end* that is *width* pixels wide
+fn draw_line(
+    image: &mut RgbaImage,
+    start: (i32, i32),
+    end: (i32, i32),
+    width: u32,
+    color: Rgba<u8>,
+) {
+    // The pen is a square centered on the line
+    let before = (width as i32 - 1) / 2;
+    let after = width as i32 / 2;
+    let (mut x, mut y) = start;
+    let dx = (end.0 - x).abs();
+    let dy = -(end.1 - y).abs();
+    let step_x = if x < end.0 { 1 } else { -1 };
+    let step_y = if y < end.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    loop {
+        fill_rect(image, x - before, y - before, x + after, y + after, color);
+        if (x, y) == end {
+            break;
+        }
+        let double = 2 * error;
+        if double >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if double <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Fills the pixels from *left*, *top* to *right*, *bottom* that are inside the image
+fn fill_rect(image: &mut RgbaImage, left: i32, top: i32, right: i32, bottom: i32, color: Rgba<u8>) {
+    for y in top.max(0)..=bottom.min(image.height() as i32 - 1) {
+        for x in left.max(0)..=right.min(image.width() as i32 - 1) {
+            image.put_pixel(x as u32, y as u32, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotations::{draw_annotations, parse_annotations, Shape};
+    use crate::transform::MapTransform;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_annotations() {
+        let annotations = parse_annotations(
+            r##"[
+                {"type": "point", "x": -90, "z": -90, "label": "Spawn"},
+                {"type": "line", "points": [[-100, -80], [-61, -80]], "color": "#00ff00", "width": 3},
+                {"type": "rectangle", "left": -70, "top": -70, "right": -61, "bottom": -61,
+                 "color": "blue", "fill": true, "dimension": "nether"}
+            ]"##,
+        )
+        .unwrap();
+        assert_eq!(annotations.len(), 3);
+        assert_eq!(annotations[0].shape, Shape::Point { x: -90, z: -90 });
+        assert!(annotations[2].is_in("Nether"));
+        assert!(!annotations[2].is_in("Overworld"));
+        assert!(
+            parse_annotations(r#"[{"type": "point", "x": 0, "z": 0, "color": "pink"}]"#).is_err()
+        );
+        assert!(parse_annotations(r#"[{"type": "circle"}]"#).is_err());
+
+        let transform = MapTransform {
+            left: -100,
+            top: -100,
+            scale: 0,
+        };
+        let mut image = RgbaImage::new(40, 40);
+        draw_annotations(&mut image, &transform, &annotations, 1);
+        assert_eq!(*image.get_pixel(10, 10), Rgba([0xff, 0x55, 0x55, 255]));
+        // The line is three pixels wide and cut at the edge of the image
+        for y in 19..=21 {
+            assert_eq!(*image.get_pixel(39, y), Rgba([0, 0xff, 0, 255]));
+        }
+        assert_eq!(image.get_pixel(39, 22)[3], 0);
+        // Rectangle edges and the filled inside
+        assert_eq!(*image.get_pixel(30, 35), Rgba([0x55, 0x55, 0xff, 255]));
+        assert_eq!(image.get_pixel(35, 35)[3], 64);
+
+        // Polygon with a hole, filled where the centers of the pixels are inside
+        let polygon = parse_annotations(
+            r#"[{"type": "polygon", "points": [[-100, -100], [-80, -100], [-80, -80], [-100, -80]],
+                 "holes": [[[-95, -95], [-85, -95], [-85, -85], [-95, -85]]],
+                 "fill": true, "opacity": 1.0, "color": "white", "width": 1}]"#,
+        )
+        .unwrap();
+        let mut image = RgbaImage::new(40, 40);
+        draw_annotations(&mut image, &transform, &polygon, 1);
+        assert_eq!(image.get_pixel(2, 2)[3], 255);
+        assert_eq!(image.get_pixel(10, 10)[3], 0);
+        assert_eq!(image.get_pixel(25, 25)[3], 0);
+    }
+}
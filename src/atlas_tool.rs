@@ -0,0 +1,158 @@
+use crate::changes::sink;
+use crate::image_tool::ColorArgs;
+use anyhow::{anyhow, Result};
+use clap::Args;
+use log::{error, info};
+use minecraft_map_tool::layout::GridLayout;
+use minecraft_map_tool::pdf::{Page, PdfDocument};
+use minecraft_map_tool::source::map_id;
+use minecraft_map_tool::{read_maps_sorted, MapItem, SortSpec};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Lines in the caption under each map
+const CAPTION_LINES: usize = 3;
+
+/// Font size for the captions
+const CAPTION_SIZE: f32 = 8.0;
+
+/// Font size for the page headings
+const HEADING_SIZE: f32 = 14.0;
+
+#[derive(Args, Debug)]
+pub struct AtlasArgs {
+    /// The directory or world backup archive (.zip, .tar.gz) from which map files are searched for.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    path: PathBuf,
+
+    /// Filename for the PDF document
+    output_file: PathBuf,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Order of the maps in the atlas. Keys are name, time, id, data-version and scale.
+    /// Combine keys with commas and reverse them with :desc, for example scale,time:desc
+    #[arg(short, long, default_value = "name")]
+    sort: Option<SortSpec>,
+
+    /// Start a new page for each dimension
+    #[arg(short, long)]
+    group_by_dimension: bool,
+
+    /// Number of maps on each page
+    #[arg(short = 'n', long, default_value_t = 6)]
+    per_page: usize,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+}
+
+pub fn run(args: &AtlasArgs) -> ExitCode {
+    match make_atlas(args) {
+        Ok(pages) => {
+            info!(
+                "Atlas with {pages} pages written to: {:?}",
+                args.output_file
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            error!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn make_atlas(args: &AtlasArgs) -> Result<usize> {
+    let maps = read_maps_sorted(&args.path, &args.sort, args.recursive)
+        .map_err(|err| anyhow!("Could not get maps: {err}"))?;
+    let mut maps: Vec<MapItem> = maps.flatten().collect();
+    if maps.is_empty() {
+        return Err(anyhow!("No map files found"));
+    }
+    if args.group_by_dimension {
+        // Stable sort keeps the selected order inside the dimensions
+        maps.sort_by_key(|map_item| map_item.data.pretty_dimension());
+    }
+
+    let layout = GridLayout::a4(args.per_page, CAPTION_LINES);
+    let palette = args.colors.palette();
+    let mut document = PdfDocument::new();
+    let mut page: Option<Page> = None;
+    let mut index_on_page = 0;
+    let mut heading = String::new();
+    for map_item in &maps {
+        let dimension = map_item.data.pretty_dimension();
+        let new_group = args.group_by_dimension && dimension != heading;
+        if page.is_none() || index_on_page == layout.cells_per_page() || new_group {
+            if let Some(page) = page.take() {
+                document.add_page(page);
+            }
+            if args.group_by_dimension {
+                heading = dimension;
+            } else if heading.is_empty() {
+                heading = String::from("Maps");
+            }
+            let mut new_page = Page::new(layout.page_width, layout.page_height);
+            let (x, y) = layout.heading_position();
+            let title = format!("{heading} - Page {}", document.page_count() + 1);
+            new_page.add_text(&title, x, y, HEADING_SIZE);
+            page = Some(new_page);
+            index_on_page = 0;
+        }
+        let page = page.as_mut().expect("page was created above");
+
+        let cell = layout.cell(index_on_page);
+        let area = layout.image_area(&cell);
+        let image = map_item
+            .make_image(&palette)
+            .map_err(|err| anyhow!("Could not create image: {err}"))?;
+        page.add_image(image, area.x, area.y, area.width, area.height);
+        for (line, text) in caption(map_item).iter().enumerate() {
+            let y = area.y - (line + 1) as f32 * (CAPTION_SIZE + 3.0);
+            page.add_text(text, area.x, y, CAPTION_SIZE);
+        }
+        index_on_page += 1;
+    }
+    if let Some(page) = page {
+        document.add_page(page);
+    }
+
+    if let Some(parent) = args.output_file.parent() {
+        sink().create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(sink().create(&args.output_file)?);
+    document
+        .write(writer)
+        .map_err(|err| anyhow!("Could not write PDF: {err}"))?;
+    Ok(document.page_count())
+}
+
+fn caption(map_item: &MapItem) -> [String; CAPTION_LINES] {
+    let name = map_item
+        .file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let id = match map_id(&map_item.file) {
+        Some(id) => format!("Map #{id}"),
+        None => name.to_string(),
+    };
+    [
+        format!("{id} ({})", map_item.data.pretty_dimension()),
+        format!(
+            "Center {}, {}  Scale {}",
+            map_item.data.x_center,
+            map_item.data.z_center,
+            map_item.data.scale_description()
+        ),
+        format!(
+            "Version {} ({})",
+            map_item.version_description(),
+            map_item.data_version
+        ),
+    ]
+}
@@ -0,0 +1,145 @@
+use crate::charset::preset;
+use clap::Args;
+use comfy_table::{CellAlignment, ContentArrangement, Table};
+use log::{error, info, warn};
+use minecraft_map_tool::legend::{banner_groups, color_name};
+use minecraft_map_tool::markers::{collect_markers, MarkerKind};
+use minecraft_map_tool::read_maps_from;
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use minecraft_map_tool::transform::NETHER_NAME;
+use minecraft_map_tool::MapItem;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Most common names shown for each color with --stats
+const STATS_NAMES: usize = 3;
+
+#[derive(Args, Debug)]
+pub struct BannersArgs {
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which map files are
+    /// searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Only list banners from maps with matching dimensions name [default: all dimensions]
+    #[arg(short, long)]
+    dimension: Option<String>,
+
+    /// Only use the maps with these ids, for example 10..200,500,731. Ranges include both ends
+    #[arg(long, value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(long)]
+    recursive: bool,
+
+    /// Show the Nether banners at the matching Overworld coordinates, multiplied by 8
+    #[arg(long)]
+    project_nether: bool,
+
+    /// Summarize how many banners of each color there are and the names used for them
+    #[arg(long)]
+    stats: bool,
+}
+
+pub fn run(args: &BannersArgs) -> ExitCode {
+    let mut maps = match read_maps_from(&args.paths, &None, args.recursive) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not read maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(ids) = &args.ids {
+        maps.retain_ids(ids);
+    }
+
+    // The same position means another banner in each dimension
+    let dimension = args.dimension.as_ref().map(|name| name.to_lowercase());
+    let mut dimensions: BTreeMap<String, Vec<MapItem>> = BTreeMap::new();
+    while let Some((path, map_item)) = maps.next_with_path() {
+        match map_item {
+            Ok(map_item) => {
+                let name = map_item.data.pretty_dimension();
+                let wanted = match &dimension {
+                    Some(dimension) => &name.to_lowercase() == dimension,
+                    None => true,
+                };
+                if wanted {
+                    dimensions.entry(name).or_default().push(map_item);
+                }
+            }
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    let mut count = 0;
+    if args.stats {
+        table.set_header(["Dimension", "Color", "Banners", "Named", "Common names"]);
+        for (dimension, map_items) in &dimensions {
+            for group in banner_groups(&collect_markers(map_items)) {
+                count += group.count;
+                let names: Vec<String> = group
+                    .names
+                    .iter()
+                    .take(STATS_NAMES)
+                    .map(|(name, count)| format!("{name} ({count})"))
+                    .collect();
+                table.add_row([
+                    dimension.clone(),
+                    color_name(&group.color),
+                    group.count.to_string(),
+                    (group.count - group.nameless()).to_string(),
+                    names.join(", "),
+                ]);
+            }
+        }
+        for column in 2..=3 {
+            if let Some(column) = table.column_mut(column) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+    } else {
+        table.set_header(["Dimension", "Color", "Name", "X", "Y", "Z"]);
+        for (dimension, map_items) in &dimensions {
+            let markers = collect_markers(map_items);
+            let banners = markers
+                .iter()
+                .filter(|marker| marker.kind == MarkerKind::Banner);
+            for banner in banners {
+                count += 1;
+                let banner = match args.project_nether && dimension == NETHER_NAME {
+                    true => banner.projected_to_overworld(),
+                    false => banner.clone(),
+                };
+                table.add_row([
+                    dimension.clone(),
+                    banner.color.as_ref().map(color_name).unwrap_or_default(),
+                    banner.label.clone(),
+                    banner.pos.x.to_string(),
+                    banner.pos.y.to_string(),
+                    banner.pos.z.to_string(),
+                ]);
+            }
+        }
+        for column in 3..=5 {
+            if let Some(column) = table.column_mut(column) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+    }
+    info!("{count} banners found from {} dimensions", dimensions.len());
+    if args.project_nether && !args.stats && dimensions.contains_key(NETHER_NAME) {
+        info!("The Nether banners are shown at their Overworld coordinates");
+    }
+    if count > 0 {
+        println!("{table}");
+    }
+    ExitCode::SUCCESS
+}
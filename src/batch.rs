@@ -0,0 +1,61 @@
+//! Exit codes and error summary for the commands processing many map files
+//!
+//! | Exit code | Meaning                                              |
+//! | --------- | ---------------------------------------------------- |
+//! | 0         | All map files were processed                         |
+//! | 1         | Some map files failed, the rest were processed       |
+//! | 2         | Fatal error, nothing or only part of it was finished |
+
+use log::{error, warn};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Exit code when some of the map files failed
+pub const PARTIAL_FAILURE: u8 = 1;
+
+/// Exit code when the command could not do its job
+pub const FATAL: u8 = 2;
+
+/// Help text for the commands using these exit codes
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  All map files were processed
+  1  Some map files failed, the rest were processed
+  2  Fatal error";
+
+/// Collects the map files that failed, so that processing can continue with the other files
+#[derive(Debug, Default)]
+pub struct Failures {
+    files: Vec<(PathBuf, String)>,
+}
+
+impl Failures {
+    pub fn new() -> Failures {
+        Failures::default()
+    }
+
+    /// Records that the *file* failed for the *reason*
+    pub fn add(&mut self, file: &Path, reason: impl Display) {
+        warn!("{file:?}: {reason}");
+        self.files.push((PathBuf::from(file), reason.to_string()));
+    }
+
+    /// Prints the summary of the failed files and returns the exit code for the result
+    pub fn finish(self) -> ExitCode {
+        if self.files.is_empty() {
+            return ExitCode::SUCCESS;
+        }
+        let mut summary = format!("{} map files failed:", self.files.len());
+        for (file, reason) in &self.files {
+            summary.push_str(&format!("\n  {file:?}: {reason}"));
+        }
+        error!("{summary}");
+        ExitCode::from(PARTIAL_FAILURE)
+    }
+}
+
+/// Exit code for the fatal errors
+pub fn fatal() -> ExitCode {
+    ExitCode::from(FATAL)
+}
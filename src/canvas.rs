@@ -0,0 +1,224 @@
+//! Sparse canvas for stitching maps
+//!
+//! Stitched areas are often mostly empty, with islands of explored maps far apart. The
+//! [Canvas] stores only the chunks that have painted pixels, and remembers which map file
+//! painted each pixel, so odd looking places in a stitched image can be traced back to
+//! their maps.
+
+use crate::stitch::Area;
+use crate::transform::MapTransform;
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Width and height of the chunks in pixels
+pub const CHUNK_SIZE: u32 = 256;
+
+/// Painted pixels of a square of the canvas
+struct Chunk {
+    pixels: Vec<Rgba<u8>>,
+
+    /// Index of the source for each pixel, plus one, so zero means unpainted
+    sources: Vec<u32>,
+}
+
+impl Chunk {
+    fn new() -> Chunk {
+        let count = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        Chunk {
+            pixels: vec![Rgba([0, 0, 0, 0]); count],
+            sources: vec![0; count],
+        }
+    }
+}
+
+/// Image of an area in the world, painted with map images
+pub struct Canvas {
+    transform: MapTransform,
+    width: u32,
+    height: u32,
+    chunks: HashMap<(u32, u32), Chunk>,
+    sources: Vec<PathBuf>,
+}
+
+impl Canvas {
+    /// Empty canvas for the *area*, with a pixel for each block like the maps with scale 0
+    pub fn new(area: &Area) -> Canvas {
+        Canvas {
+            transform: MapTransform::of_area(area, 0),
+            width: area.width(),
+            height: area.height(),
+            chunks: HashMap::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Mapping between the world coordinates and the pixels of the canvas
+    pub fn transform(&self) -> MapTransform {
+        self.transform
+    }
+
+    /// Width of the canvas in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the canvas in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Paints the non-transparent pixels of the *image* with its upper left corner at the
+    /// world coordinates *left*, *top*, recording the *source* file for them
+    ///
+    /// Pixels outside the canvas are skipped, and later images are painted on top.
+    pub fn paint(&mut self, image: &RgbaImage, left: i32, top: i32, source: &Path) {
+        let (x, y) = self.transform.pixel_at(left, top);
+        let mut index = None;
+        for (in_x, in_y, pixel) in image.enumerate_pixels() {
+            if pixel[3] == 0 {
+                continue; // Transparent
+            }
+            let (out_x, out_y) = (in_x as i64 + x as i64, in_y as i64 + y as i64);
+            if out_x < 0 || out_y < 0 || out_x >= self.width as i64 || out_y >= self.height as i64 {
+                continue; // Outside of the canvas
+            }
+            // The source is added only when it paints something
+            let index = *index.get_or_insert_with(|| {
+                self.sources.push(source.to_path_buf());
+                self.sources.len() as u32
+            });
+            let (out_x, out_y) = (out_x as u32, out_y as u32);
+            let chunk = self
+                .chunks
+                .entry((out_x / CHUNK_SIZE, out_y / CHUNK_SIZE))
+                .or_insert_with(Chunk::new);
+            let offset = ((out_y % CHUNK_SIZE) * CHUNK_SIZE + out_x % CHUNK_SIZE) as usize;
+            chunk.pixels[offset] = *pixel;
+            chunk.sources[offset] = index;
+        }
+    }
+
+    /// Color of the pixel at the world coordinates *x*, *z*, if it has been painted
+    pub fn pixel_at(&self, x: i32, z: i32) -> Option<Rgba<u8>> {
+        let (chunk, offset) = self.locate(x, z)?;
+        match chunk.sources[offset] {
+            0 => None,
+            _ => Some(chunk.pixels[offset]),
+        }
+    }
+
+    /// Map file that painted the pixel at the world coordinates *x*, *z*
+    pub fn source_at(&self, x: i32, z: i32) -> Option<&Path> {
+        let (chunk, offset) = self.locate(x, z)?;
+        match chunk.sources[offset] {
+            0 => None,
+            index => Some(&self.sources[index as usize - 1]),
+        }
+    }
+
+    /// Index of the source that painted the pixel at *x*, *y* in the canvas, see [Canvas::sources]
+    pub fn source_index(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let chunk = self.chunks.get(&(x / CHUNK_SIZE, y / CHUNK_SIZE))?;
+        match chunk.sources[((y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE) as usize] {
+            0 => None,
+            index => Some(index as usize - 1),
+        }
+    }
+
+    /// Map files that painted pixels, in the order they were painted
+    ///
+    /// The same file is listed again if it was painted more than once.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Chunks with painted pixels as the pixel coordinates of their upper left corners
+    pub fn chunks(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.chunks
+            .keys()
+            .map(|&(x, y)| (x * CHUNK_SIZE, y * CHUNK_SIZE))
+    }
+
+    /// Number of chunks with painted pixels
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Image of the whole canvas, unpainted pixels are transparent
+    pub fn to_image(&self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        for (&(chunk_x, chunk_y), chunk) in &self.chunks {
+            let (left, top) = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+            let width = CHUNK_SIZE.min(self.width - left);
+            let height = CHUNK_SIZE.min(self.height - top);
+            for y in 0..height {
+                for x in 0..width {
+                    let offset = (y * CHUNK_SIZE + x) as usize;
+                    if chunk.sources[offset] != 0 {
+                        image.put_pixel(left + x, top + y, chunk.pixels[offset]);
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    /// Chunk and the offset in it for the world coordinates *x*, *z*
+    fn locate(&self, x: i32, z: i32) -> Option<(&Chunk, usize)> {
+        let (x, y) = self.transform.pixel_at(x, z);
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return None;
+        }
+        let (x, y) = (x as u32, y as u32);
+        let chunk = self.chunks.get(&(x / CHUNK_SIZE, y / CHUNK_SIZE))?;
+        Some((
+            chunk,
+            ((y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE) as usize,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::stitch::Area;
+    use image::{Rgba, RgbaImage};
+    use std::path::Path;
+
+    #[test]
+    fn test_canvas() {
+        let area = Area {
+            left: -300,
+            top: -10,
+            right: 299,
+            bottom: 9,
+        };
+        let mut canvas = Canvas::new(&area);
+        let red = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+        let mut blue = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 255, 255]));
+        blue.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        canvas.paint(&red, -2, -2, Path::new("map_1.dat"));
+        canvas.paint(&blue, 0, 0, Path::new("map_2.dat"));
+        // Nothing of this is inside the canvas
+        canvas.paint(&red, 400, 0, Path::new("map_3.dat"));
+
+        assert_eq!(canvas.source_at(-2, -2), Some(Path::new("map_1.dat")));
+        assert_eq!(canvas.source_at(0, 0), Some(Path::new("map_1.dat")));
+        assert_eq!(canvas.source_at(1, 1), Some(Path::new("map_2.dat")));
+        assert_eq!(canvas.pixel_at(1, 1), Some(Rgba([0, 0, 255, 255])));
+        assert_eq!(canvas.source_at(10, 0), None);
+        assert_eq!(canvas.source_at(1000, 0), None);
+        // Only the chunk from x = -44 to 211 was painted
+        assert_eq!(canvas.chunk_count(), 1);
+
+        let image = canvas.to_image();
+        assert_eq!(image.dimensions(), (600, 20));
+        assert_eq!(*image.get_pixel(300, 10), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(301, 11), Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+    }
+}
@@ -0,0 +1,20 @@
+//! Sink for the files changed by the commands
+//!
+//! The sink is chosen when the program starts, so every command writes, moves and deletes
+//! files the same way. With `--dry-run` the changes are only logged to the standard error.
+
+use minecraft_map_tool::sink::Sink;
+use std::sync::OnceLock;
+
+/// Sink selected with [init]
+static SINK: OnceLock<Sink> = OnceLock::new();
+
+/// Selects the sink for the whole program, see [Sink::new]
+pub fn init(dry_run: bool) {
+    SINK.get_or_init(|| Sink::new(dry_run));
+}
+
+/// Sink of the program, changing the files on the disk if [init] was not called
+pub fn sink() -> &'static Sink {
+    SINK.get_or_init(Sink::default)
+}
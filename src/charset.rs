@@ -0,0 +1,148 @@
+//! Characters for drawing the borders of the printed tables
+//!
+//! Box drawing characters are chosen when the program starts. Most terminals show them
+//! fine, but the default Windows console font lacks the rounded corners, and logs of CI
+//! systems and terminals without UTF-8 can mangle them. The `--ascii` option draws the
+//! borders with plain ASCII characters instead.
+
+use std::env;
+use std::sync::OnceLock;
+
+/// Characters selected with [init]
+static CHARSET: OnceLock<Charset> = OnceLock::new();
+
+/// Set of characters for the table borders
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Charset {
+    /// Box drawing with rounded corners
+    Rounded,
+
+    /// Box drawing with square corners, for fonts missing the rounded corners
+    Square,
+
+    /// Plain ASCII characters
+    Ascii,
+}
+
+impl Charset {
+    /// Picks the characters the terminal can show, or ASCII if *ascii* is set
+    pub fn detect(ascii: bool) -> Charset {
+        if ascii {
+            Charset::Ascii
+        } else {
+            detect_terminal()
+        }
+    }
+
+    /// Preset for the comfy-table tables
+    pub fn preset(&self) -> &'static str {
+        match self {
+            Charset::Rounded => "││──╞═╪╡┆    ┬┴╭╮╰╯",
+            Charset::Square => "││──├─┼┤│    ┬┴┌┐└┘",
+            Charset::Ascii => "||--+=++|    ++++++",
+        }
+    }
+
+    /// Upper left, upper right, lower left and lower right corners of a box
+    pub fn corners(&self) -> [char; 4] {
+        match self {
+            Charset::Rounded => ['╭', '╮', '╰', '╯'],
+            Charset::Square => ['┌', '┐', '└', '┘'],
+            Charset::Ascii => ['+'; 4],
+        }
+    }
+
+    /// Left and right ends of a line splitting a box, also used around titles
+    pub fn tees(&self) -> [char; 2] {
+        match self {
+            Charset::Rounded | Charset::Square => ['├', '┤'],
+            Charset::Ascii => ['+'; 2],
+        }
+    }
+
+    /// Horizontal line
+    pub fn horizontal(&self) -> char {
+        match self {
+            Charset::Rounded | Charset::Square => '─',
+            Charset::Ascii => '-',
+        }
+    }
+
+    /// Vertical line
+    pub fn vertical(&self) -> char {
+        match self {
+            Charset::Rounded | Charset::Square => '│',
+            Charset::Ascii => '|',
+        }
+    }
+
+    /// Dashed line under the table headers
+    pub fn dashed(&self) -> char {
+        match self {
+            Charset::Rounded | Charset::Square => '╌',
+            Charset::Ascii => '-',
+        }
+    }
+}
+
+/// Selects the characters for the whole program, see [Charset::detect]
+pub fn init(ascii: bool) {
+    CHARSET.get_or_init(|| Charset::detect(ascii));
+}
+
+/// Characters selected for the program, detected from the terminal if [init] was not called
+pub fn charset() -> Charset {
+    *CHARSET.get_or_init(|| Charset::detect(false))
+}
+
+/// Preset of the selected characters for the comfy-table tables
+pub fn preset() -> &'static str {
+    charset().preset()
+}
+
+/// Locale settings name their character encoding after a dot, like `en_US.UTF-8`. Settings
+/// without an encoding, like `C`, are common in containers that still show UTF-8 fine. The
+/// fonts of the Linux console lack the rounded corners.
+#[cfg(not(target_os = "windows"))]
+fn detect_terminal() -> Charset {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty());
+    let encoding = locale
+        .as_deref()
+        .and_then(|locale| locale.split_once('.'))
+        .map(|(_, encoding)| encoding.split('@').next().unwrap_or_default());
+    if let Some(encoding) = encoding {
+        if !encoding.eq_ignore_ascii_case("utf-8") && !encoding.eq_ignore_ascii_case("utf8") {
+            return Charset::Ascii;
+        }
+    }
+    match env::var("TERM") {
+        Ok(term) if term == "linux" => Charset::Square,
+        _ => Charset::Rounded,
+    }
+}
+
+/// Windows Terminal sets `WT_SESSION` and shows the rounded corners, as does the console
+/// when the user has switched it to the UTF-8 code page. The default console font lacks the
+/// rounded corners but has the square ones.
+#[cfg(target_os = "windows")]
+fn detect_terminal() -> Charset {
+    /// UTF-8 code page
+    const CP_UTF8: u32 = 65001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetConsoleOutputCP() -> u32;
+    }
+
+    if env::var_os("WT_SESSION").is_some() {
+        return Charset::Rounded;
+    }
+    // SAFETY: GetConsoleOutputCP has no arguments and returns 0 without a console
+    match unsafe { GetConsoleOutputCP() } {
+        CP_UTF8 => Charset::Rounded,
+        _ => Charset::Square,
+    }
+}
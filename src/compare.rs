@@ -0,0 +1,84 @@
+//! Combining stitched images of two worlds for comparing them
+//!
+//! Two copies of a world, like a survival world and its creative copy, are often mapped
+//! differently. Stitching the same area from both and putting the images side by side, or
+//! the second image over the first as a see-through onion skin, shows what was explored in
+//! each and how the terrain differs.
+
+use image::{Rgba, RgbaImage};
+
+/// Puts the *first* and the *second* image next to each other with *gap* transparent pixels
+/// between them
+///
+/// The images are aligned at their upper edges.
+pub fn side_by_side(first: &RgbaImage, second: &RgbaImage, gap: u32) -> RgbaImage {
+    let width = first.width() + gap + second.width();
+    let height = first.height().max(second.height());
+    let mut image = RgbaImage::new(width, height);
+    for (x, y, pixel) in first.enumerate_pixels() {
+        image.put_pixel(x, y, *pixel);
+    }
+    let left = first.width() + gap;
+    for (x, y, pixel) in second.enumerate_pixels() {
+        image.put_pixel(left + x, y, *pixel);
+    }
+    image
+}
+
+/// Draws the *second* image over the *first* with the *opacity* between 0.0 and 1.0
+///
+/// With opacity 0.0 only the first image shows, and with 1.0 the second image covers the
+/// first wherever it has pixels. The images must be of the same size.
+pub fn onion_skin(first: &RgbaImage, second: &RgbaImage, opacity: f32) -> RgbaImage {
+    let opacity = opacity.clamp(0.0, 1.0);
+    let mut image = first.clone();
+    for (bottom, top) in image.pixels_mut().zip(second.pixels()) {
+        *bottom = over(*bottom, *top, opacity);
+    }
+    image
+}
+
+/// The *top* color with its alpha multiplied by *opacity* composited over the *bottom* color
+fn over(bottom: Rgba<u8>, top: Rgba<u8>, opacity: f32) -> Rgba<u8> {
+    let top_alpha = top[3] as f32 / 255.0 * opacity;
+    let bottom_alpha = bottom[3] as f32 / 255.0 * (1.0 - top_alpha);
+    let alpha = top_alpha + bottom_alpha;
+    if alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let mix =
+        |b: u8, t: u8| ((t as f32 * top_alpha + b as f32 * bottom_alpha) / alpha).round() as u8;
+    Rgba([
+        mix(bottom[0], top[0]),
+        mix(bottom[1], top[1]),
+        mix(bottom[2], top[2]),
+        (alpha * 255.0).round() as u8,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compare::{onion_skin, side_by_side};
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_compare_images() {
+        let mut first = RgbaImage::from_pixel(4, 2, Rgba([200, 0, 0, 255]));
+        first.put_pixel(3, 1, Rgba([0, 0, 0, 0]));
+        let mut second = RgbaImage::from_pixel(4, 2, Rgba([0, 0, 100, 255]));
+        second.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+
+        let image = side_by_side(&first, &second, 2);
+        assert_eq!(image.dimensions(), (10, 2));
+        assert_eq!(*image.get_pixel(0, 1), Rgba([200, 0, 0, 255]));
+        assert_eq!(image.get_pixel(4, 0)[3], 0);
+        assert_eq!(*image.get_pixel(9, 1), Rgba([0, 0, 100, 255]));
+
+        let image = onion_skin(&first, &second, 0.5);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([200, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([100, 0, 50, 255]));
+        // Only the second image has a pixel here
+        assert_eq!(*image.get_pixel(3, 1), Rgba([0, 0, 100, 128]));
+        assert_eq!(onion_skin(&first, &second, 0.0), first);
+    }
+}
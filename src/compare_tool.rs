@@ -0,0 +1,152 @@
+use crate::changes::sink;
+use crate::image_tool::ColorArgs;
+use anyhow::{anyhow, Result};
+use clap::{Args, ValueEnum};
+use image::RgbaImage;
+use log::{error, info};
+use minecraft_map_tool::compare::{onion_skin, side_by_side};
+use minecraft_map_tool::palette::Palette;
+use minecraft_map_tool::stitch::{filter_maps, stitch_maps, Area};
+use minecraft_map_tool::{read_maps_from, ReadMap, SortingOrder};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct CompareArgs {
+    /// Directory, world backup archive, or map files of the first world. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path)
+    first: PathBuf,
+
+    /// Directory, world backup archive, or map files of the second world. With the sftp
+    /// feature, this can also be a remote directory (sftp://user@host/path)
+    second: PathBuf,
+
+    /// Filename for the output image
+    filename: String,
+
+    /// Only draw maps with matching dimensions name
+    #[arg(short, long, default_value = "Overworld")]
+    dimension: String,
+
+    /// Search map files recursively in subdirectories
+    #[arg(long)]
+    recursive: bool,
+
+    /// How the images of the worlds are combined
+    #[arg(long, default_value = "side-by-side")]
+    layout: Layout,
+
+    /// Opacity of the second world in the onion skin layout, from 0.0 to 1.0
+    #[arg(long, default_value_t = 0.5, value_parser = parse_opacity)]
+    opacity: f32,
+
+    /// Transparent pixels between the images in the side by side layout
+    #[arg(long, default_value_t = 16)]
+    gap: u32,
+
+    /// Left coordinate (Smaller X) [default: left edge of the maps in both worlds]
+    #[arg(short, long)]
+    left: Option<i32>,
+
+    /// Top coordinate (Smaller Z) [default: top edge of the maps in both worlds]
+    #[arg(short, long)]
+    top: Option<i32>,
+
+    /// Right coordinate (Larger X) [default: right edge of the maps in both worlds]
+    #[arg(short, long)]
+    right: Option<i32>,
+
+    /// Bottom coordinate (Larger Z) [default: bottom edge of the maps in both worlds]
+    #[arg(short, long)]
+    bottom: Option<i32>,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+}
+
+/// Ways to combine the images of the two worlds
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Layout {
+    /// The first world on the left and the second on the right
+    SideBySide,
+
+    /// The second world drawn over the first with the --opacity
+    Onion,
+}
+
+fn parse_opacity(text: &str) -> std::result::Result<f32, String> {
+    let opacity: f32 = text.parse().map_err(|err| format!("{err}"))?;
+    match (0.0..=1.0).contains(&opacity) {
+        true => Ok(opacity),
+        false => Err(String::from("Opacity must be from 0.0 to 1.0")),
+    }
+}
+
+/// Reads the maps of the world in the *path* that are in the dimension, and their area
+fn find_maps(args: &CompareArgs, path: &Path) -> Result<(ReadMap, Area)> {
+    let sort = Some(SortingOrder::Time.into());
+    let maps = read_maps_from(&[path.to_path_buf()], &sort, args.recursive)
+        .map_err(|err| anyhow!("Could not read maps from {}: {err}", path.display()))?;
+    let (maps, area) = filter_maps(maps, 0, Some(&args.dimension))
+        .map_err(|err| anyhow!("{}: {err}", path.display()))?;
+    info!(
+        "Found {} map files in {}",
+        maps.file_count(),
+        path.display()
+    );
+    Ok((maps, area))
+}
+
+/// Draws the *maps* of a world into an image of the *area*
+fn stitch(maps: ReadMap, area: &Area, palette: &Palette) -> Result<RgbaImage> {
+    stitch_maps(maps, area, palette, || {}).map_err(|err| anyhow!("Could not paint image: {err}"))
+}
+
+fn process(args: &CompareArgs) -> Result<()> {
+    let (first, first_area) = find_maps(args, &args.first)?;
+    let (second, second_area) = find_maps(args, &args.second)?;
+
+    // Both worlds are drawn from the same area, so the images line up
+    let mut area = first_area.union(&second_area);
+    if let Some(value) = args.left {
+        area.left = value;
+    }
+    if let Some(value) = args.top {
+        area.top = value;
+    }
+    if let Some(value) = args.right {
+        area.right = value;
+    }
+    if let Some(value) = args.bottom {
+        area.bottom = value;
+    }
+    info!("Comparison area");
+    info!("  Upper Left  : {} {}", area.left, area.top);
+    info!("  Lower Right : {} {}", area.right, area.bottom);
+    info!("  Size        : {}×{}", area.width(), area.height());
+
+    let palette = args.colors.palette();
+    let first = stitch(first, &area, &palette)?;
+    let second = stitch(second, &area, &palette)?;
+    let image = match args.layout {
+        Layout::SideBySide => side_by_side(&first, &second, args.gap),
+        Layout::Onion => onion_skin(&first, &second, args.opacity),
+    };
+
+    if let Some(output_path) = PathBuf::from(&args.filename).parent() {
+        sink().create_dir_all(output_path)?;
+    }
+    info!("Saving image as {:?}", args.filename);
+    sink().write_with(&args.filename, |file| image.save(file))?;
+    Ok(())
+}
+
+pub fn run(args: &CompareArgs) -> ExitCode {
+    match process(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::changes::sink;
+use clap::{Args, Command};
+use clap_complete::{generate, Shell};
+use clap_mangen::Man;
+use log::{error, info};
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell for the completion script
+    shell: Shell,
+}
+
+#[derive(Args, Debug)]
+pub struct ManGenArgs {
+    /// Write man pages for the program and all commands to this directory.
+    /// Without it, only the main page is written to the standard output.
+    #[arg(short, long)]
+    output_dir: Option<PathBuf>,
+}
+
+/// Writes the completion script for the *command* to the standard output
+pub fn run_completions(args: &CompletionsArgs, mut command: Command) -> ExitCode {
+    let name = command.get_name().to_string();
+    generate(args.shell, &mut command, name, &mut stdout());
+    ExitCode::SUCCESS
+}
+
+/// Writes the man pages for the *command*
+pub fn run_mangen(args: &ManGenArgs, command: Command) -> ExitCode {
+    let result = match &args.output_dir {
+        None => Man::new(command).render(&mut stdout()),
+        Some(output_dir) => write_man_pages(command, output_dir),
+    };
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("Could not write man pages: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Writes a page for the *command* and its subcommands, named like `program-command.1`
+fn write_man_pages(command: Command, output_dir: &Path) -> std::io::Result<()> {
+    sink().create_dir_all(output_dir)?;
+    let mut commands = vec![(command.get_name().to_string(), command)];
+    while let Some((name, command)) = commands.pop() {
+        for subcommand in command.get_subcommands() {
+            if subcommand.is_hide_set() || subcommand.get_name() == "help" {
+                continue;
+            }
+            let sub_name = format!("{name}-{}", subcommand.get_name());
+            commands.push((sub_name.clone(), subcommand.clone().name(sub_name)));
+        }
+        let file = output_dir.join(format!("{name}.1"));
+        let mut buffer = Vec::new();
+        Man::new(command).render(&mut buffer)?;
+        sink().write(&file, buffer)?;
+        info!("Man page written to: {file:?}");
+    }
+    Ok(())
+}
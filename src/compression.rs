@@ -0,0 +1,241 @@
+//! Compression of NBT files
+//!
+//! Minecraft writes map files with gzip, but tools that extract or convert worlds may write
+//! them with zlib or without compression. The compression is detected from the first bytes
+//! when the files are read.
+//!
+//! The level of the gzip and zlib compression is set for the whole program with
+//! [set_compression_level]. With the `zstd` feature, files can also be compressed with
+//! Zstandard, which packs archived maps smaller but cannot be read by the game.
+
+use crate::error::Result;
+use crate::limits::{read_limited, read_options};
+use crate::nbt::replace_file;
+use crate::sink::Sink;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use serde::Serialize;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Compression level used when writing, see [set_compression_level]
+static COMPRESSION_LEVEL: AtomicU32 = AtomicU32::new(DEFAULT_COMPRESSION_LEVEL);
+
+/// Compression level Minecraft and most tools use
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// Sets the compression level for all files written by the library
+///
+/// Levels go from 0 for the fastest to 9 for the smallest files. Zstandard levels go up
+/// to 22, and higher levels are lowered to the maximum of each compression.
+pub fn set_compression_level(level: u32) {
+    COMPRESSION_LEVEL.store(level, Ordering::Relaxed);
+}
+
+/// Compression level used for writing, see [set_compression_level]
+pub fn compression_level() -> u32 {
+    COMPRESSION_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Compression used for NBT data
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum NbtCompression {
+    /// Gzip, used by Minecraft for map files
+    #[default]
+    Gzip,
+
+    /// Zlib, used in region files
+    Zlib,
+
+    /// No compression
+    None,
+
+    /// Zstandard, smaller than gzip but not readable by Minecraft
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl NbtCompression {
+    /// Detects the compression from the first bytes of the content
+    pub fn detect(header: &[u8]) -> NbtCompression {
+        match header {
+            [0x1f, 0x8b, ..] => NbtCompression::Gzip,
+            #[cfg(feature = "zstd")]
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => NbtCompression::Zstd,
+            [cmf, flg, ..] if cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]) % 31 == 0 => {
+                NbtCompression::Zlib
+            }
+            _ => NbtCompression::None,
+        }
+    }
+
+    /// Highest compression level that makes the smallest files
+    pub fn max_level(&self) -> u32 {
+        match self {
+            NbtCompression::Gzip | NbtCompression::Zlib => 9,
+            NbtCompression::None => 0,
+            #[cfg(feature = "zstd")]
+            NbtCompression::Zstd => 22,
+        }
+    }
+}
+
+/// Returns a reader giving the uncompressed NBT data of the *reader*
+///
+/// The compression is detected with [NbtCompression::detect].
+pub fn decompress<'a, R: Read + 'a>(reader: R) -> Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+    Ok(match NbtCompression::detect(reader.fill_buf()?) {
+        NbtCompression::Gzip => Box::new(GzDecoder::new(reader)),
+        NbtCompression::Zlib => Box::new(ZlibDecoder::new(reader)),
+        NbtCompression::None => Box::new(reader),
+        #[cfg(feature = "zstd")]
+        NbtCompression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+    })
+}
+
+/// Writes the *value* as NBT to the *writer* with the *compression*
+///
+/// The level set with [set_compression_level] is used.
+pub fn write_compressed<W: Write, T: Serialize>(
+    writer: W,
+    value: &T,
+    compression: NbtCompression,
+) -> Result<()> {
+    write_compressed_with_level(writer, value, compression, compression_level())
+}
+
+/// Writes the *value* as NBT to the *writer* with the *compression* at the *level*
+pub fn write_compressed_with_level<W: Write, T: Serialize>(
+    writer: W,
+    value: &T,
+    compression: NbtCompression,
+    level: u32,
+) -> Result<()> {
+    compress_with(writer, compression, level, |writer| {
+        Ok(fastnbt::to_writer(writer, value)?)
+    })
+}
+
+/// Compresses the *content* of a file again with the *compression* at the *level*
+///
+/// The content is decompressed and checked against the limits set with
+/// [set_read_options](crate::limits::set_read_options), so files that are not NBT or are
+/// too large are refused. It is not read as NBT values, so data unknown to this library is
+/// kept.
+pub fn recompress(content: &[u8], compression: NbtCompression, level: u32) -> Result<Vec<u8>> {
+    let nbt = read_limited(content, &read_options())?;
+    let mut compressed = Vec::new();
+    compress_with(&mut compressed, compression, level, |writer| {
+        Ok(writer.write_all(&nbt)?)
+    })?;
+    Ok(compressed)
+}
+
+/// Compresses the *file* again with the *compression* at the *level*, see [recompress]
+///
+/// The file is replaced only when it gets smaller or its compression changes, and it is
+/// written through a temporary file with the *sink*. Returns the old and the new size of the
+/// file.
+pub fn recompress_file(
+    file: &Path,
+    compression: NbtCompression,
+    level: u32,
+    sink: &Sink,
+) -> Result<(u64, u64)> {
+    let content = fs::read(file)?;
+    let recompressed = recompress(&content, compression, level)?;
+    if recompressed.len() >= content.len() && NbtCompression::detect(&content) == compression {
+        return Ok((content.len() as u64, content.len() as u64));
+    }
+    replace_file(file, sink, |writer| Ok(writer.write_all(&recompressed)?))?;
+    Ok((content.len() as u64, recompressed.len() as u64))
+}
+
+/// Calls *write* with a writer compressing to the *writer*
+fn compress_with<W, F>(writer: W, compression: NbtCompression, level: u32, write: F) -> Result<()>
+where
+    W: Write,
+    F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+    let flate_level = Compression::new(level.min(9));
+    match compression {
+        NbtCompression::Gzip => {
+            let mut encoder = GzEncoder::new(writer, flate_level);
+            write(&mut encoder)?;
+            encoder.finish()?;
+        }
+        NbtCompression::Zlib => {
+            let mut encoder = ZlibEncoder::new(writer, flate_level);
+            write(&mut encoder)?;
+            encoder.finish()?;
+        }
+        NbtCompression::None => {
+            let mut writer = writer;
+            write(&mut writer)?;
+        }
+        #[cfg(feature = "zstd")]
+        NbtCompression::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, level.clamp(1, 22) as i32)?;
+            write(&mut encoder)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::{recompress, write_compressed, NbtCompression};
+    use crate::limits::ReadOptions;
+    use crate::MapItem;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::path::Path;
+
+    #[test]
+    fn test_compressions_are_detected() {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/map_0.dat");
+        let map_item = MapItem::read_from(&file).unwrap();
+        for compression in [
+            NbtCompression::Gzip,
+            NbtCompression::Zlib,
+            NbtCompression::None,
+        ] {
+            let mut bytes = Vec::new();
+            write_compressed(&mut bytes, &map_item, compression).unwrap();
+            assert_eq!(NbtCompression::detect(&bytes), compression);
+            let read = MapItem::read_from_reader(bytes.as_slice(), &file);
+            assert_eq!(read.unwrap().data.colors, map_item.data.colors);
+        }
+    }
+
+    #[test]
+    fn test_recompress() {
+        let content = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/map_0.dat"));
+        let content = content.unwrap();
+        let fastest = recompress(&content, NbtCompression::Gzip, 0).unwrap();
+        let smallest = recompress(&content, NbtCompression::Gzip, 9).unwrap();
+        assert!(smallest.len() < fastest.len());
+        let plain = recompress(&smallest, NbtCompression::None, 0).unwrap();
+        assert_eq!(
+            plain,
+            recompress(&content, NbtCompression::None, 0).unwrap()
+        );
+
+        // Anything else than NBT is not compressed again
+        assert!(recompress(b"not nbt", NbtCompression::Gzip, 9).is_err());
+        let mut bomb = GzEncoder::new(Vec::new(), Compression::best());
+        bomb.write_all(&[10, 0, 0, 7, 0, 1, b'x', 0x7f, 0xff, 0xff, 0xff])
+            .unwrap();
+        bomb.write_all(&vec![0; ReadOptions::DEFAULT.max_size as usize])
+            .unwrap();
+        let bomb = bomb.finish().unwrap();
+        assert!(recompress(&bomb, NbtCompression::None, 0).is_err());
+    }
+}
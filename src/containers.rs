@@ -0,0 +1,111 @@
+//! Maps stored in containers placed in a world
+//!
+//! Chests, barrels, shulker boxes and other containers are block entities in the chunks of
+//! the `region` directory. Since Java Edition 1.18 they are in the `block_entities` list of
+//! the chunk, and before that in the `Level.TileEntities` list.
+
+use crate::error::Result;
+use crate::player::ItemStack;
+use crate::region::{field, number, scan_chunks, Scan, ScanError};
+use crate::source::IdRanges;
+use fastnbt::Value;
+use log::warn;
+use std::path::{Path, PathBuf};
+
+/// Map found in a container block
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerMap {
+    /// Id of the map
+    pub map_id: u32,
+
+    /// Number of copies in the stack
+    pub count: i32,
+
+    /// Pretty name of the dimension
+    pub dimension: String,
+
+    /// Block position of the container
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+
+    /// Block entity id of the container, like minecraft:chest
+    pub block: String,
+
+    /// Item inside the container holding the map, like minecraft:shulker_box
+    pub item: Option<String>,
+
+    /// Region file the container was found in
+    pub region: PathBuf,
+}
+
+/// Finds the maps stored in containers in the *world* directory
+///
+/// Only maps with ids in *ids* are returned, or all of them when *ids* is None.
+/// The maps are sorted by the map id.
+pub fn find_container_maps(world: &Path, ids: Option<&IdRanges>) -> Result<Vec<ContainerMap>> {
+    Ok(scan_container_maps(world, ids)?.found)
+}
+
+/// Finds the maps in containers like [find_container_maps], also returning the parts of
+/// the world that could not be read
+pub fn scan_container_maps(world: &Path, ids: Option<&IdRanges>) -> Result<Scan<ContainerMap>> {
+    let mut maps = Vec::new();
+    let mut bad_items = Vec::new();
+    let mut errors = scan_chunks(world, &["region"], |dimension, region, chunk| {
+        for block_entity in chunk_block_entities(chunk) {
+            let Some(items) = field(block_entity, "Items") else {
+                continue;
+            };
+            let items: Vec<ItemStack> = match fastnbt::from_value(items) {
+                Ok(items) => items,
+                Err(err) => {
+                    let error = ScanError {
+                        file: PathBuf::from(region),
+                        message: format!("Bad container items: {err}"),
+                    };
+                    warn!("{error}");
+                    bad_items.push(error);
+                    continue;
+                }
+            };
+            let block = match field(block_entity, "id") {
+                Some(Value::String(id)) => id.clone(),
+                _ => String::from("unknown"),
+            };
+            let position = |name| field(block_entity, name).and_then(number).unwrap_or(0) as i32;
+            for map in items.iter().flat_map(ItemStack::maps) {
+                if ids.is_some_and(|ids| !ids.contains(map.map_id)) {
+                    continue;
+                }
+                maps.push(ContainerMap {
+                    map_id: map.map_id,
+                    count: map.count,
+                    dimension: dimension.to_string(),
+                    x: position("x"),
+                    y: position("y"),
+                    z: position("z"),
+                    block: block.clone(),
+                    item: map.container,
+                    region: PathBuf::from(region),
+                });
+            }
+        }
+    })?;
+    errors.append(&mut bad_items);
+    maps.sort_by_key(|map| map.map_id);
+    Ok(Scan {
+        found: maps,
+        errors,
+    })
+}
+
+/// Block entities of a region chunk in any version
+fn chunk_block_entities(chunk: &Value) -> &[Value] {
+    let block_entities =
+        field(chunk, "block_entities").or_else(|| field(field(chunk, "Level")?, "TileEntities"));
+    match block_entities {
+        Some(Value::List(block_entities)) => block_entities,
+        _ => &[],
+    }
+}
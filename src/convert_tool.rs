@@ -0,0 +1,87 @@
+use crate::changes::sink;
+use clap::{Args, ValueEnum};
+use log::{error, info};
+use minecraft_map_tool::compression::NbtCompression;
+use minecraft_map_tool::nbt::{from_json, read_nbt, to_json, to_snbt, write_nbt};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    /// NBT file to convert to text, or a JSON file (.json) to convert back to NBT
+    input: PathBuf,
+
+    /// Output file. Without it, the text is written to the standard output.
+    output: Option<PathBuf>,
+
+    /// Text format when converting from NBT. SNBT cannot be converted back.
+    #[arg(short, long, default_value = "json")]
+    format: ConvertFormat,
+
+    /// Compression of the written NBT file
+    #[arg(short, long, default_value = "gzip")]
+    compression: NbtCompression,
+}
+
+/// Text formats for the NBT data
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ConvertFormat {
+    /// JSON with type annotations
+    Json,
+
+    /// Stringified NBT that can be pasted into Minecraft commands
+    Snbt,
+}
+
+pub fn run(args: &ConvertArgs) -> ExitCode {
+    let is_json = args.input.extension().unwrap_or_default() == "json";
+    let result = match (is_json, &args.output) {
+        (true, Some(output)) => json_to_nbt(&args.input, output, args.compression),
+        (true, None) => Err(String::from("Output file is needed for NBT")),
+        (false, output) => nbt_to_text(&args.input, output.as_deref(), args.format),
+    };
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn nbt_to_text(input: &Path, output: Option<&Path>, format: ConvertFormat) -> Result<(), String> {
+    let file = File::open(input).map_err(|err| format!("Could not open NBT file: {err}"))?;
+    let root = read_nbt(file).map_err(|err| format!("Could not read NBT file: {err}"))?;
+    let text = match format {
+        ConvertFormat::Json => {
+            let json = to_json(&root).map_err(|err| format!("Could not convert to JSON: {err}"))?;
+            serde_json::to_string_pretty(&json).map_err(|err| err.to_string())?
+        }
+        ConvertFormat::Snbt => to_snbt(&root),
+    };
+    match output {
+        Some(output) => {
+            sink()
+                .write(output, text + "\n")
+                .map_err(|err| format!("Could not write text file: {err}"))?;
+            info!("Text written to: {output:?}");
+        }
+        None => println!("{text}"),
+    }
+    Ok(())
+}
+
+fn json_to_nbt(input: &Path, output: &Path, compression: NbtCompression) -> Result<(), String> {
+    let text = fs::read(input).map_err(|err| format!("Could not read JSON file: {err}"))?;
+    let json: serde_json::Value =
+        serde_json::from_slice(&text).map_err(|err| format!("Invalid JSON: {err}"))?;
+    let root = from_json(&json).map_err(|err| format!("Could not convert to NBT: {err}"))?;
+    let file = sink()
+        .create(output)
+        .map_err(|err| format!("Could not create NBT file: {err}"))?;
+    write_nbt(file, &root, compression)
+        .map_err(|err| format!("Could not write NBT file: {err}"))?;
+    info!("NBT written to: {output:?}");
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! Comparing map items
+
+use crate::error::{Error, Result};
+use crate::palette::Palette;
+use crate::{MapItem, ReadMap};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Color for changed pixels in the diff image
+const CHANGED_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// A metadata field that differs between two maps
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FieldChange {
+    /// Name of the field
+    pub name: &'static str,
+
+    /// Value in the old map
+    pub old: String,
+
+    /// Value in the new map
+    pub new: String,
+}
+
+/// Differences between two map items
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MapDiff {
+    /// Number of pixels with different color values
+    pub changed_pixels: usize,
+
+    /// Number of pixels compared
+    pub total_pixels: usize,
+
+    /// Metadata fields with different values
+    pub changed_fields: Vec<FieldChange>,
+}
+
+impl MapDiff {
+    /// Compares the *old* and *new* map items
+    pub fn between(old: &MapItem, new: &MapItem) -> MapDiff {
+        let mut changed_fields = Vec::new();
+        let mut compare = |name: &'static str, old: String, new: String| {
+            if old != new {
+                changed_fields.push(FieldChange { name, old, new });
+            }
+        };
+        compare(
+            "Scale",
+            old.data.scale.to_string(),
+            new.data.scale.to_string(),
+        );
+        compare(
+            "Dimension",
+            old.data.dimension.clone(),
+            new.data.dimension.clone(),
+        );
+        compare(
+            "Tracking position",
+            old.data.tracking_position.to_string(),
+            new.data.tracking_position.to_string(),
+        );
+        compare(
+            "Unlimited tracking",
+            old.data.unlimited_tracking.to_string(),
+            new.data.unlimited_tracking.to_string(),
+        );
+        compare(
+            "Locked",
+            old.data.locked.to_string(),
+            new.data.locked.to_string(),
+        );
+        compare(
+            "Center",
+            format!("{}, {}", old.data.x_center, old.data.z_center),
+            format!("{}, {}", new.data.x_center, new.data.z_center),
+        );
+        if old.data.banners != new.data.banners {
+            // Same count of banners can still have modified content
+            compare(
+                "Banners",
+                old.data.banners.len().to_string(),
+                format!("{} (modified)", new.data.banners.len()),
+            );
+        }
+        if old.data.frames != new.data.frames {
+            // Same count of frames can still have modified content
+            compare(
+                "Frames",
+                old.data.frames.len().to_string(),
+                format!("{} (modified)", new.data.frames.len()),
+            );
+        }
+        compare(
+            "Data version",
+            old.data_version.to_string(),
+            new.data_version.to_string(),
+        );
+
+        let total_pixels = old.data.colors.len().max(new.data.colors.len());
+        let changed_pixels = (0..total_pixels)
+            .filter(|index| old.data.colors.get(*index) != new.data.colors.get(*index))
+            .count();
+        MapDiff {
+            changed_pixels,
+            total_pixels,
+            changed_fields,
+        }
+    }
+
+    /// Tests if the maps were identical
+    pub fn is_empty(&self) -> bool {
+        self.changed_pixels == 0 && self.changed_fields.is_empty()
+    }
+
+    /// Changed pixels as a percentage of all pixels
+    pub fn changed_percentage(&self) -> f64 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+        self.changed_pixels as f64 * 100.0 / self.total_pixels as f64
+    }
+}
+
+/// A map that exists in both collections but has changed
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ModifiedMap {
+    /// File name of the map
+    pub file: PathBuf,
+
+    /// Changed percentage of the pixels
+    pub changed_percentage: f64,
+
+    /// The differences
+    #[serde(flatten)]
+    pub diff: MapDiff,
+}
+
+/// Differences between two collections of maps, such as two backups of the same world
+///
+/// Maps are correlated by their file names, so the directory structure does not matter.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CollectionDiff {
+    /// Maps only in the new collection
+    pub added: Vec<PathBuf>,
+
+    /// Maps only in the old collection
+    pub removed: Vec<PathBuf>,
+
+    /// Maps that have changed
+    pub modified: Vec<ModifiedMap>,
+
+    /// Number of identical maps
+    pub unchanged: usize,
+
+    /// Map files that could not be read
+    pub unreadable: Vec<PathBuf>,
+}
+
+impl CollectionDiff {
+    /// Compares *old* and *new* collections of maps
+    pub fn between(old: ReadMap, new: ReadMap) -> CollectionDiff {
+        let mut diff = CollectionDiff::default();
+        let mut old_maps = diff.read_by_name(old);
+        for (name, new_map) in diff.read_by_name(new) {
+            match old_maps.remove(&name) {
+                None => diff.added.push(name),
+                Some(old_map) => {
+                    let map_diff = MapDiff::between(&old_map, &new_map);
+                    if map_diff.is_empty() {
+                        diff.unchanged += 1;
+                    } else {
+                        diff.modified.push(ModifiedMap {
+                            file: name,
+                            changed_percentage: map_diff.changed_percentage(),
+                            diff: map_diff,
+                        });
+                    }
+                }
+            }
+        }
+        diff.removed = old_maps.into_keys().collect();
+
+        // Natural order is easier to read than the order of the BTreeMap
+        let natural =
+            |a: &PathBuf, b: &PathBuf| natord::compare(&a.to_string_lossy(), &b.to_string_lossy());
+        diff.added.sort_by(natural);
+        diff.removed.sort_by(natural);
+        diff.modified.sort_by(|a, b| natural(&a.file, &b.file));
+        diff
+    }
+
+    fn read_by_name(&mut self, maps: ReadMap) -> BTreeMap<PathBuf, MapItem> {
+        let mut by_name = BTreeMap::new();
+        let paths: Vec<PathBuf> = maps.paths().cloned().collect();
+        for (path, map_item) in paths.into_iter().zip(maps) {
+            match map_item {
+                Ok(map_item) => {
+                    let name = PathBuf::from(path.file_name().unwrap_or_default());
+                    by_name.insert(name, map_item);
+                }
+                Err(_) => self.unreadable.push(path),
+            }
+        }
+        by_name
+    }
+}
+
+/// Draws the *new* map with changed pixels highlighted in red
+///
+/// Unchanged pixels are drawn at half opacity so that the changes stand out.
+pub fn diff_image(old: &MapItem, new: &MapItem, palette: &Palette) -> Result<RgbaImage> {
+    if old.data.colors.len() != new.data.colors.len() {
+        return Err(Error::map_item_error("Color buffers have different sizes"));
+    }
+    let mut image = new.make_image(palette)?;
+    for (index, pixel) in image.pixels_mut().enumerate() {
+        if old.data.colors.get(index) != new.data.colors.get(index) {
+            *pixel = CHANGED_COLOR;
+        } else {
+            pixel[3] /= 2;
+        }
+    }
+    Ok(image)
+}
@@ -0,0 +1,187 @@
+use crate::changes::sink;
+use crate::charset::preset;
+use clap::{Args, ValueEnum};
+use comfy_table::{presets, Cell, ContentArrangement, Table, TableComponent};
+use log::{error, info, warn};
+use minecraft_map_tool::diff::{diff_image, CollectionDiff, MapDiff};
+use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
+use minecraft_map_tool::{read_maps, MapItem, SortingOrder};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// The old map_#.dat file, or a directory or world backup archive with the old map files.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    old: PathBuf,
+
+    /// The new map_#.dat file, or a directory or world backup archive with the new map files.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    new: PathBuf,
+
+    /// Write an image where changed pixels are highlighted in red.
+    /// Only available when comparing two map files.
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Output format when comparing directories
+    #[arg(short, long, default_value = "table")]
+    format: DiffFormat,
+}
+
+/// Output formats for the directory comparison
+#[derive(Clone, Debug, ValueEnum)]
+pub enum DiffFormat {
+    /// Table of the added, removed and modified maps
+    Table,
+
+    /// JSON report for other tools
+    Json,
+}
+
+pub fn run(args: &DiffArgs) -> ExitCode {
+    if args.old.is_file() && args.new.is_file() && args.old.extension().unwrap_or_default() == "dat"
+    {
+        diff_files(args)
+    } else {
+        diff_directories(args)
+    }
+}
+
+fn diff_directories(args: &DiffArgs) -> ExitCode {
+    if args.output_file.is_some() {
+        error!("The diff image can only be made when comparing two map files");
+        return ExitCode::FAILURE;
+    }
+    let sort = Some(SortingOrder::Name);
+    let (old, new) = match (
+        read_maps(&args.old, &sort, args.recursive),
+        read_maps(&args.new, &sort, args.recursive),
+    ) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Could not get maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let diff = CollectionDiff::between(old, new);
+    match args.format {
+        DiffFormat::Json => match serde_json::to_string_pretty(&diff) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                error!("Could not make JSON report: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+        DiffFormat::Table => print_collection_diff(&diff),
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_collection_diff(diff: &CollectionDiff) {
+    if !diff.added.is_empty() || !diff.removed.is_empty() || !diff.modified.is_empty() {
+        let mut table = Table::new();
+        table
+            .load_preset(preset())
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(vec!["File", "Status", "Changed Pixels", "Changed Fields"]);
+        for file in &diff.added {
+            table.add_row(vec![
+                Cell::new(file.display()),
+                Cell::new("Added"),
+                Cell::new(""),
+                Cell::new(""),
+            ]);
+        }
+        for file in &diff.removed {
+            table.add_row(vec![
+                Cell::new(file.display()),
+                Cell::new("Removed"),
+                Cell::new(""),
+                Cell::new(""),
+            ]);
+        }
+        for modified in &diff.modified {
+            let fields: Vec<&str> = modified
+                .diff
+                .changed_fields
+                .iter()
+                .map(|change| change.name)
+                .collect();
+            table.add_row(vec![
+                Cell::new(modified.file.display()),
+                Cell::new("Modified"),
+                Cell::new(format!("{:.1} %", modified.changed_percentage)),
+                Cell::new(fields.join(", ")),
+            ]);
+        }
+        println!("{table}");
+    }
+    println!(
+        "Added: {}, Removed: {}, Modified: {}, Unchanged: {}",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.modified.len(),
+        diff.unchanged
+    );
+    for file in &diff.unreadable {
+        warn!("Could not read: {file:?}");
+    }
+}
+
+fn diff_files(args: &DiffArgs) -> ExitCode {
+    let (old, new) = match (MapItem::read_from(&args.old), MapItem::read_from(&args.new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Could not read map item: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let diff = MapDiff::between(&old, &new);
+    if diff.is_empty() {
+        println!("Maps are identical");
+    } else {
+        println!(
+            "Changed pixels: {} / {} ({:.1} %)",
+            diff.changed_pixels,
+            diff.total_pixels,
+            diff.changed_percentage()
+        );
+        if !diff.changed_fields.is_empty() {
+            let mut table = Table::new();
+            table.load_preset(presets::NOTHING);
+            table.set_style(TableComponent::HeaderLines, '╌');
+            table.set_style(TableComponent::VerticalLines, ' ');
+            table.set_header(vec!["Field", "Old", "New"]);
+            for change in &diff.changed_fields {
+                table.add_row(vec![change.name, &change.old, &change.new]);
+            }
+            println!("{table}");
+        }
+    }
+
+    if let Some(output_file) = &args.output_file {
+        let image = match diff_image(&old, &new, &generate_palette(&BASE_COLORS_2699)) {
+            Ok(image) => image,
+            Err(err) => {
+                error!("Could not create image: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+        match sink().write_with(output_file, |file| image.save(file)) {
+            Ok(_) => info!("Image written to: {output_file:?}"),
+            Err(err) => {
+                error!("Could not write image: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,63 @@
+use clap::{Args, ValueEnum};
+use log::error;
+use minecraft_map_tool::nbt::{dump, read_nbt, select, to_snbt};
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct DumpNbtArgs {
+    /// Gzip compressed NBT file, for example map_0.dat or level.dat
+    file: PathBuf,
+
+    /// Show only the node at this path, for example data.banners or data.banners.0.Pos
+    #[arg(short, long, default_value = "")]
+    path: String,
+
+    /// Show at most this many values from byte, int and long arrays in the tree
+    #[arg(long, default_value_t = 16)]
+    max_array: usize,
+
+    /// Output format
+    #[arg(short, long, default_value = "tree")]
+    format: DumpFormat,
+}
+
+/// Output formats for the NBT data
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum DumpFormat {
+    /// Indented tree with the types of the values
+    Tree,
+
+    /// Stringified NBT that can be pasted into Minecraft commands
+    Snbt,
+}
+
+pub fn run(args: &DumpNbtArgs) -> ExitCode {
+    let root = match File::open(&args.file)
+        .map_err(|err| err.to_string())
+        .and_then(|file| read_nbt(file).map_err(|err| err.to_string()))
+    {
+        Ok(root) => root,
+        Err(err) => {
+            error!("Could not read NBT file: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let node = match select(&root, &args.path) {
+        Ok(node) => node,
+        Err(err) => {
+            error!("{err}: {}", args.path);
+            return ExitCode::FAILURE;
+        }
+    };
+    let name = match args.path.rsplit('.').next() {
+        Some(name) if !name.is_empty() => name,
+        _ => "(root)",
+    };
+    match args.format {
+        DumpFormat::Tree => print!("{}", dump(node, name, args.max_array)),
+        DumpFormat::Snbt => println!("{}", to_snbt(node)),
+    }
+    ExitCode::SUCCESS
+}
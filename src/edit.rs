@@ -0,0 +1,305 @@
+//! Editing the contents of map items
+//!
+//! Most edits change the [MapData] in place, so several edits can be applied before the map
+//! file is written back. [rescale] makes a new map instead.
+
+use crate::error::{Error, Result};
+use crate::stitch::Area;
+use crate::transform::MapTransform;
+use crate::{Banner, MapData, Pos};
+use fastnbt::ByteArray;
+
+/// Marks all the pixels of the map unexplored
+pub fn clear_colors(map: &mut MapData) {
+    map.colors.iter_mut().for_each(|color| *color = 0);
+}
+
+/// Marks the pixels covering any block of the *area* unexplored
+///
+/// Banners inside the area are removed too, so their names do not give the place away.
+/// Returns the number of pixels that were cleared.
+pub fn clear_area(map: &mut MapData, area: &Area) -> usize {
+    let transform = MapTransform::of_map(map);
+    let mut cleared = 0;
+    for (index, color) in map.colors.iter_mut().enumerate() {
+        let pixel = transform.pixel_area((index % 128) as i32, (index / 128) as i32);
+        if *color != 0 && pixel.overlaps(area) {
+            *color = 0;
+            cleared += 1;
+        }
+    }
+    map.banners.retain(|banner| !contains(area, &banner.pos));
+    cleared
+}
+
+/// Moves the center of the map, keeping the pixels at their places in the world
+///
+/// The center is rounded so that the pixels stay aligned with the old ones. Pixels moved
+/// outside the map are dropped, uncovered pixels are unexplored, and banners and frame
+/// markers outside the map are removed.
+pub fn recenter(map: &mut MapData, x_center: i32, z_center: i32) {
+    let step = 2i32.pow(map.scale as u32);
+    let shift_x = (x_center - map.x_center) as f64 / step as f64;
+    let shift_z = (z_center - map.z_center) as f64 / step as f64;
+    let (shift_x, shift_z) = (shift_x.round() as i32, shift_z.round() as i32);
+
+    let old_colors = map.colors.to_vec();
+    for (index, color) in map.colors.iter_mut().enumerate() {
+        let old_x = (index % 128) as i32 + shift_x;
+        let old_z = (index / 128) as i32 + shift_z;
+        *color = if (0..128).contains(&old_x) && (0..128).contains(&old_z) {
+            old_colors[(old_z * 128 + old_x) as usize]
+        } else {
+            0
+        };
+    }
+    map.x_center += shift_x * step;
+    map.z_center += shift_z * step;
+
+    let area = Area::of_map(map);
+    map.banners.retain(|banner| contains(&area, &banner.pos));
+    map.frames.retain(|frame| contains(&area, &frame.pos));
+}
+
+/// Keeps only the pixels covering the *area* and centers the map on it
+///
+/// The area must fit in the map at its scale. Banners and frame markers outside the area
+/// are removed.
+pub fn crop(map: &mut MapData, area: &Area) -> Result<()> {
+    let size = 128 * 2i32.pow(map.scale as u32);
+    if area.width() > size as u32 || area.height() > size as u32 {
+        return Err(Error::map_item_error("Crop area is larger than the map"));
+    }
+    let transform = MapTransform::of_map(map);
+    for (index, color) in map.colors.iter_mut().enumerate() {
+        let pixel = transform.pixel_area((index % 128) as i32, (index / 128) as i32);
+        if !pixel.overlaps(area) {
+            *color = 0;
+        }
+    }
+    map.banners.retain(|banner| contains(area, &banner.pos));
+    map.frames.retain(|frame| contains(area, &frame.pos));
+    recenter(
+        map,
+        (area.left + area.right + 1).div_euclid(2),
+        (area.top + area.bottom + 1).div_euclid(2),
+    );
+    Ok(())
+}
+
+/// Center coordinate of the map with the *scale* that the game makes at the *coordinate*
+///
+/// The game aligns the maps to a grid, so the maps of the same scale never overlap.
+pub fn aligned_center(coordinate: i32, scale: i8) -> i32 {
+    let size = 128 * 2i32.pow(scale as u32);
+    (coordinate + 64).div_euclid(size) * size + size / 2 - 64
+}
+
+/// Makes a map with a larger *scale* that contains the area of the *map*
+///
+/// The new map is aligned like the game does, and each of its pixels gets the most common
+/// explored color of the pixels it covers in the *map*. Pixels outside the *map* are
+/// unexplored.
+pub fn rescale(map: &MapData, scale: i8) -> Result<MapData> {
+    if !(0..=4).contains(&scale) || scale <= map.scale {
+        return Err(Error::map_item_error(
+            "New scale must be larger than the scale of the map and at most 4",
+        ));
+    }
+    let x_center = aligned_center(map.x_center, scale);
+    let z_center = aligned_center(map.z_center, scale);
+    let mut votes = ColorVotes::new(scale, x_center, z_center);
+    votes.add(map);
+    let mut rescaled = votes.into_map(&map.dimension);
+    rescaled.tracking_position = map.tracking_position;
+    rescaled.unlimited_tracking = map.unlimited_tracking;
+    rescaled.locked = map.locked;
+    Ok(rescaled)
+}
+
+/// Makes a new map with the *scale* and center from the more detailed *maps*
+///
+/// This works like zooming out a map in a cartography table, but the new map is filled
+/// with the exploration of the *maps* covering its area. Each pixel gets the most common
+/// explored color of the blocks it covers, so detailed maps and maps with the same scale
+/// are all used. Maps with a larger scale or from another dimension than the first map are
+/// skipped. The center is aligned like the game does.
+pub fn synthesize<'a, I>(maps: I, scale: i8, x_center: i32, z_center: i32) -> Result<MapData>
+where
+    I: IntoIterator<Item = &'a MapData>,
+{
+    if !(0..=4).contains(&scale) {
+        return Err(Error::map_item_error("Scale must be from 0 to 4"));
+    }
+    let mut votes = ColorVotes::new(
+        scale,
+        aligned_center(x_center, scale),
+        aligned_center(z_center, scale),
+    );
+    let mut dimension = None;
+    for map in maps {
+        if map.scale > scale || dimension.is_some_and(|dimension| dimension != &map.dimension) {
+            continue;
+        }
+        dimension = Some(&map.dimension);
+        votes.add(map);
+    }
+    let dimension = dimension.ok_or_else(|| Error::map_item_error("No maps to sample"))?;
+    Ok(votes.into_map(dimension))
+}
+
+/// Colors of the source map pixels collected for the pixels of a new map
+struct ColorVotes {
+    scale: i8,
+    area: Area,
+    banners: Vec<Banner>,
+    /// Colors with the number of blocks having them for each pixel
+    votes: Vec<Vec<(i8, u32)>>,
+}
+
+impl ColorVotes {
+    fn new(scale: i8, x_center: i32, z_center: i32) -> ColorVotes {
+        let half_width = 64 * 2i32.pow(scale as u32);
+        ColorVotes {
+            scale,
+            area: Area {
+                left: x_center - half_width,
+                top: z_center - half_width,
+                right: x_center + half_width - 1,
+                bottom: z_center + half_width - 1,
+            },
+            banners: vec![],
+            votes: vec![Vec::new(); 128 * 128],
+        }
+    }
+
+    /// Adds the explored pixels and the banners of the *map*
+    fn add(&mut self, map: &MapData) {
+        let source = MapTransform::of_map(map);
+        let target = MapTransform::of_area(&self.area, self.scale);
+        let weight = (source.block_size() * source.block_size()) as u32;
+        for (index, &color) in map.colors.iter().enumerate() {
+            // Colors 0 to 3 are transparent, which means unexplored
+            if (color as u8) < 4 {
+                continue;
+            }
+            let (x, z) = source.world_at((index % 128) as i32, (index / 128) as i32);
+            let (pixel_x, pixel_z) = target.pixel_at(x, z);
+            if !(0..128).contains(&pixel_x) || !(0..128).contains(&pixel_z) {
+                continue;
+            }
+            let votes = &mut self.votes[(pixel_z * 128 + pixel_x) as usize];
+            match votes.iter_mut().find(|(voted, _)| *voted == color) {
+                Some((_, count)) => *count += weight,
+                None => votes.push((color, weight)),
+            }
+        }
+        for banner in &map.banners {
+            if contains(&self.area, &banner.pos) && !self.banners.contains(banner) {
+                self.banners.push(banner.clone());
+            }
+        }
+    }
+
+    /// Makes the map with the most common color for each pixel
+    fn into_map(self, dimension: &str) -> MapData {
+        let colors = self
+            .votes
+            .iter()
+            .map(|votes| {
+                // The first color wins ties, so the result does not depend on sorting
+                votes
+                    .iter()
+                    .fold(
+                        (0, 0),
+                        |best, &vote| if vote.1 > best.1 { vote } else { best },
+                    )
+                    .0
+            })
+            .collect();
+        MapData {
+            scale: self.scale,
+            dimension: dimension.to_string(),
+            tracking_position: 1,
+            unlimited_tracking: 0,
+            locked: 0,
+            x_center: self.area.left + 64 * 2i32.pow(self.scale as u32),
+            z_center: self.area.top + 64 * 2i32.pow(self.scale as u32),
+            banners: self.banners,
+            frames: vec![],
+            colors: ByteArray::new(colors),
+        }
+    }
+}
+
+fn contains(area: &Area, pos: &Pos) -> bool {
+    (area.left..=area.right).contains(&pos.x) && (area.top..=area.bottom).contains(&pos.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit::{clear_area, crop, rescale, synthesize};
+    use crate::stitch::parse_area;
+    use crate::synthetic::synthetic_maps;
+    use std::path::Path;
+
+    #[test]
+    fn test_clear_area() {
+        let mut maps = synthetic_maps(Path::new("world"));
+        // Map 0 covers -64..=63 in both axes and has a banner at 1, 1
+        let map = &mut maps[0].data;
+        let area = parse_area("-10,-10,9,4").unwrap();
+        assert_eq!(clear_area(map, &area), 20 * 15);
+        assert!(map.banners.is_empty());
+        assert_eq!(map.colors[128 * 68 + 73], 0);
+        assert_ne!(map.colors[128 * 68 + 74], 0);
+        assert_ne!(map.colors[128 * 69 + 73], 0);
+        assert!(parse_area("1,2,0,3").is_err());
+    }
+
+    #[test]
+    fn test_crop() {
+        let mut maps = synthetic_maps(Path::new("world"));
+        let map = &mut maps[0].data;
+        let color = map.colors[128 * 64 + 64];
+        crop(map, &parse_area("0,0,63,63").unwrap()).unwrap();
+        assert_eq!((map.x_center, map.z_center), (32, 32));
+        // The pixel at 0, 0 moved from the center to the upper left quarter
+        assert_eq!(map.colors[128 * 32 + 32], color);
+        assert_eq!(map.colors[128 * 32 + 31], 0);
+        assert_ne!(map.colors[128 * 95 + 95], 0);
+        assert_eq!(map.colors[128 * 96 + 95], 0);
+        assert_eq!(map.banners.len(), 1);
+        assert!(crop(map, &parse_area("0,0,128,10").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_rescale() {
+        let maps = synthetic_maps(Path::new("world"));
+        // Map 5 at column 1 and row 1 of the detailed maps is the lower right quarter of map
+        // 16, and it has unexplored rows at the bottom
+        let rescaled = rescale(&maps[5].data, 1).unwrap();
+        assert_eq!(rescaled.x_center, maps[16].data.x_center);
+        assert_eq!(rescaled.z_center, maps[16].data.z_center);
+        assert_eq!(rescaled.colors[0], 0);
+        assert_eq!(rescaled.colors[128 * 64 + 64], maps[5].data.colors[0]);
+        assert_eq!(rescaled.colors[128 * 127 + 127], 0);
+        assert!(rescale(&rescaled, 1).is_err());
+    }
+
+    #[test]
+    fn test_synthesize() {
+        let maps = synthetic_maps(Path::new("world"));
+        let data: Vec<_> = maps.iter().map(|map| &map.data).collect();
+        // The detailed maps 0, 1, 4 and 5 cover map 16, and 2, 3, 6 and 7 cover map 17
+        let synthesized = synthesize(data.iter().copied(), 1, 100, 100).unwrap();
+        assert_eq!(synthesized.x_center, maps[16].data.x_center);
+        assert_eq!(synthesized.colors[0], maps[0].data.colors[0]);
+        assert_eq!(synthesized.colors[128 * 64 + 64], maps[5].data.colors[0]);
+        // Map 5 has unexplored rows at the bottom, but map 16 covers them
+        assert_eq!(
+            synthesized.colors[128 * 127 + 127],
+            maps[16].data.colors[128 * 127 + 127]
+        );
+    }
+}
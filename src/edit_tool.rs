@@ -0,0 +1,85 @@
+use crate::changes::sink;
+use clap::{ArgGroup, Args};
+use log::{error, info};
+use minecraft_map_tool::edit::{clear_area, clear_colors, crop};
+use minecraft_map_tool::stitch::{parse_area, Area};
+use minecraft_map_tool::MapItem;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("edits").required(true).multiple(true)))]
+pub struct EditArgs {
+    /// The map file to edit
+    map_file: PathBuf,
+
+    /// Write the edited map to this file instead of the original map file
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    /// Mark the whole map unexplored
+    #[arg(long, group = "edits")]
+    clear_colors: bool,
+
+    /// Mark the pixels in the area unexplored and remove the banners in it. The area is given
+    /// in world coordinates as LEFT,TOP,RIGHT,BOTTOM and can be repeated.
+    #[arg(
+        long,
+        group = "edits",
+        value_name = "AREA",
+        value_parser = parse_area,
+        allow_hyphen_values = true
+    )]
+    clear_area: Vec<Area>,
+
+    /// Keep only the area given in world coordinates as LEFT,TOP,RIGHT,BOTTOM and center the
+    /// map on it. The area must fit in the map at its scale.
+    #[arg(
+        long,
+        group = "edits",
+        value_name = "AREA",
+        value_parser = parse_area,
+        allow_hyphen_values = true
+    )]
+    crop: Option<Area>,
+}
+
+pub fn run(args: &EditArgs) -> ExitCode {
+    let mut map_item = match MapItem::read_from(&args.map_file) {
+        Ok(map_item) => map_item,
+        Err(err) => {
+            error!("Could not read map file: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.clear_colors {
+        clear_colors(&mut map_item.data);
+        info!("Cleared all colors");
+    }
+    for area in &args.clear_area {
+        let cleared = clear_area(&mut map_item.data, area);
+        info!(
+            "Cleared {cleared} pixels from {}, {} to {}, {}",
+            area.left, area.top, area.right, area.bottom
+        );
+    }
+
+    if let Some(area) = &args.crop {
+        if let Err(err) = crop(&mut map_item.data, area) {
+            error!("Could not crop the map: {err}");
+            return ExitCode::FAILURE;
+        }
+        info!(
+            "Cropped the map, the new center is {}, {}",
+            map_item.data.x_center, map_item.data.z_center
+        );
+    }
+
+    let output_file = args.output_file.as_ref().unwrap_or(&args.map_file);
+    if let Err(err) = sink().write_with(output_file, |file| map_item.write_to(file)) {
+        error!("Could not write {}: {err}", output_file.display());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -20,6 +20,10 @@ impl std::fmt::Display for Error {
             ErrorKind::ImageError(ref err) => err.fmt(f),
             ErrorKind::IoError(ref err) => err.fmt(f),
             ErrorKind::MapItemError(message) => message.fmt(f),
+            #[cfg(feature = "sftp")]
+            ErrorKind::SshError(ref err) => err.fmt(f),
+            #[cfg(feature = "fs")]
+            ErrorKind::ZipError(ref err) => err.fmt(f),
         }
     }
 }
@@ -42,10 +46,28 @@ impl From<image::ImageError> for Error {
     }
 }
 
+#[cfg(feature = "sftp")]
+impl From<ssh2::Error> for Error {
+    fn from(err: ssh2::Error) -> Self {
+        Error::new(ErrorKind::SshError(err))
+    }
+}
+
+#[cfg(feature = "fs")]
+impl From<zip::result::ZipError> for Error {
+    fn from(err: zip::result::ZipError) -> Self {
+        Error::new(ErrorKind::ZipError(err))
+    }
+}
+
 #[derive(Debug)]
 pub enum ErrorKind {
     FastNbtError(fastnbt::error::Error),
     ImageError(image::ImageError),
     IoError(std::io::Error),
     MapItemError(&'static str),
+    #[cfg(feature = "sftp")]
+    SshError(ssh2::Error),
+    #[cfg(feature = "fs")]
+    ZipError(zip::result::ZipError),
 }
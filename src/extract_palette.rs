@@ -0,0 +1,386 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::palette::BASE_COLORS_2699;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+The map colors are found by scanning the classes of the jar for a static initializer that \
+creates at least 50 instances of its own class with two int arguments, the color id and \
+the RGB color. This works with the obfuscated client jars, so no mappings are needed.")]
+pub struct ExtractPaletteArgs {
+    /// Minecraft client jar, for example .minecraft/versions/1.21/1.21.jar
+    #[arg(long, value_name = "FILE")]
+    jar: PathBuf,
+
+    /// Write the source code to the file instead of the standard output
+    #[arg(short, long, value_name = "FILE")]
+    output_file: Option<PathBuf>,
+}
+
+pub fn run(args: &ExtractPaletteArgs) -> ExitCode {
+    info!("Reading: {}", args.jar.display());
+    let (version, colors) = match read_jar(&args.jar) {
+        Ok(result) => result,
+        Err(err) => {
+            error!("Could not read the jar: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(colors) = colors else {
+        error!("Could not find the map colors in the jar");
+        return ExitCode::FAILURE;
+    };
+    info!(
+        "Found {} map colors of {} (Data version {})",
+        colors.len(),
+        version.name,
+        version.data_version
+    );
+    for (id, rgb) in &colors {
+        let old = BASE_COLORS_2699
+            .get(id)
+            .map(|color| [color[0], color[1], color[2]]);
+        if old.is_some_and(|old| old != *rgb) {
+            info!("Color {id} has changed since data version 2699");
+        } else if old.is_none() && *id != 0 {
+            info!("Color {id} is new since data version 2699");
+        }
+    }
+
+    let code = base_colors_code(&version, &colors);
+    match &args.output_file {
+        None => print!("{code}"),
+        Some(output_file) => match sink().write(output_file, code) {
+            Ok(_) => info!("Source code written to: {output_file:?}"),
+            Err(err) => {
+                error!("Error while writing source code: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+    }
+    ExitCode::SUCCESS
+}
+
+/// Base colors by their ids
+type Colors = BTreeMap<u8, [u8; 3]>;
+
+/// Version of the client jar from its version.json
+struct Version {
+    name: String,
+    data_version: i64,
+}
+
+/// Reads the version and the map colors by their ids from the client *jar*
+fn read_jar(jar: &Path) -> Result<(Version, Option<Colors>), String> {
+    let mut zip = zip::ZipArchive::new(File::open(jar).map_err(|err| err.to_string())?)
+        .map_err(|err| err.to_string())?;
+
+    let mut json = String::new();
+    zip.by_name("version.json")
+        .map_err(|err| format!("Could not find version.json: {err}"))?
+        .read_to_string(&mut json)
+        .map_err(|err| err.to_string())?;
+    let json: serde_json::Value = serde_json::from_str(&json).map_err(|err| err.to_string())?;
+    let version = Version {
+        name: json["name"].as_str().unwrap_or("unknown").to_string(),
+        data_version: json["world_version"]
+            .as_i64()
+            .ok_or("No world_version in version.json")?,
+    };
+
+    // The class creating the most colors wins, other classes may match by accident
+    let mut best: Option<Colors> = None;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|err| err.to_string())?;
+        if !entry.name().ends_with(".class") {
+            continue;
+        }
+        let mut class = Vec::new();
+        entry
+            .read_to_end(&mut class)
+            .map_err(|err| err.to_string())?;
+        let colors = match map_colors(&class) {
+            Ok(colors) => colors,
+            Err(err) => {
+                warn!("Skipping {}: {err}", entry.name());
+                continue;
+            }
+        };
+        if colors.len() >= 50 && best.as_ref().is_none_or(|best| colors.len() > best.len()) {
+            info!("Map colors found in {}", entry.name());
+            best = Some(colors);
+        }
+    }
+    Ok((version, best))
+}
+
+/// Rust source code of the base colors table
+fn base_colors_code(version: &Version, colors: &Colors) -> String {
+    let mut code = format!(
+        "/// Base colors of Minecraft {} (Data version {})\n\
+         ///\n\
+         /// Extracted from the client jar with the extract-palette command.\n\
+         pub const BASE_COLORS_{}: BaseColors = phf_map! {{\n",
+        version.name, version.data_version, version.data_version
+    );
+    // Color 0 is for the unexplored pixels and is always transparent
+    for (id, [r, g, b]) in colors.iter().filter(|(id, _)| **id != 0) {
+        code.push_str(&format!("        {id}u8 => [{r}, {g}, {b}, 255],\n"));
+    }
+    code.push_str("};\n");
+    code
+}
+
+/// Reader for the big-endian values of a class file
+struct ClassReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl ClassReader<'_> {
+    fn read(&mut self, length: usize) -> Result<&[u8], String> {
+        let end = self.position + length;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or("Unexpected end of class file")?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.read(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.read(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// Constant pool entries needed for finding the map colors
+#[derive(Clone, Debug, Default)]
+enum Constant {
+    #[default]
+    Other,
+    Utf8(String),
+    Integer(i32),
+    MethodRef(u16, u16),
+    NameAndType(u16, u16),
+}
+
+/// Finds the colors created in the static initializer of the *class* file
+///
+/// Colors are the instances of the class itself created with a constructor taking two ints,
+/// which are the color id and the RGB color.
+fn map_colors(class: &[u8]) -> Result<Colors, String> {
+    let mut reader = ClassReader {
+        bytes: class,
+        position: 0,
+    };
+    if reader.u32()? != 0xcafe_babe {
+        return Err(String::from("Not a class file"));
+    }
+    reader.read(4)?; // Version
+
+    // Constant pool indexes start from 1, and longs and doubles take two entries
+    let count = reader.u16()? as usize;
+    let mut constants = vec![Constant::Other; count];
+    let mut index = 1;
+    while index < count {
+        let tag = reader.u8()?;
+        constants[index] = match tag {
+            1 => {
+                let length = reader.u16()? as usize;
+                Constant::Utf8(String::from_utf8_lossy(reader.read(length)?).to_string())
+            }
+            3 => Constant::Integer(reader.u32()? as i32),
+            10 => Constant::MethodRef(reader.u16()?, reader.u16()?),
+            12 => Constant::NameAndType(reader.u16()?, reader.u16()?),
+            5 | 6 => {
+                reader.read(8)?;
+                index += 1;
+                Constant::Other
+            }
+            _ => {
+                let length = match tag {
+                    7 | 8 | 16 | 19 | 20 => 2,
+                    15 => 3,
+                    4 | 9 | 11 | 17 | 18 => 4,
+                    _ => return Err(format!("Unknown constant pool tag {tag}")),
+                };
+                reader.read(length)?;
+                Constant::Other
+            }
+        };
+        index += 1;
+    }
+    let utf8 = |index: u16| match constants.get(index as usize) {
+        Some(Constant::Utf8(text)) => text.as_str(),
+        _ => "",
+    };
+
+    reader.read(2)?; // Access flags
+    let this_class = reader.u16()?;
+    reader.read(2)?; // Super class
+    let interfaces = reader.u16()? as usize;
+    reader.read(interfaces * 2)?;
+    let fields = reader.u16()?;
+    for _ in 0..fields {
+        reader.read(6)?;
+        skip_attributes(&mut reader)?;
+    }
+
+    let methods = reader.u16()?;
+    for _ in 0..methods {
+        reader.read(2)?; // Access flags
+        let name = utf8(reader.u16()?);
+        reader.read(2)?; // Descriptor
+        let attributes = reader.u16()?;
+        for _ in 0..attributes {
+            let attribute_name = utf8(reader.u16()?);
+            let length = reader.u32()? as usize;
+            let attribute = reader.read(length)?;
+            if name == "<clinit>" && attribute_name == "Code" {
+                // Max stack, max locals and the code length come before the code
+                let code_length = u32::from_be_bytes(
+                    attribute
+                        .get(4..8)
+                        .ok_or("Code attribute is too short")?
+                        .try_into()
+                        .expect("four bytes"),
+                ) as usize;
+                let code = attribute
+                    .get(8..8 + code_length)
+                    .ok_or("Code attribute is too short")?;
+                return scan_colors(code, &constants, this_class);
+            }
+        }
+    }
+    Ok(BTreeMap::new())
+}
+
+/// Skips the attributes of a field or a method
+fn skip_attributes(reader: &mut ClassReader) -> Result<(), String> {
+    let attributes = reader.u16()?;
+    for _ in 0..attributes {
+        reader.read(2)?;
+        let length = reader.u32()? as usize;
+        reader.read(length)?;
+    }
+    Ok(())
+}
+
+/// Collects the (id, color) pairs of the `new ThisClass(id, color)` calls in the *code*
+fn scan_colors(code: &[u8], constants: &[Constant], this_class: u16) -> Result<Colors, String> {
+    let index_at = |pc: usize| u16::from_be_bytes([code[pc + 1], code[pc + 2]]);
+    let is_constructor = |method: u16| {
+        let Some(Constant::MethodRef(class, name_and_type)) = constants.get(method as usize) else {
+            return false;
+        };
+        let Some(Constant::NameAndType(name, descriptor)) = constants.get(*name_and_type as usize)
+        else {
+            return false;
+        };
+        let utf8 = |index: u16| match constants.get(index as usize) {
+            Some(Constant::Utf8(text)) => text.as_str(),
+            _ => "",
+        };
+        *class == this_class && utf8(*name) == "<init>" && utf8(*descriptor) == "(II)V"
+    };
+    let integer = |index: u16| match constants.get(index as usize) {
+        Some(Constant::Integer(value)) => Some(*value),
+        _ => None,
+    };
+
+    let mut colors = BTreeMap::new();
+    // Integers pushed after the latest `new` of this class
+    let mut arguments: Option<Vec<i32>> = None;
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let length = instruction_length(code, pc).ok_or("Unknown instruction")?;
+        if pc + length > code.len() {
+            return Err(String::from("Truncated instruction"));
+        }
+        let value = match opcode {
+            0x02..=0x08 => Some(opcode as i32 - 0x03), // iconst_m1 to iconst_5
+            0x10 => Some(code[pc + 1] as i8 as i32),   // bipush
+            0x11 => Some(index_at(pc) as i16 as i32),  // sipush
+            0x12 => integer(code[pc + 1] as u16),      // ldc
+            0x13 => integer(index_at(pc)),             // ldc_w
+            _ => None,
+        };
+        match opcode {
+            // new
+            0xbb => {
+                arguments = (index_at(pc) == this_class).then(Vec::new);
+            }
+            // invokespecial
+            0xb7 if is_constructor(index_at(pc)) => {
+                if let Some(&[id, color]) = arguments.as_deref().and_then(|a| a.last_chunk()) {
+                    if let Ok(id) = u8::try_from(id) {
+                        let [_, r, g, b] = color.to_be_bytes();
+                        colors.insert(id, [r, g, b]);
+                    }
+                }
+                arguments = None;
+            }
+            _ => {
+                if let (Some(arguments), Some(value)) = (arguments.as_mut(), value) {
+                    arguments.push(value);
+                }
+            }
+        }
+        pc += length;
+    }
+    Ok(colors)
+}
+
+/// Length of the JVM instruction at the *pc* in bytes, None for unknown opcodes
+fn instruction_length(code: &[u8], pc: usize) -> Option<usize> {
+    let int_at = |position: usize| -> Option<i32> {
+        Some(i32::from_be_bytes(
+            code.get(position..position + 4)?.try_into().ok()?,
+        ))
+    };
+    let length = match code[pc] {
+        0x00..=0x0f | 0x1a..=0x35 | 0x3b..=0x83 | 0x85..=0x98 | 0xac..=0xb1 => 1,
+        0xbe | 0xbf | 0xc2 | 0xc3 => 1,
+        0x10 | 0x12 | 0x15..=0x19 | 0x36..=0x3a | 0xa9 | 0xbc => 2,
+        0x11 | 0x13 | 0x14 | 0x84 | 0x99..=0xa8 | 0xb2..=0xb8 => 3,
+        0xbb | 0xbd | 0xc0 | 0xc1 | 0xc6 | 0xc7 => 3,
+        0xc5 => 4,
+        0xb9 | 0xba | 0xc8 | 0xc9 => 5,
+        // wide
+        0xc4 => match code.get(pc + 1)? {
+            0x84 => 6,
+            _ => 4,
+        },
+        // tableswitch and lookupswitch are aligned to four bytes
+        0xaa => {
+            let start = (pc + 4) & !3;
+            let low = int_at(start + 4)?;
+            let high = int_at(start + 8)?;
+            start + 12 + (high - low + 1) as usize * 4 - pc
+        }
+        0xab => {
+            let start = (pc + 4) & !3;
+            let pairs = int_at(start + 4)?;
+            start + 8 + pairs as usize * 8 - pc
+        }
+        _ => return None,
+    };
+    Some(length)
+}
@@ -0,0 +1,248 @@
+//! C API for using the library from other languages
+//!
+//! The functions here let plugins written in other languages read map files and render them
+//! without running the command line tool. Maps are handled through opaque pointers that
+//! must be released with [mmt_free]. Functions that fail return a null pointer or a
+//! negative value, and the reason can be read with [mmt_last_error].
+//!
+//! The API is enabled with the `ffi` feature. A shared library is built with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`, and the declarations
+//! for C are in `include/minecraft_map_tool.h`. Panics are caught at the boundary and
+//! reported like other failures, because unwinding into C is undefined behavior.
+
+use crate::palette::{generate_palette, BASE_COLORS_2699};
+use crate::MapItem;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+/// Number of bytes in an RGBA image of a map
+pub const MMT_RGBA_SIZE: usize = 128 * 128 * 4;
+
+/// Map item handle given to the C side
+pub struct MmtMap(MapItem);
+
+/// Basic information about a map
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MmtMapInfo {
+    /// How zoomed out the map is, from 0 to 4
+    pub scale: i8,
+
+    /// 1 if the map is locked in a cartography table
+    pub locked: i8,
+
+    /// Center of the map by X
+    pub x_center: i32,
+
+    /// Center of the map by Z
+    pub z_center: i32,
+
+    /// Data version of the Minecraft version that saved the map
+    pub data_version: i32,
+
+    /// Number of banner markers
+    pub banner_count: u32,
+}
+
+thread_local! {
+    /// Error message of the last failed call in this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Messages with NUL bytes cannot be C strings, so the bytes are dropped
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs the *call*, returning *failed* if it panics
+fn guard<T>(failed: T, call: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown reason");
+        set_last_error(format!("Library panicked: {message}"));
+        failed
+    })
+}
+
+/// Returns the error message of the last failed call in this thread, or null
+///
+/// The string is owned by the library and is valid until the next failing call.
+#[no_mangle]
+pub extern "C" fn mmt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Reads the map file at the UTF-8 *path*, returns null if the file could not be read
+///
+/// # Safety
+///
+/// The *path* must be null or a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn mmt_read_map(path: *const c_char) -> *mut MmtMap {
+    guard(ptr::null_mut(), || read_map(path))
+}
+
+unsafe fn read_map(path: *const c_char) -> *mut MmtMap {
+    if path.is_null() {
+        set_last_error(String::from("Path is null"));
+        return ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(err) => {
+            set_last_error(format!("Path is not UTF-8: {err}"));
+            return ptr::null_mut();
+        }
+    };
+    match MapItem::read_from(Path::new(path)) {
+        Ok(map_item) => Box::into_raw(Box::new(MmtMap(map_item))),
+        Err(err) => {
+            set_last_error(format!("Could not read map file: {err}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases the *map* read with [mmt_read_map], null is ignored
+///
+/// # Safety
+///
+/// The *map* must be null or a pointer from [mmt_read_map] that is not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn mmt_free(map: *mut MmtMap) {
+    if !map.is_null() {
+        guard((), || drop(Box::from_raw(map)));
+    }
+}
+
+/// Fills the *info* of the *map*, returns 0 on success and -1 on failure
+///
+/// # Safety
+///
+/// The *map* must be null or a valid map pointer, and the *info* must be null or point to
+/// a writable [MmtMapInfo].
+#[no_mangle]
+pub unsafe extern "C" fn mmt_map_info(map: *const MmtMap, info: *mut MmtMapInfo) -> i32 {
+    guard(-1, || map_info(map, info))
+}
+
+unsafe fn map_info(map: *const MmtMap, info: *mut MmtMapInfo) -> i32 {
+    let (Some(MmtMap(map_item)), Some(info)) = (map.as_ref(), info.as_mut()) else {
+        set_last_error(String::from("Map or info is null"));
+        return -1;
+    };
+    *info = MmtMapInfo {
+        scale: map_item.data.scale,
+        locked: map_item.data.locked,
+        x_center: map_item.data.x_center,
+        z_center: map_item.data.z_center,
+        data_version: map_item.data_version,
+        banner_count: map_item.data.banners.len() as u32,
+    };
+    0
+}
+
+/// Renders the *map* as 128×128 RGBA pixels to the *buffer* of *length* bytes
+///
+/// The buffer must have room for [MMT_RGBA_SIZE] bytes. Rows are written from top to
+/// bottom, and unexplored pixels are transparent. Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// The *map* must be null or a valid map pointer, and the *buffer* must be null or point to
+/// *length* writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mmt_render_rgba(
+    map: *const MmtMap,
+    buffer: *mut u8,
+    length: usize,
+) -> i32 {
+    guard(-1, || render_rgba(map, buffer, length))
+}
+
+unsafe fn render_rgba(map: *const MmtMap, buffer: *mut u8, length: usize) -> i32 {
+    let Some(MmtMap(map_item)) = map.as_ref() else {
+        set_last_error(String::from("Map is null"));
+        return -1;
+    };
+    if buffer.is_null() || length < MMT_RGBA_SIZE {
+        set_last_error(format!("Buffer must have room for {MMT_RGBA_SIZE} bytes"));
+        return -1;
+    }
+    let buffer = std::slice::from_raw_parts_mut(buffer, MMT_RGBA_SIZE);
+    match map_item
+        .data
+        .render_into(buffer, &generate_palette(&BASE_COLORS_2699))
+    {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(format!("Could not render map: {err}"));
+            -1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::{
+        guard, mmt_free, mmt_last_error, mmt_map_info, mmt_read_map, mmt_render_rgba, MmtMapInfo,
+        MMT_RGBA_SIZE,
+    };
+    use crate::synthetic::synthetic_maps;
+    use std::ffi::{CStr, CString};
+    use std::path::Path;
+
+    #[test]
+    fn test_ffi() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("map_0.dat");
+        let map_item = &synthetic_maps(Path::new("world"))[0];
+        map_item.write_to(&file).unwrap();
+        let path = CString::new(file.to_str().unwrap()).unwrap();
+        let missing = CString::new("missing.dat").unwrap();
+        unsafe {
+            assert!(mmt_read_map(missing.as_ptr()).is_null());
+            assert!(!mmt_last_error().is_null());
+
+            let map = mmt_read_map(path.as_ptr());
+            assert!(!map.is_null());
+            let mut info = MmtMapInfo::default();
+            assert_eq!(mmt_map_info(map, &mut info), 0);
+            assert_eq!(info.x_center, map_item.data.x_center);
+            let mut buffer = vec![0u8; MMT_RGBA_SIZE];
+            assert_eq!(mmt_render_rgba(map, buffer.as_mut_ptr(), 16), -1);
+            assert_eq!(mmt_render_rgba(map, buffer.as_mut_ptr(), buffer.len()), 0);
+            assert_eq!(buffer[3], 255);
+            mmt_free(map);
+        }
+
+        assert_eq!(guard(-1, || panic!("broken map")), -1);
+        let message = unsafe { CStr::from_ptr(mmt_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "Library panicked: broken map");
+
+        // Every function is declared in the C header
+        let header = include_str!("../include/minecraft_map_tool.h");
+        for function in [
+            "mmt_last_error(",
+            "mmt_read_map(",
+            "mmt_free(",
+            "mmt_map_info(",
+            "mmt_render_rgba(",
+        ] {
+            assert!(
+                header.contains(function),
+                "{function} missing from the header"
+            );
+        }
+    }
+}
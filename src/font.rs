@@ -0,0 +1,110 @@
+//! Built-in bitmap font
+//!
+//! Images made by the library, like contact sheets and legends, have short captions. The
+//! font has 3×5 pixel glyphs for digits, upper case letters and a few punctuation marks, so
+//! the captions can be drawn without font files.
+
+use image::{Rgba, RgbaImage};
+
+/// Width of the font glyphs in font pixels
+pub const GLYPH_WIDTH: u32 = 3;
+
+/// Height of the font glyphs in font pixels
+pub const GLYPH_HEIGHT: u32 = 5;
+
+/// Width of the *text* in pixels when drawn with the *scale*
+pub fn text_width(text: &str, scale: u32) -> u32 {
+    let count = text.chars().count() as u32;
+    (count * (GLYPH_WIDTH + 1)).saturating_sub(1) * scale
+}
+
+/// Draws the *text* to the *image* at *x*, *y* in the *color*
+///
+/// Each font pixel is *scale* image pixels wide. Letters are drawn in upper case, characters
+/// missing from the font as question marks, and the text is cut at *max_width* pixels.
+pub fn draw_text(
+    image: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    max_width: u32,
+    scale: u32,
+    text: &str,
+    color: Rgba<u8>,
+) {
+    let advance = (GLYPH_WIDTH + 1) * scale;
+    for (index, character) in text.chars().enumerate() {
+        let glyph_x = x + index as u32 * advance;
+        if glyph_x + GLYPH_WIDTH * scale > x + max_width {
+            break;
+        }
+        let rows = glyph(character.to_ascii_uppercase()).unwrap_or_else(|| glyph('?').unwrap());
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let pixel_x = glyph_x + column * scale + dx;
+                        let pixel_y = y + row as u32 * scale + dy;
+                        if pixel_x < image.width() && pixel_y < image.height() {
+                            image.put_pixel(pixel_x, pixel_y, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Rows of the 3×5 glyph of the *character*, the highest bit is the leftmost pixel
+fn glyph(character: char) -> Option<[u8; 5]> {
+    let rows = match character {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '?' => [0b111, 0b001, 0b011, 0b000, 0b010],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        _ => return None,
+    };
+    Some(rows)
+}
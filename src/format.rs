@@ -0,0 +1,308 @@
+//! Layouts of the map data in different game versions
+//!
+//! [MapItem](crate::MapItem) follows the layout of the versions before 1.20.5, where the
+//! banners and frames have `PascalCase` keys, positions are compounds and banner names are
+//! JSON text. Since 1.20.5 the markers have `snake_case` keys and positions are int arrays,
+//! and since 1.21.5 the banner names are text components stored as NBT, so a plain name is
+//! just a string tag.
+//!
+//! Map files are read into a [Value] first, and the markers are converted to the older
+//! layout with [normalize_markers] before the map item is made. Writing converts them back
+//! to the layout of the data version with [versioned_markers].
+
+use fastnbt::{IntArray, Value};
+use std::collections::HashMap;
+
+/// First data version with `snake_case` marker keys and int array positions (1.20.5)
+pub const SNAKE_CASE_MARKERS_VERSION: i32 = 3837;
+
+/// First data version storing banner names as NBT text components (1.21.5)
+pub const NBT_TEXT_VERSION: i32 = 4325;
+
+/// Marker keys in the older and the newer layouts
+const MARKER_KEYS: [(&str, &str); 5] = [
+    ("Color", "color"),
+    ("Name", "name"),
+    ("Pos", "pos"),
+    ("EntityId", "entity_id"),
+    ("Rotation", "rotation"),
+];
+
+/// Converts the banners and frames in the map item *root* to the older layout
+///
+/// Both layouts are accepted with any data version, so files written by tools that do not
+/// follow the game exactly are read too. Only string names depend on the *data_version*:
+/// newer versions store plain text in them, older versions JSON text.
+pub fn normalize_markers(root: &mut Value, data_version: i32) {
+    for marker in markers_mut(root) {
+        let Value::Compound(marker) = marker else {
+            continue;
+        };
+        for (old, new) in MARKER_KEYS {
+            if let Some(value) = marker.remove(new) {
+                marker.entry(old.to_string()).or_insert(value);
+            }
+        }
+        if let Some(Value::IntArray(pos)) = marker.get("Pos") {
+            if let [x, y, z] = pos.iter().as_slice() {
+                let pos = HashMap::from([
+                    (String::from("X"), Value::Int(*x)),
+                    (String::from("Y"), Value::Int(*y)),
+                    (String::from("Z"), Value::Int(*z)),
+                ]);
+                marker.insert(String::from("Pos"), Value::Compound(pos));
+            }
+        }
+        let name = match marker.remove("Name") {
+            Some(Value::String(text)) if data_version < NBT_TEXT_VERSION => Some(text),
+            // Plain text that would be read as a component is stored as a JSON string
+            Some(Value::String(text)) => match serde_json::from_str(&text) {
+                Ok(serde_json::Value::String(_) | serde_json::Value::Array(_))
+                | Ok(serde_json::Value::Object(_)) => {
+                    Some(serde_json::Value::String(text).to_string())
+                }
+                _ => Some(text),
+            },
+            Some(name) => Some(nbt_to_json(&name).to_string()),
+            None => None,
+        };
+        if let Some(name) = name {
+            marker.insert(String::from("Name"), Value::String(name));
+        }
+    }
+    // Newer versions leave out white, the default color
+    if let Some(Value::List(banners)) = data_mut(root).and_then(|data| data.get_mut("banners")) {
+        for banner in banners {
+            if let Value::Compound(banner) = banner {
+                banner
+                    .entry(String::from("Color"))
+                    .or_insert_with(|| Value::String(String::from("white")));
+            }
+        }
+    }
+}
+
+/// Converts the banners and frames in the map item *root* from the older layout to the
+/// layout of the *data_version*
+pub fn versioned_markers(root: &mut Value, data_version: i32) {
+    if data_version < SNAKE_CASE_MARKERS_VERSION {
+        return;
+    }
+    for marker in markers_mut(root) {
+        let Value::Compound(marker) = marker else {
+            continue;
+        };
+        if let Some(Value::Compound(pos)) = marker.get("Pos") {
+            let coordinate = |key: &str| match pos.get(key) {
+                Some(Value::Int(value)) => *value,
+                _ => 0,
+            };
+            let pos = vec![coordinate("X"), coordinate("Y"), coordinate("Z")];
+            marker.insert(String::from("Pos"), Value::IntArray(IntArray::new(pos)));
+        }
+        if data_version >= NBT_TEXT_VERSION {
+            if let Some(Value::String(json)) = marker.get("Name") {
+                // Names that are not components are kept as they are, like the game shows them
+                let name = match serde_json::from_str(json) {
+                    Ok(serde_json::Value::String(text)) => Value::String(text),
+                    Ok(
+                        component @ (serde_json::Value::Array(_) | serde_json::Value::Object(_)),
+                    ) => json_to_nbt(&component),
+                    _ => Value::String(json.clone()),
+                };
+                marker.insert(String::from("Name"), name);
+            }
+        }
+        for (old, new) in MARKER_KEYS {
+            if let Some(value) = marker.remove(old) {
+                marker.insert(new.to_string(), value);
+            }
+        }
+    }
+}
+
+/// The `data` compound of the map item *root*
+fn data_mut(root: &mut Value) -> Option<&mut HashMap<String, Value>> {
+    match root {
+        Value::Compound(root) => match root.get_mut("data") {
+            Some(Value::Compound(data)) => Some(data),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The banners and frames of the map item *root*
+fn markers_mut(root: &mut Value) -> impl Iterator<Item = &mut Value> {
+    data_mut(root)
+        .into_iter()
+        .flat_map(|data| data.iter_mut())
+        .filter(|(key, _)| *key == "banners" || *key == "frames")
+        .flat_map(|(_, markers)| match markers {
+            Value::List(markers) => markers.iter_mut(),
+            _ => Default::default(),
+        })
+}
+
+/// Text component stored as NBT converted to JSON
+///
+/// The game wraps the elements of mixed lists in compounds with an empty key, those are
+/// unwrapped. Bytes are the booleans of the styles.
+fn nbt_to_json(value: &Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+    match value {
+        Value::String(text) => Json::String(text.clone()),
+        Value::Byte(value) => Json::Bool(*value != 0),
+        Value::Short(value) => Json::from(*value),
+        Value::Int(value) => Json::from(*value),
+        Value::Long(value) => Json::from(*value),
+        Value::Float(value) => Json::from(*value),
+        Value::Double(value) => Json::from(*value),
+        Value::List(values) => Json::Array(values.iter().map(nbt_to_json).collect()),
+        Value::Compound(compound) => match compound.get("") {
+            Some(value) if compound.len() == 1 => nbt_to_json(value),
+            _ => Json::Object(
+                compound
+                    .iter()
+                    .map(|(key, value)| (key.clone(), nbt_to_json(value)))
+                    .collect(),
+            ),
+        },
+        Value::ByteArray(values) => Json::from(values.iter().copied().collect::<Vec<_>>()),
+        Value::IntArray(values) => Json::from(values.iter().copied().collect::<Vec<_>>()),
+        Value::LongArray(values) => Json::from(values.iter().copied().collect::<Vec<_>>()),
+    }
+}
+
+/// Text component in JSON converted to NBT
+///
+/// NBT lists hold one type only, so the elements of mixed lists are wrapped in compounds
+/// with an empty key like the game does.
+fn json_to_nbt(value: &serde_json::Value) -> Value {
+    use serde_json::Value as Json;
+    match value {
+        Json::Null => Value::String(String::new()),
+        Json::Bool(value) => Value::Byte(*value as i8),
+        Json::Number(number) => match number.as_i64() {
+            Some(value) => i32::try_from(value)
+                .map(Value::Int)
+                .unwrap_or(Value::Long(value)),
+            None => Value::Double(number.as_f64().unwrap_or_default()),
+        },
+        Json::String(text) => Value::String(text.clone()),
+        Json::Array(values) => {
+            let values: Vec<Value> = values.iter().map(json_to_nbt).collect();
+            let mixed = values
+                .windows(2)
+                .any(|pair| std::mem::discriminant(&pair[0]) != std::mem::discriminant(&pair[1]));
+            match mixed {
+                true => Value::List(
+                    values
+                        .into_iter()
+                        .map(|value| match value {
+                            Value::Compound(_) => value,
+                            value => Value::Compound(HashMap::from([(String::new(), value)])),
+                        })
+                        .collect(),
+                ),
+                false => Value::List(values),
+            }
+        }
+        Json::Object(object) => Value::Compound(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), json_to_nbt(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{NBT_TEXT_VERSION, SNAKE_CASE_MARKERS_VERSION};
+    use crate::synthetic::synthetic_maps;
+    use crate::{Banner, BannerColor, MapItem, Pos};
+    use fastnbt::{nbt, ByteArray};
+    use std::path::Path;
+
+    fn read(value: &fastnbt::Value) -> MapItem {
+        let mut bytes = Vec::new();
+        crate::compression::write_compressed(
+            &mut bytes,
+            value,
+            crate::compression::NbtCompression::Gzip,
+        )
+        .unwrap();
+        MapItem::read_from_reader(bytes.as_slice(), Path::new("map_0.dat")).unwrap()
+    }
+
+    #[test]
+    fn test_newer_layouts() {
+        let colors = ByteArray::new(vec![0; 128 * 128]);
+        let map = |data_version: i32, banners: fastnbt::Value| {
+            nbt!({
+                "DataVersion": data_version,
+                "data": {
+                    "scale": 0i8,
+                    "dimension": "minecraft:overworld",
+                    "trackingPosition": 1i8,
+                    "unlimitedTracking": 0i8,
+                    "locked": 1i8,
+                    "xCenter": 0,
+                    "zCenter": 0,
+                    "banners": banners,
+                    "frames": [{"entity_id": 7, "rotation": 90, "pos": [I; 1, 2, 3]}],
+                    "colors": colors.clone(),
+                }
+            })
+        };
+
+        // 1.20.5: snake_case keys, int array positions and JSON names
+        let map_item = read(&map(
+            SNAKE_CASE_MARKERS_VERSION,
+            nbt!([{"color": "red", "name": r#"{"text":"Base"}"#, "pos": [I; -5, 64, 10]}]),
+        ));
+        let banner = &map_item.data.banners[0];
+        assert_eq!(banner.color, BannerColor::Red);
+        assert_eq!(banner.extract_name(), "Base");
+        assert_eq!(
+            banner.pos,
+            Pos {
+                x: -5,
+                y: 64,
+                z: 10
+            }
+        );
+        assert_eq!(map_item.data.frames[0].entity_id, 7);
+        assert_eq!(map_item.data.frames[0].pos, Pos { x: 1, y: 2, z: 3 });
+
+        // 1.21.5: names as NBT, plain strings are not JSON, and white is left out
+        let map_item = read(&map(
+            NBT_TEXT_VERSION,
+            nbt!([
+                {"name": r#"{"text":"Not JSON"}"#, "pos": [I; 0, 0, 0]},
+                {"color": "blue", "name": {"text": "Base", "bold": 1i8}, "pos": [I; 1, 0, 0]},
+            ]),
+        ));
+        let banners = &map_item.data.banners;
+        assert_eq!(banners[0].color, BannerColor::White);
+        assert_eq!(banners[0].extract_name(), r#"{"text":"Not JSON"}"#);
+        let name = banners[1].styled_name().unwrap();
+        assert_eq!(name.plain(), "Base");
+        assert!(name.spans[0].style.bold);
+
+        // Written back in the layout of the data version
+        for data_version in [3700, SNAKE_CASE_MARKERS_VERSION, NBT_TEXT_VERSION] {
+            let mut map_item = synthetic_maps(Path::new("world")).remove(0);
+            map_item.data_version = data_version;
+            map_item.data.banners = vec![Banner {
+                color: BannerColor::Lime,
+                name: Some(String::from(r#"["A",{"text":"B","italic":true}]"#)),
+                pos: Pos { x: 1, y: 2, z: 3 },
+            }];
+            let read = read(&map_item.to_nbt().unwrap());
+            assert_eq!(read.data.banners[0].pos, map_item.data.banners[0].pos);
+            assert_eq!(read.data.banners[0].extract_name(), "AB");
+        }
+    }
+}
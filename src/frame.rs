@@ -0,0 +1,134 @@
+//! Item-like presentation for the map images
+//!
+//! In the game, a map is drawn on a parchment background and is often placed in an item frame.
+//! The textures here are drawn procedurally in the same spirit, so no game assets are needed.
+//! The texture size follows the image size, so stitched images get the same look as single maps.
+
+use crate::stitch::paint_image;
+use image::{Rgba, RgbaImage};
+
+/// Parchment color
+const PAPER: [u8; 3] = [224, 208, 168];
+
+/// Darker parchment color for the edges
+const PAPER_EDGE: [u8; 3] = [170, 146, 104];
+
+/// Wood color of the item frame
+const WOOD: [u8; 3] = [150, 108, 62];
+
+/// Darker wood color for the frame edges
+const WOOD_EDGE: [u8; 3] = [92, 64, 36];
+
+/// How the map image is presented
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum FrameStyle {
+    /// Only the map pixels
+    #[default]
+    Plain,
+
+    /// Map pixels on a parchment background, like the map item in hand
+    Paper,
+
+    /// Parchment background in a wooden item frame
+    Framed,
+}
+
+/// Draws the *image* with the given *style*
+///
+/// Unexplored (transparent) pixels show the parchment under them, as in the game.
+pub fn apply_frame(image: &RgbaImage, style: FrameStyle) -> RgbaImage {
+    match style {
+        FrameStyle::Plain => image.clone(),
+        FrameStyle::Paper => paper(image),
+        FrameStyle::Framed => framed(image),
+    }
+}
+
+/// Border width for the *image*, which is 8 pixels for a single 128×128 map
+fn border_width(image: &RgbaImage) -> u32 {
+    (image.width().min(image.height()) / 16).max(2)
+}
+
+/// Deterministic noise in the range from -1.0 to 1.0
+fn noise(x: u32, y: u32) -> f32 {
+    let mut hash = x.wrapping_mul(0x27d4_eb2d) ^ y.wrapping_mul(0x1656_67b1);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    (hash & 0xff) as f32 / 127.5 - 1.0
+}
+
+fn shade(color: [u8; 3], amount: f32) -> Rgba<u8> {
+    let [r, g, b] = color.map(|c| (c as f32 * (1.0 + amount)).clamp(0.0, 255.0) as u8);
+    Rgba([r, g, b, 255])
+}
+
+/// Texture cell is the size of one texture pixel in the output image
+fn texture_pixel(border: u32) -> u32 {
+    (border / 4).max(1)
+}
+
+fn paper(image: &RgbaImage) -> RgbaImage {
+    let border = border_width(image);
+    let cell = texture_pixel(border);
+    let width = image.width() + 2 * border;
+    let height = image.height() + 2 * border;
+    let (cells_x, cells_y) = ((width - 1) / cell, (height - 1) / cell);
+    let mut output = RgbaImage::from_fn(width, height, |x, y| {
+        let (cx, cy) = (x / cell, y / cell);
+        if cx == 0 || cy == 0 || cx == cells_x || cy == cells_y {
+            shade(PAPER_EDGE, noise(cx, cy) * 0.05)
+        } else {
+            shade(PAPER, noise(cx, cy) * 0.04)
+        }
+    });
+    paint_image(image, &mut output, border as i32, border as i32);
+    output
+}
+
+fn framed(image: &RgbaImage) -> RgbaImage {
+    let border = border_width(image);
+    let cell = texture_pixel(border);
+    let inner = paper(image);
+    let width = inner.width() + 2 * border;
+    let height = inner.height() + 2 * border;
+    let mut output = RgbaImage::from_fn(width, height, |x, y| {
+        let (cx, cy) = (x / cell, y / cell);
+        let from_edge = x.min(y).min(width - 1 - x).min(height - 1 - y);
+        if from_edge < cell || from_edge >= border - cell {
+            shade(WOOD_EDGE, noise(cx, cy) * 0.08)
+        } else {
+            // Wood grain runs along the frame side
+            let grain = if x.min(width - 1 - x) < y.min(height - 1 - y) {
+                noise(cx, 0)
+            } else {
+                noise(0, cy)
+            };
+            shade(WOOD, grain * 0.1 + noise(cx, cy) * 0.04)
+        }
+    });
+    paint_image(&inner, &mut output, border as i32, border as i32);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame::{apply_frame, FrameStyle};
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_frame_sizes() {
+        let mut image = RgbaImage::new(128, 128);
+        image.put_pixel(0, 0, Rgba([1, 2, 3, 255]));
+        assert_eq!(apply_frame(&image, FrameStyle::Plain), image);
+        let paper = apply_frame(&image, FrameStyle::Paper);
+        assert_eq!(paper.dimensions(), (144, 144));
+        assert_eq!(paper.get_pixel(8, 8), &Rgba([1, 2, 3, 255]));
+        assert_eq!(paper.get_pixel(9, 9)[3], 255);
+        assert_eq!(
+            apply_frame(&image, FrameStyle::Framed).dimensions(),
+            (160, 160)
+        );
+    }
+}
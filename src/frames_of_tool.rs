@@ -0,0 +1,51 @@
+use crate::charset::preset;
+use clap::Args;
+use comfy_table::{Cell, ContentArrangement, Table};
+use log::{error, info};
+use minecraft_map_tool::item_frames::find_map_frames;
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct FramesOfArgs {
+    /// The world directory whose region files are searched
+    world: PathBuf,
+
+    /// Map ids to look for, for example 212 or 0..10,20,100..
+    #[arg(value_parser = parse_id_ranges)]
+    ids: IdRanges,
+}
+
+pub fn run(args: &FramesOfArgs) -> ExitCode {
+    let frames = match find_map_frames(&args.world, Some(&args.ids)) {
+        Ok(frames) => frames,
+        Err(err) => {
+            error!("Could not search item frames: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if frames.is_empty() {
+        info!("No item frames holding the maps were found");
+        return ExitCode::SUCCESS;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Map", "Dimension", "X", "Y", "Z", "Facing", "Frame"]);
+    for frame in frames {
+        table.add_row(vec![
+            Cell::new(frame.map_id),
+            Cell::new(frame.dimension),
+            Cell::new(frame.x),
+            Cell::new(frame.y),
+            Cell::new(frame.z),
+            Cell::new(frame.facing),
+            Cell::new(if frame.glowing { "Glow" } else { "Normal" }),
+        ]);
+    }
+    println!("{table}");
+    ExitCode::SUCCESS
+}
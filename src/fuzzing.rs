@@ -0,0 +1,169 @@
+//! Arbitrary map data for fuzzing and property tests
+//!
+//! The [Arbitrary] implementations make map items from unstructured bytes, so fuzzers like
+//! cargo-fuzz and property test frameworks can generate them. The maps have a full color
+//! array and mostly vanilla dimensions and scales, but any values the file format can store
+//! are possible, including names that are not valid JSON text.
+
+use crate::compression::{write_compressed, NbtCompression};
+use crate::format::NBT_TEXT_VERSION;
+use crate::{Banner, BannerColor, MapData, MapItem, Marker, Pos};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use fastnbt::ByteArray;
+use std::path::PathBuf;
+
+/// Dimensions the game uses, picked more often than arbitrary names
+const DIMENSIONS: [&str; 3] = [
+    "minecraft:overworld",
+    "minecraft:the_nether",
+    "minecraft:the_end",
+];
+
+/// Banner colors in the order of their dye ids
+const BANNER_COLORS: [BannerColor; 16] = [
+    BannerColor::White,
+    BannerColor::Orange,
+    BannerColor::Magenta,
+    BannerColor::LightBlue,
+    BannerColor::Yellow,
+    BannerColor::Lime,
+    BannerColor::Pink,
+    BannerColor::Gray,
+    BannerColor::LightGray,
+    BannerColor::Cyan,
+    BannerColor::Purple,
+    BannerColor::Blue,
+    BannerColor::Brown,
+    BannerColor::Green,
+    BannerColor::Red,
+    BannerColor::Black,
+];
+
+impl<'a> Arbitrary<'a> for Pos {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Pos {
+            x: u.arbitrary()?,
+            y: u.int_in_range(-64..=319)?,
+            z: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for BannerColor {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&BANNER_COLORS).cloned()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Banner {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Banner {
+            color: u.arbitrary()?,
+            name: u.arbitrary()?,
+            pos: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Marker {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Marker {
+            entity_id: u.arbitrary()?,
+            rotation: u.int_in_range(0..=360)?,
+            pos: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for MapData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let dimension = if u.ratio(1, 8)? {
+            u.arbitrary()?
+        } else {
+            u.choose(&DIMENSIONS)?.to_string()
+        };
+        let scale = if u.ratio(1, 8)? {
+            u.arbitrary()?
+        } else {
+            u.int_in_range(0..=4)?
+        };
+        // Missing bytes become zeros, which are unexplored pixels
+        let mut colors = vec![0; 128 * 128];
+        for color in colors.iter_mut() {
+            if u.is_empty() {
+                break;
+            }
+            *color = u.arbitrary()?;
+        }
+        Ok(MapData {
+            scale,
+            dimension,
+            tracking_position: u.int_in_range(0..=1)?,
+            unlimited_tracking: u.int_in_range(0..=1)?,
+            locked: u.int_in_range(0..=1)?,
+            x_center: u.arbitrary()?,
+            z_center: u.arbitrary()?,
+            banners: u.arbitrary()?,
+            frames: u.arbitrary()?,
+            colors: ByteArray::new(colors),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for MapItem {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let id: u16 = u.arbitrary()?;
+        Ok(MapItem {
+            file: PathBuf::from(format!("map_{id}.dat")),
+            data: u.arbitrary()?,
+            data_version: u.arbitrary()?,
+        })
+    }
+}
+
+/// Writes the *map_item* to a map file and checks that it is read back the same
+///
+/// Panics on any difference, so fuzz targets and property tests can share the check.
+pub fn check_round_trip(map_item: &MapItem) {
+    let mut file = Vec::new();
+    write_compressed(&mut file, &map_item.to_nbt().unwrap(), NbtCompression::Gzip).unwrap();
+    let read = MapItem::read_from_reader(file.as_slice(), &map_item.file).unwrap();
+    assert_eq!(read.data_version, map_item.data_version);
+    assert_eq!(read.data.dimension, map_item.data.dimension);
+    if map_item.data_version < NBT_TEXT_VERSION {
+        assert_eq!(read.data.banners, map_item.data.banners);
+    } else {
+        // Names are stored as NBT components, so only the shown text stays the same
+        assert_eq!(read.data.banners.len(), map_item.data.banners.len());
+        for (read, banner) in read.data.banners.iter().zip(&map_item.data.banners) {
+            assert_eq!(read.color, banner.color);
+            assert_eq!(read.pos, banner.pos);
+            assert_eq!(read.extract_name(), banner.extract_name());
+        }
+    }
+    assert_eq!(read.data.frames, map_item.data.frames);
+    assert_eq!(read.data.colors.as_ref(), map_item.data.colors.as_ref());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fuzzing::check_round_trip;
+    use crate::MapItem;
+    use arbitrary::{Arbitrary, Unstructured};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_round_trip(bytes in vec(any::<u8>(), 0..20_000)) {
+            let map_item = MapItem::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            check_round_trip(&map_item);
+        }
+
+        #[test]
+        fn test_read_garbage(bytes in vec(any::<u8>(), 0..1024)) {
+            // Errors are fine, but reading must not panic
+            let _ = MapItem::read_from_reader(bytes.as_slice(), "map_0.dat".as_ref());
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::changes::sink;
+use crate::image_tool::ColorArgs;
+use anyhow::{anyhow, Result};
+use clap::Args;
+use image::imageops::{resize, FilterType};
+use log::{error, info};
+use minecraft_map_tool::{read_maps_sorted, MapItem, SortSpec};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct GalleryArgs {
+    /// The directory or world backup archive (.zip, .tar.gz) from which map files are searched for.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    path: PathBuf,
+
+    /// Output directory for the index.html and the images
+    #[arg(short, long, default_value = "gallery")]
+    output_dir: PathBuf,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Order of the maps in the gallery. Keys are name, time, id, data-version and scale.
+    /// Combine keys with commas and reverse them with :desc, for example scale,time:desc
+    #[arg(short, long, default_value = "name")]
+    sort: Option<SortSpec>,
+
+    /// Size multiplier for the full-size images
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..=16))]
+    image_scale: u32,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+}
+
+/// Gallery entry for one map
+struct Entry {
+    name: String,
+    dimension: String,
+    scale: i8,
+    tooltip: String,
+}
+
+pub fn run(args: &GalleryArgs) -> ExitCode {
+    match make_gallery(args) {
+        Ok(count) => {
+            let index = args.output_dir.join("index.html");
+            info!("Gallery with {count} maps written to: {index:?}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            error!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn make_gallery(args: &GalleryArgs) -> Result<usize> {
+    let maps = read_maps_sorted(&args.path, &args.sort, args.recursive)
+        .map_err(|err| anyhow!("Could not get maps: {err}"))?;
+    if maps.is_empty() {
+        return Err(anyhow!("No map files found"));
+    }
+    let thumbnail_dir = args.output_dir.join("thumbnails");
+    let image_dir = args.output_dir.join("images");
+    sink().create_dir_all(&thumbnail_dir)?;
+    sink().create_dir_all(&image_dir)?;
+
+    let palette = args.colors.palette();
+    let mut entries = Vec::new();
+    let mut names = BTreeMap::new();
+    for map_item in maps.flatten() {
+        let name = image_name(&map_item.file, &args.path, &names);
+        if let Some(other) = names.insert(name.clone(), map_item.file.clone()) {
+            return Err(anyhow!(
+                "Maps {other:?} and {:?} would both be written to {name}.png",
+                map_item.file
+            ));
+        }
+        let thumbnail = map_item
+            .make_image(&palette)
+            .map_err(|err| anyhow!("Could not create image: {err}"))?;
+        let size = thumbnail.width() * args.image_scale;
+        let image = resize(&thumbnail, size, size, FilterType::Nearest);
+        sink().write_with(thumbnail_dir.join(format!("{name}.png")), |file| {
+            thumbnail.save(file)
+        })?;
+        sink().write_with(image_dir.join(format!("{name}.png")), |file| {
+            image.save(file)
+        })?;
+        entries.push(Entry {
+            tooltip: tooltip(&map_item),
+            dimension: map_item.data.pretty_dimension(),
+            scale: map_item.data.scale,
+            name,
+        });
+    }
+    sink().write(args.output_dir.join("index.html"), index_html(&entries))?;
+    Ok(entries.len())
+}
+
+/// Name of the images for the map *file* found from the *root*
+///
+/// The file name is used when no other map has it yet. Otherwise, the name comes from the
+/// path relative to the *root*, so maps with the same name in different directories or
+/// dimensions do not overwrite each other's images.
+fn image_name(file: &Path, root: &Path, names: &BTreeMap<String, PathBuf>) -> String {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    if !names.contains_key(stem.as_ref()) {
+        return stem.to_string();
+    }
+    let relative = file.strip_prefix(root).unwrap_or(file).with_extension("");
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn tooltip(map_item: &MapItem) -> String {
+    let data = &map_item.data;
+    format!(
+        "{}\nDimension: {}\nScale: {}\nCenter: {}, {}\nArea: {}, {} – {}, {}\nLocked: {}\nBanners: {}\nVersion: {}",
+        map_item.file.file_name().unwrap_or_default().to_string_lossy(),
+        data.pretty_dimension(),
+        data.scale_description(),
+        data.x_center,
+        data.z_center,
+        data.left(),
+        data.top(),
+        data.right(),
+        data.bottom(),
+        if data.locked != 0 { "Yes" } else { "No" },
+        data.banners.len(),
+        map_item.version_description()
+    )
+}
+
+fn index_html(entries: &[Entry]) -> String {
+    let dimensions: BTreeSet<&str> = entries.iter().map(|e| e.dimension.as_str()).collect();
+    let scales: BTreeSet<i8> = entries.iter().map(|e| e.scale).collect();
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Minecraft maps</title>
+<style>
+body { font-family: sans-serif; background: #2b2b2b; color: #eee; margin: 1em; }
+#filters { margin-bottom: 1em; }
+#maps { display: flex; flex-wrap: wrap; gap: 12px; }
+figure { margin: 0; background: #3c3c3c; padding: 6px; border-radius: 4px; }
+figure img { display: block; width: 128px; height: 128px; image-rendering: pixelated; }
+figcaption { font-size: 0.8em; text-align: center; margin-top: 4px; }
+</style>
+</head>
+<body>
+<h1>Minecraft maps</h1>
+<div id="filters">
+<label>Dimension <select id="dimension"><option value="">All</option>
+"#,
+    );
+    for dimension in &dimensions {
+        let dimension = escape(dimension);
+        let _ = writeln!(html, r#"<option value="{dimension}">{dimension}</option>"#);
+    }
+    html.push_str(
+        r#"</select></label>
+<label>Zoom <select id="scale"><option value="">All</option>
+"#,
+    );
+    for scale in &scales {
+        let _ = writeln!(html, r#"<option value="{scale}">{scale}</option>"#);
+    }
+    html.push_str("</select></label>\n</div>\n<div id=\"maps\">\n");
+    for entry in entries {
+        let name = escape(&entry.name);
+        let file = percent_encode(&entry.name);
+        let _ = writeln!(
+            html,
+            r#"<figure data-dimension="{}" data-scale="{}" title="{}"><a href="images/{file}.png"><img src="thumbnails/{file}.png" alt="{name}" loading="lazy"></a><figcaption>{name}</figcaption></figure>"#,
+            escape(&entry.dimension),
+            entry.scale,
+            escape(&entry.tooltip),
+        );
+    }
+    html.push_str(
+        r##"</div>
+<script>
+function applyFilters() {
+  const dimension = document.getElementById("dimension").value;
+  const scale = document.getElementById("scale").value;
+  for (const figure of document.querySelectorAll("#maps figure")) {
+    const visible = (!dimension || figure.dataset.dimension === dimension)
+      && (!scale || figure.dataset.scale === scale);
+    figure.style.display = visible ? "" : "none";
+  }
+}
+document.getElementById("dimension").addEventListener("change", applyFilters);
+document.getElementById("scale").addEventListener("change", applyFilters);
+</script>
+</body>
+</html>
+"##,
+    );
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
+
+/// Percent-encodes the *text* for a path in a URL, keeping only the unreserved characters
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(char::from(byte))
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,416 @@
+//! Map files with the metadata read first
+//!
+//! Colors take almost all the space in a map file. A [MapHandle] keeps only the metadata
+//! in memory, so maps can be listed and filtered without keeping the colors, and the full
+//! [MapItem] is read only when it is needed.
+
+use crate::compression::decompress;
+use crate::error::{Error, Result};
+use crate::limits::{read_options, ReadOptions};
+use crate::source::{open_file, MapSource};
+use crate::stitch::Area;
+use crate::{pretty_dimension, pretty_dimension_from_path, MapItem};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Map item without the colors, banners and frames
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapMetadata {
+    /// The version the map was created
+    pub data_version: i32,
+
+    /// How zoomed in the map is, see [MapData::scale](crate::MapData::scale)
+    pub scale: i8,
+
+    /// Dimension, see [MapData::dimension](crate::MapData::dimension)
+    pub dimension: String,
+
+    /// 1 if the map has been locked in a cartography table.
+    pub locked: i8,
+
+    /// Center of map according to real world by X.
+    pub x_center: i32,
+
+    /// Center of map according to real world by Z.
+    pub z_center: i32,
+
+    /// Number of banner markers
+    pub banner_count: usize,
+
+    /// Number of map markers
+    pub frame_count: usize,
+}
+
+impl MapMetadata {
+    /// Reads the metadata from the map *file*
+    pub fn read_from(file: &Path) -> Result<MapMetadata> {
+        MapMetadata::read_from_reader(open_file(file, &read_options())?)
+    }
+
+    /// Reads the metadata from the *reader* giving map file content
+    ///
+    /// The NBT data is scanned as a stream, so the colors, banners and frames are skipped
+    /// without storing them. The whole file is still read to notice truncated files.
+    /// Dimensions stored as numbers by versions before 1.16 are converted to names.
+    /// The limits of the [read_options] are followed.
+    pub fn read_from_reader<R: Read>(reader: R) -> Result<MapMetadata> {
+        let options = read_options();
+        let mut nbt = NbtScanner::new(decompress(reader)?.take(options.max_size), &options);
+        nbt.read_root()?;
+
+        let mut data_version = None;
+        let mut metadata = None;
+        while let Some((tag, name)) = nbt.next_field()? {
+            match (tag, name.as_str()) {
+                (TAG_COMPOUND, "data") => metadata = Some(read_data(&mut nbt)?),
+                (_, "DataVersion") => data_version = Some(nbt.read_number(tag)? as i32),
+                _ => nbt.skip_field(tag, &name)?,
+            }
+        }
+        let mut metadata = metadata.ok_or_else(|| Error::map_item_error("Map data not found"))?;
+        metadata.data_version =
+            data_version.ok_or_else(|| Error::map_item_error("DataVersion not found"))?;
+        Ok(metadata)
+    }
+
+    /// Pretty dimension, see [MapData::pretty_dimension](crate::MapData::pretty_dimension)
+    pub fn pretty_dimension(&self) -> String {
+        pretty_dimension(&self.dimension)
+    }
+
+    /// The area covered by the map in the world coordinates
+    pub fn area(&self) -> Area {
+        let half_width = 64 * 2i32.pow(self.scale as u32);
+        Area {
+            left: self.x_center - half_width,
+            top: self.z_center - half_width,
+            right: self.x_center + half_width - 1,
+            bottom: self.z_center + half_width - 1,
+        }
+    }
+}
+
+/// Map file in a source with its metadata
+#[derive(Clone, Debug)]
+pub struct MapHandle {
+    source: Arc<dyn MapSource>,
+    path: PathBuf,
+    metadata: MapMetadata,
+}
+
+impl MapHandle {
+    /// Reads the metadata of the map file *path* in the *source*
+    pub fn open(source: Arc<dyn MapSource>, path: &Path) -> Result<MapHandle> {
+        let metadata = MapMetadata::read_from_reader(source.open(path)?)?;
+        Ok(MapHandle {
+            source,
+            path: PathBuf::from(path),
+            metadata,
+        })
+    }
+
+    /// Path to the map file in the source
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Metadata read when the handle was opened
+    pub fn metadata(&self) -> &MapMetadata {
+        &self.metadata
+    }
+
+    /// Pretty dimension, see [MapItem::pretty_dimension_from_path]
+    pub fn pretty_dimension_from_path(&self) -> String {
+        pretty_dimension_from_path(&self.path, &self.metadata.dimension)
+    }
+
+    /// Reads the whole map item, including the colors
+    pub fn load(&self) -> Result<MapItem> {
+        MapItem::read_from_source(self.source.as_ref(), &self.path)
+    }
+}
+
+pub(crate) const TAG_END: u8 = 0;
+pub(crate) const TAG_BYTE: u8 = 1;
+pub(crate) const TAG_SHORT: u8 = 2;
+pub(crate) const TAG_INT: u8 = 3;
+pub(crate) const TAG_LONG: u8 = 4;
+pub(crate) const TAG_FLOAT: u8 = 5;
+pub(crate) const TAG_DOUBLE: u8 = 6;
+pub(crate) const TAG_BYTE_ARRAY: u8 = 7;
+pub(crate) const TAG_STRING: u8 = 8;
+pub(crate) const TAG_LIST: u8 = 9;
+pub(crate) const TAG_COMPOUND: u8 = 10;
+pub(crate) const TAG_INT_ARRAY: u8 = 11;
+pub(crate) const TAG_LONG_ARRAY: u8 = 12;
+
+/// Reads the fields of the `data` compound
+fn read_data<R: Read>(nbt: &mut NbtScanner<R>) -> Result<MapMetadata> {
+    let mut scale = None;
+    let mut dimension = None;
+    let mut locked = 0;
+    let mut x_center = None;
+    let mut z_center = None;
+    let mut banner_count = 0;
+    let mut frame_count = 0;
+    while let Some((tag, name)) = nbt.next_field()? {
+        match (tag, name.as_str()) {
+            (_, "scale") => scale = Some(nbt.read_number(tag)? as i8),
+            (TAG_STRING, "dimension") => dimension = Some(nbt.read_string()?),
+            (_, "dimension") => {
+                dimension = Some(match nbt.read_number(tag)? {
+                    -1 => String::from("minecraft:the_nether"),
+                    0 => String::from("minecraft:overworld"),
+                    1 => String::from("minecraft:the_end"),
+                    other => other.to_string(),
+                })
+            }
+            (_, "locked") => locked = nbt.read_number(tag)? as i8,
+            (_, "xCenter") => x_center = Some(nbt.read_number(tag)? as i32),
+            (_, "zCenter") => z_center = Some(nbt.read_number(tag)? as i32),
+            (TAG_LIST, "banners") => banner_count = nbt.skip_markers()?,
+            (TAG_LIST, "frames") => frame_count = nbt.skip_markers()?,
+            _ => nbt.skip_field(tag, &name)?,
+        }
+    }
+    let missing = |field| move || Error::map_item_error(field);
+    Ok(MapMetadata {
+        data_version: 0,
+        scale: scale.ok_or_else(missing("Map scale not found"))?,
+        dimension: dimension.ok_or_else(missing("Map dimension not found"))?,
+        locked,
+        x_center: x_center.ok_or_else(missing("Map xCenter not found"))?,
+        z_center: z_center.ok_or_else(missing("Map zCenter not found"))?,
+        banner_count,
+        frame_count,
+    })
+}
+
+/// Minimal streaming reader for uncompressed NBT data
+///
+/// The data is only read forward, and every length is checked by reading that much, so
+/// lengths larger than the data end with an error instead of an allocation. Both the
+/// metadata scanning here and [read_limited](crate::limits::read_limited) use this reader
+/// to follow the limits of the [ReadOptions].
+pub(crate) struct NbtScanner<R: Read> {
+    reader: R,
+
+    /// Nesting of the compounds and lists being skipped
+    depth: usize,
+    max_depth: usize,
+
+    /// Most elements in lists named `banners` or `frames`
+    max_markers: usize,
+}
+
+impl<R: Read> NbtScanner<R> {
+    pub(crate) fn new(reader: R, options: &ReadOptions) -> NbtScanner<R> {
+        NbtScanner {
+            reader,
+            depth: 0,
+            max_depth: options.max_depth,
+            max_markers: options.max_markers,
+        }
+    }
+
+    /// Reads the tag and name of the root compound
+    pub(crate) fn read_root(&mut self) -> Result<()> {
+        if self.read_u8()? != TAG_COMPOUND {
+            return Err(Error::map_item_error("Map file is not an NBT compound"));
+        }
+        self.skip_string()
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0u8; N];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    fn read_length(&mut self) -> Result<u64> {
+        // Negative lengths are handled as empty
+        Ok(i32::from_be_bytes(self.read_bytes()?).max(0) as u64)
+    }
+
+    /// Reads the tag and name of the next field in a compound, None at the end of the compound
+    fn next_field(&mut self) -> Result<Option<(u8, String)>> {
+        match self.read_u8()? {
+            TAG_END => Ok(None),
+            tag => Ok(Some((tag, self.read_string()?))),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let length = u16::from_be_bytes(self.read_bytes()?) as usize;
+        let mut bytes = vec![0u8; length];
+        self.reader.read_exact(&mut bytes)?;
+        // Java uses modified UTF-8, which is the same for the names used in map files
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        let length = u16::from_be_bytes(self.read_bytes()?) as u64;
+        self.skip(length)
+    }
+
+    /// Reads an integer payload of any size
+    fn read_number(&mut self, tag: u8) -> Result<i64> {
+        match tag {
+            TAG_BYTE => Ok(i8::from_be_bytes(self.read_bytes()?) as i64),
+            TAG_SHORT => Ok(i16::from_be_bytes(self.read_bytes()?) as i64),
+            TAG_INT => Ok(i32::from_be_bytes(self.read_bytes()?) as i64),
+            TAG_LONG => Ok(i64::from_be_bytes(self.read_bytes()?)),
+            _ => Err(Error::map_item_error("Expected a number in the map data")),
+        }
+    }
+
+    /// Skips the payload of the compound field *name*, checking the marker lists
+    fn skip_field(&mut self, tag: u8, name: &str) -> Result<()> {
+        match (tag, name) {
+            (TAG_LIST, "banners" | "frames") => self.skip_markers().map(|_| ()),
+            _ => self.skip_payload(tag),
+        }
+    }
+
+    /// Skips a list of banners or frames and returns the number of them
+    fn skip_markers(&mut self) -> Result<usize> {
+        let tag = self.read_u8()?;
+        let length = self.read_length()?;
+        if length > self.max_markers as u64 {
+            return Err(Error::map_item_error("Map has too many banners or frames"));
+        }
+        self.nested(|nbt| nbt.skip_elements(tag, length))?;
+        Ok(length as usize)
+    }
+
+    /// Skips a payload of the *tag*, checking the whole NBT data under it
+    pub(crate) fn skip_payload(&mut self, tag: u8) -> Result<()> {
+        match tag {
+            TAG_END => Ok(()),
+            TAG_BYTE => self.skip(1),
+            TAG_SHORT => self.skip(2),
+            TAG_INT | TAG_FLOAT => self.skip(4),
+            TAG_LONG | TAG_DOUBLE => self.skip(8),
+            TAG_BYTE_ARRAY => {
+                let length = self.read_length()?;
+                self.skip(length)
+            }
+            TAG_STRING => self.skip_string(),
+            TAG_LIST => {
+                let tag = self.read_u8()?;
+                let length = self.read_length()?;
+                self.nested(|nbt| nbt.skip_elements(tag, length))
+            }
+            TAG_COMPOUND => self.nested(|nbt| {
+                while let Some((tag, name)) = nbt.next_field()? {
+                    nbt.skip_field(tag, &name)?;
+                }
+                Ok(())
+            }),
+            TAG_INT_ARRAY => {
+                let length = self.read_length()?;
+                self.skip(length.saturating_mul(4))
+            }
+            TAG_LONG_ARRAY => {
+                let length = self.read_length()?;
+                self.skip(length.saturating_mul(8))
+            }
+            _ => Err(Error::map_item_error("Unknown NBT tag in the map file")),
+        }
+    }
+
+    /// Skips *length* list elements of the *tag*
+    fn skip_elements(&mut self, tag: u8, length: u64) -> Result<()> {
+        match payload_size(tag) {
+            // Fixed size elements are skipped at once, and lists of TAG_End have nothing to
+            // skip whatever length they claim
+            Some(size) => self.skip(length.saturating_mul(size)),
+            // Other elements take at least a byte each, so the end of the data ends the loop
+            None => {
+                for _ in 0..length {
+                    self.skip_payload(tag)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Calls *skip* one level deeper in the NBT data
+    fn nested<F>(&mut self, skip: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        if self.depth >= self.max_depth {
+            return Err(Error::map_item_error("NBT data is nested too deeply"));
+        }
+        self.depth += 1;
+        let result = skip(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn skip(&mut self, count: u64) -> Result<()> {
+        let skipped = io::copy(&mut self.reader.by_ref().take(count), &mut io::sink())?;
+        if skipped < count {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(())
+    }
+}
+
+/// Size of the payload of the *tag* if it is always the same
+fn payload_size(tag: u8) -> Option<u64> {
+    match tag {
+        TAG_END => Some(0),
+        TAG_BYTE => Some(1),
+        TAG_SHORT => Some(2),
+        TAG_INT | TAG_FLOAT => Some(4),
+        TAG_LONG | TAG_DOUBLE => Some(8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handle::MapMetadata;
+    use crate::stitch::Area;
+    use crate::synthetic::write_synthetic_world;
+    use crate::{read_maps, MapItem};
+    use std::path::Path;
+
+    #[test]
+    fn test_metadata_matches_map_item() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let maps = read_maps(&world.path().join("data"), &None, false).unwrap();
+        for (_, handle) in maps.handles() {
+            let handle = handle.unwrap();
+            let metadata = handle.metadata();
+            let map_item = handle.load().unwrap();
+            assert_eq!(metadata.data_version, map_item.data_version);
+            assert_eq!(metadata.scale, map_item.data.scale);
+            assert_eq!(
+                metadata.pretty_dimension(),
+                map_item.data.pretty_dimension()
+            );
+            assert_eq!(metadata.banner_count, map_item.data.banners.len());
+            assert_eq!(metadata.area(), Area::of_map(&map_item.data));
+        }
+
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/map_0.dat");
+        let metadata = MapMetadata::read_from(&file).unwrap();
+        let map_item = MapItem::read_from(&file).unwrap();
+        assert_eq!(metadata.data_version, map_item.data_version);
+        assert_eq!(metadata.dimension, map_item.data.dimension);
+        assert_eq!(metadata.frame_count, map_item.data.frames.len());
+
+        // A list of TAG_End claiming 2^31 - 1 elements is skipped at once
+        let bytes = [10, 0, 0, 9, 0, 1, b'x', 0, 0x7f, 0xff, 0xff, 0xff, 0];
+        assert!(MapMetadata::read_from_reader(&bytes[..]).is_err());
+    }
+}
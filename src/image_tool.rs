@@ -1,7 +1,19 @@
-use clap::Args;
-use image::DynamicImage;
-use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
+use crate::changes::sink;
+use clap::{Args, ValueEnum};
+use image::{DynamicImage, ImageOutputFormat, RgbaImage};
+use log::{error, info};
+use minecraft_map_tool::frame::{apply_frame, FrameStyle};
+use minecraft_map_tool::mask::{draw_mask, Mask, MaskStyle};
+use minecraft_map_tool::palette::{
+    apply_color_matrix, emphasize_water_depth, generate_palette_with, parse_color_matrix,
+    parse_multipliers, ColorMatrix, ColorMode, Palette, Shading, BASE_COLORS_2699,
+};
+use minecraft_map_tool::postprocess::{Adjustment, PostProcess};
+use minecraft_map_tool::stitch::Area;
+use minecraft_map_tool::svg::map_svg;
+use minecraft_map_tool::uncharted::{fill_uncharted, UnchartedStyle};
 use minecraft_map_tool::MapItem;
+use std::io::{self, Cursor, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
 
@@ -11,30 +23,208 @@ pub struct ImageArgs {
     map_file: PathBuf,
 
     /// Write the map image to the file. Standard file formats are supported.
+    /// With -, a PNG image is written to the standard output.
     #[arg(short, long)]
     output_file: Option<PathBuf>,
 
-    /// Show map in terminal
-    #[arg(short, long, group = "term")]
+    /// Show map in terminal. Cannot be used with --output-file, which can also write to the
+    /// standard output
+    #[arg(short, long, group = "term", conflicts_with = "output_file")]
     show_in_terminal: bool,
+
+    /// Copy the map image to the clipboard
+    #[cfg(feature = "clipboard")]
+    #[arg(long)]
+    to_clipboard: bool,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+
+    #[command(flatten)]
+    adjustments: PostProcessArgs,
+
+    #[command(flatten)]
+    mask: MaskArgs,
+
+    /// Draw the map like the map item, on parchment or in an item frame
+    #[arg(long, default_value = "plain")]
+    frame_style: FrameStyle,
+
+    /// How the uncharted pixels of explorer maps, like buried treasure maps, are drawn
+    #[arg(long, default_value = "transparent", conflicts_with = "mask")]
+    uncharted_style: UnchartedStyle,
+
+    /// Output file format. Format is also selected by the file extension .svg.
+    #[arg(short, long, default_value = "image")]
+    format: ImageFormat,
+}
+
+/// Output formats for the map image
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ImageFormat {
+    /// Raster image in the format given by the file extension (png, jpg, ...)
+    Image,
+
+    /// Scalable vector graphics with the banners as labeled markers.
+    /// The frame style is not used with this format.
+    Svg,
+}
+
+/// Palette options shared by the commands making images
+#[derive(Args, Debug)]
+pub struct ColorArgs {
+    /// How the shades showing the slopes are drawn, for example to make elevation changes
+    /// more visible
+    #[arg(long, default_value = "normal")]
+    shading: Shading,
+
+    /// Brightness multipliers for the dark, normal, light and darkest shades instead of the
+    /// ones of the game, for matching old versions or resource packs. 255 keeps the base color.
+    /// With three numbers the darkest shade uses the dark multiplier, like in old versions.
+    #[arg(long, value_parser = parse_multipliers, conflicts_with = "shading")]
+    multipliers: Option<[u16; 4]>,
+
+    /// Draw the water shades as a blue gradient from deep to shallow water. The optional
+    /// intensity from 0.0 to 1.0, given as `--water-depth=0.5`, mixes the gradient with the
+    /// normal water colors.
+    #[arg(long, value_name = "INTENSITY", num_args = 0..=1, require_equals = true,
+        default_missing_value = "1.0", value_parser = parse_intensity)]
+    water_depth: Option<f32>,
+
+    /// Transform the map colors, for example to tell terrain types apart with color blindness
+    #[arg(long, default_value = "normal")]
+    color_mode: ColorMode,
+
+    /// Transform the map colors with a custom 3×3 matrix after the color mode.
+    /// The matrix is given as nine comma-separated numbers in row-major order.
+    #[arg(long, value_parser = parse_color_matrix, allow_hyphen_values = true)]
+    color_matrix: Option<ColorMatrix>,
+}
+
+impl ColorArgs {
+    /// Palette for drawing the maps with the selected colors
+    pub fn palette(&self) -> Palette {
+        let mut palette = match &self.multipliers {
+            Some(multipliers) => generate_palette_with(&BASE_COLORS_2699, multipliers),
+            None => self.shading.palette(&BASE_COLORS_2699),
+        };
+        if let Some(intensity) = self.water_depth {
+            palette = emphasize_water_depth(&palette, intensity);
+        }
+        let palette = self.color_mode.apply(&palette);
+        match &self.color_matrix {
+            Some(matrix) => apply_color_matrix(&palette, matrix),
+            None => palette,
+        }
+    }
+}
+
+fn parse_intensity(text: &str) -> std::result::Result<f32, String> {
+    let intensity: f32 = text.parse().map_err(|err| format!("{err}"))?;
+    match (0.0..=1.0).contains(&intensity) {
+        true => Ok(intensity),
+        false => Err(String::from("Intensity must be from 0.0 to 1.0")),
+    }
+}
+
+/// Image adjustments shared by the commands making images
+#[derive(Args, Debug)]
+pub struct PostProcessArgs {
+    /// Multiply the brightness of the image (1.0 keeps the image unchanged)
+    #[arg(long)]
+    brightness: Option<f32>,
+
+    /// Multiply the contrast of the image (1.0 keeps the image unchanged)
+    #[arg(long)]
+    contrast: Option<f32>,
+
+    /// Gamma correction, values over 1.0 make the image lighter (1.0 keeps the image unchanged)
+    #[arg(long)]
+    gamma: Option<f32>,
+
+    /// Multiply the color saturation of the image (1.0 keeps the image unchanged)
+    #[arg(long)]
+    saturation: Option<f32>,
+}
+
+impl PostProcessArgs {
+    /// Adjustments in the order of brightness, contrast, saturation and gamma
+    pub fn post_process(&self) -> PostProcess {
+        let mut post_process = PostProcess::new();
+        if let Some(value) = self.brightness {
+            post_process = post_process.then(Adjustment::Brightness(value));
+        }
+        if let Some(value) = self.contrast {
+            post_process = post_process.then(Adjustment::Contrast(value));
+        }
+        if let Some(value) = self.saturation {
+            post_process = post_process.then(Adjustment::Saturation(value));
+        }
+        if let Some(value) = self.gamma {
+            post_process = post_process.then(Adjustment::Gamma(value));
+        }
+        post_process
+    }
+}
+
+/// Mask options shared by the commands making images
+#[derive(Args, Debug)]
+pub struct MaskArgs {
+    /// Draw a mask of the selected pixels instead of the plain map, for example to see which
+    /// parts of the maps are still unexplored
+    #[arg(long)]
+    mask: Option<Mask>,
+
+    /// How the mask is drawn
+    #[arg(long, default_value = "binary", requires = "mask")]
+    mask_style: MaskStyle,
+}
+
+impl MaskArgs {
+    /// Tests if a mask is drawn
+    pub fn is_enabled(&self) -> bool {
+        self.mask.is_some()
+    }
+
+    /// Draws the mask of the *image* showing the *area*, or returns the image if no mask is
+    /// selected. The *bounds* are the areas covered by maps.
+    pub fn apply(&self, image: RgbaImage, area: &Area, bounds: &[Area]) -> RgbaImage {
+        match self.mask {
+            Some(mask) => draw_mask(&image, area, bounds, mask, self.mask_style),
+            None => image,
+        }
+    }
 }
 
 pub fn run(args: &ImageArgs) -> ExitCode {
     let map_item = match MapItem::read_from(&args.map_file) {
         Ok(map_item) => map_item,
         Err(err) => {
-            eprintln!("Could not read map item: {err}");
+            error!("Could not read map item: {err}");
             return ExitCode::FAILURE;
         }
     };
 
-    let image = match map_item.make_image(&generate_palette(&BASE_COLORS_2699)) {
+    let palette = args.colors.palette();
+    let mut image = match map_item.make_image(&palette) {
         Ok(image) => image,
         Err(err) => {
-            eprintln!("Could not create image: {err}");
+            error!("Could not create image: {err}");
             return ExitCode::FAILURE;
         }
     };
+    fill_uncharted(&mut image, &map_item, &palette, args.uncharted_style);
+
+    args.adjustments.post_process().apply(&mut image);
+    // The whole map image is inside the map bounds
+    let area = Area {
+        left: 0,
+        top: 0,
+        right: image.width() as i32 - 1,
+        bottom: image.height() as i32 - 1,
+    };
+    let image = args.mask.apply(image, &area, &[area]);
+    let framed_image = apply_frame(&image, args.frame_style);
 
     if args.show_in_terminal {
         let config = viuer::Config {
@@ -43,18 +233,41 @@ pub fn run(args: &ImageArgs) -> ExitCode {
             truecolor: true,
             ..Default::default()
         };
-        let dynamic_image = DynamicImage::from(image.clone());
+        let dynamic_image = DynamicImage::from(framed_image.clone());
         if let Err(err) = viuer::print(&dynamic_image, &config) {
-            eprintln!("Could not show image: {err}");
+            error!("Could not show image: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    if args.to_clipboard {
+        if let Err(err) = copy_to_clipboard(&framed_image) {
+            error!("Could not copy image to the clipboard: {err}");
             return ExitCode::FAILURE;
         }
+        info!("Image copied to the clipboard");
     }
 
     if let Some(output_file) = &args.output_file {
-        match image.save(output_file) {
-            Ok(_) => println!("Image written to: {output_file:?}"),
+        let is_svg = args.format == ImageFormat::Svg
+            || output_file
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+        let to_stdout = output_file.as_os_str() == "-";
+        let result = match (is_svg, to_stdout) {
+            (true, true) => write_stdout(map_svg(&map_item, &image).as_bytes()),
+            (true, false) => sink().write(output_file, map_svg(&map_item, &image)),
+            (false, true) => png_bytes(&framed_image).and_then(|png| write_stdout(&png)),
+            (false, false) => sink().write_with(output_file, |file| {
+                framed_image.save(file).map_err(io::Error::other)
+            }),
+        };
+        match result {
+            Ok(_) if to_stdout => {}
+            Ok(_) => info!("Image written to: {output_file:?}"),
             Err(err) => {
-                eprintln!("Could not write image: {err}");
+                error!("Could not write image: {err}");
                 return ExitCode::FAILURE;
             }
         }
@@ -62,3 +275,30 @@ pub fn run(args: &ImageArgs) -> ExitCode {
 
     ExitCode::SUCCESS
 }
+
+/// Encodes the *image* as PNG
+fn png_bytes(image: &RgbaImage) -> io::Result<Vec<u8>> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(png)
+}
+
+/// Writes the *content* to the standard output
+fn write_stdout(content: &[u8]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(content)?;
+    stdout.flush()
+}
+
+/// Puts the *image* on the system clipboard
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(image: &RgbaImage) -> Result<(), arboard::Error> {
+    let image = arboard::ImageData {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        bytes: image.as_raw().into(),
+    };
+    arboard::Clipboard::new()?.set_image(image)
+}
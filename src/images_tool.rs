@@ -1,75 +1,157 @@
-use clap::{arg, Args};
-use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
-use minecraft_map_tool::read_maps;
+use crate::batch::{fatal, Failures};
+use crate::changes::sink;
+use crate::image_tool::ColorArgs;
+use crate::input::read_input;
+use clap::Args;
+use log::{error, info};
+use minecraft_map_tool::frame::{apply_frame, FrameStyle};
+use minecraft_map_tool::naming::{check_template, fill_template, map_values, MAP_PLACEHOLDERS};
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use minecraft_map_tool::uncharted::{fill_uncharted, UnchartedStyle};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::SystemTime;
 
 #[derive(Args, Debug)]
 pub struct ImagesArgs {
-    /// The directory from which map files are searched for.
-    path: PathBuf,
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which map files are
+    /// searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path).
+    #[arg(required_unless_present = "files_from")]
+    paths: Vec<PathBuf>,
+
+    /// Read the map files from a list with one path on each line, or from standard input with -.
+    #[arg(long, value_name = "FILE", conflicts_with = "paths")]
+    files_from: Option<PathBuf>,
 
     /// Output directory. Default is the current directory.
     #[arg(short, long)]
     output_dir: Option<PathBuf>,
 
+    /// Image file names in the output directory. Placeholders are {id}, {name} for the file
+    /// name, {dimension}, {scale}, {x} and {z} for the center, and {data_version}.
+    #[arg(long, default_value = "{dimension}/{name}.png", value_parser = parse_name_template)]
+    name_template: String,
+
     /// Search map files recursively in subdirectories.
     #[arg(short, long)]
     recursive: bool,
 
+    /// Only use the maps with these ids, for example 10..200,500,731. Ranges include both ends.
+    #[arg(long, value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+
     /// Try to detect world dimensions from the file path instead of map item data.
     #[arg(short, long)]
     dimension_from_path: bool,
+
+    /// Only write the images that are missing or older than their map files. Images are not
+    /// updated when only the colors or the frame style change.
+    #[arg(long)]
+    only_newer: bool,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+
+    /// Draw the map like the map item, on parchment or in an item frame
+    #[arg(long, default_value = "plain")]
+    frame_style: FrameStyle,
+
+    /// How the uncharted pixels of explorer maps, like buried treasure maps, are drawn
+    #[arg(long, default_value = "transparent")]
+    uncharted_style: UnchartedStyle,
+}
+
+fn parse_name_template(template: &str) -> Result<String, String> {
+    check_template(template, &MAP_PLACEHOLDERS).map(|_| template.to_string())
 }
 
 pub fn run(args: &ImagesArgs) -> ExitCode {
     // Collect map information
-    let maps = match read_maps(&args.path, &None, args.recursive) {
+    let mut maps = match read_input(
+        &args.paths,
+        args.files_from.as_deref(),
+        &None,
+        args.recursive,
+    ) {
         Ok(maps) => maps,
         Err(err) => {
-            eprintln!("Could not get maps: {err}");
-            return ExitCode::FAILURE;
+            error!("Could not get maps: {err}");
+            return fatal();
         }
     };
+    if let Some(ids) = &args.ids {
+        maps.retain_ids(ids);
+    }
     if maps.is_empty() {
-        println!("Could not find any maps!");
-        return ExitCode::FAILURE;
+        error!("Could not find any maps!");
+        return fatal();
     }
 
     // Prepare palette
-    let palette = generate_palette(&BASE_COLORS_2699);
+    let palette = args.colors.palette();
 
-    // Process maps
-    for map in maps.flatten() {
-        let mut output_dir = args.output_dir.clone().unwrap_or_default();
-        output_dir.push(PathBuf::from(if args.dimension_from_path {
+    // Process maps, failed files are reported at the end
+    let source = maps.source();
+    let mut failures = Failures::new();
+    let mut up_to_date = 0;
+    while let Some((file, map)) = maps.next_with_path() {
+        let map = match map {
+            Ok(map) => map,
+            Err(err) => {
+                failures.add(&file, format!("Could not read map item: {err}"));
+                continue;
+            }
+        };
+        let dimension = if args.dimension_from_path {
             map.pretty_dimension_from_path()
         } else {
             map.data.pretty_dimension()
-        }));
-        let output_file =
-            Path::join(&output_dir, map.file.file_stem().unwrap()).with_extension("png");
-        if let Err(error) = fs::create_dir_all(output_dir) {
-            eprintln!("Could not create output directory: {error}");
-            return ExitCode::FAILURE;
+        };
+        let name = fill_template(&args.name_template, &map_values(&map, &dimension));
+        let output_file = args.output_dir.clone().unwrap_or_default().join(name);
+        if args.only_newer && is_up_to_date(&output_file, source.modified(&file)) {
+            up_to_date += 1;
+            continue;
+        }
+        if let Some(output_dir) = output_file.parent() {
+            if let Err(error) = sink().create_dir_all(output_dir) {
+                failures.add(&file, format!("Could not create output directory: {error}"));
+                continue;
+            }
         }
         let image = match map.make_image(&palette) {
-            Ok(image) => image,
-            Err(err) => {
-                eprintln!("Could not create image: {err}");
-                return ExitCode::FAILURE;
+            Ok(mut image) => {
+                fill_uncharted(&mut image, &map, &palette, args.uncharted_style);
+                apply_frame(&image, args.frame_style)
             }
-        };
-        match image.save(&output_file) {
-            Ok(_) => println!("Image written to: {output_file:?}"),
             Err(err) => {
-                eprintln!("Could not write image: {output_file:?}\n{err}");
-                return ExitCode::FAILURE;
+                failures.add(&file, format!("Could not create image: {err}"));
+                continue;
             }
         };
+        match sink().write_with(&output_file, |file| image.save(file)) {
+            Ok(_) => info!("Image written to: {output_file:?}"),
+            Err(err) => failures.add(
+                &file,
+                format!("Could not write image: {output_file:?}, {err}"),
+            ),
+        };
     }
 
     // Done
-    ExitCode::SUCCESS
+    if up_to_date > 0 {
+        info!("{up_to_date} images were up to date");
+    }
+    failures.finish()
+}
+
+/// Tests if the *image* exists and is not older than the map file *modified* time
+fn is_up_to_date(image: &Path, modified: Option<SystemTime>) -> bool {
+    let image_modified = fs::metadata(image).and_then(|metadata| metadata.modified());
+    match (image_modified, modified) {
+        (Ok(image_modified), Some(modified)) => image_modified >= modified,
+        _ => false,
+    }
 }
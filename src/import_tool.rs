@@ -0,0 +1,107 @@
+use crate::changes::sink;
+use anyhow::{anyhow, Result};
+use clap::Args;
+use fastnbt::ByteArray;
+use log::{error, info};
+use minecraft_map_tool::compression::NbtCompression;
+use minecraft_map_tool::map_art::{quantize, ArtStyle};
+use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
+use minecraft_map_tool::schematic::write_schematic;
+use minecraft_map_tool::versions::MINECRAFT_VERSIONS;
+use minecraft_map_tool::{MapData, MapItem};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// Image to be converted to map colors
+    image_file: PathBuf,
+
+    /// Write a map_#.dat file with the converted colors
+    #[arg(short, long, value_name = "FILE")]
+    output_file: Option<PathBuf>,
+
+    /// Write a Sponge schematic (.schem) for building the map art
+    #[arg(long, value_name = "FILE")]
+    schematic: Option<PathBuf>,
+
+    /// How the map art is going to be built
+    #[arg(short, long, default_value = "staircase")]
+    style: ArtStyle,
+
+    /// Center of the map by X
+    #[arg(short, long, default_value_t = 0, allow_hyphen_values = true)]
+    x_center: i32,
+
+    /// Center of the map by Z
+    #[arg(short, long, default_value_t = 0, allow_hyphen_values = true)]
+    z_center: i32,
+
+    /// Set data version to [default: latest known version]
+    #[arg(short, long, value_name = "VERSION")]
+    data_version: Option<i32>,
+
+    /// Compression of the map file
+    #[arg(long, default_value = "gzip")]
+    compression: NbtCompression,
+}
+
+pub fn run(args: &ImportArgs) -> ExitCode {
+    if let Err(err) = process(args) {
+        error!("{err}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn process(args: &ImportArgs) -> Result<()> {
+    if args.output_file.is_none() && args.schematic.is_none() {
+        return Err(anyhow!(
+            "Nothing to do, give an output file or a schematic file"
+        ));
+    }
+
+    let image = image::open(&args.image_file)
+        .map_err(|err| anyhow!("Could not read image: {err}"))?
+        .to_rgba8();
+    let colors = quantize(&image, &generate_palette(&BASE_COLORS_2699), args.style);
+    let data_version = args
+        .data_version
+        .unwrap_or_else(|| *MINECRAFT_VERSIONS.keys().max().unwrap_or(&0));
+
+    if let Some(output_file) = &args.output_file {
+        let map_item = MapItem {
+            file: output_file.clone(),
+            data: MapData {
+                scale: 0,
+                dimension: "minecraft:overworld".to_string(),
+                tracking_position: 0,
+                unlimited_tracking: 0,
+                locked: 1,
+                x_center: args.x_center,
+                z_center: args.z_center,
+                banners: vec![],
+                frames: vec![],
+                colors: ByteArray::new(colors.iter().map(|c| *c as i8).collect()),
+            },
+            data_version,
+        };
+        sink()
+            .write_with(output_file, |file| {
+                map_item.write_compressed(file, args.compression)
+            })
+            .map_err(|err| anyhow!("Could not write map item: {err}"))?;
+        info!("Map item written to: {output_file:?}");
+    }
+
+    if let Some(schematic) = &args.schematic {
+        sink()
+            .write_with(schematic, |file| {
+                write_schematic(&colors, data_version, file)
+            })
+            .map_err(|err| anyhow!("Could not write schematic: {err}"))?;
+        info!("Schematic written to: {schematic:?}");
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
+use crate::charset::charset;
+use crate::list_tool::TextFormat;
+use crate::style::Styled;
 use clap::Args;
 use comfy_table::{presets, Cell, CellAlignment, ContentArrangement, Table, TableComponent};
 use crossterm::queue;
-use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::style::{Attribute, Print};
+use log::error;
+use minecraft_map_tool::locale::tr;
+use minecraft_map_tool::palette::{base_color_name, SHADE_NAMES};
 use minecraft_map_tool::MapItem;
 use std::{
     io::{stdout, Write},
@@ -17,22 +23,25 @@ pub struct InfoArgs {
     /// Try to detect world dimensions from the file path instead of map item data.
     #[arg(short, long)]
     dimension_from_path: bool,
-}
 
-#[cfg(not(target_os = "windows"))]
-pub const CORNERS: &str = "╭╮╰╯";
+    /// Show statistics of the colors used in the map
+    #[arg(short, long)]
+    colors: bool,
 
-// In Windows, rounded corners will work if the user has changed the command prompt to use
-// a UTF-8 compatible font. However, by default, this is not the case; therefore, we use
-// rectangular borders instead.
-#[cfg(target_os = "windows")]
-pub const CORNERS: &str = "┌┐└┘";
+    /// How many of the most common colors are shown with the --colors option
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+
+    /// Output format
+    #[arg(short, long, default_value = "table")]
+    format: TextFormat,
+}
 
 pub fn run(args: &InfoArgs) -> ExitCode {
     let map_item = match MapItem::read_from(&args.file) {
         Ok(map_item) => map_item,
         Err(err) => {
-            eprintln!("Could not read map item: {err}");
+            error!("Could not read map item: {err}");
             return ExitCode::FAILURE;
         }
     };
@@ -44,26 +53,38 @@ pub fn run(args: &InfoArgs) -> ExitCode {
         content: make_basic_info_table(&map_item, args.dimension_from_path),
     });
     frames.push(TextFrame {
-        title: "Tracking",
+        title: tr("Tracking"),
         content: make_tracking_table(&map_item),
     });
     frames.push(TextFrame {
-        title: "Coordinates (X, Z)",
+        title: tr("Coordinates (X, Z)"),
         content: make_coordinate_table(&map_item),
     });
     if !map_item.data.banners.is_empty() {
         frames.push(TextFrame {
-            title: "Banners",
+            title: tr("Banners"),
             content: make_banners_table(&map_item),
         });
     }
     if !map_item.data.frames.is_empty() {
         frames.push(TextFrame {
-            title: "Frames",
+            title: tr("Frames"),
             content: make_frames_table(&map_item),
         });
     }
 
+    if args.colors {
+        frames.push(TextFrame {
+            title: tr("Colors"),
+            content: make_colors_table(&map_item, args.top),
+        });
+    }
+
+    if args.format == TextFormat::Markdown {
+        print_markdown(frames);
+        return ExitCode::SUCCESS;
+    }
+
     // Finding maximum width and set it to all tables
     let mut width = 20; // Minimum width
     for frame in &frames {
@@ -71,16 +92,40 @@ pub fn run(args: &InfoArgs) -> ExitCode {
     }
 
     // Printing frames
-    let mut corners = CORNERS.chars();
-    frames[0].print(width, corners.next().unwrap(), corners.next().unwrap());
+    let charset = charset();
+    let [upper_left, upper_right, lower_left, lower_right] = charset.corners();
+    let [left_tee, right_tee] = charset.tees();
+    frames[0].print(width, upper_left, upper_right);
     for frame in &mut frames[1..] {
-        frame.print(width, '├', '┤');
+        frame.print(width, left_tee, right_tee);
     }
-    TextFrame::print_bottom(width, corners.next().unwrap(), corners.next().unwrap());
+    TextFrame::print_bottom(width, lower_left, lower_right);
 
     ExitCode::SUCCESS
 }
 
+/// Prints the frames as Markdown sections, the first frame title is the main heading
+fn print_markdown(frames: Vec<TextFrame>) {
+    for (index, mut frame) in frames.into_iter().enumerate() {
+        let heading = if index == 0 { "#" } else { "##" };
+        println!("{heading} {}\n", frame.title);
+        frame
+            .content
+            .load_preset(presets::ASCII_MARKDOWN)
+            .set_content_arrangement(ContentArrangement::Disabled);
+        if frame.content.header().is_none() {
+            // Markdown tables must have a header
+            let columns = frame.content.column_count();
+            let mut header = vec![""; columns];
+            if let Some(first) = header.first_mut() {
+                *first = tr("Property");
+            }
+            frame.content.set_header(header);
+        }
+        println!("{}\n", frame.content);
+    }
+}
+
 struct TextFrame<'a> {
     title: &'a str,
     content: Table,
@@ -96,16 +141,22 @@ impl TextFrame<'_> {
     }
 
     fn print(&mut self, width: u16, left: char, right: char) {
-        let fill_width = width as usize - self.title.chars().count() - 3;
+        let charset = charset();
+        let [left_tee, right_tee] = charset.tees();
+        let line = charset.horizontal();
+        let border = charset.vertical();
+        let fill = line
+            .to_string()
+            .repeat(width as usize - self.title.chars().count() - 4);
         let empty_row_width = width as usize + 2;
         queue!(
             stdout(),
-            SetAttribute(Attribute::Bold),
+            Styled(Attribute::Bold),
             Print(format!(
-                "{}──┤ {} ├{:─>fill_width$}\n",
-                left, self.title, right
+                "{left}{line}{line}{right_tee} {} {left_tee}{fill}{right}\n",
+                self.title
             )),
-            Print(format!("│{:empty_row_width$}│\n", ' ')),
+            Print(format!("{border}{:empty_row_width$}{border}\n", ' ')),
         )
         .unwrap();
         self.content.set_width(width);
@@ -114,29 +165,32 @@ impl TextFrame<'_> {
         for line in self.content.lines() {
             queue!(
                 stdout(),
-                Print("│ "),
-                SetAttribute(Attribute::Reset),
+                Print(format!("{border} ")),
+                Styled(Attribute::Reset),
                 Print(line),
-                SetAttribute(Attribute::Bold),
-                Print(" │\n"),
+                Styled(Attribute::Bold),
+                Print(format!(" {border}\n")),
             )
             .unwrap();
         }
         queue!(
             stdout(),
-            Print(format!("│{:empty_row_width$}│\n", ' ')),
-            SetAttribute(Attribute::Reset),
+            Print(format!("{border}{:empty_row_width$}{border}\n", ' ')),
+            Styled(Attribute::Reset),
         )
         .unwrap();
     }
 
     fn print_bottom(width: u16, left: char, right: char) {
-        let fill_width = width as usize + 2;
+        let fill = charset()
+            .horizontal()
+            .to_string()
+            .repeat(width as usize + 1);
         queue!(
             stdout(),
-            SetAttribute(Attribute::Bold),
-            Print(format!("{left}─{right:─>fill_width$}\n")),
-            SetAttribute(Attribute::Reset)
+            Styled(Attribute::Bold),
+            Print(format!("{left}{fill}{right}\n")),
+            Styled(Attribute::Reset)
         )
         .unwrap();
         stdout().flush().unwrap();
@@ -145,10 +199,10 @@ impl TextFrame<'_> {
 
 /// Helper function to turn NBT byte to Yes or No string
 fn yes_or_no(byte: i8) -> String {
-    match byte {
+    tr(match byte {
         0 => "No",
         _ => "Yes",
-    }
+    })
     .to_string()
 }
 
@@ -156,24 +210,32 @@ fn make_basic_info_table(map_item: &MapItem, dimension_from_path: bool) -> Table
     let mut table = Table::new();
     table.load_preset(presets::NOTHING);
     table.add_row(vec![
-        "Scale".to_string(),
+        tr("Scale").to_string(),
         map_item.data.scale.to_string(),
         map_item.data.scale_description(),
     ]);
     table.add_row(vec![
-        "Version".to_string(),
+        tr("Version").to_string(),
         map_item.data_version.to_string(),
         map_item.version_description(),
     ]);
     table.add_row(vec![
-        "Dimension".to_string(),
-        if dimension_from_path {
+        tr("Dimension").to_string(),
+        tr(&if dimension_from_path {
             map_item.pretty_dimension_from_path()
         } else {
             map_item.data.pretty_dimension()
-        },
+        })
+        .to_string(),
+    ]);
+    table.add_row(vec![
+        tr("Locked").to_string(),
+        yes_or_no(map_item.data.locked),
+    ]);
+    table.add_row(vec![
+        tr("Kind").to_string(),
+        tr(&map_item.kind().to_string()).to_string(),
     ]);
-    table.add_row(vec!["Locked".to_string(), yes_or_no(map_item.data.locked)]);
     table
 }
 
@@ -181,11 +243,11 @@ fn make_tracking_table(map_item: &MapItem) -> Table {
     let mut table = Table::new();
     table.load_preset(presets::NOTHING);
     table.add_row(vec![
-        "Tracking position".to_string(),
+        tr("Tracking position").to_string(),
         yes_or_no(map_item.data.tracking_position),
     ]);
     table.add_row(vec![
-        "Unlimited tracking".to_string(),
+        tr("Unlimited tracking").to_string(),
         yes_or_no(map_item.data.unlimited_tracking),
     ]);
     table
@@ -195,27 +257,27 @@ fn make_coordinate_table(map_item: &MapItem) -> Table {
     let mut table = Table::new();
     table.load_preset(presets::NOTHING);
     table.add_row(vec![
-        "Upper Left".to_string(),
+        tr("Upper Left").to_string(),
         map_item.data.left().to_string(),
         map_item.data.top().to_string(),
     ]);
     table.add_row(vec![
-        "Lower Left".to_string(),
+        tr("Lower Left").to_string(),
         map_item.data.left().to_string(),
         map_item.data.bottom().to_string(),
     ]);
     table.add_row(vec![
-        "Upper Right".to_string(),
+        tr("Upper Right").to_string(),
         map_item.data.right().to_string(),
         map_item.data.top().to_string(),
     ]);
     table.add_row(vec![
-        "Lower Right".to_string(),
+        tr("Lower Right").to_string(),
         map_item.data.right().to_string(),
         map_item.data.bottom().to_string(),
     ]);
     table.add_row(vec![
-        "Center".to_string(),
+        tr("Center").to_string(),
         map_item.data.x_center.to_string(),
         map_item.data.z_center.to_string(),
     ]);
@@ -225,11 +287,11 @@ fn make_coordinate_table(map_item: &MapItem) -> Table {
 fn make_banners_table(map_item: &MapItem) -> Table {
     let mut table = Table::new();
     table.load_preset(presets::NOTHING);
-    table.set_style(TableComponent::HeaderLines, '╌');
+    table.set_style(TableComponent::HeaderLines, charset().dashed());
     table.set_style(TableComponent::VerticalLines, ' ');
     table.set_header(vec![
-        Cell::new("Name").set_alignment(CellAlignment::Left),
-        Cell::new("Color").set_alignment(CellAlignment::Left),
+        Cell::new(tr("Name")).set_alignment(CellAlignment::Left),
+        Cell::new(tr("Color")).set_alignment(CellAlignment::Left),
         Cell::new("X").set_alignment(CellAlignment::Right),
         Cell::new("Y").set_alignment(CellAlignment::Right),
         Cell::new("Z").set_alignment(CellAlignment::Right),
@@ -237,7 +299,7 @@ fn make_banners_table(map_item: &MapItem) -> Table {
     for banner in &map_item.data.banners {
         table.add_row(vec![
             Cell::new(banner.extract_name()).set_alignment(CellAlignment::Left),
-            Cell::new(banner.color.to_string()).set_alignment(CellAlignment::Left),
+            Cell::new(tr(&banner.color.to_string())).set_alignment(CellAlignment::Left),
             Cell::new(banner.pos.x).set_alignment(CellAlignment::Right),
             Cell::new(banner.pos.y).set_alignment(CellAlignment::Right),
             Cell::new(banner.pos.z).set_alignment(CellAlignment::Right),
@@ -249,11 +311,12 @@ fn make_banners_table(map_item: &MapItem) -> Table {
 fn make_frames_table(map_item: &MapItem) -> Table {
     let mut table = Table::new();
     table.load_preset(presets::NOTHING);
-    table.set_style(TableComponent::HeaderLines, '╌');
+    table.set_style(TableComponent::HeaderLines, charset().dashed());
     table.set_style(TableComponent::VerticalLines, ' ');
     table.set_header(vec![
-        Cell::new("Entity ID").set_alignment(CellAlignment::Left),
-        Cell::new("Angle").set_alignment(CellAlignment::Left),
+        Cell::new(tr("Entity ID")).set_alignment(CellAlignment::Left),
+        Cell::new(tr("Angle")).set_alignment(CellAlignment::Right),
+        Cell::new(tr("Facing")).set_alignment(CellAlignment::Left),
         Cell::new("X").set_alignment(CellAlignment::Right),
         Cell::new("Y").set_alignment(CellAlignment::Right),
         Cell::new("Z").set_alignment(CellAlignment::Right),
@@ -261,7 +324,8 @@ fn make_frames_table(map_item: &MapItem) -> Table {
     for frame in &map_item.data.frames {
         table.add_row(vec![
             Cell::new(frame.entity_id).set_alignment(CellAlignment::Left),
-            Cell::new(frame.rotation).set_alignment(CellAlignment::Left),
+            Cell::new(frame.normalized_rotation()).set_alignment(CellAlignment::Right),
+            Cell::new(frame.cardinal_direction()).set_alignment(CellAlignment::Left),
             Cell::new(frame.pos.x).set_alignment(CellAlignment::Right),
             Cell::new(frame.pos.y).set_alignment(CellAlignment::Right),
             Cell::new(frame.pos.z).set_alignment(CellAlignment::Right),
@@ -269,3 +333,57 @@ fn make_frames_table(map_item: &MapItem) -> Table {
     }
     table
 }
+
+fn make_colors_table(map_item: &MapItem, top: usize) -> Table {
+    let histogram = map_item.data.color_histogram();
+    let total = histogram.iter().sum::<usize>().max(1);
+    let percentage = |count: usize| format!("{:.1} %", count as f64 * 100.0 / total as f64);
+
+    // Collecting counts by base color, base color 0 is for the unexplored pixels
+    let mut base_colors: Vec<(u8, [usize; 4])> = histogram
+        .chunks(4)
+        .enumerate()
+        .skip(1)
+        .map(|(base_color, shades)| {
+            (
+                base_color as u8,
+                [shades[0], shades[1], shades[2], shades[3]],
+            )
+        })
+        .filter(|(_, shades)| shades.iter().sum::<usize>() > 0)
+        .collect();
+    base_colors.sort_by_key(|(_, shades)| std::cmp::Reverse(shades.iter().sum::<usize>()));
+
+    let mut table = Table::new();
+    table.load_preset(presets::NOTHING);
+    table.set_style(TableComponent::HeaderLines, charset().dashed());
+    table.set_style(TableComponent::VerticalLines, ' ');
+    let mut header = vec![
+        Cell::new(tr("Color")).set_alignment(CellAlignment::Left),
+        Cell::new(tr("Pixels")).set_alignment(CellAlignment::Right),
+        Cell::new(tr("Coverage")).set_alignment(CellAlignment::Right),
+    ];
+    for shade in SHADE_NAMES {
+        header.push(Cell::new(tr(shade)).set_alignment(CellAlignment::Right));
+    }
+    table.set_header(header);
+    for (base_color, shades) in base_colors.iter().take(top) {
+        let count = shades.iter().sum::<usize>();
+        let mut row = vec![
+            Cell::new(tr(base_color_name(*base_color))).set_alignment(CellAlignment::Left),
+            Cell::new(count).set_alignment(CellAlignment::Right),
+            Cell::new(percentage(count)).set_alignment(CellAlignment::Right),
+        ];
+        for shade in shades {
+            row.push(Cell::new(shade).set_alignment(CellAlignment::Right));
+        }
+        table.add_row(row);
+    }
+    let unexplored = histogram[0..4].iter().sum::<usize>();
+    table.add_row(vec![
+        Cell::new(tr("Unexplored")).set_alignment(CellAlignment::Left),
+        Cell::new(unexplored).set_alignment(CellAlignment::Right),
+        Cell::new(percentage(unexplored)).set_alignment(CellAlignment::Right),
+    ]);
+    table
+}
@@ -0,0 +1,46 @@
+//! Selecting the map files for the commands
+
+use minecraft_map_tool::error::Result;
+use minecraft_map_tool::{read_maps_from, ReadMap, SortSpec};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{stdin, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Reads the map files from the *paths* or from the list in *files_from*
+///
+/// The list has one map file path on each line, so the output of `find` or `fd` can be used.
+/// With `-` the list is read from the standard input.
+pub fn read_input(
+    paths: &[PathBuf],
+    files_from: Option<&Path>,
+    sort: &Option<SortSpec>,
+    recursive: bool,
+) -> Result<ReadMap> {
+    let files_from = match files_from {
+        Some(files_from) => files_from,
+        None => return read_maps_from(paths, sort, recursive),
+    };
+    let map_files = if files_from == Path::new("-") {
+        read_path_list(stdin().lock())?
+    } else {
+        read_path_list(BufReader::new(File::open(files_from)?))?
+    };
+    let mut maps = ReadMap::from_paths(map_files);
+    if let Some(sort) = sort {
+        maps.sort(sort);
+    }
+    Ok(maps)
+}
+
+fn read_path_list(reader: impl BufRead) -> std::io::Result<VecDeque<PathBuf>> {
+    let mut paths = VecDeque::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if !line.is_empty() {
+            paths.push_back(PathBuf::from(line));
+        }
+    }
+    Ok(paths)
+}
@@ -0,0 +1,179 @@
+//! Item frames holding maps in a world
+//!
+//! Maps placed in item frames are stored as entities in the region files. Since Java
+//! Edition 1.17 entities are in the `entities` directory, and before that they were in
+//! the chunks of the `region` directory. Both are scanned, so the same search works
+//! for old and new worlds.
+
+use crate::error::Result;
+use crate::region::{field, number, scan_chunks, Scan};
+use crate::source::IdRanges;
+use fastnbt::Value;
+use std::path::{Path, PathBuf};
+
+/// Item frame found in the world with a map in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapFrame {
+    /// Id of the map in the frame
+    pub map_id: u32,
+
+    /// Pretty name of the dimension
+    pub dimension: String,
+
+    /// Block position of the frame
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+
+    /// Direction the frame is facing, like north or up
+    pub facing: String,
+
+    /// True for glow item frames
+    pub glowing: bool,
+
+    /// Region file the frame was found in
+    pub region: PathBuf,
+}
+
+/// Finds the item frames holding maps in the *world* directory
+///
+/// Only frames holding maps with ids in *ids* are returned, or all of them when *ids* is
+/// None. The frames are sorted by the map id.
+pub fn find_map_frames(world: &Path, ids: Option<&IdRanges>) -> Result<Vec<MapFrame>> {
+    Ok(scan_map_frames(world, ids)?.found)
+}
+
+/// Finds the item frames like [find_map_frames], also returning the parts of the world
+/// that could not be read
+pub fn scan_map_frames(world: &Path, ids: Option<&IdRanges>) -> Result<Scan<MapFrame>> {
+    let mut frames = Vec::new();
+    let errors = scan_chunks(
+        world,
+        &["entities", "region"],
+        |dimension, region, chunk| {
+            for entity in chunk_entities(chunk) {
+                let Some(frame) = map_frame(entity, dimension, region) else {
+                    continue;
+                };
+                if ids.is_none_or(|ids| ids.contains(frame.map_id)) {
+                    frames.push(frame);
+                }
+            }
+        },
+    )?;
+    frames.sort_by_key(|frame| frame.map_id);
+    Ok(Scan {
+        found: frames,
+        errors,
+    })
+}
+
+/// Entities of an entity chunk (1.17+) or a region chunk (before 1.18)
+fn chunk_entities(chunk: &Value) -> &[Value] {
+    let entities = field(chunk, "Entities").or_else(|| field(field(chunk, "Level")?, "Entities"));
+    match entities {
+        Some(Value::List(entities)) => entities,
+        _ => &[],
+    }
+}
+
+/// Reads the item frame *entity*, if it holds a map
+fn map_frame(entity: &Value, dimension: &str, region: &Path) -> Option<MapFrame> {
+    let glowing = match field(entity, "id")? {
+        Value::String(id) if id == "minecraft:item_frame" || id == "ItemFrame" => false,
+        Value::String(id) if id == "minecraft:glow_item_frame" => true,
+        _ => return None,
+    };
+    let item = field(entity, "Item")?;
+    match field(item, "id")? {
+        Value::String(id) if id == "minecraft:filled_map" => {}
+        _ => return None,
+    }
+    // The id moved from the item tag to the item components in 1.20.5
+    let map_id = field(item, "components")
+        .and_then(|components| field(components, "minecraft:map_id"))
+        .or_else(|| field(field(item, "tag")?, "map"))
+        .and_then(number);
+    // Before 1.13 the id was the damage value of the item
+    let legacy = map_id.is_none();
+    let map_id = match map_id {
+        Some(map_id) => map_id,
+        None => number(field(item, "Damage")?)?,
+    };
+    let facing_value = field(entity, "Facing").and_then(number);
+    Some(MapFrame {
+        map_id: u32::try_from(map_id).ok()?,
+        dimension: dimension.to_string(),
+        x: number(field(entity, "TileX")?)? as i32,
+        y: number(field(entity, "TileY")?)? as i32,
+        z: number(field(entity, "TileZ")?)? as i32,
+        facing: match legacy {
+            true => legacy_facing(facing_value),
+            false => facing(facing_value),
+        },
+        glowing,
+        region: PathBuf::from(region),
+    })
+}
+
+fn facing(facing: Option<i64>) -> String {
+    match facing {
+        Some(0) => "down",
+        Some(1) => "up",
+        Some(2) => "north",
+        Some(3) => "south",
+        Some(4) => "west",
+        Some(5) => "east",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+/// Frames before 1.13 could only hang on walls, and their facing was counted differently
+fn legacy_facing(facing: Option<i64>) -> String {
+    match facing {
+        Some(0) => "south",
+        Some(1) => "west",
+        Some(2) => "north",
+        Some(3) => "east",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::item_frames::{find_map_frames, map_frame};
+    use crate::source::parse_id_ranges;
+    use crate::synthetic::write_synthetic_world;
+    use fastnbt::nbt;
+    use std::path::Path;
+
+    #[test]
+    fn test_find_map_frames() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let frames = find_map_frames(world.path(), None).unwrap();
+        let ids: Vec<_> = frames.iter().map(|frame| frame.map_id).collect();
+        assert_eq!(ids, [0, 5, 24]);
+
+        let ids = parse_id_ranges("5").unwrap();
+        let frames = find_map_frames(world.path(), Some(&ids)).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].dimension, "Overworld");
+        assert_eq!((frames[0].x, frames[0].y, frames[0].z), (20, 65, -3));
+        assert_eq!(frames[0].facing, "north");
+        assert!(frames[0].glowing);
+
+        // Frames from before 1.13 keep the map id in the damage value
+        let entity = nbt!({
+            "id": "ItemFrame",
+            "Item": { "id": "minecraft:filled_map", "Count": 1_i8, "Damage": 7_i16 },
+            "TileX": 1, "TileY": 70, "TileZ": -2, "Facing": 1_i8,
+        });
+        let frame = map_frame(&entity, "Overworld", Path::new("r.0.-1.mca")).unwrap();
+        assert_eq!(frame.map_id, 7);
+        assert_eq!(frame.facing, "west");
+        assert!(!frame.glowing);
+    }
+}
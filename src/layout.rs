@@ -0,0 +1,134 @@
+//! Page layout for printed documents
+//!
+//! Pages are split into a grid of equally sized cells. Each cell has room for an image and
+//! a caption under it. Units are PDF points (1/72 inch) and the origin is in the lower-left
+//! corner of the page, as in PDF.
+
+/// A4 page width in points
+pub const A4_WIDTH: f32 = 595.0;
+
+/// A4 page height in points
+pub const A4_HEIGHT: f32 = 842.0;
+
+/// Rectangle on a page
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Grid of cells on a page
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridLayout {
+    /// Page width
+    pub page_width: f32,
+
+    /// Page height
+    pub page_height: f32,
+
+    /// Empty space around the grid
+    pub margin: f32,
+
+    /// Space reserved for the heading at the top of the page
+    pub heading_height: f32,
+
+    /// Space between the cells
+    pub gap: f32,
+
+    /// Number of columns
+    pub columns: usize,
+
+    /// Number of rows
+    pub rows: usize,
+
+    /// Space reserved for the caption under each image
+    pub caption_height: f32,
+}
+
+impl GridLayout {
+    /// A4 page layout with room for *per_page* cells
+    ///
+    /// The grid is made as close to square as possible, having more rows than columns
+    /// because the page is taller than it is wide.
+    pub fn a4(per_page: usize, caption_lines: usize) -> GridLayout {
+        let per_page = per_page.max(1);
+        let columns = ((per_page as f32).sqrt().floor() as usize).max(1);
+        GridLayout {
+            page_width: A4_WIDTH,
+            page_height: A4_HEIGHT,
+            margin: 36.0,
+            heading_height: 24.0,
+            gap: 12.0,
+            columns,
+            rows: per_page.div_ceil(columns),
+            caption_height: caption_lines as f32 * 11.0 + 4.0,
+        }
+    }
+
+    /// Number of cells on one page
+    pub fn cells_per_page(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Area of the cell with the *index* on a page, including the caption
+    pub fn cell(&self, index: usize) -> Rect {
+        let column = index % self.columns;
+        let row = (index / self.columns) % self.rows;
+        let width = (self.page_width - 2.0 * self.margin - (self.columns - 1) as f32 * self.gap)
+            / self.columns as f32;
+        let height = (self.page_height
+            - 2.0 * self.margin
+            - self.heading_height
+            - (self.rows - 1) as f32 * self.gap)
+            / self.rows as f32;
+        Rect {
+            x: self.margin + column as f32 * (width + self.gap),
+            y: self.page_height
+                - self.margin
+                - self.heading_height
+                - (row + 1) as f32 * height
+                - row as f32 * self.gap,
+            width,
+            height,
+        }
+    }
+
+    /// Square area for the image in the cell, centered above the caption
+    pub fn image_area(&self, cell: &Rect) -> Rect {
+        let size = cell.width.min(cell.height - self.caption_height).max(0.0);
+        Rect {
+            x: cell.x + (cell.width - size) / 2.0,
+            y: cell.y + self.caption_height,
+            width: size,
+            height: size,
+        }
+    }
+
+    /// Position of the heading baseline
+    pub fn heading_position(&self) -> (f32, f32) {
+        (
+            self.margin,
+            self.page_height - self.margin - self.heading_height + 8.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::GridLayout;
+
+    #[test]
+    fn test_grid_layout() {
+        let layout = GridLayout::a4(6, 3);
+        assert_eq!((layout.columns, layout.rows), (2, 3));
+        let first = layout.cell(0);
+        let last = layout.cell(5);
+        assert!(first.x < last.x && first.y > last.y);
+        assert!((last.y - layout.margin).abs() < 0.01);
+        let image = layout.image_area(&first);
+        assert_eq!(image.width, image.height);
+        assert!(image.y + image.height <= first.y + first.height + 0.01);
+    }
+}
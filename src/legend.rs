@@ -0,0 +1,236 @@
+//! Banner statistics and legends
+//!
+//! Communities often agree on banner colors, like red for danger and blue for bases. The
+//! banners marked on maps are grouped here by their color, with the names used for each
+//! color, so the conventions can be checked and shown as a legend on stitched images.
+
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::markers::{MarkerKind, PointMarker};
+use crate::BannerColor;
+use image::{Rgba, RgbaImage};
+
+/// Most names listed for one color in a legend
+pub const MAX_LEGEND_NAMES: usize = 8;
+
+/// Name of the banners without a custom name, see [Banner::extract_name](crate::Banner::extract_name)
+const NAMELESS: &str = "[nameless]";
+
+/// Background of the legend box
+const LEGEND_BACKGROUND: Rgba<u8> = Rgba([24, 24, 24, 220]);
+
+/// Color of the legend text
+const LEGEND_TEXT: Rgba<u8> = Rgba([238, 238, 238, 255]);
+
+/// Banners with the same color
+#[derive(Clone, Debug, PartialEq)]
+pub struct BannerGroup {
+    pub color: BannerColor,
+
+    /// Number of banners with the color
+    pub count: usize,
+
+    /// Names of the named banners and how many banners have each name, most common first
+    pub names: Vec<(String, usize)>,
+}
+
+impl BannerGroup {
+    /// Number of banners without a custom name
+    pub fn nameless(&self) -> usize {
+        self.count - self.names.iter().map(|(_, count)| count).sum::<usize>()
+    }
+}
+
+/// Groups the banners in the *markers* by their color
+///
+/// Frame markers are skipped. The most used colors come first, and colors with as many
+/// banners are in alphabetical order.
+pub fn banner_groups(markers: &[PointMarker]) -> Vec<BannerGroup> {
+    let mut groups: Vec<BannerGroup> = Vec::new();
+    let banners = markers
+        .iter()
+        .filter(|marker| marker.kind == MarkerKind::Banner);
+    for marker in banners {
+        let Some(color) = &marker.color else {
+            continue;
+        };
+        let index = match groups.iter().position(|group| &group.color == color) {
+            Some(index) => index,
+            None => {
+                groups.push(BannerGroup {
+                    color: color.clone(),
+                    count: 0,
+                    names: Vec::new(),
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.count += 1;
+        if marker.label == NAMELESS {
+            continue;
+        }
+        match group
+            .names
+            .iter_mut()
+            .find(|(name, _)| name == &marker.label)
+        {
+            Some((_, count)) => *count += 1,
+            None => group.names.push((marker.label.clone(), 1)),
+        }
+    }
+    for group in &mut groups {
+        group
+            .names
+            .sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    }
+    groups.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.color.to_string().cmp(&b.color.to_string()))
+    });
+    groups
+}
+
+/// Draws a legend of the banner *groups* into the upper left corner of the *image*
+///
+/// Each color has a swatch with the color name and the banner count, followed by the
+/// [MAX_LEGEND_NAMES] most common names. Each font pixel is *scale* image pixels wide.
+pub fn draw_legend(image: &mut RgbaImage, groups: &[BannerGroup], scale: u32) {
+    let lines = legend_lines(groups);
+    if lines.is_empty() {
+        return;
+    }
+    let padding = 3 * scale;
+    let line_height = (GLYPH_HEIGHT + 2) * scale;
+    let swatch = GLYPH_HEIGHT * scale;
+    let indent = swatch + 2 * scale;
+    let width = lines
+        .iter()
+        .map(|(_, text)| indent + text_width(text, scale))
+        .max()
+        .unwrap_or(0)
+        + 2 * padding;
+    let height = lines.len() as u32 * line_height - 2 * scale + 2 * padding;
+    for y in 0..height.min(image.height()) {
+        for x in 0..width.min(image.width()) {
+            let pixel = image.get_pixel_mut(x, y);
+            *pixel = blend(*pixel, LEGEND_BACKGROUND);
+        }
+    }
+
+    let max_width = image.width().saturating_sub(padding + indent);
+    for (row, (color, text)) in lines.iter().enumerate() {
+        let y = padding + row as u32 * line_height;
+        if let Some(color) = color {
+            let [r, g, b] = color.rgb();
+            for dy in 0..swatch {
+                for dx in 0..swatch {
+                    if let Some(pixel) = image.get_pixel_mut_checked(padding + dx, y + dy) {
+                        *pixel = Rgba([r, g, b, 255]);
+                    }
+                }
+            }
+        }
+        draw_text(
+            image,
+            padding + indent,
+            y,
+            max_width,
+            scale,
+            text,
+            LEGEND_TEXT,
+        );
+    }
+}
+
+/// Lines of the legend, the color is given on the first line of each group
+fn legend_lines(groups: &[BannerGroup]) -> Vec<(Option<BannerColor>, String)> {
+    let mut lines = Vec::new();
+    for group in groups {
+        lines.push((
+            Some(group.color.clone()),
+            format!("{} {}", color_name(&group.color), group.count),
+        ));
+        for (name, count) in group.names.iter().take(MAX_LEGEND_NAMES) {
+            let text = match count {
+                1 => format!("  {name}"),
+                _ => format!("  {name} {count}"),
+            };
+            lines.push((None, text));
+        }
+        if group.names.len() > MAX_LEGEND_NAMES {
+            let more = group.names.len() - MAX_LEGEND_NAMES;
+            lines.push((None, format!("  +{more} more")));
+        }
+    }
+    lines
+}
+
+/// Name of the *color* with spaces, like "light blue"
+pub fn color_name(color: &BannerColor) -> String {
+    let mut name = String::new();
+    for (index, character) in color.to_string().chars().enumerate() {
+        if index > 0 && character.is_ascii_uppercase() {
+            name.push(' ');
+        }
+        name.push(character.to_ascii_lowercase());
+    }
+    name
+}
+
+/// The *top* color drawn over the *bottom* color with its alpha
+pub(crate) fn blend(bottom: Rgba<u8>, top: Rgba<u8>) -> Rgba<u8> {
+    let alpha = top[3] as u32;
+    let mix = |b: u8, t: u8| ((t as u32 * alpha + b as u32 * (255 - alpha)) / 255) as u8;
+    Rgba([
+        mix(bottom[0], top[0]),
+        mix(bottom[1], top[1]),
+        mix(bottom[2], top[2]),
+        bottom[3].max(top[3]),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::legend::{banner_groups, color_name, draw_legend, LEGEND_BACKGROUND};
+    use crate::markers::{MarkerKind, PointMarker};
+    use crate::{BannerColor, Pos};
+    use image::RgbaImage;
+
+    fn banner(color: BannerColor, label: &str, x: i32) -> PointMarker {
+        PointMarker {
+            kind: MarkerKind::Banner,
+            label: label.to_string(),
+            pos: Pos { x, y: 64, z: 0 },
+            color: Some(color),
+        }
+    }
+
+    #[test]
+    fn test_banner_groups() {
+        let markers = [
+            banner(BannerColor::Blue, "Base", 0),
+            banner(BannerColor::Red, "Danger", 1),
+            banner(BannerColor::Red, "[nameless]", 2),
+            banner(BannerColor::Red, "Danger", 3),
+            banner(BannerColor::LightBlue, "Ice", 4),
+        ];
+        let groups = banner_groups(&markers);
+        let colors: Vec<_> = groups.iter().map(|group| group.color.clone()).collect();
+        assert_eq!(
+            colors,
+            [BannerColor::Red, BannerColor::Blue, BannerColor::LightBlue]
+        );
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].names, [(String::from("Danger"), 2)]);
+        assert_eq!(groups[0].nameless(), 1);
+        assert_eq!(color_name(&BannerColor::LightBlue), "light blue");
+
+        let mut image = RgbaImage::new(200, 100);
+        draw_legend(&mut image, &groups, 1);
+        // The swatch of the first group is at the padding
+        assert_eq!(image.get_pixel(3, 3).0[..3], BannerColor::Red.rgb());
+        assert_eq!(image.get_pixel(1, 1).0[3], LEGEND_BACKGROUND[3]);
+        assert_eq!(image.get_pixel(199, 99).0[3], 0);
+    }
+}
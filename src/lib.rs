@@ -1,25 +1,98 @@
+use crate::compression::{write_compressed, NbtCompression};
+use crate::edit::aligned_center;
 use crate::error::{Error, Result};
-use crate::palette::Palette;
-use crate::versions::MINECRAFT_VERSIONS;
-use clap::ValueEnum;
+use crate::handle::{MapHandle, MapMetadata};
+use crate::limits::{read_limited, ReadOptions};
+use crate::palette::{apply_palette, Palette};
+#[cfg(feature = "fs")]
+use crate::source::expand_paths;
+use crate::source::{
+    is_map_file, map_id, open_file, open_source, DirectorySource, IdRanges, MapSource, MultiSource,
+};
+use crate::text::StyledText;
 use fastnbt::ByteArray;
-use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use heck::ToTitleCase;
-use image::{Rgba, RgbaImage};
+use image::RgbaImage;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     collections::VecDeque,
     fs::File,
+    io::Read,
     path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::SystemTime,
 };
 
+pub mod annotations;
+pub mod canvas;
+pub mod compare;
+pub mod compression;
+pub mod containers;
+pub mod diff;
+pub mod edit;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod font;
+pub mod format;
+pub mod frame;
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+pub mod handle;
+pub mod item_frames;
+pub mod layout;
+pub mod legend;
+pub mod limits;
+pub mod locale;
+#[cfg(feature = "fs")]
+pub mod manifest;
+pub mod map_art;
+pub mod markers;
+pub mod mask;
+pub mod merge;
+pub mod naming;
+pub mod nbt;
+#[cfg(feature = "async")]
+pub mod nonblocking;
 pub mod palette;
+pub mod pdf;
+pub mod player;
+pub mod postprocess;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod region;
+pub mod regions;
+pub mod renumber;
+pub mod routes;
+pub mod schematic;
+pub mod seams;
+pub mod sheet;
+pub mod sink;
+#[cfg(feature = "fs")]
+pub mod snapshot;
+pub mod source;
+pub mod stats;
+pub mod stitch;
+pub mod svg;
+#[cfg(any(test, feature = "dev_tools"))]
+pub mod synthetic;
+pub mod text;
+pub mod thumbs;
+pub mod transfer;
+pub mod transform;
+pub mod uncharted;
+pub mod usage;
+pub mod verify;
 pub mod versions;
+pub mod wall;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod waypoints;
 
 /// Banner color options
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum BannerColor {
     Black,
@@ -40,26 +113,47 @@ pub enum BannerColor {
     Yellow,
 }
 
+impl BannerColor {
+    /// Dye color as RGB
+    ///
+    /// Source: [https://minecraft.wiki/w/Dye#Color_values](https://minecraft.wiki/w/Dye#Color_values)
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            BannerColor::Black => [0x1d, 0x1d, 0x21],
+            BannerColor::Blue => [0x3c, 0x44, 0xaa],
+            BannerColor::Brown => [0x83, 0x54, 0x32],
+            BannerColor::Cyan => [0x16, 0x9c, 0x9c],
+            BannerColor::Gray => [0x47, 0x4f, 0x52],
+            BannerColor::Green => [0x5e, 0x7c, 0x16],
+            BannerColor::LightBlue => [0x3a, 0xb3, 0xda],
+            BannerColor::LightGray => [0x9d, 0x9d, 0x97],
+            BannerColor::Lime => [0x80, 0xc7, 0x1f],
+            BannerColor::Magenta => [0xc7, 0x4e, 0xbd],
+            BannerColor::Orange => [0xf9, 0x80, 0x1d],
+            BannerColor::Pink => [0xf3, 0x8b, 0xaa],
+            BannerColor::Purple => [0x89, 0x32, 0xb8],
+            BannerColor::Red => [0xb0, 0x2e, 0x26],
+            BannerColor::White => [0xf9, 0xff, 0xfe],
+            BannerColor::Yellow => [0xfe, 0xd8, 0x3d],
+        }
+    }
+}
+
 impl std::fmt::Display for BannerColor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{self:?}")
     }
 }
 
-/// For deserializing banner name from JSON
-#[derive(Debug, Deserialize, Serialize)]
-struct BannerName {
-    text: String,
-}
-
 /// A banner marker
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Banner {
     /// The color of the banner.
     pub color: BannerColor,
 
     /// The custom name of the banner, in JSON text. May not exist.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
 
     /// The block position of the banner in the world.
@@ -69,26 +163,19 @@ pub struct Banner {
 impl Banner {
     /// Returns the name only
     ///
-    /// Names are stored into JSON, and this function tries to extract the name out of JSON.
-    /// If banner does not have name, then `[nameless]` is returned.
-    ///
-    /// If name parsing from JSON fails, then error message is returned as name
+    /// Names are stored as JSON text components, and this function flattens the component
+    /// into plain text, see [StyledText]. If banner does not have name, then `[nameless]` is
+    /// returned. Names that are not valid JSON are returned as they are.
     pub fn extract_name(&self) -> String {
-        let json = match &self.name {
-            None => return "[nameless]".to_string(),
-            Some(json) => json,
-        };
-
-        // Try to deserialize from BannerName JSON format
-        if let Ok(banner_name) = serde_json::from_str::<BannerName>(json) {
-            return banner_name.text;
+        match self.styled_name() {
+            None => "[nameless]".to_string(),
+            Some(name) => name.plain(),
         }
+    }
 
-        // Try to deserialize as plain string
-        match serde_json::from_str::<String>(json) {
-            Ok(banner_name) => banner_name,
-            Err(error) => error.to_string(),
-        }
+    /// Returns the name with its colors and other styles, if the banner has a name
+    pub fn styled_name(&self) -> Option<StyledText> {
+        self.name.as_deref().map(StyledText::parse)
     }
 }
 
@@ -143,10 +230,7 @@ impl MapData {
     ///
     /// Returns `Overworld` instead of `minecraft:overworld`
     pub fn pretty_dimension(&self) -> String {
-        match self.dimension.find(':') {
-            None => self.dimension.clone(),
-            Some(pos) => self.dimension[pos + 1..].replace('_', " ").to_title_case(),
-        }
+        pretty_dimension(&self.dimension)
     }
 
     /// X coordinate for pixels on the left edge of the map
@@ -168,6 +252,94 @@ impl MapData {
     pub fn bottom(&self) -> i32 {
         self.z_center + 64 * 2i32.pow(self.scale as u32) - 1
     }
+
+    /// Center of the map with the *scale* that the game makes at the world coordinates *x*, *z*
+    ///
+    /// The game aligns the maps of each scale to a grid, so the maps of the same scale never
+    /// overlap. See also [edit::aligned_center] for a single coordinate.
+    pub fn aligned_center_for(x: i32, z: i32, scale: i8) -> (i32, i32) {
+        (aligned_center(x, scale), aligned_center(z, scale))
+    }
+
+    /// Column and row of the map in the grid of the maps with the *scale*
+    ///
+    /// The cell is found by the center of the map, and the cell (0, 0) is the one with the world
+    /// origin. Maps of the same scale are next to each other when their cells are next to each
+    /// other, and a map with a larger scale covers the maps that are in its cell at its scale.
+    pub fn grid_cell(&self, scale: i8) -> (i32, i32) {
+        let size = 128 * 2i32.pow(scale as u32);
+        (
+            (self.x_center + 64).div_euclid(size),
+            (self.z_center + 64).div_euclid(size),
+        )
+    }
+
+    /// Counts how many pixels there are for each color value
+    ///
+    /// The returned array is indexed with the color byte. Base color is `index / 4`
+    /// and shade is `index % 4`.
+    pub fn color_histogram(&self) -> [usize; 256] {
+        let mut histogram = [0usize; 256];
+        for color in self.colors.iter() {
+            histogram[*color as u8 as usize] += 1;
+        }
+        histogram
+    }
+
+    /// Fraction of the pixels that are explored, from 0.0 to 1.0
+    pub fn explored_fraction(&self) -> f64 {
+        let explored = self.colors.iter().filter(|&&c| c as u8 >= 4).count();
+        explored as f64 / self.colors.len().max(1) as f64
+    }
+
+    /// Shannon entropy of the explored pixel colors in bits
+    ///
+    /// Maps of only one color have zero entropy, and the more evenly the pixels are spread
+    /// over different colors, the higher the entropy is. Unexplored pixels are not counted.
+    pub fn color_entropy(&self) -> f64 {
+        let histogram = self.color_histogram();
+        let explored = &histogram[4..];
+        let total = explored.iter().sum::<usize>() as f64;
+        explored
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    /// Color value of the pixel in the column *x* and the row *z*
+    ///
+    /// Returns None for pixels outside the 128×128 map.
+    pub fn color_at(&self, x: usize, z: usize) -> Option<i8> {
+        if x >= 128 {
+            return None;
+        }
+        self.colors.get(z * 128 + x).copied()
+    }
+
+    /// Rows of color values from top to bottom, without copying them
+    pub fn rows(&self) -> impl Iterator<Item = &[i8]> + '_ {
+        self.colors.chunks_exact(128)
+    }
+
+    /// Writes the map as RGBA pixels into the *buffer* using the *palette*
+    ///
+    /// The buffer must have 128 × 128 × 4 bytes, and it can be reused for many maps, for
+    /// example the buffer of an [RgbaImage].
+    pub fn render_into(&self, buffer: &mut [u8], palette: &Palette) -> Result<()> {
+        let colors = self
+            .colors
+            .get(..128 * 128)
+            .ok_or_else(|| Error::map_item_error("Color buffer incomplete"))?;
+        if buffer.len() != 128 * 128 * 4 {
+            return Err(Error::map_item_error("Pixel buffer must have 65536 bytes"));
+        }
+        apply_palette(colors, palette, buffer);
+        Ok(())
+    }
 }
 
 /// Custom debug implementation to avoid printing all 16384 color values
@@ -194,6 +366,63 @@ impl std::fmt::Debug for MapData {
     }
 }
 
+/// Returns `Overworld` instead of `minecraft:overworld`
+pub(crate) fn pretty_dimension(dimension: &str) -> String {
+    match dimension.find(':') {
+        None => dimension.to_string(),
+        Some(pos) => dimension[pos + 1..].replace('_', " ").to_title_case(),
+    }
+}
+
+/// Dimension name from the world directory in the *path*, or from the *dimension*
+pub(crate) fn pretty_dimension_from_path(path: &Path, dimension: &str) -> String {
+    let path = path.to_string_lossy();
+    if path.contains("_nether") {
+        String::from("The Nether")
+    } else if path.contains("_the_end") {
+        String::from("The End")
+    } else {
+        pretty_dimension(dimension)
+    }
+}
+
+/// Kind of a map guessed from its data
+///
+/// Explorer maps and map art are recognized from the colors, so the guess can be wrong for
+/// maps that happen to have similar colors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MapKind {
+    /// Explorer map, like buried treasure, woodland mansion and ocean monument maps
+    Explorer,
+
+    /// Map art, a picture built from blocks
+    Artwork,
+
+    /// Map that shows the position of the player
+    Locator,
+
+    /// Map without the position of the player
+    Plain,
+}
+
+impl std::fmt::Display for MapKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MapKind::Explorer => "Explorer",
+            MapKind::Artwork => "Artwork",
+            MapKind::Locator => "Locator",
+            MapKind::Plain => "Plain",
+        })
+    }
+}
+
+/// Base colors the game uses for the terrain of explorer maps (orange and brown)
+const EXPLORER_COLORS: [usize; 2] = [15, 26];
+
+/// Base colors of wool and concrete that are rare in the nature, from magenta to blue, and
+/// green and red
+const ARTWORK_COLORS: [usize; 12] = [16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 27, 28];
+
 /// Content of the map_<#>.dat files
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MapItem {
@@ -214,17 +443,7 @@ pub struct MapItem {
 impl MapItem {
     pub fn make_image(&self, palette: &Palette) -> Result<RgbaImage> {
         let mut image = RgbaImage::new(128, 128);
-        let mut color = self.data.colors.iter();
-        for y in 0..128 {
-            for x in 0..128 {
-                let c = *color
-                    .next()
-                    .ok_or_else(|| Error::map_item_error("Color buffer incomplete"))?
-                    as u8;
-                let pixel = palette.get(c as usize).unwrap_or(&Rgba([0, 0, 0, 0]));
-                image.put_pixel(x, y, *pixel);
-            }
-        }
+        self.data.render_into(&mut image, palette)?;
         Ok(image)
     }
 
@@ -239,31 +458,77 @@ impl MapItem {
     /// | _the_end        | The End                        |
     /// | (none of above) | `self.data.pretty_dimension()` |
     pub fn pretty_dimension_from_path(&self) -> String {
-        let path = self.file.to_string_lossy();
-        if path.contains("_nether") {
-            String::from("The Nether")
-        } else if path.contains("_the_end") {
-            String::from("The End")
-        } else {
-            self.data.pretty_dimension()
-        }
+        pretty_dimension_from_path(&self.file, &self.data.dimension)
     }
 
     /// Read map item from the given *file* path
     pub fn read_from(file: &Path) -> Result<MapItem> {
-        let file_reader = File::open(file)?;
-        let decoder = GzDecoder::new(&file_reader);
-        let mut map_item: MapItem = fastnbt::from_reader(decoder)?;
+        MapItem::read_from_with(file, &limits::read_options())
+    }
+
+    /// Read map item from the *file* in the *source*
+    pub fn read_from_source(source: &dyn MapSource, file: &Path) -> Result<MapItem> {
+        MapItem::read_from_reader(source.open(file)?, file)
+    }
+
+    /// Read map item from the given *file* path with the limits in the *options*
+    pub fn read_from_with(file: &Path, options: &ReadOptions) -> Result<MapItem> {
+        MapItem::read_from_reader_with(open_file(file, options)?, file, options)
+    }
+
+    /// Read map item from the *reader* giving map file content
+    ///
+    /// The content can be compressed with gzip or zlib, or not compressed at all (see
+    /// [decompress](compression::decompress)). The *file* is only stored to the
+    /// [file](MapItem::file) field. The content is checked against the [limits::read_options].
+    pub fn read_from_reader<R: Read>(reader: R, file: &Path) -> Result<MapItem> {
+        MapItem::read_from_reader_with(reader, file, &limits::read_options())
+    }
+
+    /// Read map item from the *reader* with the limits in the *options*
+    ///
+    /// Files over the limits are errors, see [ReadOptions].
+    pub fn read_from_reader_with<R: Read>(
+        reader: R,
+        file: &Path,
+        options: &ReadOptions,
+    ) -> Result<MapItem> {
+        let bytes = read_limited(reader, options)?;
+        MapItem::from_nbt(fastnbt::from_bytes(&bytes)?, file)
+    }
+
+    /// Makes a map item from the NBT *root* of a map *file*
+    ///
+    /// The banners and frames can be in the layout of any game version, see [format].
+    pub fn from_nbt(mut root: fastnbt::Value, file: &Path) -> Result<MapItem> {
+        let data_version = match &root {
+            fastnbt::Value::Compound(root) => match root.get("DataVersion") {
+                Some(fastnbt::Value::Int(data_version)) => *data_version,
+                _ => 0,
+            },
+            _ => 0,
+        };
+        format::normalize_markers(&mut root, data_version);
+        let mut map_item: MapItem = fastnbt::from_value(&root)?;
         map_item.file = PathBuf::from(file);
         Ok(map_item)
     }
 
+    /// NBT of the map item, with the banners and frames in the layout of its data version
+    pub fn to_nbt(&self) -> Result<fastnbt::Value> {
+        let mut root = fastnbt::to_value(self)?;
+        format::versioned_markers(&mut root, self.data_version);
+        Ok(root)
+    }
+
     /// Write map item to custom location
     pub fn write_to(&self, file: &Path) -> Result<()> {
-        let file_writer = File::create(file)?;
-        let encoder = GzEncoder::new(file_writer, Compression::default());
-        fastnbt::to_writer(encoder, self)?;
-        Ok(())
+        self.write_compressed(file, NbtCompression::Gzip)
+    }
+
+    /// Write map item to the given *file* with the *compression*
+    pub fn write_compressed(&self, file: &Path, compression: NbtCompression) -> Result<()> {
+        write_compressed(File::create(file)?, &self.to_nbt()?, compression)
     }
 
     /// Write map item using its [file](MapItem::file) location
@@ -271,34 +536,81 @@ impl MapItem {
         self.write_to(&self.file)
     }
 
+    /// Guesses the kind of the map
+    ///
+    /// Explorer maps are drawn by the game in orange and brown, so maps where nine of ten
+    /// explored pixels have these colors are explorer maps. Nearly fully explored maps where
+    /// a quarter of the pixels have colors of wool and concrete, which are rare in the nature,
+    /// are map art. Other maps are locators or plain maps depending on the position tracking.
+    pub fn kind(&self) -> MapKind {
+        let histogram = self.data.color_histogram();
+        let explored = histogram[4..].iter().sum::<usize>().max(1) as f64;
+        let fraction = |base_colors: &[usize]| {
+            let count: usize = base_colors
+                .iter()
+                .map(|base| histogram[base * 4..base * 4 + 4].iter().sum::<usize>())
+                .sum();
+            count as f64 / explored
+        };
+        if fraction(&EXPLORER_COLORS) >= 0.9 {
+            MapKind::Explorer
+        } else if self.data.explored_fraction() >= 0.99 && fraction(&ARTWORK_COLORS) >= 0.25 {
+            MapKind::Artwork
+        } else if self.data.tracking_position != 0 {
+            MapKind::Locator
+        } else {
+            MapKind::Plain
+        }
+    }
+
     /// Version description
     ///
-    /// Returns version name from the [MINECRAFT_VERSIONS] table
-    /// or "Unknown" if matching version is not found.
+    /// Returns version name from the [versions::MINECRAFT_VERSIONS] table, or the releases around the
+    /// data version if it is not in the table. See [versions::describe].
     pub fn version_description(&self) -> String {
-        MINECRAFT_VERSIONS
-            .get(&self.data_version)
-            .unwrap_or(&"Unknown")
-            .to_string()
+        versions::describe(self.data_version)
     }
 }
 
 /// A marker
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Marker {
     /// Arbitrary unique value for the marker.
     pub entity_id: i32,
 
-    /// The rotation of the marker, ranging from 0 to 360.
+    /// The rotation of the marker, ranging from 0 to 360. Files may have rotations outside
+    /// this range, see [Marker::normalized_rotation].
     pub rotation: i32,
 
-    /// The rotation of the marker, ranging from 0 to 360.
+    /// The block position of the item frame in the world.
     pub pos: Pos,
 }
 
+/// Compass directions in the order of the rotations, starting from south
+const DIRECTIONS: [&str; 8] = ["S", "SW", "W", "NW", "N", "NE", "E", "SE"];
+
+impl Marker {
+    /// The rotation wrapped into the range from 0 to 359
+    ///
+    /// The game has written negative rotations, like -90 for frames on floors and ceilings,
+    /// and rotations of 360 and above are seen in edited files.
+    pub fn normalized_rotation(&self) -> i32 {
+        self.rotation.rem_euclid(360)
+    }
+
+    /// Compass direction the item frame faces, like `N` or `SE`
+    ///
+    /// Rotations follow the yaw of the entities, where 0 faces south and 90 faces west. The
+    /// rotation is rounded to the nearest of the eight directions.
+    pub fn cardinal_direction(&self) -> &'static str {
+        let index = (self.normalized_rotation() * 2 + 45) / 90;
+        DIRECTIONS[index as usize % DIRECTIONS.len()]
+    }
+}
+
 /// Position coordinate in the Minecraft world
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Pos {
     /// The x-position
@@ -311,8 +623,9 @@ pub struct Pos {
     pub z: i32,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ReadMap {
+    source: Arc<dyn MapSource>,
     map_files: VecDeque<PathBuf>,
 }
 
@@ -323,7 +636,9 @@ impl ReadMap {
             return None;
         }
         let mut iter = self.map_files.iter();
-        let mut base = iter.next().unwrap().clone();
+        // Starting from the directory keeps the file name when there is only one file
+        let first = iter.next().unwrap();
+        let mut base = first.parent().unwrap_or(first).to_path_buf();
         for path in iter {
             let mut new_base = PathBuf::new();
             let a_components = base.components();
@@ -343,115 +658,363 @@ impl ReadMap {
         self.map_files.len()
     }
 
+    /// Map files from the file system
     pub fn from_paths(map_files: VecDeque<PathBuf>) -> ReadMap {
-        ReadMap { map_files }
+        ReadMap {
+            source: Arc::new(DirectorySource::new(Path::new("."))),
+            map_files,
+        }
+    }
+
+    /// Map files from the given *source*
+    pub fn from_source(source: Arc<dyn MapSource>, map_files: VecDeque<PathBuf>) -> ReadMap {
+        ReadMap { source, map_files }
+    }
+
+    /// The source where the map files are read from
+    pub fn source(&self) -> Arc<dyn MapSource> {
+        self.source.clone()
     }
 
     pub fn is_empty(&self) -> bool {
         self.map_files.is_empty()
     }
+
+    /// Paths of the map files that are not read yet
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.map_files.iter()
+    }
+
+    /// Reads the map files that are not read yet without removing them
+    ///
+    /// Unlike iterating the ReadMap itself, this can be done many times.
+    pub fn iter(&self) -> impl Iterator<Item = Result<MapItem>> + '_ {
+        self.map_files
+            .iter()
+            .map(|path| MapItem::read_from_source(self.source.as_ref(), path))
+    }
+
+    /// Reads the metadata of the map files that are not read yet, see [MapHandle]
+    ///
+    /// The path is returned with the result, so files that could not be read can be reported.
+    pub fn handles(&self) -> impl Iterator<Item = (&Path, Result<MapHandle>)> + '_ {
+        self.map_files
+            .iter()
+            .map(|path| (path.as_path(), MapHandle::open(self.source.clone(), path)))
+    }
+
+    /// Reads the map file at the *index* of the files that are not read yet
+    pub fn get(&self, index: usize) -> Option<Result<MapItem>> {
+        self.map_files
+            .get(index)
+            .map(|path| MapItem::read_from_source(self.source.as_ref(), path))
+    }
+
+    /// Keeps only the map files for which the *predicate* returns true
+    pub fn retain<F: FnMut(&Path) -> bool>(&mut self, mut predicate: F) {
+        self.map_files.retain(|path| predicate(path));
+    }
+
+    /// Keeps only the map files with the selected *ids*
+    ///
+    /// The id is parsed from the file name, so files not named map_<#>.dat are removed.
+    pub fn retain_ids(&mut self, ids: &IdRanges) {
+        self.retain(|path| map_id(path).is_some_and(|id| ids.contains(id)));
+    }
+
+    /// Sorts the map files that are not read yet
+    ///
+    /// The sort key of each file is found only once, so sorting by the data version or scale
+    /// reads each map file once.
+    pub fn sort(&mut self, spec: &SortSpec) {
+        let mut keyed: Vec<(Vec<SortKey>, PathBuf)> = self
+            .map_files
+            .drain(..)
+            .map(|path| (spec.keys_in(self.source.as_ref(), &path), path))
+            .collect();
+        keyed.sort_by(|a, b| cmp_keys(&a.0, &b.0, &spec.keys));
+        self.map_files = keyed.into_iter().map(|(_, path)| path).collect();
+    }
+
+    /// Reads the next map file and returns its path with the result
+    ///
+    /// Useful for reporting which map files could not be read.
+    pub fn next_with_path(&mut self) -> Option<(PathBuf, Result<MapItem>)> {
+        self.map_files.pop_front().map(|path| {
+            let map_item = MapItem::read_from_source(self.source.as_ref(), &path);
+            (path, map_item)
+        })
+    }
 }
 
 impl Iterator for ReadMap {
     type Item = Result<MapItem>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.map_files
-            .pop_front()
-            .map(|path| MapItem::read_from(&path))
+        self.next_with_path().map(|(_, map_item)| map_item)
     }
 }
 
+/// Finds map files from the *path*
+///
+/// The *path* can be a directory or a world backup archive (see [open_source]).
 pub fn read_maps(path: &Path, sort: &Option<SortingOrder>, recursive: bool) -> Result<ReadMap> {
-    let mut directory_stack = VecDeque::new();
-    let mut map_files = VecDeque::new();
-    directory_stack.push_back(PathBuf::from(path));
-    while !directory_stack.is_empty() {
-        let dir = directory_stack.pop_front().unwrap();
-        let read_dir = match dir.read_dir() {
-            Ok(read_dir) => read_dir,
-            Err(err) => {
-                eprintln!("Warning: Could not read: {dir:?}, {err}");
-                continue;
-            }
-        };
-        for dir_entry in read_dir.flatten() {
-            let path = dir_entry.path();
-            if path.is_symlink() {
-                // We do not follow symlinks for now, could cause forever loop
-                continue;
-            } else if path.is_file()
-                && path.extension().unwrap_or_default() == "dat"
-                && path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_str()
-                    .unwrap_or_default()
-                    .starts_with("map_")
-            {
-                map_files.push_back(dir_entry.path());
-            } else if path.is_dir() && recursive {
-                directory_stack.push_back(dir_entry.path());
-            }
+    read_maps_sorted(path, &sort.map(SortSpec::from), recursive)
+}
+
+/// Finds map files from the *path* like [read_maps], sorting them by one or more keys
+pub fn read_maps_sorted(path: &Path, sort: &Option<SortSpec>, recursive: bool) -> Result<ReadMap> {
+    let source: Arc<dyn MapSource> = Arc::from(open_source(path)?);
+    let map_files = source.find_map_files(recursive)?;
+    let mut maps = ReadMap { source, map_files };
+    if let Some(sort) = sort {
+        maps.sort(sort);
+    }
+    Ok(maps)
+}
+
+/// Finds map files from all the *paths* and merges them
+///
+/// Each path can be a map file, a directory, or a world backup archive, and glob patterns
+/// like `data/map_1*.dat` are expanded with the `fs` feature (see `source::expand_paths`).
+/// The map files are sorted together, so maps from different paths are mixed in the given
+/// order.
+pub fn read_maps_from(
+    paths: &[PathBuf],
+    sort: &Option<SortSpec>,
+    recursive: bool,
+) -> Result<ReadMap> {
+    #[cfg(feature = "fs")]
+    let paths = expand_paths(paths)?;
+    #[cfg(not(feature = "fs"))]
+    let paths = paths.to_vec();
+    if let [path] = paths.as_slice() {
+        if !path.is_file() || !is_map_file(path) {
+            return read_maps_sorted(path, sort, recursive);
         }
     }
+    let mut multi_source = MultiSource::new();
+    for path in &paths {
+        multi_source.add_path(path, recursive)?;
+    }
+    let source: Arc<dyn MapSource> = Arc::new(multi_source);
+    let map_files = source.find_map_files(recursive)?;
+    let mut maps = ReadMap { source, map_files };
     if let Some(sort) = sort {
-        map_files.make_contiguous().sort_by(|a, b| sort.cmp(a, b));
+        maps.sort(sort);
     }
-    Ok(ReadMap { map_files })
+    Ok(maps)
 }
 
 /// Sorting order for map files
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum SortingOrder {
     /// Files are organized by name and numbers in the natural order
     Name,
 
     /// Files are organized from oldest to newest
     Time,
+
+    /// Files are organized by the map id in the file name (map_<#>.dat)
+    Id,
+
+    /// Files are organized from the oldest to the newest Minecraft version that created them
+    DataVersion,
+
+    /// Files are organized from the most detailed to the least detailed maps
+    Scale,
 }
 
 impl SortingOrder {
+    /// Returns an Ordering between map files *a* and *b* in the *source*
+    ///
+    /// Files with unknown modification times, ids, data versions or scales are ordered first.
+    /// Sorting by data version or scale reads the map files, so [ReadMap::sort] should be
+    /// preferred when sorting many files.
+    pub fn cmp_in(&self, source: &dyn MapSource, a: &Path, b: &Path) -> Ordering {
+        self.key_in(source, a).cmp(&self.key_in(source, b))
+    }
+
     /// This method returns an Ordering between *a* and *b* path based on *self* value.
     pub fn cmp(&self, a: &Path, b: &Path) -> Ordering {
+        self.cmp_in(&DirectorySource::new(Path::new(".")), a, b)
+    }
+
+    /// Parses the sorting order from its *name*, like `data-version`
+    fn from_name(name: &str) -> Option<SortingOrder> {
+        match name.to_lowercase().as_str() {
+            "name" => Some(SortingOrder::Name),
+            "time" => Some(SortingOrder::Time),
+            "id" => Some(SortingOrder::Id),
+            "data-version" => Some(SortingOrder::DataVersion),
+            "scale" => Some(SortingOrder::Scale),
+            _ => None,
+        }
+    }
+
+    fn key_in(&self, source: &dyn MapSource, path: &Path) -> SortKey {
+        // Only the metadata is needed, so the colors and markers are not decoded
+        let read_metadata = || {
+            source
+                .open(path)
+                .and_then(MapMetadata::read_from_reader)
+                .ok()
+        };
         match self {
-            SortingOrder::Name => {
-                let a_str = a.as_os_str().to_str().expect("invalid path");
-                let b_str = b.as_os_str().to_str().expect("invalid path");
-                natord::compare(a_str, b_str)
+            SortingOrder::Name => SortKey::Name(path.to_path_buf()),
+            SortingOrder::Time => SortKey::Time(source.modified(path)),
+            SortingOrder::Id => SortKey::Number(map_id(path).map(i64::from)),
+            SortingOrder::DataVersion => {
+                SortKey::Number(read_metadata().map(|metadata| i64::from(metadata.data_version)))
+            }
+            SortingOrder::Scale => {
+                SortKey::Number(read_metadata().map(|metadata| i64::from(metadata.scale)))
             }
-            SortingOrder::Time => {
-                let a_modified = &a
-                    .metadata()
-                    .expect("could not read metadata")
-                    .modified()
-                    .expect("could not get modification time");
-                let b_modified = &b
-                    .metadata()
-                    .expect("could not read metadata")
-                    .modified()
-                    .expect("could not get modification time");
-                a_modified.cmp(b_modified)
+        }
+    }
+}
+
+/// Sorting by one or more keys, like `scale,time` or `name:desc`
+///
+/// Each key is a [SortingOrder] and can end with `:asc` or `:desc`. Later keys are used when
+/// the earlier keys are equal.
+#[derive(Clone, Debug)]
+pub struct SortSpec {
+    keys: Vec<(SortingOrder, bool)>,
+}
+
+impl SortSpec {
+    /// Returns an Ordering between map files *a* and *b* in the *source*
+    pub fn cmp_in(&self, source: &dyn MapSource, a: &Path, b: &Path) -> Ordering {
+        cmp_keys(
+            &self.keys_in(source, a),
+            &self.keys_in(source, b),
+            &self.keys,
+        )
+    }
+
+    fn keys_in(&self, source: &dyn MapSource, path: &Path) -> Vec<SortKey> {
+        self.keys
+            .iter()
+            .map(|(order, _)| order.key_in(source, path))
+            .collect()
+    }
+}
+
+impl From<SortingOrder> for SortSpec {
+    fn from(order: SortingOrder) -> Self {
+        SortSpec {
+            keys: vec![(order, false)],
+        }
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        let mut keys = Vec::new();
+        for key in text.split(',') {
+            let (name, direction) = key.trim().split_once(':').unwrap_or((key.trim(), "asc"));
+            let order = SortingOrder::from_name(&name.replace('_', "-"))
+                .ok_or_else(|| format!("Unknown sorting order: {name}"))?;
+            let descending = match direction.to_lowercase().as_str() {
+                "asc" => false,
+                "desc" => true,
+                _ => return Err(format!("Unknown sorting direction: {direction}")),
+            };
+            keys.push((order, descending));
+        }
+        Ok(SortSpec { keys })
+    }
+}
+
+fn cmp_keys(a: &[SortKey], b: &[SortKey], spec: &[(SortingOrder, bool)]) -> Ordering {
+    for ((a, b), (_, descending)) in a.iter().zip(b).zip(spec) {
+        let ordering = match descending {
+            true => b.cmp(a),
+            false => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Value of a map file used for sorting, None is ordered first
+#[derive(Debug, PartialEq, Eq)]
+enum SortKey {
+    Name(PathBuf),
+    Time(Option<SystemTime>),
+    Number(Option<i64>),
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            // Paths that are not valid UTF-8 are compared lossily and then by their bytes,
+            // so the order is still total
+            (SortKey::Name(a), SortKey::Name(b)) => {
+                natord::compare(&a.to_string_lossy(), &b.to_string_lossy()).then_with(|| {
+                    a.as_os_str()
+                        .as_encoded_bytes()
+                        .cmp(b.as_os_str().as_encoded_bytes())
+                })
             }
+            (SortKey::Time(a), SortKey::Time(b)) => a.cmp(b),
+            (SortKey::Number(a), SortKey::Number(b)) => a.cmp(b),
+            // Keys from one sorting order are always the same kind
+            _ => Ordering::Equal,
         }
     }
 }
 
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::palette::{generate_palette, BASE_COLORS_2699};
-    use crate::MapItem;
+    use crate::source::map_id;
+    use crate::synthetic::{synthetic_maps, write_synthetic_world};
+    use crate::{read_maps, MapData, MapItem, MapKind, Marker, Pos, SortingOrder};
+    use fastnbt::ByteArray;
     use image::{GenericImageView, Pixel};
     use std::collections::BTreeMap;
     use std::path::{Path, PathBuf};
 
+    #[test]
+    fn test_render_into() {
+        let map_item = MapItem::read_from(&project_file(Path::new("tests/map_0.dat"))).unwrap();
+        let palette = generate_palette(&BASE_COLORS_2699);
+        let image = map_item.make_image(&palette).unwrap();
+        let mut buffer = vec![0; 128 * 128 * 4];
+        map_item.data.render_into(&mut buffer, &palette).unwrap();
+        assert_eq!(buffer, image.into_raw());
+        assert!(map_item
+            .data
+            .render_into(&mut buffer[4..], &palette)
+            .is_err());
+
+        let row = map_item.data.rows().nth(5).unwrap();
+        assert_eq!(map_item.data.rows().count(), 128);
+        assert_eq!(map_item.data.color_at(7, 5), Some(row[7]));
+        assert_eq!(map_item.data.color_at(128, 0), None);
+        assert_eq!(map_item.data.color_at(0, 128), None);
+    }
+
     #[test]
     fn test_make_image() {
-        let map_item = MapItem::read_from(&project_file(&Path::new("tests/map_0.dat"))).unwrap();
+        let map_item = MapItem::read_from(&project_file(Path::new("tests/map_0.dat"))).unwrap();
         let map_image = map_item
             .make_image(&generate_palette(&BASE_COLORS_2699))
             .unwrap();
-        let reference_image = image::open(&project_file(&Path::new("tests/map_0.png"))).unwrap();
+        let reference_image = image::open(project_file(Path::new("tests/map_0.png"))).unwrap();
         assert_eq!(map_image.dimensions(), reference_image.dimensions());
 
         // Comparing each pixel and collecting wrong colors to map
@@ -489,6 +1052,126 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_color_histogram() {
+        // The test map has 8×8 pixels for each color value
+        let map_item = MapItem::read_from(&project_file(Path::new("tests/map_0.dat"))).unwrap();
+        let histogram = map_item.data.color_histogram();
+        assert!(histogram.iter().all(|count| *count == 64));
+
+        // 252 explored colors with equal counts
+        assert_eq!(map_item.data.explored_fraction(), 252.0 / 256.0);
+        assert!((map_item.data.color_entropy() - 252f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_alignment() {
+        assert_eq!(MapData::aligned_center_for(0, 0, 0), (0, 0));
+        assert_eq!(MapData::aligned_center_for(-65, 63, 0), (-128, 0));
+        assert_eq!(MapData::aligned_center_for(100, -100, 1), (64, -192));
+        assert_eq!(MapData::aligned_center_for(1000, 1000, 4), (960, 960));
+
+        let mut map_item = synthetic_maps(Path::new("world")).remove(0);
+        (map_item.data.x_center, map_item.data.z_center) = (-128, 256);
+        assert_eq!(map_item.data.grid_cell(0), (-1, 2));
+        assert_eq!(map_item.data.grid_cell(1), (-1, 1));
+        assert_eq!(map_item.data.grid_cell(4), (-1, 0));
+    }
+
+    #[test]
+    fn test_marker_direction() {
+        let marker = |rotation| Marker {
+            entity_id: 1,
+            rotation,
+            pos: Pos { x: 0, y: 0, z: 0 },
+        };
+        assert_eq!(marker(0).cardinal_direction(), "S");
+        assert_eq!(marker(90).cardinal_direction(), "W");
+        assert_eq!(marker(180).cardinal_direction(), "N");
+        assert_eq!(marker(225).cardinal_direction(), "NE");
+        assert_eq!(marker(-90).normalized_rotation(), 270);
+        assert_eq!(marker(-90).cardinal_direction(), "E");
+        assert_eq!(marker(720).cardinal_direction(), "S");
+        assert_eq!(marker(337).cardinal_direction(), "SE");
+        assert_eq!(marker(338).cardinal_direction(), "S");
+    }
+
+    #[test]
+    fn test_map_kind() {
+        let mut map_item = synthetic_maps(Path::new("world")).remove(0);
+        assert_eq!(map_item.kind(), MapKind::Locator);
+        map_item.data.tracking_position = 0;
+        assert_eq!(map_item.kind(), MapKind::Plain);
+
+        // Orange land and brown water like on a buried treasure map
+        let colors = (0..128 * 128).map(|i| if i % 5 == 0 { 104 } else { 61 });
+        map_item.data.colors = ByteArray::new(colors.collect());
+        assert_eq!(map_item.kind(), MapKind::Explorer);
+
+        // Half of the pixels in blue wool
+        let colors = (0..128 * 128).map(|i| if i % 2 == 0 { 101 } else { 5 });
+        map_item.data.colors = ByteArray::new(colors.collect());
+        assert_eq!(map_item.kind(), MapKind::Artwork);
+    }
+
+    #[test]
+    fn test_sorting_without_metadata() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let data = world.path().join("data");
+        let missing = data.join("map_100.dat");
+        for order in [
+            SortingOrder::Time,
+            SortingOrder::DataVersion,
+            SortingOrder::Scale,
+        ] {
+            assert_eq!(
+                order.cmp(&missing, &data.join("map_0.dat")),
+                std::cmp::Ordering::Less
+            );
+        }
+
+        let mut maps = read_maps(&data, &Some(SortingOrder::Id), false).unwrap();
+        let ids: Vec<u32> = maps.paths().filter_map(|path| map_id(path)).collect();
+        assert_eq!(ids, (0..32).collect::<Vec<u32>>());
+        maps.sort(&"scale:desc,id".parse().unwrap());
+        assert_eq!(maps.next().unwrap().unwrap().data.scale, 4);
+    }
+
+    #[test]
+    fn test_read_map_reiteration() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let mut maps =
+            read_maps(&world.path().join("data"), &Some(SortingOrder::Id), false).unwrap();
+        maps.retain(|path| map_id(path).is_some_and(|id| id % 2 == 0));
+        assert_eq!(maps.iter().filter(|map_item| map_item.is_ok()).count(), 16);
+        assert_eq!(maps.iter().count(), 16);
+        let map_item = maps.get(1).unwrap().unwrap();
+        assert_eq!(map_id(&map_item.file), Some(2));
+        assert!(maps.get(16).is_none());
+        assert_eq!(maps.count(), 16);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sorting_non_utf8_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = Path::new(OsStr::from_bytes(b"map_\xff.dat"));
+        let order = SortingOrder::Name;
+        assert_eq!(
+            order.cmp(Path::new("map_2.dat"), Path::new("map_10.dat")),
+            std::cmp::Ordering::Less
+        );
+        assert_ne!(
+            order.cmp(invalid, Path::new("map_\u{fffd}.dat")),
+            std::cmp::Ordering::Equal
+        );
+        assert_eq!(order.cmp(invalid, invalid), std::cmp::Ordering::Equal);
+    }
+
     fn project_file(path: &Path) -> PathBuf {
         let mut relative_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         relative_path.push(path);
@@ -0,0 +1,152 @@
+//! Limits for reading map files from untrusted sources
+//!
+//! A small compressed file can expand to gigabytes, and NBT data can declare huge lists or
+//! nest compounds deep enough to exhaust the stack. Map files are checked against the
+//! [ReadOptions] before they are decoded, so services reading uploaded files fail with an
+//! error instead of running out of memory.
+
+use crate::compression::decompress;
+use crate::error::{Error, Result};
+use crate::handle::{NbtScanner, TAG_COMPOUND};
+use std::io::Read;
+use std::sync::RwLock;
+
+/// Limits for reading map files
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReadOptions {
+    /// Most bytes in the decompressed NBT data
+    pub max_size: u64,
+
+    /// Most banners and most frames in a map
+    pub max_markers: usize,
+
+    /// Deepest nesting of compounds and lists
+    pub max_depth: usize,
+
+    /// Memory map the map files on disk instead of reading them with system calls
+    ///
+    /// Decoding from a mapped file avoids a read call for each block of the file, which
+    /// helps when scanning tens of thousands of maps on fast drives. The mapping is only
+    /// safe while no other process changes the files: a file truncated while it is mapped
+    /// kills the program with `SIGBUS`, and any change is undefined behavior. Turning this
+    /// on is a promise that the files are left alone, for example that the game is not
+    /// running. Files in archives and on remote servers are never mapped.
+    pub memory_mapping: bool,
+}
+
+impl ReadOptions {
+    /// Limits used unless others are set, far above anything the game writes
+    pub const DEFAULT: ReadOptions = ReadOptions {
+        max_size: 16 * 1024 * 1024,
+        max_markers: 65_536,
+        max_depth: 64,
+        memory_mapping: false,
+    };
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions::DEFAULT
+    }
+}
+
+/// Limits for the reads that are not given their own, see [set_read_options]
+static READ_OPTIONS: RwLock<ReadOptions> = RwLock::new(ReadOptions::DEFAULT);
+
+/// Sets the limits used by [MapItem::read_from](crate::MapItem::read_from) and the other
+/// functions reading map files without their own [ReadOptions]
+pub fn set_read_options(options: ReadOptions) {
+    *READ_OPTIONS.write().unwrap_or_else(|err| err.into_inner()) = options;
+}
+
+/// Limits used when no other [ReadOptions] are given
+pub fn read_options() -> ReadOptions {
+    *READ_OPTIONS.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Reads and decompresses the NBT data from the *reader* and checks it against the *options*
+///
+/// Only the structure is checked, so the data can still be something else than a map item.
+pub fn read_limited<R: Read>(reader: R, options: &ReadOptions) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    decompress(reader)?
+        .take(options.max_size.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > options.max_size {
+        return Err(Error::map_item_error(
+            "Map file is larger than the size limit",
+        ));
+    }
+    check_nbt(&bytes, options)?;
+    Ok(bytes)
+}
+
+/// Checks the depth, the lengths and the banner and frame counts of the NBT data
+///
+/// The data is walked through with the same scanner that reads the metadata of map files.
+fn check_nbt(bytes: &[u8], options: &ReadOptions) -> Result<()> {
+    let mut nbt = NbtScanner::new(bytes, options);
+    nbt.read_root()?;
+    nbt.skip_payload(TAG_COMPOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::{write_compressed, NbtCompression};
+    use crate::limits::{read_limited, ReadOptions};
+    use crate::synthetic::synthetic_maps;
+    use crate::{Banner, BannerColor, MapItem, Pos};
+    use fastnbt::{nbt, Value};
+    use std::path::Path;
+
+    fn compressed<T: serde::Serialize>(value: &T) -> Vec<u8> {
+        let mut file = Vec::new();
+        write_compressed(&mut file, value, NbtCompression::Gzip).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_read_limited() {
+        let mut map_item = synthetic_maps(Path::new("world")).remove(0);
+        let banner = Banner {
+            color: BannerColor::Red,
+            name: None,
+            pos: Pos { x: 0, y: 0, z: 0 },
+        };
+        map_item.data.banners = vec![banner; 10];
+        let file = compressed(&map_item);
+        let options = ReadOptions::default();
+        let bytes = read_limited(file.as_slice(), &options).unwrap();
+        let read: MapItem = fastnbt::from_bytes(&bytes).unwrap();
+        assert_eq!(read.data.banners.len(), 10);
+
+        let small = ReadOptions {
+            max_size: 1000,
+            ..options
+        };
+        assert!(read_limited(file.as_slice(), &small).is_err());
+        let few_markers = ReadOptions {
+            max_markers: 9,
+            ..options
+        };
+        assert!(read_limited(file.as_slice(), &few_markers).is_err());
+
+        // Lists nested deeper than the limit
+        let mut value = nbt!([]);
+        for _ in 0..10 {
+            value = Value::List(vec![value]);
+        }
+        let file = compressed(&nbt!({ "data": value }));
+        let shallow = ReadOptions {
+            max_depth: 8,
+            ..options
+        };
+        assert!(read_limited(file.as_slice(), &options).is_ok());
+        assert!(read_limited(file.as_slice(), &shallow).is_err());
+
+        // Truncated file
+        let mut truncated = compressed(&map_item);
+        truncated.truncate(truncated.len() / 2);
+        assert!(read_limited(truncated.as_slice(), &options).is_err());
+    }
+}
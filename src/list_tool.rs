@@ -1,89 +1,280 @@
-use clap::{arg, Args};
-use comfy_table::{Cell, ContentArrangement, Table};
-use minecraft_map_tool::{read_maps, SortingOrder};
-use std::path::PathBuf;
+use crate::charset::preset;
+use crate::input::read_input;
+use clap::{Args, ValueEnum};
+use comfy_table::{presets, Cell, ContentArrangement, Table};
+use log::{error, info, warn};
+use minecraft_map_tool::locale::tr;
+use minecraft_map_tool::region::ChunkTimes;
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use minecraft_map_tool::SortSpec;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-
-#[cfg(not(target_os = "windows"))]
-const PRESET: &str = "││──╞═╪╡┆    ┬┴╭╮╰╯";
-
-// In Windows, rounded corners will work if the user has changed the command prompt to use
-// a UTF-8 compatible font. However, by default, this is not the case; therefore, we use
-// rectangular borders instead.
-#[cfg(target_os = "windows")]
-const PRESET: &str = "││──├─┼┤│    ┬┴┌┐└┘";
+use std::time::SystemTime;
+use time::macros::format_description;
+use time::OffsetDateTime;
 
 #[derive(Args, Debug)]
 pub struct ListArgs {
-    /// The directory from which map files are searched for
-    path: PathBuf,
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which map files are
+    /// searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path)
+    #[arg(required_unless_present = "files_from")]
+    paths: Vec<PathBuf>,
+
+    /// Read the map files from a list with one path on each line, or from standard input with -
+    #[arg(long, value_name = "FILE", conflicts_with = "paths")]
+    files_from: Option<PathBuf>,
 
     /// Search map files recursively in subdirectories
     #[arg(short, long)]
     recursive: bool,
 
-    /// Sorting order for files
+    /// Only use the maps with these ids, for example 10..200,500,731. Ranges include both ends
+    #[arg(long, value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+
+    /// Sorting order for files. Keys are name, time, id, data-version and scale.
+    /// Combine keys with commas and reverse them with :desc, for example scale,time:desc
     #[arg(short, long, default_value = "name")]
-    sort: Option<SortingOrder>,
+    sort: Option<SortSpec>,
 
     /// Try to detect world dimensions from the file path instead of map item data.
     #[arg(short, long)]
     dimension_from_path: bool,
+
+    /// Output format
+    #[arg(short, long, default_value = "table")]
+    format: TextFormat,
+
+    /// Add links to the images made with `images --output-dir <IMAGE_DIR>` to the Markdown output
+    #[arg(long)]
+    image_dir: Option<PathBuf>,
+
+    /// Score the maps and list the highest scores first. The maps are read fully, which is
+    /// slower than listing
+    #[arg(long)]
+    rank: Option<Rank>,
+
+    /// Add the modification times of the map files. For maps in a world directory, also add
+    /// the latest save time of the chunks under the map, and flag the maps older than their
+    /// terrain as stale. Stale maps need to be walked through again in the game
+    #[arg(long)]
+    age: bool,
+
+    /// Add the kind of the map guessed from its colors: explorer map, map art, or a regular map
+    /// with or without the player position. The maps are read fully, which is slower
+    #[arg(long)]
+    kind: bool,
+}
+
+/// Ways to score how interesting a map is
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Rank {
+    /// Fraction of the map that is explored
+    Explored,
+
+    /// Entropy of the explored colors in bits, maps with more different colors score higher
+    Variety,
+}
+
+/// Output formats for the text commands
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum TextFormat {
+    /// Table for the terminal
+    Table,
+
+    /// Markdown for GitHub wikis and Discord
+    Markdown,
 }
 
 pub fn run(args: &ListArgs) -> ExitCode {
-    let maps = match read_maps(&args.path, &args.sort, args.recursive) {
+    let mut maps = match read_input(
+        &args.paths,
+        args.files_from.as_deref(),
+        &args.sort,
+        args.recursive,
+    ) {
         Ok(maps) => maps,
         Err(err) => {
-            eprintln!("Could not get maps: {err}");
+            error!("Could not get maps: {err}");
             return ExitCode::FAILURE;
         }
     };
+    if let Some(ids) = &args.ids {
+        maps.retain_ids(ids);
+    }
     if maps.is_empty() {
-        println!("Nothing to list");
+        error!("Nothing to list");
         return ExitCode::FAILURE;
     }
     let common_base_path = maps.common_base_path().unwrap_or_default();
+    let image_dir = match args.format {
+        TextFormat::Markdown => args.image_dir.as_ref(),
+        TextFormat::Table => None,
+    };
+    let mut header = vec![
+        "File",
+        "Zoom",
+        "Dimension",
+        "Locked",
+        "Center",
+        "Left",
+        "Top",
+        "Right",
+        "Bottom",
+        "Banners",
+        "Frames",
+    ];
+    if image_dir.is_some() {
+        header.push("Image");
+    }
+    if args.rank.is_some() {
+        header.push("Score");
+    }
+    if args.kind {
+        header.push("Kind");
+    }
+    if args.age {
+        header.extend(["Modified (UTC)", "Terrain saved (UTC)"]);
+    }
     let mut table = Table::new();
-    table
-        .load_preset(PRESET)
-        .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_header(vec![
-            "File",
-            "Zoom",
-            "Dimension",
-            "Locked",
-            "Center",
-            "Left",
-            "Top",
-            "Right",
-            "Bottom",
-            "Banners",
-            "Frames",
-        ]);
-    for map in maps.flatten() {
-        let file = match map.file.strip_prefix(&common_base_path) {
-            Ok(file) => file,
-            Err(_) => map.file.as_path(),
+    match args.format {
+        TextFormat::Table => table
+            .load_preset(preset())
+            .set_content_arrangement(ContentArrangement::Dynamic),
+        TextFormat::Markdown => table
+            .load_preset(presets::ASCII_MARKDOWN)
+            .set_content_arrangement(ContentArrangement::Disabled),
+    };
+    table.set_header(header.into_iter().map(tr));
+    let mut rows = Vec::new();
+    let mut chunk_times = HashMap::new();
+    let mut stale_count = 0;
+    for (listed_file, handle) in maps.handles() {
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(_) => continue,
+        };
+        let map = handle.metadata();
+        let area = map.area();
+        let file = listed_file
+            .strip_prefix(&common_base_path)
+            .unwrap_or(listed_file);
+        let dimension = if args.dimension_from_path {
+            handle.pretty_dimension_from_path()
+        } else {
+            map.pretty_dimension()
         };
-        table.add_row(vec![
+        let mut row = vec![
             Cell::new(file.display()),
-            Cell::new(map.data.scale),
-            Cell::new(if args.dimension_from_path {
-                map.pretty_dimension_from_path()
-            } else {
-                map.data.pretty_dimension()
-            }),
-            Cell::new(map.data.locked),
-            Cell::new(format!("{}, {}", map.data.x_center, map.data.z_center)),
-            Cell::new(map.data.left()),
-            Cell::new(map.data.top()),
-            Cell::new(map.data.right()),
-            Cell::new(map.data.bottom()),
-            Cell::new(map.data.banners.len()),
-            Cell::new(map.data.frames.len()),
-        ]);
+            Cell::new(map.scale),
+            Cell::new(tr(&dimension)),
+            Cell::new(map.locked),
+            Cell::new(format!("{}, {}", map.x_center, map.z_center)),
+            Cell::new(area.left),
+            Cell::new(area.top),
+            Cell::new(area.right),
+            Cell::new(area.bottom),
+            Cell::new(map.banner_count),
+            Cell::new(map.frame_count),
+        ];
+        if let Some(image_dir) = image_dir {
+            // Same path as the images command uses
+            let stem = handle
+                .path()
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy();
+            let image = image_dir.join(&dimension).join(format!("{stem}.png"));
+            let link = image
+                .to_string_lossy()
+                .replace('\\', "/")
+                .replace(' ', "%20");
+            row.push(Cell::new(format!("![{stem}]({link})")));
+        }
+        let map_item = if args.rank.is_some() || args.kind {
+            match handle.load() {
+                Ok(map_item) => Some(map_item),
+                Err(_) => continue,
+            }
+        } else {
+            None
+        };
+        let mut score = 0.0;
+        if let (Some(rank), Some(map_item)) = (args.rank, &map_item) {
+            let text = match rank {
+                Rank::Explored => {
+                    score = map_item.data.explored_fraction();
+                    format!("{:.1}%", score * 100.0)
+                }
+                Rank::Variety => {
+                    score = map_item.data.color_entropy();
+                    format!("{score:.2}")
+                }
+            };
+            row.push(Cell::new(text));
+        }
+        if let (true, Some(map_item)) = (args.kind, &map_item) {
+            row.push(Cell::new(map_item.kind()));
+        }
+        if args.age {
+            let modified = maps.source().modified(listed_file);
+            let terrain_saved = world_directory(handle.path()).and_then(|world| {
+                let chunk_times = chunk_times
+                    .entry(world.clone())
+                    .or_insert_with(|| ChunkTimes::new(&world));
+                chunk_times
+                    .latest(&map.pretty_dimension(), &area)
+                    .unwrap_or_else(|err| {
+                        warn!("Could not read chunk times of {}: {err}", world.display());
+                        None
+                    })
+            });
+            row.push(Cell::new(
+                modified.map_or_else(|| "-".to_string(), format_time),
+            ));
+            row.push(Cell::new(match (terrain_saved, modified) {
+                (Some(saved), Some(modified)) if saved > modified => {
+                    stale_count += 1;
+                    format!("{} (stale)", format_time(saved))
+                }
+                (Some(saved), _) => format_time(saved),
+                (None, _) => "-".to_string(),
+            }));
+        }
+        rows.push((score, row));
+    }
+    // Stable sort keeps the --sort order for maps with equal scores
+    if args.rank.is_some() {
+        rows.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    }
+    for (_, row) in rows {
+        table.add_row(row);
     }
     println!("{table}");
+    if stale_count > 0 {
+        info!("{stale_count} maps are older than the terrain under them");
+    }
     ExitCode::SUCCESS
 }
+
+/// World directory of the map *file* in its `data` directory, if the world has a level.dat
+fn world_directory(file: &Path) -> Option<PathBuf> {
+    let data = file.parent()?;
+    if data.file_name()? != "data" {
+        return None;
+    }
+    let world = data.parent()?;
+    world
+        .join("level.dat")
+        .is_file()
+        .then(|| world.to_path_buf())
+}
+
+/// Formats the *time* in UTC with minute precision
+pub fn format_time(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+        .unwrap_or_default()
+}
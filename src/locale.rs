@@ -0,0 +1,86 @@
+//! Translations of the texts in the printed tables
+//!
+//! The tables of the `info` and `list` commands are in English by default. Other languages
+//! are added with a JSON file mapping the English texts to their translations, for example
+//! `{"Dimension": "Dimensio", "Overworld": "Ylämaailma", "Water": "Vesi"}`. Base color names,
+//! dimension names and table headers are translated this way, and texts missing from the
+//! file stay in English.
+//!
+//! The language is never taken from the environment, so the same command prints the same
+//! output everywhere unless a language is selected with [set_locale].
+
+use crate::error::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Locale used by [tr], see [set_locale]
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Name of the built-in English locale
+pub const ENGLISH: &str = "en";
+
+/// Translations from English texts
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Locale {
+    translations: HashMap<String, String>,
+}
+
+impl Locale {
+    /// Locale keeping all texts in English
+    pub fn english() -> Locale {
+        Locale::default()
+    }
+
+    /// Reads the translations from the JSON *file*
+    pub fn read_from(file: &Path) -> Result<Locale> {
+        Locale::parse(&fs::read_to_string(file)?)
+    }
+
+    /// Parses the translations from a JSON object with the English texts as the keys
+    pub fn parse(text: &str) -> Result<Locale> {
+        let translations = serde_json::from_str(text)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Locale { translations })
+    }
+
+    /// Translation of the English *text*, or the text itself if it has no translation
+    pub fn translate<'a>(&'a self, text: &'a str) -> &'a str {
+        self.translations.get(text).map_or(text, String::as_str)
+    }
+}
+
+/// Sets the locale for the whole program
+///
+/// The locale can be set only once. Returns false if it was already set.
+pub fn set_locale(locale: Locale) -> bool {
+    LOCALE.set(locale).is_ok()
+}
+
+/// Translation of the English *text* with the locale of the program
+pub fn tr(text: &str) -> &str {
+    match LOCALE.get() {
+        Some(locale) => locale.translate(text),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::Locale;
+
+    #[test]
+    fn test_locale() {
+        let locale = Locale::parse(r#"{"Water": "Vesi", "The Nether": "Horna"}"#).unwrap();
+        assert_eq!(locale.translate("Water"), "Vesi");
+        assert_eq!(locale.translate("The Nether"), "Horna");
+        // Missing texts stay in English
+        assert_eq!(locale.translate("Sand"), "Sand");
+        assert_eq!(Locale::english().translate("Water"), "Water");
+
+        assert!(Locale::parse(r#"{"Water": 1}"#).is_err());
+        assert!(Locale::parse(r#"["Water"]"#).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use clap::ValueEnum;
+use env_logger::{Builder, Env};
+use log::{Level, LevelFilter};
+use std::io::Write;
+
+/// Format of the log messages
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum LogFormat {
+    /// Plain messages for people
+    Text,
+
+    /// One JSON object per line for log collectors
+    Json,
+}
+
+/// Sets up the logger for the messages written to the standard error
+///
+/// Messages are shown from the info level by default. Each *verbose* step shows one level
+/// more and *quiet* shows only errors. The `RUST_LOG` environment variable overrides these.
+pub fn init(verbose: u8, quiet: bool, format: LogFormat) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Info,
+            1 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    let mut builder = Builder::new();
+    builder.filter_level(level);
+    builder.parse_env(Env::default());
+    match format {
+        LogFormat::Text => builder.format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "{}", record.args()),
+            Level::Warn => writeln!(buf, "Warning: {}", record.args()),
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            level => writeln!(buf, "[{level}] {}", record.args()),
+        }),
+        LogFormat::Json => builder.format(|buf, record| {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            let line = serde_json::json!({
+                "timestamp": timestamp,
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{line}")
+        }),
+    };
+    builder.init();
+}
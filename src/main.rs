@@ -1,15 +1,64 @@
-use clap::{Parser, Subcommand};
+use clap::{value_parser, ArgAction, CommandFactory, Parser, Subcommand};
+use log::error;
+use minecraft_map_tool::compression::{set_compression_level, DEFAULT_COMPRESSION_LEVEL};
+use minecraft_map_tool::limits::{set_read_options, ReadOptions};
+use minecraft_map_tool::locale::{set_locale, Locale, ENGLISH};
+use std::path::Path;
 use std::process::ExitCode;
 
+mod atlas_tool;
+mod banners_tool;
+mod batch;
+mod changes;
+mod charset;
+mod compare_tool;
+mod completions_tool;
+mod convert_tool;
+mod diff_tool;
+mod dump_nbt_tool;
+mod edit_tool;
+mod frames_of_tool;
+mod gallery_tool;
 mod image_tool;
 mod images_tool;
+mod import_tool;
 mod info_tool;
+mod input;
 mod list_tool;
+mod logging;
+mod manifest_tool;
+mod markers_tool;
+mod materials_tool;
+mod merge_tool;
+mod prune_tool;
+mod recompress_tool;
+mod renumber_tool;
+mod rescale_tool;
+mod schema_tool;
+mod serve_tool;
+mod sheet_tool;
+mod snapshot_tool;
+mod stats_tool;
 mod stitching_tool;
+mod style;
+mod synthesize_tool;
+mod thumbs_tool;
+mod transfer_tool;
+mod usage_tool;
+mod verify_palette_tool;
+mod wall_tool;
+mod waypoints_tool;
+mod who_has_tool;
+
+#[cfg(feature = "dev_tools")]
+mod extract_palette;
 
 #[cfg(feature = "dev_tools")]
 mod test_map;
 
+#[cfg(feature = "dev_tools")]
+mod test_world;
+
 #[cfg(feature = "dev_tools")]
 mod update_versions;
 
@@ -34,6 +83,49 @@ struct Cli {
     /// Which action should the tool take?
     #[command(subcommand)]
     command: Commands,
+
+    /// Show more messages, repeat for even more (-vv)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Show only errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of the messages written to the standard error
+    #[arg(long, global = true, default_value = "text")]
+    log_format: logging::LogFormat,
+
+    /// Memory map the map files instead of reading them, which can be faster when reading
+    /// many maps from a fast drive. Do not use while the game is writing the maps.
+    #[arg(long, global = true)]
+    mmap: bool,
+
+    /// Only list the files that would be written, moved or deleted on the standard error,
+    /// without changing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Compression level of the written files, from 0 for the fastest to 9 for the smallest
+    /// [default: 6]
+    #[arg(long, global = true, value_parser = value_parser!(u32).range(0..=9))]
+    compression_level: Option<u32>,
+
+    /// Refuse map files larger than this many bytes after decompression [default: 16777216]
+    #[arg(long, global = true, value_name = "BYTES")]
+    max_size: Option<u64>,
+
+    /// When to use bold text and other styling, auto styles only terminals without NO_COLOR
+    #[arg(long, global = true, default_value = "auto")]
+    color: style::ColorChoice,
+
+    /// Draw the table borders with plain ASCII characters, for example for logs and CI
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Language of the info and list tables, "en" or a JSON file translating the English texts
+    #[arg(long, global = true, value_name = "LANG", default_value = ENGLISH)]
+    lang: String,
 }
 
 #[derive(Debug, Subcommand)]
@@ -48,15 +140,125 @@ enum Commands {
     Image(image_tool::ImageArgs),
 
     /// Create images from multiple map files
+    #[command(after_help = batch::EXIT_CODES_HELP)]
     Images(images_tool::ImagesArgs),
 
     /// Drawing multiple maps into a single image
+    #[command(after_help = batch::EXIT_CODES_HELP)]
     Stitch(stitching_tool::StitchingArgs),
 
+    /// Lay out maps into a printable PDF atlas
+    Atlas(atlas_tool::AtlasArgs),
+
+    /// Make a static HTML gallery of the maps
+    Gallery(gallery_tool::GalleryArgs),
+
+    /// Convert an image to a map file or a schematic for building map art
+    Import(import_tool::ImportArgs),
+
+    /// Compare two map files
+    Diff(diff_tool::DiffArgs),
+
+    /// Stitch the same area from two worlds side by side or as an onion skin overlay
+    Compare(compare_tool::CompareArgs),
+
+    /// Count blocks needed to build map art from a map file or an image
+    Materials(materials_tool::MaterialsArgs),
+
+    /// Serve map images and information over HTTP
+    Serve(serve_tool::ServeArgs),
+
+    /// Show statistics from multiple maps
+    Stats(stats_tool::StatsArgs),
+
+    /// Store map files to snapshots and restore them later
+    Snapshot(snapshot_tool::SnapshotArgs),
+
+    /// Write SHA-256 hashes of the map files, or check the files against earlier hashes
+    Manifest(manifest_tool::ManifestArgs),
+
+    /// Show the NBT tree of a map file or any other NBT file
+    DumpNbt(dump_nbt_tool::DumpNbtArgs),
+
+    /// Convert an NBT file to JSON or SNBT, and JSON back to NBT
+    Convert(convert_tool::ConvertArgs),
+
+    /// Compress map files again with the highest level, or with another compression
+    Recompress(recompress_tool::RecompressArgs),
+
+    /// Find the item frames in a world that hold the given maps
+    FramesOf(frames_of_tool::FramesOfArgs),
+
+    /// Find the players carrying maps in their inventory or ender chest
+    WhoHas(who_has_tool::WhoHasArgs),
+
+    /// Report where each map is used in a world and which map files are orphaned
+    Usage(usage_tool::UsageArgs),
+
+    /// Delete or quarantine map files that nothing in the world refers to
+    Prune(prune_tool::PruneArgs),
+
+    /// Renumber the maps of a world to contiguous ids
+    Renumber(renumber_tool::RenumberArgs),
+
+    /// Copy maps to another world with new ids
+    Transfer(transfer_tool::TransferArgs),
+
+    /// Combine the explored areas of two map files
+    Merge(merge_tool::MergeArgs),
+
+    /// Edit the contents of a map file
+    Edit(edit_tool::EditArgs),
+
+    /// Make a zoomed out map from a more detailed map file
+    Rescale(rescale_tool::RescaleArgs),
+
+    /// Make a zoomed out map from all the more detailed maps covering its area
+    Synthesize(synthesize_tool::SynthesizeArgs),
+
+    /// Add JourneyMap and Xaero's Minimap waypoints as banners to the maps covering them
+    ImportWaypoints(waypoints_tool::WaypointsArgs),
+
+    /// Export banners and map frames as BlueMap or Dynmap markers
+    ExportMarkers(markers_tool::MarkersArgs),
+
+    /// List the banners on the maps, or summarize the banner colors with --stats
+    Banners(banners_tool::BannersArgs),
+
+    /// Print the JSON Schema of a JSON output
+    Schema(schema_tool::SchemaArgs),
+
+    /// Draw thumbnails of all maps into one image with a JSON index
+    Thumbs(thumbs_tool::ThumbsArgs),
+
+    /// Draw the maps in a grid with their ids and centers for looking through a collection
+    Sheet(sheet_tool::SheetArgs),
+
+    /// Plan the maps for a wall of item frames showing an area of the world
+    Wall(wall_tool::WallArgs),
+
+    /// Compare the palette colors with an in-game screenshot of a map
+    VerifyPalette(verify_palette_tool::VerifyPaletteArgs),
+
+    /// Print a shell completion script
+    Completions(completions_tool::CompletionsArgs),
+
+    /// Write man pages
+    #[command(hide = true)]
+    Mangen(completions_tool::ManGenArgs),
+
+    /// Generate a base colors table from the map colors of a Minecraft client jar
+    #[cfg(feature = "dev_tools")]
+    ExtractPalette(extract_palette::ExtractPaletteArgs),
+
     /// Create test map item with all colors
     #[cfg(feature = "dev_tools")]
     TestMap(test_map::TestMapArgs),
 
+    /// Create synthetic world with maps in different scales and dimensions
+    #[cfg(feature = "dev_tools")]
+    TestWorld(test_world::TestWorldArgs),
+
     /// Attempts to download the versions list from
     /// the Minecraft Wiki and update the versions source file.
     #[cfg(feature = "dev_tools")]
@@ -64,7 +266,8 @@ enum Commands {
 }
 
 impl Commands {
-    fn run(&self) -> ExitCode {
+    /// Runs the command, with the *compression_level* given on the command line
+    fn run(&self, compression_level: Option<u32>) -> ExitCode {
         match self {
             // Default tools
             Commands::Info(args) => info_tool::run(args),
@@ -72,11 +275,50 @@ impl Commands {
             Commands::Images(args) => images_tool::run(args),
             Commands::List(args) => list_tool::run(args),
             Commands::Stitch(args) => stitching_tool::run(args),
+            Commands::Import(args) => import_tool::run(args),
+            Commands::Atlas(args) => atlas_tool::run(args),
+            Commands::Gallery(args) => gallery_tool::run(args),
+            Commands::Diff(args) => diff_tool::run(args),
+            Commands::Compare(args) => compare_tool::run(args),
+            Commands::Materials(args) => materials_tool::run(args),
+            Commands::Serve(args) => serve_tool::run(args),
+            Commands::Stats(args) => stats_tool::run(args),
+            Commands::Snapshot(args) => snapshot_tool::run(args),
+            Commands::Manifest(args) => manifest_tool::run(args),
+            Commands::DumpNbt(args) => dump_nbt_tool::run(args),
+            Commands::Convert(args) => convert_tool::run(args),
+            Commands::Recompress(args) => recompress_tool::run(args, compression_level),
+            Commands::FramesOf(args) => frames_of_tool::run(args),
+            Commands::WhoHas(args) => who_has_tool::run(args),
+            Commands::Usage(args) => usage_tool::run(args),
+            Commands::Prune(args) => prune_tool::run(args),
+            Commands::Renumber(args) => renumber_tool::run(args),
+            Commands::Transfer(args) => transfer_tool::run(args),
+            Commands::Merge(args) => merge_tool::run(args),
+            Commands::Edit(args) => edit_tool::run(args),
+            Commands::Rescale(args) => rescale_tool::run(args),
+            Commands::Synthesize(args) => synthesize_tool::run(args),
+            Commands::ImportWaypoints(args) => waypoints_tool::run(args),
+            Commands::ExportMarkers(args) => markers_tool::run(args),
+            Commands::Banners(args) => banners_tool::run(args),
+            Commands::Schema(args) => schema_tool::run(args),
+            Commands::Thumbs(args) => thumbs_tool::run(args),
+            Commands::Sheet(args) => sheet_tool::run(args),
+            Commands::Wall(args) => wall_tool::run(args),
+            Commands::VerifyPalette(args) => verify_palette_tool::run(args),
+            Commands::Completions(args) => completions_tool::run_completions(args, Cli::command()),
+            Commands::Mangen(args) => completions_tool::run_mangen(args, Cli::command()),
 
             // Development tools
+            #[cfg(feature = "dev_tools")]
+            Commands::ExtractPalette(args) => extract_palette::run(args),
+
             #[cfg(feature = "dev_tools")]
             Commands::TestMap(args) => test_map::run(args),
 
+            #[cfg(feature = "dev_tools")]
+            Commands::TestWorld(args) => test_world::run(args),
+
             #[cfg(feature = "dev_tools")]
             Commands::UpdateVersions(args) => update_versions::run(args),
         }
@@ -84,5 +326,27 @@ impl Commands {
 }
 
 fn main() -> ExitCode {
-    Cli::parse().command.run()
+    let cli = Cli::parse();
+    logging::init(cli.verbose, cli.quiet, cli.log_format);
+    charset::init(cli.ascii);
+    style::init(cli.color);
+    changes::init(cli.dry_run);
+    set_compression_level(cli.compression_level.unwrap_or(DEFAULT_COMPRESSION_LEVEL));
+    set_read_options(ReadOptions {
+        max_size: cli.max_size.unwrap_or(ReadOptions::DEFAULT.max_size),
+        memory_mapping: cli.mmap,
+        ..ReadOptions::default()
+    });
+    if cli.lang != ENGLISH {
+        match Locale::read_from(Path::new(&cli.lang)) {
+            Ok(locale) => {
+                set_locale(locale);
+            }
+            Err(err) => {
+                error!("Could not read language file {}: {err}", cli.lang);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    cli.command.run(cli.compression_level)
 }
@@ -0,0 +1,143 @@
+//! Checksum manifests of map files
+//!
+//! A manifest lists the SHA-256 hash of every map file in a directory or archive. It is
+//! written in the same format as the `sha256sum` tool uses, a hash and a path relative to the
+//! source on each line, so backups can also be checked with `sha256sum -c`. Comparing two
+//! manifests tells which maps were added, changed or removed without reading them again.
+
+use crate::error::{Error, Result};
+use crate::source::open_source;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Read;
+use std::path::Path;
+
+/// Hashes of map files, keyed by the path relative to the source
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Manifest {
+    pub hashes: BTreeMap<String, String>,
+}
+
+/// Differences between two manifests
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ManifestDiff {
+    /// Files that are only in the newer manifest
+    pub added: Vec<String>,
+
+    /// Files with different hashes
+    pub changed: Vec<String>,
+
+    /// Files that are only in the older manifest
+    pub removed: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// Tests if the manifests list the same files with the same hashes
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Hashes the map files found from the *path*
+    ///
+    /// The *path* can be anything supported by [open_source]. The files are hashed as they
+    /// are stored, so recompressing a map changes its hash.
+    pub fn create(path: &Path, recursive: bool) -> Result<Manifest> {
+        let source = open_source(path)?;
+        let mut hashes = BTreeMap::new();
+        for file in source.find_map_files(recursive)? {
+            let mut content = Vec::new();
+            source.open(&file)?.read_to_end(&mut content)?;
+            let name = file.strip_prefix(path).unwrap_or(&file);
+            hashes.insert(
+                name.to_string_lossy().replace('\\', "/"),
+                hash_content(&content),
+            );
+        }
+        Ok(Manifest { hashes })
+    }
+
+    /// Reads a manifest from the *text* in the `sha256sum` format
+    ///
+    /// Empty lines are skipped, and the `*` that marks binary mode before the path is allowed.
+    pub fn parse(text: &str) -> Result<Manifest> {
+        let mut hashes = BTreeMap::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (hash, name) = line
+                .split_once(' ')
+                .ok_or_else(|| Error::map_item_error("Manifest line has no file name"))?;
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::map_item_error("Manifest line has no SHA-256 hash"));
+            }
+            let name = name.strip_prefix([' ', '*']).unwrap_or(name);
+            hashes.insert(name.to_string(), hash.to_ascii_lowercase());
+        }
+        Ok(Manifest { hashes })
+    }
+
+    /// Manifest in the `sha256sum` format, one line for each file in the order of the paths
+    pub fn to_text(&self) -> String {
+        self.hashes
+            .iter()
+            .fold(String::new(), |mut text, (name, hash)| {
+                let _ = writeln!(text, "{hash}  {name}");
+                text
+            })
+    }
+
+    /// Compares this newer manifest with the *previous* one
+    pub fn diff(&self, previous: &Manifest) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+        for (name, hash) in &self.hashes {
+            match previous.hashes.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(previous_hash) if previous_hash != hash => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = previous
+            .hashes
+            .keys()
+            .filter(|name| !self.hashes.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// SHA-256 hash of the *content* as lowercase hexadecimal
+pub fn hash_content(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifest::Manifest;
+    use crate::synthetic::write_synthetic_world;
+
+    #[test]
+    fn test_manifest() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let data = world.path().join("data");
+        let manifest = Manifest::create(&data, false).unwrap();
+        assert_eq!(manifest.hashes.len(), 32);
+        assert_eq!(Manifest::parse(&manifest.to_text()).unwrap(), manifest);
+        assert!(Manifest::parse("not a hash  map_0.dat").is_err());
+
+        std::fs::copy(data.join("map_1.dat"), data.join("map_0.dat")).unwrap();
+        std::fs::remove_file(data.join("map_2.dat")).unwrap();
+        std::fs::copy(data.join("map_3.dat"), data.join("map_99.dat")).unwrap();
+        let diff = Manifest::create(&data, false).unwrap().diff(&manifest);
+        assert_eq!(diff.added, ["map_99.dat"]);
+        assert_eq!(diff.changed, ["map_0.dat"]);
+        assert_eq!(diff.removed, ["map_2.dat"]);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::changes::sink;
+use crate::charset::preset;
+use clap::Args;
+use comfy_table::{ContentArrangement, Table};
+use log::{error, info};
+use minecraft_map_tool::manifest::Manifest;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct ManifestArgs {
+    /// The directory or world backup archive (.zip, .tar.gz) from which map files are searched
+    /// for. With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    path: PathBuf,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Write the manifest to this file instead of the standard output
+    #[arg(short, long, conflicts_with = "verify")]
+    output_file: Option<PathBuf>,
+
+    /// Compare the map files with this earlier manifest instead of writing a new one. Fails
+    /// if a map file has changed or is missing
+    #[arg(long, value_name = "MANIFEST")]
+    verify: Option<PathBuf>,
+}
+
+pub fn run(args: &ManifestArgs) -> ExitCode {
+    let previous = match &args.verify {
+        Some(file) => match fs::read_to_string(file) {
+            Ok(text) => match Manifest::parse(&text) {
+                Ok(manifest) => Some(manifest),
+                Err(err) => {
+                    error!("Could not parse {}: {err}", file.display());
+                    return ExitCode::FAILURE;
+                }
+            },
+            Err(err) => {
+                error!("Could not read {}: {err}", file.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+    let manifest = match Manifest::create(&args.path, args.recursive) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            error!("Could not hash the map files: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let Some(previous) = previous else {
+        let text = manifest.to_text();
+        match &args.output_file {
+            Some(file) => {
+                if let Err(err) = sink().write(file, text) {
+                    error!("Could not write {}: {err}", file.display());
+                    return ExitCode::FAILURE;
+                }
+                info!(
+                    "Hashes of {} map files written to {}",
+                    manifest.hashes.len(),
+                    file.display()
+                );
+            }
+            None => print!("{text}"),
+        }
+        return ExitCode::SUCCESS;
+    };
+
+    let diff = manifest.diff(&previous);
+    if diff.is_empty() {
+        info!("All {} map files match the manifest", manifest.hashes.len());
+        return ExitCode::SUCCESS;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Status", "File"]);
+    for (status, names) in [
+        ("changed", &diff.changed),
+        ("missing", &diff.removed),
+        ("new", &diff.added),
+    ] {
+        for name in names {
+            table.add_row(vec![status, name]);
+        }
+    }
+    println!("{table}");
+    info!(
+        "{} changed, {} missing and {} new map files",
+        diff.changed.len(),
+        diff.removed.len(),
+        diff.added.len()
+    );
+    if diff.changed.is_empty() && diff.removed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,168 @@
+//! Tools for planning map art
+//!
+//! Map art is built in the game from blocks so that the map drawn from the area shows
+//! the wanted picture. Each pixel is one block, and the shade of the pixel depends on
+//! the height of the block compared to the block north of it.
+
+use crate::palette::{building_block, compose, decompose, Palette};
+use image::{imageops, imageops::FilterType, Rgba, RgbaImage};
+
+/// Items in a stack
+pub const STACK_SIZE: usize = 64;
+
+/// Items in a full shulker box
+pub const SHULKER_BOX_SIZE: usize = 27 * STACK_SIZE;
+
+/// How the map art is going to be built
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ArtStyle {
+    /// All blocks are at the same height, so only the normal shade is used
+    Flat,
+
+    /// Blocks are placed at different heights to get the dark, normal and light shades
+    Staircase,
+}
+
+impl ArtStyle {
+    /// Shades that can be built with this style
+    ///
+    /// The darkest shade (index 3) cannot be built in survival, so it is never included.
+    pub fn shades(&self) -> &'static [u8] {
+        match self {
+            ArtStyle::Flat => &[1],
+            ArtStyle::Staircase => &[0, 1, 2],
+        }
+    }
+}
+
+/// Converts the *image* to map colors
+///
+/// Image is resized to 128×128 pixels if needed, and each pixel is replaced with
+/// the closest color from the *palette* that can be built with the given *style*. Colors
+/// without a solid block, like water, are left out of staircases.
+/// Pixels that are mostly transparent are left unexplored (color 0).
+pub fn quantize(image: &RgbaImage, palette: &Palette, style: ArtStyle) -> Vec<u8> {
+    let resized;
+    let image = if image.dimensions() == (128, 128) {
+        image
+    } else {
+        resized = imageops::resize(image, 128, 128, FilterType::Triangle);
+        &resized
+    };
+
+    // Candidate colors, skipping base color 0 and colors missing from the palette
+    let candidates: Vec<(u8, Rgba<u8>)> = (1..64u8)
+        .filter(|base_color| style == ArtStyle::Flat || building_block(*base_color).is_some())
+        .flat_map(|base_color| {
+            style
+                .shades()
+                .iter()
+                .map(move |shade| compose(base_color, *shade))
+        })
+        .map(|color| (color, palette[color as usize]))
+        .filter(|(_, rgba)| rgba[3] != 0)
+        .collect();
+
+    image
+        .pixels()
+        .map(|pixel| {
+            if pixel[3] < 128 {
+                return 0;
+            }
+            candidates
+                .iter()
+                .min_by_key(|(_, rgba)| color_distance(pixel, rgba))
+                .map(|(color, _)| *color)
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Squared distance between two colors, alpha channel is ignored
+fn color_distance(a: &Rgba<u8>, b: &Rgba<u8>) -> u32 {
+    (0..3)
+        .map(|i| (a[i] as i32 - b[i] as i32).pow(2) as u32)
+        .sum()
+}
+
+/// Blocks needed for one base color
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Material {
+    /// The base color of the blocks
+    pub base_color: u8,
+
+    /// Number of blocks for each shade, in the same order as in the palette
+    pub shades: [usize; 4],
+}
+
+impl Material {
+    /// Total number of blocks
+    pub fn total(&self) -> usize {
+        self.shades.iter().sum()
+    }
+
+    /// Number of full stacks and remaining blocks
+    pub fn stacks(&self) -> (usize, usize) {
+        (self.total() / STACK_SIZE, self.total() % STACK_SIZE)
+    }
+
+    /// Number of shulker boxes needed to carry the blocks
+    pub fn shulker_boxes(&self) -> usize {
+        self.total().div_ceil(SHULKER_BOX_SIZE)
+    }
+}
+
+/// Counts blocks needed for the given map *colors*
+///
+/// Unexplored pixels are skipped. Materials are sorted from the most used to the least used.
+pub fn materials(colors: &[u8]) -> Vec<Material> {
+    let mut counts = [[0usize; 4]; 64];
+    for color in colors {
+        let (base_color, shade) = decompose(*color);
+        counts[base_color as usize][shade as usize] += 1;
+    }
+    let mut materials: Vec<Material> = counts
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, shades)| shades.iter().any(|count| *count > 0))
+        .map(|(base_color, shades)| Material {
+            base_color: base_color as u8,
+            shades: *shades,
+        })
+        .collect();
+    materials.sort_by_key(|material| std::cmp::Reverse(material.total()));
+    materials
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map_art::{materials, quantize, ArtStyle};
+    use crate::palette::{generate_palette, BASE_COLORS_2699};
+    use image::RgbaImage;
+
+    #[test]
+    fn test_quantize_palette_colors() {
+        let palette = generate_palette(&BASE_COLORS_2699);
+        let image = RgbaImage::from_fn(128, 128, |x, _| palette[(x / 2 * 4 + 2) as usize]);
+        let colors = quantize(&image, &palette, ArtStyle::Staircase);
+        for (x, color) in colors.iter().take(124).enumerate() {
+            match x / 2 {
+                0 => assert_eq!(*color, 0),
+                // Water cannot be built as a staircase, so a nearby color is used
+                12 => assert_ne!(*color / 4, 12),
+                base_color => assert_eq!(*color as usize, base_color * 4 + 2),
+            }
+        }
+    }
+
+    #[test]
+    fn test_materials() {
+        let materials = materials(&[0, 4, 5, 5, 9]);
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].base_color, 1);
+        assert_eq!(materials[0].shades, [1, 2, 0, 0]);
+        assert_eq!(materials[1].total(), 1);
+    }
+}
@@ -0,0 +1,246 @@
+//! Exporting map markers for web maps
+//!
+//! The banners and item frames marked on maps are waypoints that players have placed on
+//! purpose. This module collects them from map items and writes them as marker sets that
+//! BlueMap and Dynmap can show.
+
+use crate::source::map_id;
+use crate::transform::nether_to_overworld;
+use crate::{BannerColor, MapItem, Pos};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+
+/// Format of the exported marker sets
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum MarkerFormat {
+    /// BlueMap marker sets as JSON
+    #[default]
+    Bluemap,
+
+    /// Dynmap markers.yml
+    Dynmap,
+}
+
+/// Kind of a map marker
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum MarkerKind {
+    /// Banner marked on a map
+    Banner,
+
+    /// Item frame holding the map
+    Frame,
+}
+
+impl MarkerKind {
+    /// Marker set id for the markers of this kind
+    fn set_id(&self) -> &'static str {
+        match self {
+            MarkerKind::Banner => "banners",
+            MarkerKind::Frame => "frames",
+        }
+    }
+
+    /// Marker set label for the markers of this kind
+    fn set_label(&self) -> &'static str {
+        match self {
+            MarkerKind::Banner => "Banners",
+            MarkerKind::Frame => "Map frames",
+        }
+    }
+}
+
+/// A banner or a frame marker found from the maps
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointMarker {
+    /// Kind of the marker
+    pub kind: MarkerKind,
+
+    /// Label shown for the marker
+    pub label: String,
+
+    /// Block position of the marker
+    pub pos: Pos,
+
+    /// Banner color, frames have none
+    pub color: Option<BannerColor>,
+}
+
+impl PointMarker {
+    /// The marker of a Nether map at the matching Overworld position
+    pub fn projected_to_overworld(&self) -> PointMarker {
+        let (x, z) = nether_to_overworld(self.pos.x, self.pos.z);
+        PointMarker {
+            pos: Pos { x, z, ..self.pos },
+            ..self.clone()
+        }
+    }
+
+    /// Id that is unique for markers of the same kind
+    pub fn id(&self) -> String {
+        format!(
+            "{}_{}_{}_{}",
+            self.kind.set_id(),
+            self.pos.x,
+            self.pos.y,
+            self.pos.z
+        )
+    }
+}
+
+/// Collects the banners and frames of the *maps*
+///
+/// The same banner is often marked on many maps, so markers of the same kind at the same
+/// position are included only once. The maps should be from the same dimension. The markers
+/// are sorted by kind and position.
+pub fn collect_markers<'a, I>(maps: I) -> Vec<PointMarker>
+where
+    I: IntoIterator<Item = &'a MapItem>,
+{
+    let mut markers: Vec<PointMarker> = Vec::new();
+    for map_item in maps {
+        let banners = map_item.data.banners.iter().map(|banner| PointMarker {
+            kind: MarkerKind::Banner,
+            label: banner.extract_name(),
+            pos: banner.pos.clone(),
+            color: Some(banner.color.clone()),
+        });
+        let frames = map_item.data.frames.iter().map(|frame| PointMarker {
+            kind: MarkerKind::Frame,
+            label: match map_id(&map_item.file) {
+                Some(id) => format!("Map #{id}"),
+                None => String::from("Map"),
+            },
+            pos: frame.pos.clone(),
+            color: None,
+        });
+        for marker in banners.chain(frames) {
+            if !markers
+                .iter()
+                .any(|other| other.kind == marker.kind && other.pos == marker.pos)
+            {
+                markers.push(marker);
+            }
+        }
+    }
+    markers.sort_by_key(|marker| (marker.kind, marker.pos.x, marker.pos.z, marker.pos.y));
+    markers
+}
+
+/// Writes the *markers* as BlueMap marker sets, one set for each kind
+///
+/// The result has the same shape as the marker sets in the markers.json of a BlueMap map,
+/// with POI markers at the centers of the blocks.
+pub fn bluemap_markers(markers: &[PointMarker]) -> Value {
+    let mut sets = Map::new();
+    for kind in [MarkerKind::Banner, MarkerKind::Frame] {
+        let mut set_markers = Map::new();
+        for marker in markers.iter().filter(|marker| marker.kind == kind) {
+            let mut poi = json!({
+                "type": "poi",
+                "label": marker.label,
+                "position": {
+                    "x": marker.pos.x as f64 + 0.5,
+                    "y": marker.pos.y as f64 + 0.5,
+                    "z": marker.pos.z as f64 + 0.5,
+                },
+            });
+            if let Some(color) = &marker.color {
+                let [r, g, b] = color.rgb();
+                poi["detail"] = json!(format!(
+                    "{} <span style=\"color: #{r:02x}{g:02x}{b:02x}\">■</span>",
+                    escape_html(&marker.label)
+                ));
+            }
+            set_markers.insert(marker.id(), poi);
+        }
+        if !set_markers.is_empty() {
+            sets.insert(
+                kind.set_id().to_string(),
+                json!({
+                    "label": kind.set_label(),
+                    "toggleable": true,
+                    "defaultHidden": false,
+                    "markers": set_markers,
+                }),
+            );
+        }
+    }
+    Value::Object(sets)
+}
+
+/// Writes the *markers* as the sets of a Dynmap markers.yml for the *world*
+///
+/// The *world* is the name of the world directory, like world or world_nether. Strings are
+/// written as JSON strings, which are valid double-quoted YAML strings.
+pub fn dynmap_markers(markers: &[PointMarker], world: &str) -> String {
+    let quote = |text: &str| Value::from(text).to_string();
+    let mut yaml = String::from("sets:\n");
+    for kind in [MarkerKind::Banner, MarkerKind::Frame] {
+        let mut set_markers = markers
+            .iter()
+            .filter(|marker| marker.kind == kind)
+            .peekable();
+        if set_markers.peek().is_none() {
+            continue;
+        }
+        let _ = writeln!(yaml, "  {}:", kind.set_id());
+        let _ = writeln!(yaml, "    label: {}", quote(kind.set_label()));
+        yaml.push_str("    hide: false\n    markers:\n");
+        for marker in set_markers {
+            let _ = writeln!(yaml, "      {}:", marker.id());
+            let _ = writeln!(yaml, "        world: {}", quote(world));
+            let _ = writeln!(yaml, "        markup: false");
+            let _ = writeln!(yaml, "        x: {:.1}", marker.pos.x as f64 + 0.5);
+            let _ = writeln!(yaml, "        y: {:.1}", marker.pos.y as f64 + 0.5);
+            let _ = writeln!(yaml, "        z: {:.1}", marker.pos.z as f64 + 0.5);
+            let icon = match marker.kind {
+                MarkerKind::Banner => "pin",
+                MarkerKind::Frame => "sign",
+            };
+            let _ = writeln!(yaml, "        icon: {icon}");
+            let _ = writeln!(yaml, "        label: {}", quote(&marker.label));
+        }
+    }
+    yaml
+}
+
+/// Escapes the characters that have a meaning in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markers::{bluemap_markers, collect_markers, dynmap_markers};
+    use crate::synthetic::synthetic_maps;
+    use crate::{Marker, Pos};
+    use std::path::Path;
+
+    #[test]
+    fn test_markers() {
+        // The detailed maps 0 to 15 have banners on maps 0, 3, 6, 9, 12 and 15
+        let mut maps = synthetic_maps(Path::new("world"));
+        maps.truncate(16);
+        maps[1].data.frames.push(Marker {
+            entity_id: 7,
+            rotation: 0,
+            pos: Pos { x: 1, y: 64, z: 1 },
+        });
+        maps[2].data.banners = maps[0].data.banners.clone();
+        let markers = collect_markers(&maps);
+        assert_eq!(markers.len(), 7);
+
+        let bluemap = bluemap_markers(&markers);
+        let frames = bluemap["frames"]["markers"].as_object().unwrap();
+        assert_eq!(frames["frames_1_64_1"]["label"], "Map #1");
+        let set = bluemap["banners"]["markers"].as_object().unwrap();
+        assert_eq!(set.len(), 6);
+
+        let dynmap = dynmap_markers(&markers, "world");
+        assert!(dynmap.contains("      frames_1_64_1:\n        world: \"world\"\n"));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::markers::{
+    bluemap_markers, collect_markers, dynmap_markers, MarkerFormat, PointMarker,
+};
+use minecraft_map_tool::read_maps_from;
+use minecraft_map_tool::transform::NETHER_NAME;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+BlueMap marker sets can be copied to the marker-sets of a map in the BlueMap configuration, \
+and Dynmap sets to plugins/dynmap/markers.yml.")]
+pub struct MarkersArgs {
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which map files are
+    /// searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Format of the marker sets
+    #[arg(short, long, default_value = "bluemap")]
+    format: MarkerFormat,
+
+    /// Only export markers from maps with matching dimensions name
+    #[arg(short, long, default_value = "Overworld")]
+    dimension: String,
+
+    /// World name used in Dynmap markers
+    #[arg(short, long, default_value = "world")]
+    world: String,
+
+    /// Write the markers to this file instead of the standard output
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(long)]
+    recursive: bool,
+
+    /// Also export the markers of the Nether maps at the matching Overworld coordinates,
+    /// multiplied by 8, for planning nether portals
+    #[arg(long)]
+    project_nether: bool,
+}
+
+pub fn run(args: &MarkersArgs) -> ExitCode {
+    let mut maps = match read_maps_from(&args.paths, &None, args.recursive) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not read maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let dimension = args.dimension.to_lowercase();
+    let nether = NETHER_NAME.to_lowercase();
+    let mut map_items = Vec::new();
+    let mut nether_items = Vec::new();
+    while let Some((path, map_item)) = maps.next_with_path() {
+        match map_item {
+            Ok(map_item) => {
+                let name = map_item.data.pretty_dimension().to_lowercase();
+                if args.project_nether && name == nether {
+                    nether_items.push(map_item);
+                } else if name == dimension {
+                    map_items.push(map_item);
+                }
+            }
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+    let mut markers = collect_markers(&map_items);
+    info!(
+        "{} markers found from {} maps",
+        markers.len(),
+        map_items.len()
+    );
+    if args.project_nether {
+        let projected = collect_markers(&nether_items);
+        info!(
+            "{} Nether markers found from {} maps",
+            projected.len(),
+            nether_items.len()
+        );
+        markers.extend(projected.iter().map(|marker| PointMarker {
+            label: format!("{} (Nether)", marker.label),
+            ..marker.projected_to_overworld()
+        }));
+    }
+
+    let output = match args.format {
+        MarkerFormat::Bluemap => match serde_json::to_string_pretty(&bluemap_markers(&markers)) {
+            Ok(json) => json + "\n",
+            Err(err) => {
+                error!("Could not write the markers: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+        MarkerFormat::Dynmap => dynmap_markers(&markers, &args.world),
+    };
+    match &args.output_file {
+        None => print!("{output}"),
+        Some(file) => {
+            if let Err(err) = sink().write(file, output) {
+                error!("Could not write {}: {err}", file.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,121 @@
+//! Masks of map images
+//!
+//! A wall of maps is complete when every pixel of it is explored. A mask shows the pixels
+//! that are still unexplored inside the map bounds, so players know where they need to walk.
+//! Transparent pixels that no map covers are not unexplored, they are just outside the wall.
+
+use crate::stitch::Area;
+use crate::transform::MapTransform;
+use image::{Rgba, RgbaImage};
+
+/// Masked pixels in the binary mask
+const MASKED: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Other pixels in the binary mask
+const UNMASKED: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Color of the masked pixels drawn over the map image
+const TINT: Rgba<u8> = Rgba([255, 0, 64, 160]);
+
+/// Pixels selected by a mask
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Mask {
+    /// Pixels inside the map bounds that are not explored yet
+    Unexplored,
+}
+
+/// How a mask is drawn
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum MaskStyle {
+    /// White masked pixels on black
+    #[default]
+    Binary,
+
+    /// Map image with the masked pixels tinted red
+    Tint,
+}
+
+/// Draws the *mask* of the *image* showing the *area*
+///
+/// The *bounds* are the areas covered by maps. Both the *area* and the *bounds* are given
+/// with one unit for each image pixel, which are blocks for stitched maps with zoom 0.
+pub fn draw_mask(
+    image: &RgbaImage,
+    area: &Area,
+    bounds: &[Area],
+    mask: Mask,
+    style: MaskStyle,
+) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let transform = MapTransform::of_area(area, 0);
+    let mut covered = vec![false; width as usize * height as usize];
+    for bound in bounds.iter().filter(|bound| bound.overlaps(area)) {
+        let (left, top) = transform.pixel_at(bound.left.max(area.left), bound.top.max(area.top));
+        let (right, bottom) =
+            transform.pixel_at(bound.right.min(area.right), bound.bottom.min(area.bottom));
+        let (left, right) = (left as usize, right as usize);
+        for y in top..=bottom {
+            let row = y as usize * width as usize;
+            covered[row + left..=row + right].fill(true);
+        }
+    }
+
+    let mut result = match style {
+        MaskStyle::Binary => RgbaImage::from_pixel(width, height, UNMASKED),
+        MaskStyle::Tint => image.clone(),
+    };
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let masked = match mask {
+            Mask::Unexplored => pixel[3] == 0 && covered[(y * width + x) as usize],
+        };
+        if masked {
+            let color = match style {
+                MaskStyle::Binary => MASKED,
+                MaskStyle::Tint => TINT,
+            };
+            result.put_pixel(x, y, color);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mask::{draw_mask, Mask, MaskStyle, MASKED, UNMASKED};
+    use crate::stitch::Area;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_draw_mask() {
+        // Explored left half, and a map covering only the top half
+        let mut image = RgbaImage::new(4, 4);
+        for y in 0..4 {
+            image.put_pixel(0, y, Rgba([10, 20, 30, 255]));
+            image.put_pixel(1, y, Rgba([10, 20, 30, 255]));
+        }
+        let area = Area {
+            left: 100,
+            top: -4,
+            right: 103,
+            bottom: -1,
+        };
+        let bounds = [Area {
+            left: 96,
+            top: -8,
+            right: 103,
+            bottom: -3,
+        }];
+        let mask = draw_mask(&image, &area, &bounds, Mask::Unexplored, MaskStyle::Binary);
+        assert_eq!(*mask.get_pixel(1, 0), UNMASKED);
+        assert_eq!(*mask.get_pixel(2, 0), MASKED);
+        assert_eq!(*mask.get_pixel(3, 1), MASKED);
+        assert_eq!(*mask.get_pixel(3, 2), UNMASKED);
+
+        let tinted = draw_mask(&image, &area, &bounds, Mask::Unexplored, MaskStyle::Tint);
+        assert_eq!(tinted.get_pixel(0, 0), image.get_pixel(0, 0));
+        assert_eq!(tinted.get_pixel(2, 3), image.get_pixel(2, 3));
+        assert_ne!(tinted.get_pixel(2, 0), image.get_pixel(2, 0));
+    }
+}
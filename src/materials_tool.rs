@@ -0,0 +1,111 @@
+use crate::charset::preset;
+use anyhow::{anyhow, Result};
+use clap::Args;
+use comfy_table::{Cell, CellAlignment, ContentArrangement, Table};
+use log::{error, warn};
+use minecraft_map_tool::map_art::{materials, quantize, ArtStyle, Material};
+use minecraft_map_tool::palette::{
+    base_color_name, block_candidates, building_block, generate_palette, BASE_COLORS_2699,
+    SHADE_NAMES,
+};
+use minecraft_map_tool::MapItem;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct MaterialsArgs {
+    /// Map_#.dat file or an image to be built as map art
+    file: PathBuf,
+
+    /// How the map art is going to be built
+    #[arg(short, long, default_value = "staircase")]
+    style: ArtStyle,
+}
+
+pub fn run(args: &MaterialsArgs) -> ExitCode {
+    let colors = match read_colors(&args.file, args.style) {
+        Ok(colors) => colors,
+        Err(err) => {
+            error!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let materials = materials(&colors);
+    if materials.is_empty() {
+        println!("Nothing to build");
+        return ExitCode::SUCCESS;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    let mut header = vec!["Block", "Color"];
+    if args.style == ArtStyle::Staircase {
+        header.extend(&SHADE_NAMES[0..3]);
+    }
+    header.extend(["Total", "Stacks", "Shulker Boxes"]);
+    table.set_header(header);
+    for material in &materials {
+        table.add_row(make_row(material, args.style));
+    }
+    println!("{table}");
+
+    let total: usize = materials.iter().map(Material::total).sum();
+    println!("Blocks in total: {total}");
+    let unbuildable: usize = materials
+        .iter()
+        .map(|material| {
+            material.total()
+                - args
+                    .style
+                    .shades()
+                    .iter()
+                    .map(|shade| material.shades[*shade as usize])
+                    .sum::<usize>()
+        })
+        .sum();
+    if unbuildable > 0 {
+        warn!("{unbuildable} pixels use shades that cannot be built in this style");
+    }
+    ExitCode::SUCCESS
+}
+
+/// Reads map colors from a map file or converts them from an image
+fn read_colors(file: &Path, style: ArtStyle) -> Result<Vec<u8>> {
+    if file.extension().unwrap_or_default() == "dat" {
+        let map_item =
+            MapItem::read_from(file).map_err(|err| anyhow!("Could not read map item: {err}"))?;
+        Ok(map_item.data.colors.iter().map(|c| *c as u8).collect())
+    } else {
+        let image = image::open(file)
+            .map_err(|err| anyhow!("Could not read image: {err}"))?
+            .to_rgba8();
+        Ok(quantize(
+            &image,
+            &generate_palette(&BASE_COLORS_2699),
+            style,
+        ))
+    }
+}
+
+fn make_row(material: &Material, style: ArtStyle) -> Vec<Cell> {
+    let block = building_block(material.base_color)
+        .or_else(|| block_candidates(material.base_color).first().copied())
+        .unwrap_or("Unknown");
+    let mut row = vec![
+        Cell::new(block),
+        Cell::new(base_color_name(material.base_color)),
+    ];
+    if style == ArtStyle::Staircase {
+        for count in &material.shades[0..3] {
+            row.push(Cell::new(count).set_alignment(CellAlignment::Right));
+        }
+    }
+    let (stacks, remainder) = material.stacks();
+    row.push(Cell::new(material.total()).set_alignment(CellAlignment::Right));
+    row.push(Cell::new(format!("{stacks} × 64 + {remainder}")).set_alignment(CellAlignment::Right));
+    row.push(Cell::new(material.shulker_boxes()).set_alignment(CellAlignment::Right));
+    row
+}
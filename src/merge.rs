@@ -0,0 +1,98 @@
+//! Merging map items
+//!
+//! When two players explore different parts of the same area, their maps can be combined
+//! by drawing the explored pixels of one map over the other. Maps with different centers
+//! or scales are resampled, so only the area covered by both maps is merged.
+
+use crate::error::{Error, Result};
+use crate::transform::MapTransform;
+use crate::MapItem;
+
+/// Result of merging two maps
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MergeStats {
+    /// Number of pixels taken from the overlay
+    pub changed_pixels: usize,
+
+    /// Number of banners added from the overlay
+    pub added_banners: usize,
+}
+
+/// Draws the explored pixels of the *overlay* over the *base* map
+///
+/// The overlay is sampled at the center of each base map pixel, so maps with a different
+/// center or scale can be merged too. Banners of the overlay that are inside the base map
+/// and not already on it are added. The maps must be from the same dimension and have the
+/// scales the game uses, from 0 to 4.
+pub fn merge_into(base: &mut MapItem, overlay: &MapItem) -> Result<MergeStats> {
+    if base.data.dimension != overlay.data.dimension {
+        return Err(Error::map_item_error("Maps are from different dimensions"));
+    }
+    if base.data.colors.len() != 128 * 128 || overlay.data.colors.len() != 128 * 128 {
+        return Err(Error::map_item_error("Color buffer incomplete"));
+    }
+
+    if !(0..=4).contains(&base.data.scale) || !(0..=4).contains(&overlay.data.scale) {
+        return Err(Error::map_item_error("Map scale must be from 0 to 4"));
+    }
+
+    let base_transform = MapTransform::of_map(&base.data);
+    let overlay_transform = MapTransform::of_map(&overlay.data);
+    let half_block = base_transform.block_size() as f64 / 2.0;
+    let on_map = |(x, y): (i32, i32)| (0..128).contains(&x) && (0..128).contains(&y);
+
+    let mut stats = MergeStats::default();
+    let overlay_colors = &overlay.data.colors[..];
+    let base_colors = &mut base.data.colors[..];
+    for y in 0..128 {
+        for x in 0..128 {
+            // Center of the base pixel, which is exact in floating point
+            let (world_x, world_z) = base_transform.world_at(x, y);
+            let (overlay_x, overlay_y) = overlay_transform
+                .position(world_x as f64 + half_block, world_z as f64 + half_block);
+            let overlay_pixel = (overlay_x.floor() as i32, overlay_y.floor() as i32);
+            if !on_map(overlay_pixel) {
+                continue;
+            }
+            let color = overlay_colors[(overlay_pixel.1 * 128 + overlay_pixel.0) as usize];
+            // Colors 0 to 3 are transparent, which means unexplored
+            let index = (y * 128 + x) as usize;
+            if (color as u8) >= 4 && base_colors[index] != color {
+                base_colors[index] = color;
+                stats.changed_pixels += 1;
+            }
+        }
+    }
+
+    for banner in &overlay.data.banners {
+        let inside = on_map(base_transform.pixel_at(banner.pos.x, banner.pos.z));
+        if inside && !base.data.banners.contains(banner) {
+            base.data.banners.push(banner.clone());
+            stats.added_banners += 1;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merge::merge_into;
+    use crate::synthetic::synthetic_maps;
+    use std::path::Path;
+
+    #[test]
+    fn test_merge_into() {
+        let mut maps = synthetic_maps(Path::new("world"));
+        // Map 1 has unexplored rows at the bottom, and map 16 with scale 1 covers maps 0, 1, 4
+        // and 5 fully explored
+        let overlay = maps.swap_remove(16);
+        let mut base = maps.swap_remove(1);
+        let unexplored = base.data.colors.iter().filter(|&&c| (c as u8) < 4).count();
+        assert_eq!(unexplored, 16 * 128);
+
+        let stats = merge_into(&mut base, &overlay).unwrap();
+        assert_eq!(stats.changed_pixels, 128 * 128);
+        let unexplored = base.data.colors.iter().filter(|&&c| (c as u8) < 4).count();
+        assert_eq!(unexplored, 0);
+    }
+}
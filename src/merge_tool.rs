@@ -0,0 +1,60 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info};
+use minecraft_map_tool::merge::merge_into;
+use minecraft_map_tool::MapItem;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    /// The map file whose pixels are kept where the other map is unexplored
+    base: PathBuf,
+
+    /// The map file whose explored pixels are drawn over the base map
+    overlay: PathBuf,
+
+    /// Write the merged map to this file, which can be the base map file
+    output: PathBuf,
+
+    /// Allow maps with different scales or centers. The overlay is resampled to the base map
+    /// and only the area covered by both maps is merged.
+    #[arg(long)]
+    resample: bool,
+}
+
+pub fn run(args: &MergeArgs) -> ExitCode {
+    let (mut base, overlay) = match (
+        MapItem::read_from(&args.base),
+        MapItem::read_from(&args.overlay),
+    ) {
+        (Ok(base), Ok(overlay)) => (base, overlay),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Could not read map file: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let same_area = base.data.scale == overlay.data.scale
+        && base.data.x_center == overlay.data.x_center
+        && base.data.z_center == overlay.data.z_center;
+    if !same_area && !args.resample {
+        error!("The maps have different scales or centers, use --resample to merge them anyway");
+        return ExitCode::FAILURE;
+    }
+    let stats = match merge_into(&mut base, &overlay) {
+        Ok(stats) => stats,
+        Err(err) => {
+            error!("Could not merge maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = sink().write_with(&args.output, |file| base.write_to(file)) {
+        error!("Could not write {}: {err}", args.output.display());
+        return ExitCode::FAILURE;
+    }
+    info!(
+        "{} pixels and {} banners taken from the overlay",
+        stats.changed_pixels, stats.added_banners
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,88 @@
+//! File names from templates
+//!
+//! Templates are text with placeholders in braces, like `{dimension}/{id}_{scale}.png`.
+//! A placeholder is replaced with the value of the map, and the rest is kept as it is.
+
+use crate::source::map_id;
+use crate::MapItem;
+
+/// Placeholders that can be used in the templates for map files
+pub const MAP_PLACEHOLDERS: [&str; 7] =
+    ["id", "name", "dimension", "scale", "x", "z", "data_version"];
+
+/// Checks that the *template* uses only the *placeholders* and has no unclosed braces
+pub fn check_template(template: &str, placeholders: &[&str]) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            return Err(format!("Missing }} in template {template:?}"));
+        };
+        let name = &rest[start + 1..start + length];
+        if !placeholders.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder {{{name}}}, known placeholders are {{{}}}",
+                placeholders.join("}, {")
+            ));
+        }
+        rest = &rest[start + length + 1..];
+    }
+    Ok(())
+}
+
+/// Replaces the placeholders in the *template* with the *values*
+///
+/// Values are given as pairs of placeholder names and texts. Unknown placeholders are kept.
+pub fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in values {
+        text = text.replace(&format!("{{{name}}}"), value);
+    }
+    text
+}
+
+/// Values of the [MAP_PLACEHOLDERS] for the *map_item* in the *dimension*
+///
+/// The id is the number in the file name map_<#>.dat, or the whole file name without the
+/// extension for other files. The name is always the file name without the extension.
+pub fn map_values(map_item: &MapItem, dimension: &str) -> Vec<(&'static str, String)> {
+    let name = map_item
+        .file
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let id = map_id(&map_item.file).map_or_else(|| name.clone(), |id| id.to_string());
+    vec![
+        ("id", id),
+        ("name", name),
+        ("dimension", dimension.to_string()),
+        ("scale", map_item.data.scale.to_string()),
+        ("x", map_item.data.x_center.to_string()),
+        ("z", map_item.data.z_center.to_string()),
+        ("data_version", map_item.data_version.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::naming::{check_template, fill_template, map_values, MAP_PLACEHOLDERS};
+    use crate::synthetic::synthetic_maps;
+    use std::path::Path;
+
+    #[test]
+    fn test_templates() {
+        assert!(check_template("{dimension}/{id}_{scale}.png", &MAP_PLACEHOLDERS).is_ok());
+        assert!(check_template("{color}.png", &MAP_PLACEHOLDERS).is_err());
+        assert!(check_template("{id.png", &MAP_PLACEHOLDERS).is_err());
+
+        let map_item = &synthetic_maps(Path::new("world"))[17];
+        let values = map_values(map_item, "Overworld");
+        assert_eq!(
+            fill_template("{dimension}/{id}_{scale}_{x}_{z}.png", &values),
+            format!(
+                "Overworld/17_1_{}_{}.png",
+                map_item.data.x_center, map_item.data.z_center
+            )
+        );
+    }
+}
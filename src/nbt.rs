@@ -0,0 +1,393 @@
+//! Inspecting any NBT data
+//!
+//! Map files are read into [MapItem](crate::MapItem), but other NBT files, and parts of map
+//! files that are not in the map item, can be read as a [Value] tree.
+//!
+//! The tree can be converted to JSON and back without losing the NBT types. Compounds,
+//! lists, strings, ints and doubles use the plain JSON types. The other types are objects
+//! with a single key naming the type, for example `{"$byte": 1}` or `{"$int_array": [1, 2]}`.
+//! A compound that would look like such an object is wrapped in `{"$compound": {...}}`.
+
+use crate::compression::{decompress, write_compressed, NbtCompression};
+use crate::error::{Error, Result};
+use crate::sink::Sink;
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read};
+use std::path::Path;
+
+/// Reads the NBT data from the *reader*, the compression is detected (see [decompress])
+pub fn read_nbt<R: Read>(reader: R) -> Result<Value> {
+    Ok(fastnbt::from_reader(decompress(reader)?)?)
+}
+
+/// Writes the *value* to the *writer* with the *compression*
+pub fn write_nbt<W: std::io::Write>(
+    writer: W,
+    value: &Value,
+    compression: NbtCompression,
+) -> Result<()> {
+    write_compressed(writer, value, compression)
+}
+
+/// Reads the NBT *file*, calls *update* with the content and writes the file back if
+/// *update* returns true
+///
+/// The file is written through the *sink* with the compression it had. Returns true if the
+/// file was changed.
+pub fn update_nbt_file<F>(file: &Path, sink: &Sink, update: F) -> Result<bool>
+where
+    F: FnOnce(&mut Value) -> bool,
+{
+    let content = fs::read(file)?;
+    let mut value = read_nbt(content.as_slice())?;
+    if !update(&mut value) {
+        return Ok(false);
+    }
+    let compression = NbtCompression::detect(&content);
+    replace_file(file, sink, |writer| write_nbt(writer, &value, compression))?;
+    Ok(true)
+}
+
+/// Writes the *file* through a temporary file, so the old content is kept if writing fails
+pub(crate) fn replace_file<F>(file: &Path, sink: &Sink, write: F) -> Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<()>,
+{
+    sink.write_with(file, |file| {
+        let temporary = file.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        let result = write(&mut writer).and_then(|_| Ok(std::io::Write::flush(&mut writer)?));
+        if let Err(err) = result {
+            let _ = fs::remove_file(&temporary);
+            return Err(err);
+        }
+        fs::rename(&temporary, file)?;
+        Ok(())
+    })
+}
+
+/// Converts the *value* to JSON with the type annotations described in the [module](self)
+pub fn to_json(value: &Value) -> Result<serde_json::Value> {
+    let non_finite = || Error::map_item_error("NaN and infinite numbers cannot be stored in JSON");
+    Ok(match value {
+        Value::Byte(v) => json!({ "$byte": v }),
+        Value::Short(v) => json!({ "$short": v }),
+        Value::Int(v) => json!(v),
+        Value::Long(v) => json!({ "$long": v }),
+        Value::Float(v) if v.is_finite() => json!({ "$float": v }),
+        Value::Double(v) if v.is_finite() => json!(v),
+        Value::Float(_) | Value::Double(_) => return Err(non_finite()),
+        Value::String(v) => json!(v),
+        Value::ByteArray(v) => json!({ "$byte_array": v.iter().collect::<Vec<_>>() }),
+        Value::IntArray(v) => json!({ "$int_array": v.iter().collect::<Vec<_>>() }),
+        Value::LongArray(v) => json!({ "$long_array": v.iter().collect::<Vec<_>>() }),
+        Value::List(list) => {
+            serde_json::Value::Array(list.iter().map(to_json).collect::<Result<_>>()?)
+        }
+        Value::Compound(compound) => {
+            let mut keys: Vec<&String> = compound.keys().collect();
+            keys.sort();
+            let mut object = serde_json::Map::new();
+            for key in keys {
+                object.insert(key.clone(), to_json(&compound[key])?);
+            }
+            if object.len() == 1 && keys_look_typed(&object) {
+                json!({ "$compound": object })
+            } else {
+                serde_json::Value::Object(object)
+            }
+        }
+    })
+}
+
+/// Converts JSON made with [to_json] back to NBT
+pub fn from_json(json: &serde_json::Value) -> Result<Value> {
+    let invalid = |message| move || Error::map_item_error(message);
+    Ok(match json {
+        serde_json::Value::Null => return Err(Error::map_item_error("NBT has no null value")),
+        serde_json::Value::Bool(v) => Value::Byte(*v as i8),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(v) => Value::Int(i32::try_from(v).map_err(|_| invalid("Int out of range")())?),
+            None => Value::Double(number.as_f64().ok_or_else(invalid("Invalid number"))?),
+        },
+        serde_json::Value::String(v) => Value::String(v.clone()),
+        serde_json::Value::Array(list) => {
+            Value::List(list.iter().map(from_json).collect::<Result<_>>()?)
+        }
+        serde_json::Value::Object(object) if object.len() == 1 && keys_look_typed(object) => {
+            let (key, value) = object.iter().next().expect("object has one key");
+            let integer = || value.as_i64().ok_or_else(invalid("Expected an integer"));
+            let integers = || -> Result<Vec<i64>> {
+                value
+                    .as_array()
+                    .ok_or_else(invalid("Expected an array"))?
+                    .iter()
+                    .map(|v| v.as_i64().ok_or_else(invalid("Expected an integer")))
+                    .collect()
+            };
+            let out_of_range = invalid("Number out of range");
+            match key.as_str() {
+                "$byte" => Value::Byte(i8::try_from(integer()?).map_err(|_| out_of_range())?),
+                "$short" => Value::Short(i16::try_from(integer()?).map_err(|_| out_of_range())?),
+                "$long" => Value::Long(integer()?),
+                "$float" => {
+                    Value::Float(value.as_f64().ok_or_else(invalid("Expected a number"))? as f32)
+                }
+                "$byte_array" => Value::ByteArray(ByteArray::new(
+                    integers()?
+                        .into_iter()
+                        .map(|v| i8::try_from(v).map_err(|_| out_of_range()))
+                        .collect::<Result<_>>()?,
+                )),
+                "$int_array" => Value::IntArray(IntArray::new(
+                    integers()?
+                        .into_iter()
+                        .map(|v| i32::try_from(v).map_err(|_| out_of_range()))
+                        .collect::<Result<_>>()?,
+                )),
+                "$long_array" => Value::LongArray(LongArray::new(integers()?)),
+                "$compound" => match value {
+                    serde_json::Value::Object(_) => compound_from_json(value)?,
+                    _ => return Err(invalid("Expected an object")()),
+                },
+                _ => return Err(invalid("Unknown NBT type annotation")()),
+            }
+        }
+        serde_json::Value::Object(_) => compound_from_json(json)?,
+    })
+}
+
+fn compound_from_json(json: &serde_json::Value) -> Result<Value> {
+    let mut compound = HashMap::new();
+    if let serde_json::Value::Object(object) = json {
+        for (key, value) in object {
+            compound.insert(key.clone(), from_json(value)?);
+        }
+    }
+    Ok(Value::Compound(compound))
+}
+
+fn keys_look_typed(object: &serde_json::Map<String, serde_json::Value>) -> bool {
+    object.keys().all(|key| key.starts_with('$'))
+}
+
+/// Formats the *value* in the stringified NBT (SNBT) syntax used in Minecraft commands
+///
+/// The text is on one line, so it can be pasted into `/data` commands. Compound keys are
+/// sorted by name.
+pub fn to_snbt(value: &Value) -> String {
+    let mut text = String::new();
+    write_snbt(&mut text, value);
+    text
+}
+
+fn write_snbt(text: &mut String, value: &Value) {
+    let join = |values: Vec<String>| values.join(",");
+    let _ = match value {
+        Value::Byte(v) => write!(text, "{v}b"),
+        Value::Short(v) => write!(text, "{v}s"),
+        Value::Int(v) => write!(text, "{v}"),
+        Value::Long(v) => write!(text, "{v}L"),
+        Value::Float(v) => write!(text, "{v}f"),
+        Value::Double(v) => write!(text, "{v}d"),
+        Value::String(v) => write!(text, "{}", snbt_string(v)),
+        Value::ByteArray(v) => write!(
+            text,
+            "[B;{}]",
+            join(v.iter().map(|v| format!("{v}b")).collect())
+        ),
+        Value::IntArray(v) => write!(text, "[I;{}]", join(v.iter().map(i32::to_string).collect())),
+        Value::LongArray(v) => write!(
+            text,
+            "[L;{}]",
+            join(v.iter().map(|v| format!("{v}L")).collect())
+        ),
+        Value::List(list) => write!(text, "[{}]", join(list.iter().map(to_snbt).collect())),
+        Value::Compound(compound) => {
+            let mut keys: Vec<&String> = compound.keys().collect();
+            keys.sort();
+            let entries = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", snbt_key(key), to_snbt(&compound[key])))
+                .collect();
+            write!(text, "{{{}}}", join(entries))
+        }
+    };
+}
+
+fn snbt_key(key: &str) -> String {
+    let is_plain = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c));
+    if is_plain {
+        key.to_string()
+    } else {
+        snbt_string(key)
+    }
+}
+
+fn snbt_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Finds the node at the *path* in the *value*
+///
+/// The path has compound keys and list indexes separated by dots, for example
+/// `data.banners.0.Pos`. An empty path selects the *value* itself.
+pub fn select<'a>(value: &'a Value, path: &str) -> Result<&'a Value> {
+    let mut node = value;
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        let child = match node {
+            Value::Compound(compound) => compound.get(key),
+            Value::List(list) => key.parse::<usize>().ok().and_then(|index| list.get(index)),
+            _ => None,
+        };
+        node = child.ok_or_else(|| Error::map_item_error("NBT path not found"))?;
+    }
+    Ok(node)
+}
+
+/// Name of the NBT type of the *value*
+pub fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Byte(_) => "Byte",
+        Value::Short(_) => "Short",
+        Value::Int(_) => "Int",
+        Value::Long(_) => "Long",
+        Value::Float(_) => "Float",
+        Value::Double(_) => "Double",
+        Value::String(_) => "String",
+        Value::ByteArray(_) => "ByteArray",
+        Value::IntArray(_) => "IntArray",
+        Value::LongArray(_) => "LongArray",
+        Value::List(_) => "List",
+        Value::Compound(_) => "Compound",
+    }
+}
+
+/// Formats the *value* as an indented tree with one node on each line
+///
+/// Compound keys are sorted by name. At most *max_array* elements are shown from byte, int
+/// and long arrays.
+pub fn dump(value: &Value, name: &str, max_array: usize) -> String {
+    let mut text = String::new();
+    dump_node(&mut text, value, name, 0, max_array);
+    text
+}
+
+fn dump_node(text: &mut String, value: &Value, name: &str, depth: usize, max_array: usize) {
+    let content = match value {
+        Value::Byte(v) => v.to_string(),
+        Value::Short(v) => v.to_string(),
+        Value::Int(v) => v.to_string(),
+        Value::Long(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Double(v) => v.to_string(),
+        Value::String(v) => format!("{v:?}"),
+        Value::ByteArray(v) => format_array(v.iter(), max_array),
+        Value::IntArray(v) => format_array(v.iter(), max_array),
+        Value::LongArray(v) => format_array(v.iter(), max_array),
+        Value::List(list) => format!("({} entries)", list.len()),
+        Value::Compound(compound) => format!("({} entries)", compound.len()),
+    };
+    let indent = "  ".repeat(depth);
+    let _ = writeln!(text, "{indent}{name}: {} {content}", type_name(value));
+    match value {
+        Value::List(list) => {
+            for (index, child) in list.iter().enumerate() {
+                dump_node(text, child, &index.to_string(), depth + 1, max_array);
+            }
+        }
+        Value::Compound(compound) => {
+            let mut keys: Vec<&String> = compound.keys().collect();
+            keys.sort();
+            for key in keys {
+                dump_node(text, &compound[key], key, depth + 1, max_array);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn format_array<T: std::fmt::Display>(
+    values: impl ExactSizeIterator<Item = T>,
+    max_array: usize,
+) -> String {
+    let len = values.len();
+    let mut shown: Vec<String> = values.take(max_array).map(|v| v.to_string()).collect();
+    if len > max_array {
+        shown.push(format!("... {} more", len - max_array));
+    }
+    format!("({len} entries) [{}]", shown.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::NbtCompression;
+    use crate::nbt::{dump, from_json, read_nbt, select, to_json, to_snbt, write_nbt};
+    use fastnbt::Value;
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::path::Path;
+
+    #[test]
+    fn test_select_and_dump() {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/map_0.dat");
+        let root = read_nbt(File::open(file).unwrap()).unwrap();
+        let colors = select(&root, "data.colors").unwrap();
+        assert_eq!(
+            dump(colors, "colors", 2),
+            "colors: ByteArray (16384 entries) [0, 0, ... 16382 more]\n"
+        );
+        assert!(dump(&root, "", 0).contains("  DataVersion: Int "));
+        assert!(select(&root, "data.missing").is_err());
+    }
+
+    #[test]
+    fn test_snbt() {
+        let value = Value::Compound(HashMap::from([
+            (String::from("Count"), Value::Byte(1)),
+            (
+                String::from("id"),
+                Value::String(String::from("say \"hi\"")),
+            ),
+            (String::from("two words"), Value::List(vec![Value::Long(2)])),
+            (
+                String::from("ints"),
+                Value::IntArray(fastnbt::IntArray::new(vec![1, -2])),
+            ),
+        ]));
+        assert_eq!(
+            to_snbt(&value),
+            r#"{Count:1b,id:"say \"hi\"",ints:[I;1,-2],"two words":[2L]}"#
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/map_0.dat");
+        let mut root = read_nbt(File::open(file).unwrap()).unwrap();
+        if let Value::Compound(compound) = &mut root {
+            compound.insert(String::from("Long"), Value::Long(-5));
+            compound.insert(String::from("Float"), Value::Float(0.5));
+            compound.insert(String::from("Double"), Value::Double(1.0));
+            compound.insert(
+                String::from("$tricky"),
+                Value::Compound(HashMap::from([(String::from("$byte"), Value::Short(3))])),
+            );
+        }
+        let json = to_json(&root).unwrap();
+        let text = serde_json::to_string_pretty(&json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(from_json(&parsed).unwrap(), root);
+
+        let mut bytes = Vec::new();
+        write_nbt(&mut bytes, &root, NbtCompression::Zlib).unwrap();
+        assert_eq!(read_nbt(bytes.as_slice()).unwrap(), root);
+    }
+}
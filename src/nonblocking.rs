@@ -0,0 +1,81 @@
+//! Async API for reading map items
+//!
+//! Reading map items requires decompressing and decoding NBT data, which is CPU work that
+//! should not be done on the executor threads. Functions in this module move the work to
+//! the blocking thread pool of tokio.
+
+use crate::error::{Error, Result};
+use crate::{read_maps_sorted, MapItem, SortSpec};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Number of map items read ahead of the stream consumer
+const STREAM_BUFFER: usize = 16;
+
+impl MapItem {
+    /// Read map item from the given *file* path without blocking the executor
+    pub async fn read_from_async(file: &Path) -> Result<MapItem> {
+        let content = tokio::fs::read(file).await?;
+        let file = PathBuf::from(file);
+        tokio::task::spawn_blocking(move || MapItem::read_from_reader(content.as_slice(), &file))
+            .await
+            .map_err(|_| Error::map_item_error("Map reading task failed"))?
+    }
+}
+
+/// Async version of [read_maps_sorted]
+///
+/// Map files are searched and read in the blocking thread pool, and the map items are
+/// delivered through the returned stream in the same order as [read_maps_sorted] would
+/// return them.
+pub async fn read_maps_async(
+    path: &Path,
+    sort: Option<SortSpec>,
+    recursive: bool,
+) -> Result<ReceiverStream<Result<MapItem>>> {
+    let path = PathBuf::from(path);
+    let maps = tokio::task::spawn_blocking(move || read_maps_sorted(&path, &sort, recursive))
+        .await
+        .map_err(|_| Error::map_item_error("Map searching task failed"))??;
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        for map_item in maps {
+            if sender.blocking_send(map_item).is_err() {
+                // The stream was dropped
+                break;
+            }
+        }
+    });
+    Ok(ReceiverStream::new(receiver))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nonblocking::read_maps_async;
+    use crate::synthetic::write_synthetic_world;
+    use crate::SortingOrder;
+    use tokio_stream::StreamExt;
+
+    #[test]
+    fn test_read_maps_async() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let count = runtime.block_on(async {
+            let data = world.path().join("data");
+            let mut stream = read_maps_async(&data, Some(SortingOrder::Name.into()), false)
+                .await
+                .unwrap();
+            let mut count = 0;
+            while let Some(map_item) = stream.next().await {
+                assert!(map_item.is_ok());
+                count += 1;
+            }
+            count
+        });
+        assert_eq!(count, 32);
+    }
+}
@@ -1,7 +1,22 @@
 use image::Rgba;
 use phf::{phf_map, Map};
 
-const MULTIPLIERS: [u16; 4] = [180, 220, 255, 135];
+/// Brightness multipliers of the four shades, in the order of [SHADE_NAMES]
+pub const MULTIPLIERS: [u16; 4] = [180, 220, 255, 135];
+
+/// Multipliers for [Shading::Exaggerated], further apart than in the game
+const EXAGGERATED_MULTIPLIERS: [u16; 4] = [135, 205, 255, 90];
+
+/// Color each shade is mixed with for [Shading::Hillshade], and how much
+///
+/// Slopes rising to the north are in a cool shadow and slopes falling to the north in a warm
+/// light.
+const HILLSHADE_TINTS: [([f32; 3], f32); 4] = [
+    ([25.0, 35.0, 90.0], 0.45),
+    ([0.0, 0.0, 0.0], 0.1),
+    ([255.0, 245.0, 215.0], 0.35),
+    ([10.0, 15.0, 50.0], 0.65),
+];
 
 /// Palette can be generated from base colors
 pub type BaseColors = Map<u8, [u8; 4]>;
@@ -77,26 +92,660 @@ pub const BASE_COLORS_2699: BaseColors = phf_map! {
         61u8 => [127, 167, 150, 255],
 };
 
+/// Names of the shades in the same order as they appear in the palette
+pub const SHADE_NAMES: [&str; 4] = ["Dark", "Normal", "Light", "Darkest"];
+
+/// Splits the palette *index* of a map pixel into its base color and shade
+///
+/// The shade indexes the [SHADE_NAMES] and the multipliers.
+pub const fn decompose(index: u8) -> (u8, u8) {
+    (index / 4, index % 4)
+}
+
+/// Palette index of the *base_color* with the *shade*, the opposite of [decompose]
+///
+/// Base color must be below 64 and shade below 4.
+pub const fn compose(base_color: u8, shade: u8) -> u8 {
+    base_color * 4 + shade
+}
+
+/// Names for the base colors
+///
+/// Names follow the map color identifiers used by the game, so for example, `Water` is the color
+/// of water and `Plant` is the color of leaves and flowers. Base color 0 is not listed because it
+/// is used for unexplored (transparent) pixels.
+pub const BASE_COLOR_NAMES: Map<u8, &'static str> = phf_map! {
+        1u8 => "Grass",
+        2u8 => "Sand",
+        3u8 => "Wool",
+        4u8 => "Fire",
+        5u8 => "Ice",
+        6u8 => "Metal",
+        7u8 => "Plant",
+        8u8 => "Snow",
+        9u8 => "Clay",
+        10u8 => "Dirt",
+        11u8 => "Stone",
+        12u8 => "Water",
+        13u8 => "Wood",
+        14u8 => "Quartz",
+        15u8 => "Orange",
+        16u8 => "Magenta",
+        17u8 => "Light Blue",
+        18u8 => "Yellow",
+        19u8 => "Light Green",
+        20u8 => "Pink",
+        21u8 => "Gray",
+        22u8 => "Light Gray",
+        23u8 => "Cyan",
+        24u8 => "Purple",
+        25u8 => "Blue",
+        26u8 => "Brown",
+        27u8 => "Green",
+        28u8 => "Red",
+        29u8 => "Black",
+        30u8 => "Gold",
+        31u8 => "Diamond",
+        32u8 => "Lapis",
+        33u8 => "Emerald",
+        34u8 => "Podzol",
+        35u8 => "Nether",
+        36u8 => "White Terracotta",
+        37u8 => "Orange Terracotta",
+        38u8 => "Magenta Terracotta",
+        39u8 => "Light Blue Terracotta",
+        40u8 => "Yellow Terracotta",
+        41u8 => "Light Green Terracotta",
+        42u8 => "Pink Terracotta",
+        43u8 => "Gray Terracotta",
+        44u8 => "Light Gray Terracotta",
+        45u8 => "Cyan Terracotta",
+        46u8 => "Purple Terracotta",
+        47u8 => "Blue Terracotta",
+        48u8 => "Brown Terracotta",
+        49u8 => "Green Terracotta",
+        50u8 => "Red Terracotta",
+        51u8 => "Black Terracotta",
+        52u8 => "Crimson Nylium",
+        53u8 => "Crimson Stem",
+        54u8 => "Crimson Hyphae",
+        55u8 => "Warped Nylium",
+        56u8 => "Warped Stem",
+        57u8 => "Warped Hyphae",
+        58u8 => "Warped Wart Block",
+        59u8 => "Deepslate",
+        60u8 => "Raw Iron",
+        61u8 => "Glow Lichen",
+};
+
+/// Returns name for the base color or "Unknown" if there is no name for the color
+pub fn base_color_name(base_color: u8) -> &'static str {
+    BASE_COLOR_NAMES.get(&base_color).unwrap_or(&"Unknown")
+}
+
+/// Representative blocks for the base colors
+///
+/// The first block in each list is the most common or practical choice for building map art.
+/// Lists are not complete; many blocks share the same map color.
+pub const BLOCK_GROUPS: Map<u8, &'static [&'static str]> = phf_map! {
+        1u8 => &["Grass Block", "Slime Block"],
+        2u8 => &[
+            "Sand",
+            "Birch Planks",
+            "Sandstone",
+            "Glowstone",
+            "End Stone",
+            "Bone Block",
+            "Scaffolding",
+        ],
+        3u8 => &["Cobweb", "Mushroom Stem"],
+        4u8 => &["Redstone Block", "TNT", "Lava", "Fire"],
+        5u8 => &["Ice", "Packed Ice", "Blue Ice"],
+        6u8 => &["Iron Block", "Iron Door", "Anvil", "Brewing Stand", "Lantern", "Grindstone"],
+        7u8 => &["Oak Leaves", "Tall Grass", "Flowers", "Sugar Cane", "Cactus", "Lily Pad"],
+        8u8 => &[
+            "Snow Block",
+            "Snow",
+            "White Wool",
+            "White Concrete",
+            "White Carpet",
+            "Powder Snow",
+        ],
+        9u8 => &["Clay"],
+        10u8 => &[
+            "Dirt",
+            "Coarse Dirt",
+            "Farmland",
+            "Dirt Path",
+            "Granite",
+            "Jungle Planks",
+            "Packed Mud",
+        ],
+        11u8 => &["Stone", "Cobblestone", "Andesite", "Stone Bricks", "Gravel", "Furnace"],
+        12u8 => &["Water", "Kelp", "Seagrass"],
+        13u8 => &["Oak Planks", "Oak Log", "Crafting Table", "Bookshelf", "Chest", "Note Block"],
+        14u8 => &["Quartz Block", "Diorite", "Sea Lantern", "Target"],
+        15u8 => &[
+            "Orange Wool",
+            "Acacia Planks",
+            "Pumpkin",
+            "Red Sand",
+            "Terracotta",
+            "Honey Block",
+        ],
+        16u8 => &["Magenta Wool", "Purpur Block"],
+        17u8 => &["Light Blue Wool", "Soul Fire"],
+        18u8 => &["Yellow Wool", "Hay Bale", "Sponge", "Bee Nest"],
+        19u8 => &["Lime Wool", "Melon"],
+        20u8 => &["Pink Wool", "Brain Coral Block", "Pearlescent Froglight"],
+        21u8 => &["Gray Wool", "Acacia Log", "Dead Coral Block", "Tinted Glass"],
+        22u8 => &["Light Gray Wool", "Structure Block", "Jigsaw Block"],
+        23u8 => &["Cyan Wool", "Prismarine", "Sculk Sensor"],
+        24u8 => &[
+            "Purple Wool",
+            "Mycelium",
+            "Amethyst Block",
+            "Chorus Plant",
+            "Bubble Coral Block",
+        ],
+        25u8 => &["Blue Wool", "Tube Coral Block"],
+        26u8 => &[
+            "Brown Wool",
+            "Dark Oak Planks",
+            "Soul Sand",
+            "Soul Soil",
+            "Brown Mushroom Block",
+        ],
+        27u8 => &["Green Wool", "Dried Kelp Block", "End Portal Frame", "Sea Pickle"],
+        28u8 => &[
+            "Red Wool",
+            "Bricks",
+            "Red Mushroom Block",
+            "Nether Wart Block",
+            "Mangrove Planks",
+            "Shroomlight",
+        ],
+        29u8 => &["Black Wool", "Obsidian", "Coal Block", "Basalt", "Blackstone", "Sculk"],
+        30u8 => &["Gold Block", "Raw Gold Block", "Bell"],
+        31u8 => &["Diamond Block", "Prismarine Bricks", "Dark Prismarine", "Beacon"],
+        32u8 => &["Lapis Block"],
+        33u8 => &["Emerald Block"],
+        34u8 => &["Podzol", "Spruce Planks", "Spruce Log", "Campfire"],
+        35u8 => &["Netherrack", "Nether Bricks", "Magma Block", "Nether Quartz Ore"],
+        36u8 => &["White Terracotta", "Calcite", "Cherry Planks"],
+        37u8 => &["Orange Terracotta"],
+        38u8 => &["Magenta Terracotta"],
+        39u8 => &["Light Blue Terracotta"],
+        40u8 => &["Yellow Terracotta"],
+        41u8 => &["Lime Terracotta"],
+        42u8 => &["Pink Terracotta"],
+        43u8 => &["Gray Terracotta", "Tuff"],
+        44u8 => &["Light Gray Terracotta", "Mud Bricks"],
+        45u8 => &["Cyan Terracotta", "Mud"],
+        46u8 => &["Purple Terracotta"],
+        47u8 => &["Blue Terracotta"],
+        48u8 => &["Brown Terracotta", "Dripstone Block", "Pointed Dripstone"],
+        49u8 => &["Green Terracotta"],
+        50u8 => &["Red Terracotta"],
+        51u8 => &["Black Terracotta"],
+        52u8 => &["Crimson Nylium"],
+        53u8 => &["Crimson Stem", "Crimson Planks"],
+        54u8 => &["Crimson Hyphae"],
+        55u8 => &["Warped Nylium"],
+        56u8 => &["Warped Stem", "Warped Planks"],
+        57u8 => &["Warped Hyphae"],
+        58u8 => &["Warped Wart Block"],
+        59u8 => &["Deepslate", "Cobbled Deepslate", "Deepslate Bricks"],
+        60u8 => &["Raw Iron Block"],
+        61u8 => &["Glow Lichen", "Verdant Froglight"],
+};
+
+/// Blocks for building map art, in the order of preference
+///
+/// Only full blocks that stay in place are listed, so there are no fluids, plants that need
+/// soil, or blocks falling down like sand and gravel. Water has no such block, so it is
+/// missing.
+pub const BUILDING_BLOCKS: Map<u8, &'static [&'static str]> = phf_map! {
+        1u8 => &["Grass Block", "Slime Block"],
+        2u8 => &["Birch Planks", "Sandstone", "End Stone", "Bone Block", "Glowstone"],
+        3u8 => &["Mushroom Stem"],
+        4u8 => &["Redstone Block"],
+        5u8 => &["Packed Ice", "Blue Ice"],
+        6u8 => &["Iron Block"],
+        7u8 => &["Oak Leaves"],
+        8u8 => &["White Concrete", "White Wool", "Snow Block"],
+        9u8 => &["Clay"],
+        10u8 => &["Granite", "Jungle Planks", "Packed Mud"],
+        11u8 => &["Stone", "Cobblestone", "Andesite", "Stone Bricks"],
+        13u8 => &["Oak Planks", "Oak Log"],
+        14u8 => &["Quartz Block", "Diorite"],
+        15u8 => &["Orange Wool", "Acacia Planks", "Terracotta"],
+        16u8 => &["Magenta Wool", "Purpur Block"],
+        17u8 => &["Light Blue Wool"],
+        18u8 => &["Yellow Wool", "Hay Bale"],
+        19u8 => &["Lime Wool", "Melon"],
+        20u8 => &["Pink Wool"],
+        21u8 => &["Gray Wool"],
+        22u8 => &["Light Gray Wool"],
+        23u8 => &["Cyan Wool", "Prismarine"],
+        24u8 => &["Purple Wool", "Amethyst Block"],
+        25u8 => &["Blue Wool"],
+        26u8 => &["Brown Wool", "Dark Oak Planks", "Soul Soil"],
+        27u8 => &["Green Wool", "Dried Kelp Block"],
+        28u8 => &["Red Wool", "Bricks"],
+        29u8 => &["Black Wool", "Obsidian", "Blackstone"],
+        30u8 => &["Gold Block", "Raw Gold Block"],
+        31u8 => &["Diamond Block", "Prismarine Bricks"],
+        32u8 => &["Lapis Block"],
+        33u8 => &["Emerald Block"],
+        34u8 => &["Spruce Planks", "Podzol"],
+        35u8 => &["Netherrack", "Nether Bricks"],
+        36u8 => &["White Terracotta", "Calcite"],
+        37u8 => &["Orange Terracotta"],
+        38u8 => &["Magenta Terracotta"],
+        39u8 => &["Light Blue Terracotta"],
+        40u8 => &["Yellow Terracotta"],
+        41u8 => &["Lime Terracotta"],
+        42u8 => &["Pink Terracotta"],
+        43u8 => &["Gray Terracotta", "Tuff"],
+        44u8 => &["Light Gray Terracotta", "Mud Bricks"],
+        45u8 => &["Cyan Terracotta"],
+        46u8 => &["Purple Terracotta"],
+        47u8 => &["Blue Terracotta"],
+        48u8 => &["Brown Terracotta", "Dripstone Block"],
+        49u8 => &["Green Terracotta"],
+        50u8 => &["Red Terracotta"],
+        51u8 => &["Black Terracotta"],
+        52u8 => &["Crimson Nylium"],
+        53u8 => &["Crimson Planks", "Crimson Stem"],
+        54u8 => &["Crimson Hyphae"],
+        55u8 => &["Warped Nylium"],
+        56u8 => &["Warped Planks", "Warped Stem"],
+        57u8 => &["Warped Hyphae"],
+        58u8 => &["Warped Wart Block"],
+        59u8 => &["Cobbled Deepslate", "Deepslate", "Deepslate Bricks"],
+        60u8 => &["Raw Iron Block"],
+        61u8 => &["Verdant Froglight"],
+};
+
+/// Returns the block for building map art with the given *base_color*
+///
+/// None is returned for the colors that cannot be built with a solid block, like water, and
+/// for base color 0.
+pub fn building_block(base_color: u8) -> Option<&'static str> {
+    BUILDING_BLOCKS.get(&base_color)?.first().copied()
+}
+
+/// Returns blocks that are drawn with the given *base_color* on the map
+///
+/// An empty list is returned for unknown colors and for base color 0, which is not drawn at all.
+pub fn block_candidates(base_color: u8) -> &'static [&'static str] {
+    BLOCK_GROUPS.get(&base_color).copied().unwrap_or_default()
+}
+
+/// Writes the RGBA *pixels* of the map *colors* with the *palette*
+///
+/// Each color id is looked up from the palette, so the *pixels* must have four bytes for each
+/// color. With the `simd` feature, AVX2 gather instructions are used when the processor
+/// supports them.
+pub fn apply_palette(colors: &[i8], palette: &Palette, pixels: &mut [u8]) {
+    assert_eq!(
+        colors.len() * 4,
+        pixels.len(),
+        "Pixel buffer size does not match colors"
+    );
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: AVX2 support was checked above
+        unsafe { simd::apply_palette_avx2(colors, palette, pixels) };
+        return;
+    }
+    apply_palette_scalar(colors, palette, pixels);
+}
+
+fn apply_palette_scalar(colors: &[i8], palette: &Palette, pixels: &mut [u8]) {
+    for (pixel, &color) in pixels.chunks_exact_mut(4).zip(colors) {
+        pixel.copy_from_slice(&palette[color as u8 as usize].0);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+    use super::{apply_palette_scalar, Palette};
+    use std::arch::x86_64::*;
+
+    /// Looks up eight pixels at a time with a gather from the palette as 32-bit values
+    ///
+    /// # Safety
+    ///
+    /// The processor must support AVX2, and the *pixels* must have four bytes for each color.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn apply_palette_avx2(colors: &[i8], palette: &Palette, pixels: &mut [u8]) {
+        let table: [i32; 256] = palette.map(|color| i32::from_ne_bytes(color.0));
+        let blocks = colors.len() / 8;
+        for block in 0..blocks {
+            let bytes = _mm_loadl_epi64(colors.as_ptr().add(block * 8) as *const __m128i);
+            let indices = _mm256_cvtepu8_epi32(bytes);
+            let rgba = _mm256_i32gather_epi32::<4>(table.as_ptr(), indices);
+            _mm256_storeu_si256(pixels.as_mut_ptr().add(block * 32) as *mut __m256i, rgba);
+        }
+        apply_palette_scalar(&colors[blocks * 8..], palette, &mut pixels[blocks * 32..]);
+    }
+}
+
 pub fn generate_palette(base_colors: &BaseColors) -> Palette {
+    generate_palette_with(base_colors, &MULTIPLIERS)
+}
+
+/// Generates a palette with other brightness *multipliers* for the four shades
+///
+/// A multiplier of 255 keeps the base color, and the channels are clamped if larger
+/// multipliers make them too bright.
+pub fn generate_palette_with(base_colors: &BaseColors, multipliers: &[u16; 4]) -> Palette {
     let mut palette: Palette = [Rgba([0u8; 4]); 256];
     for i in 0..64 {
-        // Color components are mapped to u16 so that we have enough bits for math operations,
+        // Color components are mapped to u32 so that we have enough bits for math operations,
         // final color components are u8
         let base_color = match base_colors.get(&i) {
-            None => [0u16; 4], // Using transparent for missing colors
-            Some([r, g, b, a]) => [*r as u16, *g as u16, *b as u16, *a as u16],
+            None => [0u32; 4], // Using transparent for missing colors
+            Some([r, g, b, a]) => [*r as u32, *g as u32, *b as u32, *a as u32],
         };
-        for (j, multiplier) in MULTIPLIERS.iter().enumerate() {
+        for (j, multiplier) in multipliers.iter().enumerate() {
             for (k, channel) in base_color.iter().enumerate() {
-                palette[i as usize * 4 + j][k] = if k == 3 {
+                palette[compose(i, j as u8) as usize][k] = if k == 3 {
                     // Alpha channel is passed as it is
                     *channel as u8
                 } else {
                     // For other channels, a multiplier is applied
-                    ((channel * multiplier) / 255) as u8
+                    ((channel * *multiplier as u32) / 255).min(255) as u8
                 };
             }
         }
     }
     palette
 }
+
+/// Parses shade multipliers from three or four comma-separated numbers
+///
+/// The numbers are for the dark, normal, light and darkest shades. Old versions of the game had
+/// only the first three shades, so with three numbers the darkest shade uses the dark multiplier.
+pub fn parse_multipliers(text: &str) -> Result<[u16; 4], String> {
+    let values = text
+        .split(',')
+        .map(|value| value.trim().parse::<u16>())
+        .collect::<Result<Vec<u16>, _>>()
+        .map_err(|err| format!("Invalid multipliers: {err}"))?;
+    match values.as_slice() {
+        [dark, normal, light] => Ok([*dark, *normal, *light, *dark]),
+        [dark, normal, light, darkest] => Ok([*dark, *normal, *light, *darkest]),
+        _ => Err(String::from("Multipliers must have three or four numbers")),
+    }
+}
+
+/// How the four shades of each base color are made
+///
+/// The game makes a block darker when the block north of it is higher, and lighter when it
+/// is lower, so the shades show the slopes of the terrain.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Shading {
+    /// Brightness multipliers of the game
+    #[default]
+    Normal,
+
+    /// Larger differences between the shades, so small elevation changes stand out
+    Exaggerated,
+
+    /// Slopes tinted with cool shadows and warm light like a hillshaded relief map
+    Hillshade,
+}
+
+impl Shading {
+    /// Generates the palette of the *base_colors* with this shading
+    pub fn palette(&self, base_colors: &BaseColors) -> Palette {
+        match self {
+            Shading::Normal => generate_palette(base_colors),
+            Shading::Exaggerated => generate_palette_with(base_colors, &EXAGGERATED_MULTIPLIERS),
+            Shading::Hillshade => {
+                let mut palette = generate_palette_with(base_colors, &[255; 4]);
+                for (index, color) in palette.iter_mut().enumerate() {
+                    let (tint, amount) = HILLSHADE_TINTS[index % 4];
+                    for k in 0..3 {
+                        let mixed = color[k] as f32 * (1.0 - amount) + tint[k] * amount;
+                        color[k] = mixed.round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+                palette
+            }
+        }
+    }
+}
+
+/// Base color of water, kelp and seagrass
+pub const WATER_BASE_COLOR: u8 = 12;
+
+/// Blues for the water shades from deep to shallow, in the order of [SHADE_NAMES]
+///
+/// The game picks the shade of water from the depth: the light shade is shallow, the normal
+/// shade deeper and the dark shade deepest. The darkest shade is not used by the game.
+const WATER_DEPTH_GRADIENT: [[f32; 3]; 4] = [
+    [12.0, 40.0, 130.0],
+    [40.0, 110.0, 205.0],
+    [120.0, 195.0, 245.0],
+    [5.0, 20.0, 80.0],
+];
+
+/// Replaces the water shades of the *palette* with a gradient showing the depth
+///
+/// The *intensity* from 0.0 to 1.0 mixes the gradient with the original colors. The alpha
+/// channel is not changed.
+pub fn emphasize_water_depth(palette: &Palette, intensity: f32) -> Palette {
+    let intensity = intensity.clamp(0.0, 1.0);
+    let mut result = *palette;
+    for (shade, gradient) in WATER_DEPTH_GRADIENT.iter().enumerate() {
+        let color = &mut result[compose(WATER_BASE_COLOR, shade as u8) as usize];
+        for k in 0..3 {
+            let mixed = color[k] as f32 * (1.0 - intensity) + gradient[k] * intensity;
+            color[k] = mixed.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    result
+}
+
+/// Matrix for transforming the RGB channels of a color
+pub type ColorMatrix = [[f32; 3]; 3];
+
+/// Luma weights from ITU-R BT.601
+const LUMA: [f32; 3] = [0.299, 0.587, 0.114];
+
+/// Daltonization for deuteranopia
+///
+/// Red-green differences that are lost in deuteranopia are moved to the blue channel.
+/// Made with the simulation matrix from
+/// [Machado et al. 2009](https://www.inf.ufrgs.br/~oliveira/pubs_files/CVD_Simulation/CVD_Simulation.html).
+pub const DEUTERANOPIA_MATRIX: ColorMatrix = [
+    [1.0, 0.0, 0.0],
+    [0.1628, 0.725, 0.1122],
+    [0.4547, -0.6454, 1.1907],
+];
+
+/// Grayscale using the luma weights
+pub const GRAYSCALE_MATRIX: ColorMatrix = [LUMA, LUMA, LUMA];
+
+/// Gradient for the height-like false colors, from dark to light
+const HEIGHT_GRADIENT: [[f32; 3]; 5] = [
+    [20.0, 40.0, 140.0],
+    [40.0, 150.0, 70.0],
+    [220.0, 200.0, 90.0],
+    [140.0, 80.0, 40.0],
+    [250.0, 250.0, 250.0],
+];
+
+/// How the palette colors are transformed before rendering
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ColorMode {
+    /// Colors as they are in the game
+    #[default]
+    Normal,
+
+    /// Colors adjusted for deuteranopia (red-green color blindness)
+    Deuteranopia,
+
+    /// Shades of gray
+    Grayscale,
+
+    /// False colors by brightness, like a height map from blue lowlands to white peaks
+    Heightlike,
+}
+
+impl ColorMode {
+    /// Transforms the *palette* colors with this mode
+    pub fn apply(&self, palette: &Palette) -> Palette {
+        match self {
+            ColorMode::Normal => *palette,
+            ColorMode::Deuteranopia => apply_color_matrix(palette, &DEUTERANOPIA_MATRIX),
+            ColorMode::Grayscale => apply_color_matrix(palette, &GRAYSCALE_MATRIX),
+            ColorMode::Heightlike => {
+                let mut result = *palette;
+                for color in result.iter_mut() {
+                    let luma: f32 = (0..3).map(|k| LUMA[k] * color[k] as f32).sum();
+                    let position = luma / 255.0 * (HEIGHT_GRADIENT.len() - 1) as f32;
+                    let index = (position as usize).min(HEIGHT_GRADIENT.len() - 2);
+                    let t = position - index as f32;
+                    for k in 0..3 {
+                        let low = HEIGHT_GRADIENT[index][k];
+                        let high = HEIGHT_GRADIENT[index + 1][k];
+                        color[k] = (low + (high - low) * t).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+/// Transforms the RGB channels of the *palette* colors with the *matrix*
+///
+/// Alpha channel is not changed. Results are clamped to the valid range.
+pub fn apply_color_matrix(palette: &Palette, matrix: &ColorMatrix) -> Palette {
+    let mut result = *palette;
+    for color in result.iter_mut() {
+        let rgb = [color[0] as f32, color[1] as f32, color[2] as f32];
+        for (k, row) in matrix.iter().enumerate() {
+            let value: f32 = row.iter().zip(rgb).map(|(m, c)| m * c).sum();
+            color[k] = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    result
+}
+
+/// Parses a color matrix from nine comma-separated numbers in row-major order
+pub fn parse_color_matrix(text: &str) -> Result<ColorMatrix, String> {
+    let values = text
+        .split(',')
+        .map(|value| value.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|err| format!("Invalid color matrix: {err}"))?;
+    if values.len() != 9 {
+        return Err(String::from("Color matrix must have nine numbers"));
+    }
+    let mut matrix = [[0f32; 3]; 3];
+    for (index, value) in values.into_iter().enumerate() {
+        matrix[index / 3][index % 3] = value;
+    }
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{
+        apply_palette, apply_palette_scalar, compose, decompose, emphasize_water_depth,
+        generate_palette, generate_palette_with, parse_multipliers, ColorMode, Shading,
+        BASE_COLORS_2699, MULTIPLIERS, WATER_BASE_COLOR,
+    };
+
+    #[test]
+    fn test_color_modes() {
+        let palette = generate_palette(&BASE_COLORS_2699);
+        assert_eq!(ColorMode::Normal.apply(&palette), palette);
+        let gray = ColorMode::Grayscale.apply(&palette);
+        for color in gray.iter() {
+            assert_eq!(color[0], color[1]);
+            assert_eq!(color[1], color[2]);
+        }
+        // Transparent colors stay transparent
+        assert_eq!(ColorMode::Heightlike.apply(&palette)[0][3], 0);
+    }
+
+    #[test]
+    fn test_shading() {
+        let palette = generate_palette(&BASE_COLORS_2699);
+        assert_eq!(Shading::Normal.palette(&BASE_COLORS_2699), palette);
+        assert_eq!(
+            generate_palette_with(&BASE_COLORS_2699, &MULTIPLIERS),
+            palette
+        );
+        let brighter = generate_palette_with(&BASE_COLORS_2699, &[510; 4]);
+        assert_eq!(brighter[8 * 4].0, [255, 255, 255, 255]);
+        assert_eq!(parse_multipliers("180, 220, 255, 135"), Ok(MULTIPLIERS));
+        assert_eq!(parse_multipliers("180,220,255"), Ok([180, 220, 255, 180]));
+        assert!(parse_multipliers("180,220").is_err());
+        assert!(parse_multipliers("180,220,-1").is_err());
+
+        // Grass (base color 1) with the dark and the light shades further apart
+        let exaggerated = Shading::Exaggerated.palette(&BASE_COLORS_2699);
+        assert!(exaggerated[4][1] < palette[4][1]);
+        assert_eq!(exaggerated[6], palette[6]);
+        for shading in [Shading::Exaggerated, Shading::Hillshade] {
+            let shaded = shading.palette(&BASE_COLORS_2699);
+            assert_eq!(shaded[0][3], 0);
+            let luma = |index: usize| {
+                shaded[index]
+                    .0
+                    .iter()
+                    .take(3)
+                    .map(|c| *c as u32)
+                    .sum::<u32>()
+            };
+            assert!(luma(7) < luma(4) && luma(4) < luma(5) && luma(5) < luma(6));
+        }
+    }
+
+    #[test]
+    fn test_apply_palette() {
+        let palette = generate_palette(&BASE_COLORS_2699);
+        // The length is not a multiple of eight to test the remainder too
+        let colors: Vec<i8> = (0..1003).map(|i| (i * 7) as u8 as i8).collect();
+        let mut expected = vec![0; colors.len() * 4];
+        apply_palette_scalar(&colors, &palette, &mut expected);
+        let mut pixels = vec![0; colors.len() * 4];
+        apply_palette(&colors, &palette, &mut pixels);
+        assert_eq!(pixels, expected);
+        assert_eq!(pixels[4..8], palette[7].0);
+    }
+
+    #[test]
+    fn test_compose() {
+        assert_eq!(decompose(0), (0, 0));
+        assert_eq!(decompose(50), (12, 2));
+        assert_eq!(decompose(255), (63, 3));
+        for index in 0..=255 {
+            let (base_color, shade) = decompose(index);
+            assert_eq!(compose(base_color, shade), index);
+        }
+    }
+
+    #[test]
+    fn test_water_depth() {
+        let palette = generate_palette(&BASE_COLORS_2699);
+        assert_eq!(emphasize_water_depth(&palette, 0.0), palette);
+        let emphasized = emphasize_water_depth(&palette, 1.0);
+        let water = compose(WATER_BASE_COLOR, 0) as usize;
+        assert_eq!(emphasized[water].0, [12, 40, 130, 255]);
+        assert_eq!(emphasized[water + 2].0, [120, 195, 245, 255]);
+        // Other colors are not changed
+        assert_eq!(emphasized[water - 1], palette[water - 1]);
+        assert_eq!(emphasized[water + 4], palette[water + 4]);
+    }
+}
@@ -0,0 +1,246 @@
+//! Minimal PDF writer
+//!
+//! Only what is needed for printing map images is supported: pages with RGB images and text in
+//! the standard Helvetica font. Text is encoded with WinAnsiEncoding, so characters outside of
+//! Latin-1 are replaced with question marks.
+
+use crate::error::Result;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::RgbaImage;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Background color for transparent pixels
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+
+struct PlacedImage {
+    image: RgbaImage,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+struct PlacedText {
+    text: String,
+    x: f32,
+    y: f32,
+    size: f32,
+}
+
+/// One page of the document
+pub struct Page {
+    width: f32,
+    height: f32,
+    images: Vec<PlacedImage>,
+    texts: Vec<PlacedText>,
+}
+
+impl Page {
+    /// Empty page with the size in points
+    pub fn new(width: f32, height: f32) -> Page {
+        Page {
+            width,
+            height,
+            images: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// Draws the *image* to the area where *x*, *y* is the lower-left corner
+    ///
+    /// Transparent pixels are drawn with the white background. Images are drawn without
+    /// smoothing, so map pixels stay sharp.
+    pub fn add_image(&mut self, image: RgbaImage, x: f32, y: f32, width: f32, height: f32) {
+        self.images.push(PlacedImage {
+            image,
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    /// Draws the *text* with the baseline starting at *x*, *y*
+    pub fn add_text(&mut self, text: &str, x: f32, y: f32, size: f32) {
+        self.texts.push(PlacedText {
+            text: text.to_string(),
+            x,
+            y,
+            size,
+        });
+    }
+
+    fn content(&self) -> String {
+        let mut content = String::new();
+        for (index, image) in self.images.iter().enumerate() {
+            let _ = writeln!(
+                content,
+                "q {} 0 0 {} {} {} cm /Im{index} Do Q",
+                image.width, image.height, image.x, image.y
+            );
+        }
+        for text in &self.texts {
+            let _ = writeln!(
+                content,
+                "BT /F1 {} Tf {} {} Td ({}) Tj ET",
+                text.size,
+                text.x,
+                text.y,
+                escape(&text.text)
+            );
+        }
+        content
+    }
+}
+
+/// Document made of pages
+#[derive(Default)]
+pub struct PdfDocument {
+    pages: Vec<Page>,
+}
+
+impl PdfDocument {
+    pub fn new() -> PdfDocument {
+        PdfDocument::default()
+    }
+
+    pub fn add_page(&mut self, page: Page) {
+        self.pages.push(page);
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Writes the document to the *writer*
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        // Object numbers: 1 catalog, 2 page tree, 3 font, then the pages and their resources
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            Vec::new(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        ];
+        let mut page_ids = Vec::new();
+        for page in &self.pages {
+            let mut image_refs = String::new();
+            for (index, placed) in page.images.iter().enumerate() {
+                let id = objects.len() + 1;
+                objects.push(image_object(&placed.image)?);
+                let _ = write!(image_refs, "/Im{index} {id} 0 R ");
+            }
+            let content_id = objects.len() + 1;
+            objects.push(stream_object("", page.content().as_bytes())?);
+            let page_id = objects.len() + 1;
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R >> /XObject << {image_refs}>> >> \
+                     /Contents {content_id} 0 R >>",
+                    page.width, page.height
+                )
+                .into_bytes(),
+            );
+            page_ids.push(page_id);
+        }
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{id} 0 R")).collect();
+        objects[1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            page_ids.len()
+        )
+        .into_bytes();
+
+        let mut output = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(output.len());
+            output.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            output.extend_from_slice(object);
+            output.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = output.len();
+        output.extend_from_slice(format!("xref\n0 {}\n", objects.len() + 1).as_bytes());
+        output.extend_from_slice(b"0000000000 65535 f \n");
+        for offset in offsets {
+            output.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        output.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .as_bytes(),
+        );
+        writer.write_all(&output)?;
+        Ok(())
+    }
+}
+
+fn stream_object(dictionary: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+    let mut object = format!(
+        "<< {dictionary} /Filter /FlateDecode /Length {} >>\nstream\n",
+        compressed.len()
+    )
+    .into_bytes();
+    object.extend_from_slice(&compressed);
+    object.extend_from_slice(b"\nendstream");
+    Ok(object)
+}
+
+fn image_object(image: &RgbaImage) -> Result<Vec<u8>> {
+    let mut rgb = Vec::with_capacity(image.width() as usize * image.height() as usize * 3);
+    for pixel in image.pixels() {
+        let alpha = pixel[3] as u16;
+        for (k, background) in BACKGROUND.iter().enumerate() {
+            let blended = (pixel[k] as u16 * alpha + *background as u16 * (255 - alpha)) / 255;
+            rgb.push(blended as u8);
+        }
+    }
+    stream_object(
+        &format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} \
+             /ColorSpace /DeviceRGB /BitsPerComponent 8 /Interpolate false",
+            image.width(),
+            image.height()
+        ),
+        &rgb,
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{c}"),
+            ' '..='~' => c.to_string(),
+            '\u{a0}'..='\u{ff}' => format!("\\{:03o}", c as u32),
+            _ => String::from("?"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pdf::{Page, PdfDocument};
+    use image::RgbaImage;
+
+    #[test]
+    fn test_write_pdf() {
+        let mut page = Page::new(100.0, 100.0);
+        page.add_image(RgbaImage::new(2, 2), 10.0, 10.0, 50.0, 50.0);
+        page.add_text("Map (1) ×", 10.0, 5.0, 8.0);
+        let mut document = PdfDocument::new();
+        document.add_page(page);
+        let mut output = Vec::new();
+        document.write(&mut output).unwrap();
+        let text = String::from_utf8_lossy(&output);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 1"));
+        assert!(text.ends_with("%%EOF\n"));
+    }
+}
@@ -0,0 +1,309 @@
+//! Player data files
+//!
+//! The game stores the inventory and ender chest of each player in
+//! `playerdata/<uuid>.dat` in the world directory. Only the items are read, so maps carried
+//! by the players can be found, including maps inside shulker boxes and bundles.
+
+use crate::compression::decompress;
+use crate::error::Result;
+use crate::region::{number, Scan, ScanError};
+use fastnbt::Value;
+use log::warn;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Content of the playerdata/<uuid>.dat files
+#[derive(Debug, Deserialize)]
+pub struct PlayerData {
+    /// Path to player data file
+    ///
+    /// **Note:** This is not part of the player data and therefore is not deserialized.
+    #[serde(skip)]
+    pub file: PathBuf,
+
+    /// Items in the inventory, including the armor and offhand slots
+    #[serde(rename = "Inventory", default)]
+    pub inventory: Vec<ItemStack>,
+
+    /// Items in the ender chest
+    #[serde(rename = "EnderItems", default)]
+    pub ender_items: Vec<ItemStack>,
+}
+
+/// Stack of items in an inventory slot
+#[derive(Debug, Deserialize)]
+pub struct ItemStack {
+    /// Resource location of the item, like minecraft:filled_map
+    pub id: String,
+
+    /// Number of items, stored as a byte named Count before 1.20.5
+    #[serde(alias = "Count", default, deserialize_with = "any_integer")]
+    pub count: i32,
+
+    /// Inventory slot
+    #[serde(rename = "Slot")]
+    pub slot: Option<i8>,
+
+    /// Item data before 1.20.5
+    pub tag: Option<ItemTag>,
+
+    /// Item data since 1.20.5
+    pub components: Option<ItemComponents>,
+}
+
+/// Item data before 1.20.5
+#[derive(Debug, Deserialize)]
+pub struct ItemTag {
+    /// Id of a filled map
+    pub map: Option<i32>,
+
+    /// Contents of shulker boxes
+    #[serde(rename = "BlockEntityTag")]
+    pub block_entity: Option<BlockEntityTag>,
+
+    /// Contents of bundles
+    #[serde(rename = "Items", default)]
+    pub items: Vec<ItemStack>,
+}
+
+/// Block entity stored in an item before 1.20.5
+#[derive(Debug, Deserialize)]
+pub struct BlockEntityTag {
+    #[serde(rename = "Items", default)]
+    pub items: Vec<ItemStack>,
+}
+
+/// Item components since 1.20.5
+#[derive(Debug, Deserialize)]
+pub struct ItemComponents {
+    /// Id of a filled map
+    #[serde(rename = "minecraft:map_id")]
+    pub map_id: Option<i32>,
+
+    /// Contents of shulker boxes
+    #[serde(rename = "minecraft:container", default)]
+    pub container: Vec<ContainerSlot>,
+
+    /// Contents of bundles
+    #[serde(rename = "minecraft:bundle_contents", default)]
+    pub bundle_contents: Vec<ItemStack>,
+}
+
+/// Slot of the container component
+#[derive(Debug, Deserialize)]
+pub struct ContainerSlot {
+    pub slot: i32,
+    pub item: ItemStack,
+}
+
+/// Map found in an item stack
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackedMap {
+    /// Id of the map
+    pub map_id: u32,
+
+    /// Number of copies in the stack
+    pub count: i32,
+
+    /// Item holding the map, like minecraft:shulker_box, or None if the map is not in an item
+    pub container: Option<String>,
+}
+
+/// Map carried by a player
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarriedMap {
+    /// Id of the map
+    pub map_id: u32,
+
+    /// Number of copies in the stack
+    pub count: i32,
+
+    /// True if the map is in the ender chest
+    pub ender_chest: bool,
+
+    /// Item holding the map, like minecraft:shulker_box, or None if the map is not in an item
+    pub container: Option<String>,
+}
+
+impl ItemStack {
+    /// Id of the map, if this is a filled map
+    pub fn map_id(&self) -> Option<u32> {
+        if self.id != "minecraft:filled_map" {
+            return None;
+        }
+        let map_id = match (&self.components, &self.tag) {
+            (Some(components), _) if components.map_id.is_some() => components.map_id,
+            (_, Some(tag)) => tag.map,
+            _ => None,
+        };
+        u32::try_from(map_id?).ok()
+    }
+
+    /// Items stored inside this item
+    pub fn contents(&self) -> Vec<&ItemStack> {
+        let mut contents = Vec::new();
+        if let Some(tag) = &self.tag {
+            if let Some(block_entity) = &tag.block_entity {
+                contents.extend(&block_entity.items);
+            }
+            contents.extend(&tag.items);
+        }
+        if let Some(components) = &self.components {
+            contents.extend(components.container.iter().map(|slot| &slot.item));
+            contents.extend(&components.bundle_contents);
+        }
+        contents
+    }
+
+    /// Maps in this stack, including maps inside the stored items
+    pub fn maps(&self) -> Vec<StackedMap> {
+        let mut maps = Vec::new();
+        collect_maps(self, None, &mut maps);
+        maps
+    }
+}
+
+impl PlayerData {
+    /// Read player data from the given *file* path
+    pub fn read_from(file: &Path) -> Result<PlayerData> {
+        let mut player: PlayerData = fastnbt::from_reader(decompress(File::open(file)?)?)?;
+        player.file = PathBuf::from(file);
+        Ok(player)
+    }
+
+    /// UUID of the player, taken from the file name
+    pub fn uuid(&self) -> String {
+        self.file
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Maps in the inventory and ender chest, including maps inside other items
+    pub fn maps(&self) -> Vec<CarriedMap> {
+        let mut maps = Vec::new();
+        for (items, ender_chest) in [(&self.inventory, false), (&self.ender_items, true)] {
+            for map in items.iter().flat_map(ItemStack::maps) {
+                maps.push(CarriedMap {
+                    map_id: map.map_id,
+                    count: map.count,
+                    ender_chest,
+                    container: map.container,
+                });
+            }
+        }
+        maps
+    }
+}
+
+/// Deserializes an integer stored with any size
+fn any_integer<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<i32, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    number(&value)
+        .map(|number| number as i32)
+        .ok_or_else(|| D::Error::custom("expected an integer"))
+}
+
+fn collect_maps(item: &ItemStack, container: Option<&str>, maps: &mut Vec<StackedMap>) {
+    if let Some(map_id) = item.map_id() {
+        maps.push(StackedMap {
+            map_id,
+            count: item.count,
+            container: container.map(String::from),
+        });
+    }
+    for content in item.contents() {
+        collect_maps(content, Some(&item.id), maps);
+    }
+}
+
+/// Reads the player data files in the `playerdata` directory of the *world*
+///
+/// Files that cannot be read are reported with a warning and skipped.
+pub fn read_players(world: &Path) -> Result<Vec<PlayerData>> {
+    Ok(scan_players(world)?.found)
+}
+
+/// Reads the player data files like [read_players], also returning the files that could
+/// not be read
+pub fn scan_players(world: &Path) -> Result<Scan<PlayerData>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(world.join("playerdata"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "dat") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    let mut players = Vec::with_capacity(files.len());
+    let mut errors = Vec::new();
+    for file in files {
+        match PlayerData::read_from(&file) {
+            Ok(player) => players.push(player),
+            Err(err) => {
+                let error = ScanError {
+                    file,
+                    message: format!("Could not read player data: {err}"),
+                };
+                warn!("{error}");
+                errors.push(error);
+            }
+        }
+    }
+    Ok(Scan {
+        found: players,
+        errors,
+    })
+}
+
+/// Player names by UUID from the usercache.json of the server
+///
+/// The cache is next to the world directory on servers. Returns an empty map if the cache
+/// cannot be read.
+pub fn player_names(world: &Path) -> BTreeMap<String, String> {
+    #[derive(Deserialize)]
+    struct CachedUser {
+        name: String,
+        uuid: String,
+    }
+
+    let file = world
+        .canonicalize()
+        .ok()
+        .and_then(|world| Some(world.parent()?.join("usercache.json")));
+    let Some(json) = file.and_then(|file| fs::read_to_string(file).ok()) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str::<Vec<CachedUser>>(&json)
+        .map(|users| {
+            users
+                .into_iter()
+                .map(|user| (user.uuid, user.name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::player::read_players;
+    use crate::synthetic::write_synthetic_world;
+
+    #[test]
+    fn test_player_maps() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let players = read_players(world.path()).unwrap();
+        assert_eq!(players.len(), 1);
+        let maps = players[0].maps();
+        let ids: Vec<_> = maps.iter().map(|map| map.map_id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert_eq!(maps[0].count, 2);
+        assert_eq!(maps[1].container.as_deref(), Some("minecraft:shulker_box"));
+        assert!(maps[2].ender_chest);
+    }
+}
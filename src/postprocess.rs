@@ -0,0 +1,123 @@
+//! Adjustments for the rendered images
+//!
+//! Map images are drawn with the exact palette colors. A [PostProcess] chain can be used to
+//! adjust them afterwards, for example to match how maps look in item frames in the game.
+
+use image::RgbaImage;
+
+/// Luma weights from ITU-R BT.601
+const LUMA: [f32; 3] = [0.299, 0.587, 0.114];
+
+/// Single adjustment for the image colors
+///
+/// All values are factors where 1.0 keeps the image unchanged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Adjustment {
+    /// Multiplies the color channels
+    Brightness(f32),
+
+    /// Scales the distance of the color channels from the middle gray
+    Contrast(f32),
+
+    /// Gamma correction, values over 1.0 make the image lighter
+    Gamma(f32),
+
+    /// Scales the distance of the colors from the gray with the same luma
+    Saturation(f32),
+}
+
+impl Adjustment {
+    /// Adjusts the RGB channels, which are in the range from 0.0 to 1.0
+    fn apply(&self, rgb: &mut [f32; 3]) {
+        match *self {
+            Adjustment::Brightness(factor) => rgb.iter_mut().for_each(|c| *c *= factor),
+            Adjustment::Contrast(factor) => {
+                rgb.iter_mut().for_each(|c| *c = (*c - 0.5) * factor + 0.5)
+            }
+            Adjustment::Gamma(gamma) => rgb
+                .iter_mut()
+                .for_each(|c| *c = c.max(0.0).powf(1.0 / gamma)),
+            Adjustment::Saturation(factor) => {
+                let luma: f32 = rgb.iter().zip(LUMA).map(|(c, w)| c * w).sum();
+                rgb.iter_mut()
+                    .for_each(|c| *c = luma + (*c - luma) * factor);
+            }
+        }
+        rgb.iter_mut().for_each(|c| *c = c.clamp(0.0, 1.0));
+    }
+}
+
+/// Chain of adjustments applied in order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PostProcess {
+    adjustments: Vec<Adjustment>,
+}
+
+impl PostProcess {
+    /// Empty chain that keeps images unchanged
+    pub fn new() -> PostProcess {
+        PostProcess::default()
+    }
+
+    /// Adds the *adjustment* to the end of the chain
+    pub fn then(mut self, adjustment: Adjustment) -> PostProcess {
+        self.adjustments.push(adjustment);
+        self
+    }
+
+    /// Tests if the chain has no adjustments
+    pub fn is_empty(&self) -> bool {
+        self.adjustments.is_empty()
+    }
+
+    /// Applies the adjustments to the *image*
+    ///
+    /// Alpha channel is not changed and fully transparent pixels are skipped.
+    pub fn apply(&self, image: &mut RgbaImage) {
+        if self.is_empty() {
+            return;
+        }
+        for pixel in image.pixels_mut() {
+            if pixel[3] == 0 {
+                continue;
+            }
+            let mut rgb = [0f32; 3];
+            for (k, c) in rgb.iter_mut().enumerate() {
+                *c = pixel[k] as f32 / 255.0;
+            }
+            for adjustment in &self.adjustments {
+                adjustment.apply(&mut rgb);
+            }
+            for (k, c) in rgb.iter().enumerate() {
+                pixel[k] = (c * 255.0).round() as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::postprocess::{Adjustment, PostProcess};
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_post_process_chain() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([100, 150, 200, 255]));
+        image.put_pixel(1, 0, Rgba([100, 150, 200, 0]));
+        PostProcess::new()
+            .then(Adjustment::Saturation(0.0))
+            .then(Adjustment::Brightness(2.0))
+            .apply(&mut image);
+        // Gray with the luma of the original color, doubled
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        // Transparent pixels are not touched
+        assert_eq!(image.get_pixel(1, 0), &Rgba([100, 150, 200, 0]));
+
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([64, 128, 192, 255]));
+        PostProcess::new()
+            .then(Adjustment::Contrast(1.0))
+            .then(Adjustment::Gamma(1.0))
+            .apply(&mut image);
+        assert_eq!(image.get_pixel(0, 0), &Rgba([64, 128, 192, 255]));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::region::{world_data_version, FLATTENING_DATA_VERSION};
+use minecraft_map_tool::usage::map_usage;
+use std::io::{self, stdin, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+Map items are searched from the region, entity and player data files and level.dat. \
+Maps used by mods or plugins storing items elsewhere are not found, so consider using \
+--quarantine or taking a backup first. Nothing is removed if any part of the world \
+cannot be read, or if the world is older than Java Edition 1.13, unless --force is given.")]
+pub struct PruneArgs {
+    /// The world directory
+    world: PathBuf,
+
+    /// Move the orphaned map files to this directory instead of deleting them
+    #[arg(long, value_name = "DIR")]
+    quarantine: Option<PathBuf>,
+
+    /// Ask before removing each map file, not asked with --dry-run
+    #[arg(short, long)]
+    interactive: bool,
+
+    /// Remove the maps even if the search for references was incomplete
+    #[arg(long)]
+    force: bool,
+}
+
+pub fn run(args: &PruneArgs) -> ExitCode {
+    if !args.world.join("level.dat").is_file() {
+        error!("{} is not a world directory", args.world.display());
+        return ExitCode::FAILURE;
+    }
+    // Before 1.13 the map id of an item was in Damage, which is only read from item frames
+    match world_data_version(&args.world) {
+        Ok(Some(version)) if version >= FLATTENING_DATA_VERSION => {}
+        Ok(_) if args.force => warn!("World is older than Java Edition 1.13"),
+        Ok(_) => {
+            error!("World is older than Java Edition 1.13, so every map would look orphaned");
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            error!("Could not read level.dat: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    let usage = match map_usage(&args.world) {
+        Ok(scan) if scan.errors.is_empty() || args.force => scan.found,
+        Ok(scan) => {
+            error!(
+                "{} parts of the world could not be read, so maps used there would be removed. \
+                 Use --force to remove the maps anyway.",
+                scan.errors.len()
+            );
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            error!("Could not collect map usage: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(quarantine) = &args.quarantine {
+        if let Err(err) = sink().create_dir_all(quarantine) {
+            error!("Could not create {}: {err}", quarantine.display());
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let mut removed = 0;
+    let mut failed = 0;
+    let orphaned_files = usage
+        .iter()
+        .filter(|map| map.is_orphaned())
+        .filter_map(|map| map.file.as_deref());
+    for file in orphaned_files {
+        if args.interactive && !sink().is_dry_run() {
+            match confirm(file) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(err) => {
+                    error!("Could not read the answer: {err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        let result = match &args.quarantine {
+            Some(quarantine) => move_file(file, quarantine),
+            None => sink().remove_file(file),
+        };
+        match result {
+            Ok(()) => {
+                if !sink().is_dry_run() {
+                    info!("Removed {}", file.display());
+                }
+                removed += 1;
+            }
+            Err(err) => {
+                warn!("Could not remove {}: {err}", file.display());
+                failed += 1;
+            }
+        }
+    }
+
+    match (sink().is_dry_run(), &args.quarantine) {
+        (true, _) => info!("{removed} orphaned map files would be removed"),
+        (false, Some(quarantine)) => info!(
+            "{removed} orphaned map files moved to {}",
+            quarantine.display()
+        ),
+        (false, None) => info!("{removed} orphaned map files deleted"),
+    }
+    if failed > 0 {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Asks whether the *file* should be removed
+fn confirm(file: &Path) -> io::Result<bool> {
+    eprint!("Remove {}? [y/N] ", file.display());
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Moves the *file* to the *directory*, copying it when renaming is not possible
+///
+/// A file with the same name from an earlier prune is kept, and the moved file gets a
+/// number before the extension instead, like map_5.1.dat.
+fn move_file(file: &Path, directory: &Path) -> io::Result<()> {
+    let name = Path::new(file.file_name().unwrap_or_default());
+    let mut target = directory.join(name);
+    let mut number = 0;
+    while target.exists() {
+        number += 1;
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        target = match name.extension() {
+            Some(extension) => {
+                directory.join(format!("{stem}.{number}.{}", extension.to_string_lossy()))
+            }
+            None => directory.join(format!("{stem}.{number}")),
+        };
+    }
+    if sink().rename(file, &target).is_err() {
+        // Renaming fails across file systems
+        sink().copy(file, &target)?;
+        sink().remove_file(file)?;
+    }
+    Ok(())
+}
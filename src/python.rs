@@ -0,0 +1,313 @@
+//! Python module
+//!
+//! Server administration scripts are often written in Python. This module exposes reading,
+//! rendering and stitching maps to Python, so the scripts do not need to run the command
+//! line tool and parse its tables. The module is built with the `python` feature, for
+//! example with `maturin build --release`.
+//!
+//! ```python
+//! import minecraft_map_tool as mmt
+//!
+//! for map_item in mmt.read_maps("world/data"):
+//!     print(map_item.file, map_item.scale, map_item.x_center, map_item.z_center)
+//! stitched = mmt.stitch("world/data", dimension="Overworld")
+//! ```
+
+use crate::error::Error;
+use crate::palette::{generate_palette, ColorMode, Palette, BASE_COLORS_2699};
+use crate::stitch::{filter_maps, stitch_maps};
+use crate::{MapItem, SortSpec, SortingOrder};
+use clap::ValueEnum;
+use pyo3::exceptions::{PyIOError, PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::path::PathBuf;
+
+/// Python exception for the library *err*
+fn py_error(err: Error) -> PyErr {
+    PyIOError::new_err(err.to_string())
+}
+
+/// A map item read from a map_<#>.dat file
+#[pyclass(name = "MapItem", module = "minecraft_map_tool")]
+pub struct PyMapItem {
+    inner: MapItem,
+}
+
+#[pymethods]
+impl PyMapItem {
+    /// Reads the map file at the *path*
+    #[staticmethod]
+    fn read(path: PathBuf) -> PyResult<Self> {
+        let inner = MapItem::read_from(&path).map_err(py_error)?;
+        Ok(PyMapItem { inner })
+    }
+
+    #[getter]
+    fn file(&self) -> PathBuf {
+        self.inner.file.clone()
+    }
+
+    #[getter]
+    fn scale(&self) -> i8 {
+        self.inner.data.scale
+    }
+
+    /// Pretty dimension name, like Overworld
+    #[getter]
+    fn dimension(&self) -> String {
+        self.inner.data.pretty_dimension()
+    }
+
+    #[getter]
+    fn x_center(&self) -> i32 {
+        self.inner.data.x_center
+    }
+
+    #[getter]
+    fn z_center(&self) -> i32 {
+        self.inner.data.z_center
+    }
+
+    #[getter]
+    fn left(&self) -> i32 {
+        self.inner.data.left()
+    }
+
+    #[getter]
+    fn top(&self) -> i32 {
+        self.inner.data.top()
+    }
+
+    #[getter]
+    fn right(&self) -> i32 {
+        self.inner.data.right()
+    }
+
+    #[getter]
+    fn bottom(&self) -> i32 {
+        self.inner.data.bottom()
+    }
+
+    #[getter]
+    fn locked(&self) -> bool {
+        self.inner.data.locked != 0
+    }
+
+    #[getter]
+    fn data_version(&self) -> i32 {
+        self.inner.data_version
+    }
+
+    /// Color ids of the 128×128 pixels, row by row
+    #[getter]
+    fn colors<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let colors: Vec<u8> = self.inner.data.colors.iter().map(|&c| c as u8).collect();
+        PyBytes::new(py, &colors)
+    }
+
+    /// Banners as dictionaries with the name, color, x, y and z
+    #[getter]
+    fn banners<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut banners = Vec::new();
+        for banner in &self.inner.data.banners {
+            let dict = PyDict::new(py);
+            dict.set_item("name", banner.extract_name())?;
+            dict.set_item("color", banner.color.to_string())?;
+            dict.set_item("x", banner.pos.x)?;
+            dict.set_item("y", banner.pos.y)?;
+            dict.set_item("z", banner.pos.z)?;
+            banners.push(dict);
+        }
+        Ok(banners)
+    }
+
+    /// Renders the map as 128×128 RGBA pixels, row by row
+    #[pyo3(signature = (palette=None))]
+    fn rgba<'py>(
+        &self,
+        py: Python<'py>,
+        palette: Option<PyRef<'_, PyPalette>>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let palette = match palette {
+            Some(palette) => palette.colors,
+            None => generate_palette(&BASE_COLORS_2699),
+        };
+        let image = self.inner.make_image(&palette).map_err(py_error)?;
+        Ok(PyBytes::new(py, image.as_raw()))
+    }
+
+    /// Writes the map to the *path*, or to the file it was read from
+    #[pyo3(signature = (path=None))]
+    fn write(&self, path: Option<PathBuf>) -> PyResult<()> {
+        let path = path.unwrap_or_else(|| self.inner.file.clone());
+        self.inner.write_to(&path).map_err(py_error)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MapItem(file={:?}, scale={}, dimension={:?}, x_center={}, z_center={})",
+            self.inner.file.display().to_string(),
+            self.inner.data.scale,
+            self.inner.data.pretty_dimension(),
+            self.inner.data.x_center,
+            self.inner.data.z_center
+        )
+    }
+}
+
+/// Colors used for rendering maps
+#[pyclass(name = "Palette", module = "minecraft_map_tool")]
+pub struct PyPalette {
+    colors: Palette,
+}
+
+#[pymethods]
+impl PyPalette {
+    /// Makes the game palette transformed with the color *mode*: normal, deuteranopia,
+    /// grayscale or heightlike
+    #[new]
+    #[pyo3(signature = (mode="normal"))]
+    fn new(mode: &str) -> PyResult<Self> {
+        let mode = ColorMode::from_str(mode, true).map_err(PyValueError::new_err)?;
+        Ok(PyPalette {
+            colors: mode.apply(&generate_palette(&BASE_COLORS_2699)),
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// RGBA color of the color id *index*
+    fn __getitem__(&self, index: usize) -> PyResult<(u8, u8, u8, u8)> {
+        let color = self
+            .colors
+            .get(index)
+            .ok_or_else(|| PyIndexError::new_err("Color id must be from 0 to 255"))?;
+        Ok((color[0], color[1], color[2], color[3]))
+    }
+}
+
+/// Reads the map files found from the *path*
+///
+/// The *sort* uses the same keys as the command line, for example "scale,time:desc". A file
+/// that cannot be read raises an OSError naming the file.
+#[pyfunction]
+#[pyo3(signature = (path, recursive=false, sort=None))]
+fn read_maps(path: PathBuf, recursive: bool, sort: Option<&str>) -> PyResult<Vec<PyMapItem>> {
+    let sort = sort
+        .map(|sort| sort.parse::<SortSpec>())
+        .transpose()
+        .map_err(PyValueError::new_err)?;
+    let mut maps = crate::read_maps_sorted(&path, &sort, recursive).map_err(py_error)?;
+    let mut map_items = Vec::new();
+    while let Some((file, map_item)) = maps.next_with_path() {
+        let inner = map_item.map_err(|err| {
+            PyIOError::new_err(format!("Could not read {}: {err}", file.display()))
+        })?;
+        map_items.push(PyMapItem { inner });
+    }
+    Ok(map_items)
+}
+
+/// Stitches the maps with the *zoom* and *dimension* found from the *path* into one image
+///
+/// Newer maps are drawn over older ones. Returns a dictionary with the left, top, width,
+/// height and RGBA pixels of the image.
+#[pyfunction]
+#[pyo3(signature = (path, zoom=0, dimension="Overworld", palette=None, recursive=false))]
+fn stitch<'py>(
+    py: Python<'py>,
+    path: PathBuf,
+    zoom: i8,
+    dimension: &str,
+    palette: Option<PyRef<'_, PyPalette>>,
+    recursive: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let palette = match palette {
+        Some(palette) => palette.colors,
+        None => generate_palette(&BASE_COLORS_2699),
+    };
+    let maps = crate::read_maps(&path, &Some(SortingOrder::Time), recursive).map_err(py_error)?;
+    let (maps, area) = filter_maps(maps, zoom, Some(dimension)).map_err(py_error)?;
+    let image = stitch_maps(maps, &area, &palette, || {}).map_err(py_error)?;
+    let dict = PyDict::new(py);
+    dict.set_item("left", area.left)?;
+    dict.set_item("top", area.top)?;
+    dict.set_item("width", image.width())?;
+    dict.set_item("height", image.height())?;
+    dict.set_item("rgba", PyBytes::new(py, image.as_raw()))?;
+    Ok(dict)
+}
+
+#[pymodule]
+fn minecraft_map_tool(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMapItem>()?;
+    m.add_class::<PyPalette>()?;
+    m.add_function(wrap_pyfunction!(read_maps, m)?)?;
+    m.add_function(wrap_pyfunction!(stitch, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::python::{read_maps, PyPalette};
+    use crate::synthetic::write_synthetic_world;
+    use pyo3::exceptions::{PyIOError, PyIndexError, PyValueError};
+    use pyo3::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_python_conversions() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let data_dir = world.path().join("data");
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let maps = read_maps(data_dir.clone(), false, Some("id")).unwrap();
+            let map_item = &maps[0];
+            assert!(map_item.file().ends_with("map_0.dat"));
+            assert_eq!(map_item.dimension(), "Overworld");
+
+            // Color ids are bytes from 0 to 255, not signed like in the NBT data
+            let colors = map_item.colors(py);
+            let colors = colors.as_bytes();
+            assert_eq!(colors.len(), 128 * 128);
+            assert_eq!((colors[0], colors[128 * 127]), (4, 7));
+            assert_eq!(
+                map_item.rgba(py, None).unwrap().as_bytes().len(),
+                128 * 128 * 4
+            );
+
+            let banners = map_item.banners(py).unwrap();
+            assert_eq!(banners.len(), 1);
+            let item = |key: &str| banners[0].get_item(key).unwrap().unwrap();
+            assert_eq!(item("name").extract::<String>().unwrap(), "Banner 0");
+            assert_eq!(item("x").extract::<i32>().unwrap(), 1);
+            assert_eq!(item("y").extract::<i32>().unwrap(), 64);
+
+            let palette = PyPalette::new("grayscale").unwrap();
+            assert_eq!(palette.__len__(), 256);
+            let (red, green, blue, _) = palette.__getitem__(34).unwrap();
+            assert!(red == green && green == blue);
+            assert!(palette
+                .__getitem__(256)
+                .unwrap_err()
+                .is_instance_of::<PyIndexError>(py));
+            let err = PyPalette::new("sepia").err().unwrap();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            let err = read_maps(data_dir.clone(), false, Some("size"))
+                .err()
+                .unwrap();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            // Unreadable files raise an error instead of being left out
+            fs::write(data_dir.join("map_999.dat"), "broken").unwrap();
+            let err = read_maps(data_dir.clone(), false, None).err().unwrap();
+            assert!(err.is_instance_of::<PyIOError>(py));
+            assert!(err.to_string().contains("map_999.dat"));
+        });
+    }
+}
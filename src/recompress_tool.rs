@@ -0,0 +1,81 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::compression::{recompress_file, NbtCompression};
+use minecraft_map_tool::source::{expand_paths, DirectorySource, MapSource};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct RecompressArgs {
+    /// Directories or map files to compress again. Glob patterns like data/map_1*.dat are
+    /// expanded
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Compression of the rewritten files. Minecraft reads only gzip map files. The files are
+    /// compressed with the highest level of the compression unless --compression-level is given
+    #[arg(short, long, default_value = "gzip")]
+    compression: NbtCompression,
+}
+
+pub fn run(args: &RecompressArgs, compression_level: Option<u32>) -> ExitCode {
+    let paths = match expand_paths(&args.paths) {
+        Ok(paths) => paths,
+        Err(err) => {
+            error!("Could not expand the paths: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            match DirectorySource::new(&path).find_map_files(args.recursive) {
+                Ok(found) => files.extend(found),
+                Err(err) => warn!("Could not search {}: {err}", path.display()),
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        error!("No map files found");
+        return ExitCode::FAILURE;
+    }
+
+    let level = compression_level.unwrap_or_else(|| args.compression.max_level());
+    let (mut old_total, mut new_total) = (0, 0);
+    let mut rewritten = 0;
+    let mut failed = 0;
+    for file in &files {
+        match recompress_file(file, args.compression, level, sink()) {
+            Ok((old_size, new_size)) => {
+                old_total += old_size;
+                new_total += new_size;
+                if new_size != old_size {
+                    info!("{}: {old_size} → {new_size} bytes", file.display());
+                    rewritten += 1;
+                }
+            }
+            Err(err) => {
+                warn!("Could not compress {}: {err}", file.display());
+                failed += 1;
+            }
+        }
+    }
+    let saved = old_total as i64 - new_total as i64;
+    info!(
+        "Compressed {rewritten} of {} map files again, {saved} bytes saved ({:.1} %)",
+        files.len(),
+        100.0 * saved as f64 / old_total.max(1) as f64
+    );
+    if failed > 0 {
+        error!("{failed} map files could not be compressed");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,406 @@
+//! Region files of the Anvil world format
+//!
+//! The world is stored in region files (`r.<x>.<z>.mca`) that each hold 32×32 chunks.
+//! The file starts with a table of chunk locations, and each chunk is compressed NBT data.
+//! Since Java Edition 1.17, entities such as item frames are stored in separate region files
+//! in the `entities` directory.
+
+use crate::compression::{decompress, write_compressed, NbtCompression};
+use crate::error::{Error, Result};
+use crate::nbt::{read_nbt, replace_file};
+use crate::sink::Sink;
+use crate::stitch::Area;
+use fastnbt::Value;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Size of a sector in region files
+const SECTOR_SIZE: usize = 4096;
+
+/// Number of chunks in a region file
+pub const CHUNKS_PER_REGION: usize = 32 * 32;
+
+/// Dimension directories in a world with their pretty names
+pub const DIMENSION_DIRECTORIES: [(&str, &str); 3] = [
+    ("", "Overworld"),
+    ("DIM-1", "The Nether"),
+    ("DIM1", "The End"),
+];
+
+/// Data version of Java Edition 1.13, before which map items kept their id in `Damage`
+pub const FLATTENING_DATA_VERSION: i32 = 1519;
+
+/// Reads the data version from the level.dat of the *world*
+///
+/// Returns None if the level.dat has no data version, which means the world was last
+/// opened in a version older than 1.9.
+pub fn world_data_version(world: &Path) -> Result<Option<i32>> {
+    let level = read_nbt(File::open(world.join("level.dat"))?)?;
+    Ok(field(&level, "Data")
+        .and_then(|data| field(data, "DataVersion"))
+        .and_then(number)
+        .map(|version| version as i32))
+}
+
+/// Region file read into memory
+#[derive(Debug)]
+pub struct RegionFile {
+    content: Vec<u8>,
+}
+
+impl RegionFile {
+    /// Reads the region *file*
+    pub fn open(file: &Path) -> Result<RegionFile> {
+        let content = fs::read(file)?;
+        if content.len() < 2 * SECTOR_SIZE && !content.is_empty() {
+            return Err(Error::map_item_error("Region file is too short"));
+        }
+        Ok(RegionFile { content })
+    }
+
+    /// Reads the chunk at the *index* (x + z * 32), or None if the chunk is not stored
+    pub fn chunk(&self, index: usize) -> Option<Result<Value>> {
+        let location = self.content.get(index * 4..index * 4 + 4)?;
+        let offset = u32::from_be_bytes([0, location[0], location[1], location[2]]) as usize;
+        if offset == 0 {
+            return None;
+        }
+        Some(self.read_chunk(offset * SECTOR_SIZE))
+    }
+
+    /// Reads all the stored chunks
+    pub fn chunks(&self) -> impl Iterator<Item = Result<Value>> + '_ {
+        (0..CHUNKS_PER_REGION).filter_map(|index| self.chunk(index))
+    }
+
+    fn read_chunk(&self, start: usize) -> Result<Value> {
+        let truncated = || Error::map_item_error("Chunk is outside the region file");
+        let header = self.content.get(start..start + 5).ok_or_else(truncated)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let data = self
+            .content
+            .get(start + 5..start + 4 + length)
+            .ok_or_else(truncated)?;
+        match header[4] {
+            1..=3 => Ok(fastnbt::from_reader(decompress(data)?)?),
+            4 => Err(Error::map_item_error(
+                "LZ4 compressed chunks are not supported",
+            )),
+            compression if compression & 128 != 0 => Err(Error::map_item_error(
+                "Chunks stored in separate files are not supported",
+            )),
+            _ => Err(Error::map_item_error("Unknown chunk compression")),
+        }
+    }
+}
+
+/// Save times of the chunks in a world
+///
+/// Only the headers of the region files are read, when they are first needed, and the times
+/// are kept for the later queries.
+#[derive(Debug)]
+pub struct ChunkTimes {
+    world: PathBuf,
+    regions: HashMap<PathBuf, Vec<u32>>,
+}
+
+impl ChunkTimes {
+    pub fn new(world: &Path) -> ChunkTimes {
+        ChunkTimes {
+            world: world.to_path_buf(),
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Latest save time of the chunks under the *area* in the *dimension*
+    ///
+    /// The dimension is a pretty name from [DIMENSION_DIRECTORIES]. Returns None for other
+    /// dimensions and when none of the chunks are stored.
+    pub fn latest(&mut self, dimension: &str, area: &Area) -> Result<Option<SystemTime>> {
+        let Some((directory, _)) = DIMENSION_DIRECTORIES
+            .iter()
+            .find(|(_, pretty)| *pretty == dimension)
+        else {
+            return Ok(None);
+        };
+        let region_directory = self.world.join(directory).join("region");
+        let mut latest = 0;
+        for chunk_z in area.top.div_euclid(16)..=area.bottom.div_euclid(16) {
+            for chunk_x in area.left.div_euclid(16)..=area.right.div_euclid(16) {
+                let file = region_directory.join(format!(
+                    "r.{}.{}.mca",
+                    chunk_x.div_euclid(32),
+                    chunk_z.div_euclid(32)
+                ));
+                if !self.regions.contains_key(&file) {
+                    let timestamps = read_timestamps(&file)?;
+                    self.regions.insert(file.clone(), timestamps);
+                }
+                let index = (chunk_x.rem_euclid(32) + chunk_z.rem_euclid(32) * 32) as usize;
+                if let Some(&timestamp) = self.regions[&file].get(index) {
+                    latest = latest.max(timestamp);
+                }
+            }
+        }
+        Ok((latest > 0).then(|| UNIX_EPOCH + Duration::from_secs(latest as u64)))
+    }
+}
+
+/// Reads the chunk save times from the header of the region *file*
+///
+/// Times are seconds since the Unix epoch, and zero for chunks that are not stored. A missing
+/// or empty region file has no times.
+fn read_timestamps(file: &Path) -> Result<Vec<u32>> {
+    let mut header = Vec::with_capacity(2 * SECTOR_SIZE);
+    match File::open(file) {
+        Ok(file) => file.take(2 * SECTOR_SIZE as u64).read_to_end(&mut header)?,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+    if header.is_empty() {
+        return Ok(vec![]);
+    }
+    if header.len() < 2 * SECTOR_SIZE {
+        return Err(Error::map_item_error("Region file is too short"));
+    }
+    Ok(header[SECTOR_SIZE..]
+        .chunks_exact(4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect())
+}
+
+/// Parses the region coordinates from the file name (r.<x>.<z>.mca)
+pub fn region_coordinates(file: &Path) -> Option<(i32, i32)> {
+    let name = file.file_name()?.to_str()?;
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    Some((x, z))
+}
+
+/// Part of the world that a scan had to skip
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanError {
+    /// File that could not be read completely
+    pub file: PathBuf,
+
+    /// What went wrong
+    pub message: String,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file.display(), self.message)
+    }
+}
+
+/// Things found by scanning a world, and the parts of the world that could not be read
+///
+/// Anything in the skipped parts is missing from the found things.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scan<T> {
+    pub found: Vec<T>,
+    pub errors: Vec<ScanError>,
+}
+
+/// Calls *visit* with the dimension, region file and content of every chunk in the
+/// *subdirectories* (like `region` and `entities`) of each dimension in the *world*
+///
+/// Region files and chunks that cannot be read are reported with a warning and skipped,
+/// and returned as errors.
+pub fn scan_chunks<F>(world: &Path, subdirectories: &[&str], mut visit: F) -> Result<Vec<ScanError>>
+where
+    F: FnMut(&str, &Path, &Value),
+{
+    let mut errors = Vec::new();
+    let mut skip = |region: &Path, message: String| {
+        let error = ScanError {
+            file: PathBuf::from(region),
+            message,
+        };
+        warn!("{error}");
+        errors.push(error);
+    };
+    for (directory, dimension) in DIMENSION_DIRECTORIES {
+        for subdirectory in subdirectories {
+            for region in region_files(&world.join(directory).join(subdirectory))? {
+                debug!("Scanning {}", region.display());
+                let region_file = match RegionFile::open(&region) {
+                    Ok(region_file) => region_file,
+                    Err(err) => {
+                        skip(&region, format!("Could not read region file: {err}"));
+                        continue;
+                    }
+                };
+                for chunk in region_file.chunks() {
+                    match chunk {
+                        Ok(chunk) => visit(dimension, &region, &chunk),
+                        Err(err) => skip(&region, format!("Bad chunk: {err}")),
+                    }
+                }
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// Lists the region files in the *directory*, or nothing if the directory does not exist
+fn region_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "mca") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the field *name* of the compound *value*
+pub(crate) fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    match value {
+        Value::Compound(compound) => compound.get(name),
+        _ => None,
+    }
+}
+
+/// Returns the integer *value* of any size
+pub(crate) fn number(value: &Value) -> Option<i64> {
+    match value {
+        Value::Byte(number) => Some(*number as i64),
+        Value::Short(number) => Some(*number as i64),
+        Value::Int(number) => Some(*number as i64),
+        Value::Long(number) => Some(*number),
+        _ => None,
+    }
+}
+
+/// Writes a region file with the *chunks*, given with their index (x + z * 32)
+///
+/// Chunks are compressed with zlib like the game does.
+pub fn write_region<W: Write>(writer: W, chunks: &[(usize, Value)]) -> Result<()> {
+    write_region_with_timestamps(writer, chunks, &[0u8; SECTOR_SIZE])
+}
+
+/// Reads the region *file*, calls *update* for every chunk and writes the file back if
+/// *update* returned true for any chunk
+///
+/// The timestamps of the chunks are kept. A chunk that cannot be read is an error, so the
+/// file is not written without it. The file is written through the *sink*. Returns true if
+/// the file was changed.
+pub fn update_region_file<F>(file: &Path, sink: &Sink, mut update: F) -> Result<bool>
+where
+    F: FnMut(&mut Value) -> bool,
+{
+    let region = RegionFile::open(file)?;
+    let mut chunks = Vec::new();
+    let mut changed = false;
+    for index in 0..CHUNKS_PER_REGION {
+        if let Some(chunk) = region.chunk(index) {
+            let mut chunk = chunk?;
+            changed |= update(&mut chunk);
+            chunks.push((index, chunk));
+        }
+    }
+    if !changed {
+        return Ok(false);
+    }
+    let timestamps = &region.content[SECTOR_SIZE..2 * SECTOR_SIZE];
+    replace_file(file, sink, |writer| {
+        write_region_with_timestamps(writer, &chunks, timestamps)
+    })?;
+    Ok(true)
+}
+
+fn write_region_with_timestamps<W: Write>(
+    mut writer: W,
+    chunks: &[(usize, Value)],
+    timestamps: &[u8],
+) -> Result<()> {
+    let mut locations = vec![0u8; SECTOR_SIZE];
+    let mut sectors = Vec::new();
+    let mut next_sector = 2;
+    for (index, chunk) in chunks {
+        if *index >= CHUNKS_PER_REGION {
+            return Err(Error::map_item_error("Chunk index is outside the region"));
+        }
+        let mut data = Vec::new();
+        write_compressed(&mut data, chunk, NbtCompression::Zlib)?;
+        let mut sector = Vec::with_capacity(data.len() + 5);
+        sector.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+        sector.push(2);
+        sector.extend_from_slice(&data);
+        let count = sector.len().div_ceil(SECTOR_SIZE);
+        sector.resize(count * SECTOR_SIZE, 0);
+
+        let location = (next_sector as u32) << 8 | count as u32;
+        locations[index * 4..index * 4 + 4].copy_from_slice(&location.to_be_bytes());
+        next_sector += count;
+        sectors.push(sector);
+    }
+    writer.write_all(&locations)?;
+    writer.write_all(timestamps)?;
+    for sector in sectors {
+        writer.write_all(&sector)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::region::{write_region_with_timestamps, ChunkTimes, SECTOR_SIZE};
+    use crate::stitch::Area;
+    use fastnbt::Value;
+    use std::collections::HashMap;
+    use std::fs::{self, File};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_chunk_times() {
+        let world = tempfile::tempdir().unwrap();
+        let region_directory = world.path().join("region");
+        fs::create_dir_all(&region_directory).unwrap();
+        // Chunks (0, 0) and (1, 1) of the region r.-1.0.mca, so blocks from x -512 and z 0
+        let mut timestamps = vec![0u8; SECTOR_SIZE];
+        timestamps[0..4].copy_from_slice(&1000u32.to_be_bytes());
+        timestamps[33 * 4..34 * 4].copy_from_slice(&2000u32.to_be_bytes());
+        let chunks = [(0, Value::Compound(HashMap::new()))];
+        let file = File::create(region_directory.join("r.-1.0.mca")).unwrap();
+        write_region_with_timestamps(file, &chunks, &timestamps).unwrap();
+
+        let mut chunk_times = ChunkTimes::new(world.path());
+        let area = |left, top, size| Area {
+            left,
+            top,
+            right: left + size - 1,
+            bottom: top + size - 1,
+        };
+        let time = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+        let latest = |chunk_times: &mut ChunkTimes, dimension, area| {
+            chunk_times.latest(dimension, &area).unwrap()
+        };
+        assert_eq!(
+            latest(&mut chunk_times, "Overworld", area(-512, 0, 16)),
+            time(1000)
+        );
+        assert_eq!(
+            latest(&mut chunk_times, "Overworld", area(-512, 0, 32)),
+            time(2000)
+        );
+        assert_eq!(
+            latest(&mut chunk_times, "Overworld", area(-480, 32, 64)),
+            None
+        );
+        assert_eq!(latest(&mut chunk_times, "Overworld", area(0, 0, 128)), None);
+        assert_eq!(latest(&mut chunk_times, "The End", area(-512, 0, 16)), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! Town and claim regions from GeoJSON
+//!
+//! Server plugins and web maps can export the borders of towns, factions and land claims as
+//! GeoJSON. The coordinates are read as world coordinates, the first number being X and the
+//! second Z, and each polygon becomes a filled [Annotation] for the stitched images.
+//!
+//! The style is taken from the feature properties, following the simplestyle names used by
+//! many GeoJSON tools: `stroke`, `fill`, `fill-opacity` and `stroke-width`. The label is the
+//! `name`, `label` or `title` property, and a `dimension` property limits the region to the
+//! images of that dimension. Points and lines are read too.
+
+use crate::annotations::{Annotation, Shape};
+use crate::error::{Error, Result};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Properties that can hold the name of a region, in the order they are looked up
+const LABEL_PROPERTIES: [&str; 3] = ["name", "label", "title"];
+
+/// Reads the regions from the GeoJSON *file*
+pub fn read_regions(file: &Path) -> Result<Vec<Annotation>> {
+    parse_geojson(&fs::read_to_string(file)?)
+}
+
+/// Parses the features of the GeoJSON *text* into annotations
+///
+/// The text can be a feature collection, a single feature or a bare geometry. Geometries
+/// that are not points, lines or polygons are skipped.
+pub fn parse_geojson(text: &str) -> Result<Vec<Annotation>> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let empty = Map::new();
+    let mut annotations = Vec::new();
+    match value.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            let features = value
+                .get("features")
+                .and_then(Value::as_array)
+                .ok_or_else(|| {
+                    Error::map_item_error("GeoJSON feature collection has no features")
+                })?;
+            for feature in features {
+                add_feature(&mut annotations, feature)?;
+            }
+        }
+        Some("Feature") => add_feature(&mut annotations, &value)?,
+        Some(_) => add_geometry(&mut annotations, &value, &empty)?,
+        None => return Err(Error::map_item_error("GeoJSON object has no type")),
+    }
+    Ok(annotations)
+}
+
+/// Adds the annotations of the geometry in the *feature*, styled with its properties
+fn add_feature(annotations: &mut Vec<Annotation>, feature: &Value) -> Result<()> {
+    let empty = Map::new();
+    let properties = feature
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    match feature.get("geometry") {
+        Some(geometry) if !geometry.is_null() => add_geometry(annotations, geometry, properties),
+        _ => Ok(()),
+    }
+}
+
+/// Adds the annotations of the *geometry* styled with the *properties*
+fn add_geometry(
+    annotations: &mut Vec<Annotation>,
+    geometry: &Value,
+    properties: &Map<String, Value>,
+) -> Result<()> {
+    let coordinates = geometry.get("coordinates").unwrap_or(&Value::Null);
+    let shapes = match geometry.get("type").and_then(Value::as_str) {
+        Some("Point") => vec![point_shape(coordinates)?],
+        Some("MultiPoint") => array(coordinates)?
+            .iter()
+            .map(point_shape)
+            .collect::<Result<_>>()?,
+        Some("LineString") => vec![Shape::Line {
+            points: positions(coordinates)?,
+        }],
+        Some("MultiLineString") => array(coordinates)?
+            .iter()
+            .map(|line| {
+                Ok(Shape::Line {
+                    points: positions(line)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        Some("Polygon") => vec![polygon_shape(coordinates)?],
+        Some("MultiPolygon") => array(coordinates)?
+            .iter()
+            .map(polygon_shape)
+            .collect::<Result<_>>()?,
+        Some("GeometryCollection") => {
+            let geometries = geometry.get("geometries").unwrap_or(&Value::Null);
+            for geometry in array(geometries)? {
+                add_geometry(annotations, geometry, properties)?;
+            }
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+    let string = |key: &str| properties.get(key).and_then(Value::as_str);
+    let label = LABEL_PROPERTIES
+        .iter()
+        .find_map(|key| string(key))
+        .map(String::from);
+    for (index, shape) in shapes.into_iter().enumerate() {
+        annotations.push(Annotation {
+            shape,
+            // Only the first part of a multi-part geometry is labeled
+            label: label.clone().filter(|_| index == 0),
+            color: string("stroke").or(string("fill")).map(String::from),
+            width: properties
+                .get("stroke-width")
+                .and_then(Value::as_f64)
+                .map_or(1, |width| width.round().max(1.0) as u32),
+            fill: true,
+            opacity: properties
+                .get("fill-opacity")
+                .and_then(Value::as_f64)
+                .map(|opacity| opacity as f32),
+            dimension: string("dimension").map(String::from),
+        });
+    }
+    Ok(())
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| Error::map_item_error("GeoJSON coordinates must be arrays"))
+}
+
+/// World coordinates of a GeoJSON position, rounded to blocks
+fn position(value: &Value) -> Result<[i32; 2]> {
+    match array(value)?.as_slice() {
+        [x, z, ..] => match (x.as_f64(), z.as_f64()) {
+            (Some(x), Some(z)) => Ok([x.round() as i32, z.round() as i32]),
+            _ => Err(Error::map_item_error("GeoJSON position must have numbers")),
+        },
+        _ => Err(Error::map_item_error("GeoJSON position must have X and Z")),
+    }
+}
+
+fn positions(value: &Value) -> Result<Vec<[i32; 2]>> {
+    array(value)?.iter().map(position).collect()
+}
+
+fn point_shape(value: &Value) -> Result<Shape> {
+    let [x, z] = position(value)?;
+    Ok(Shape::Point { x, z })
+}
+
+/// Polygon with the first ring as the outline and the others as holes
+fn polygon_shape(value: &Value) -> Result<Shape> {
+    let mut rings = array(value)?
+        .iter()
+        .map(positions)
+        .collect::<Result<Vec<_>>>()?
+        .into_iter();
+    let points = rings
+        .next()
+        .ok_or_else(|| Error::map_item_error("GeoJSON polygon has no rings"))?;
+    Ok(Shape::Polygon {
+        points,
+        holes: rings.collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::annotations::Shape;
+    use crate::regions::parse_geojson;
+
+    #[test]
+    fn test_parse_geojson() {
+        let annotations = parse_geojson(
+            r##"{
+                "type": "FeatureCollection",
+                "features": [
+                    {
+                        "type": "Feature",
+                        "properties": {"name": "Oakvale", "fill": "#22aa22", "fill-opacity": 0.4},
+                        "geometry": {
+                            "type": "Polygon",
+                            "coordinates": [
+                                [[0, 0], [64, 0], [64, 64], [0, 64], [0, 0]],
+                                [[16, 16], [32, 16], [32, 32], [16, 16]]
+                            ]
+                        }
+                    },
+                    {
+                        "type": "Feature",
+                        "properties": {"title": "Outposts", "dimension": "Nether"},
+                        "geometry": {
+                            "type": "MultiPolygon",
+                            "coordinates": [
+                                [[[100.4, 0], [110, 0], [110, 10]]],
+                                [[[200, 0], [210, 0], [210, 10]]]
+                            ]
+                        }
+                    },
+                    {"type": "Feature", "properties": null, "geometry": null}
+                ]
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(annotations.len(), 3);
+        let town = &annotations[0];
+        assert_eq!(town.label.as_deref(), Some("Oakvale"));
+        assert_eq!(town.color.as_deref(), Some("#22aa22"));
+        assert_eq!(town.opacity, Some(0.4));
+        match &town.shape {
+            Shape::Polygon { points, holes } => {
+                assert_eq!(points.len(), 5);
+                assert_eq!(holes.len(), 1);
+            }
+            shape => panic!("Expected a polygon, got {shape:?}"),
+        }
+        assert_eq!(annotations[1].label.as_deref(), Some("Outposts"));
+        assert_eq!(annotations[2].label, None);
+        assert!(annotations[2].is_in("nether"));
+        assert_eq!(
+            annotations[1].shape,
+            Shape::Polygon {
+                points: vec![[100, 0], [110, 0], [110, 10]],
+                holes: Vec::new()
+            }
+        );
+
+        assert!(parse_geojson(r#"{"type": "Point", "coordinates": [1]}"#).is_err());
+        assert!(parse_geojson("[]").is_err());
+    }
+}
@@ -0,0 +1,421 @@
+//! Renumbering the maps of a world
+//!
+//! After map files have been removed, the remaining maps can be renumbered to a contiguous
+//! range starting from zero. The map files are renamed, `idcounts.dat` is updated so the
+//! game continues numbering after the last map, and the filled map items in the world are
+//! patched to use the new ids.
+//!
+//! Items referring to maps without a file keep their ids, and those ids are not given to
+//! other maps, so the items do not start showing another map. Worlds from before Java
+//! Edition 1.13 are not supported, because their items keep the map id in `Damage`.
+
+use crate::error::{Error, Result};
+use crate::nbt::{read_nbt, select, update_nbt_file};
+use crate::read_maps;
+use crate::region::{
+    number, scan_chunks, update_region_file, world_data_version, Scan, ScanError,
+    DIMENSION_DIRECTORIES, FLATTENING_DATA_VERSION,
+};
+use crate::sink::Sink;
+use crate::source::map_id;
+use fastnbt::Value;
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Result of renumbering the maps
+#[derive(Debug, Default)]
+pub struct Renumbered {
+    /// New map ids by the old ids, for all the map files
+    pub ids: BTreeMap<u32, u32>,
+
+    /// Number of filled map items given a new id
+    pub patched_items: usize,
+
+    /// Number of filled map items referring to maps without a file, which were not patched
+    pub dangling_items: usize,
+
+    /// Files that were written when patching the items
+    pub patched_files: Vec<PathBuf>,
+}
+
+/// Assigns new ids starting from zero to the *ids* in ascending order, skipping the
+/// *reserved* ids
+///
+/// The *ids* must not be reserved. Then no id gets a larger new id.
+pub fn renumber_plan<I: IntoIterator<Item = u32>>(
+    ids: I,
+    reserved: &BTreeSet<u32>,
+) -> BTreeMap<u32, u32> {
+    let mut ids: Vec<u32> = ids.into_iter().collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let free_ids = (0..).filter(|id| !reserved.contains(id));
+    ids.into_iter().zip(free_ids).collect()
+}
+
+/// Renumbers the map files in the `data` directory of the *world*
+///
+/// With *patch_references*, filled map items in the region and entity files, player data
+/// and level.dat are changed to use the new ids. The world must not be open in the game
+/// while it is changed. The files are renamed and written through the *sink*.
+///
+/// All the files with items are read before anything is changed, and the world is left
+/// as it was if any of them cannot be read.
+pub fn renumber_maps(world: &Path, patch_references: bool, sink: &Sink) -> Result<Renumbered> {
+    match world_data_version(world)? {
+        Some(version) if version >= FLATTENING_DATA_VERSION => {}
+        _ => {
+            return Err(Error::map_item_error(
+                "Worlds from before Java Edition 1.13 are not supported",
+            ))
+        }
+    }
+    let data_dir = world.join("data");
+    let maps = read_maps(&data_dir, &None, false)?;
+    let files: BTreeSet<u32> = maps.paths().filter_map(|path| map_id(path)).collect();
+    let dangling: BTreeSet<u32> = referenced_map_ids(world)?
+        .difference(&files)
+        .copied()
+        .collect();
+    let mut renumbered = Renumbered {
+        ids: renumber_plan(files, &dangling),
+        ..Renumbered::default()
+    };
+
+    // New ids are never larger than the old ones, so renaming in ascending order never
+    // overwrites a map that has not been renamed yet
+    for (&old_id, &new_id) in &renumbered.ids {
+        if old_id != new_id {
+            info!("Renaming map_{old_id}.dat to map_{new_id}.dat");
+            sink.rename(
+                data_dir.join(format!("map_{old_id}.dat")),
+                data_dir.join(format!("map_{new_id}.dat")),
+            )?;
+        }
+    }
+
+    // New maps must not get the ids of the dangling items either
+    let last_id = renumbered.ids.values().chain(&dangling).max();
+    update_last_map_id(&data_dir, last_id.map_or(-1, |id| *id as i32), sink)?;
+
+    if patch_references {
+        patch_world(world, &mut renumbered, sink)?;
+    }
+    Ok(renumbered)
+}
+
+/// Reads the last map id given by the game from the `idcounts.dat` in the *data_dir*
+///
+/// Returns None if the file does not exist, which means no maps have been made.
+pub fn last_map_id(data_dir: &Path) -> Result<Option<i32>> {
+    let id_counts = data_dir.join("idcounts.dat");
+    if !id_counts.is_file() {
+        return Ok(None);
+    }
+    let value = read_nbt(File::open(id_counts)?)?;
+    Ok(select(&value, "data.map")
+        .ok()
+        .and_then(number)
+        .map(|id| id as i32))
+}
+
+/// Changes the last map id in the `idcounts.dat` of the *data_dir*, so the game continues
+/// numbering after *last_id*
+///
+/// The file is written through the *sink*. Returns false if the file does not exist.
+pub fn update_last_map_id(data_dir: &Path, last_id: i32, sink: &Sink) -> Result<bool> {
+    let id_counts = data_dir.join("idcounts.dat");
+    if !id_counts.is_file() {
+        return Ok(false);
+    }
+    update_nbt_file(&id_counts, sink, |value| {
+        if let Value::Compound(root) = value {
+            if let Some(Value::Compound(data)) = root.get_mut("data") {
+                data.insert("map".to_string(), Value::Int(last_id));
+            }
+        }
+        true
+    })
+}
+
+/// Region and entity files of the *world*
+fn region_files(world: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for (directory, _) in DIMENSION_DIRECTORIES {
+        for subdirectory in ["region", "entities"] {
+            let directory = world.join(directory).join(subdirectory);
+            files.extend(files_with_extension(&directory, "mca")?);
+        }
+    }
+    Ok(files)
+}
+
+/// Player data files and the level.dat of the *world*
+fn nbt_files(world: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = files_with_extension(&world.join("playerdata"), "dat")?;
+    files.push(world.join("level.dat"));
+    files.retain(|file| file.is_file());
+    Ok(files)
+}
+
+/// Collects the ids of all filled map items in the files of the *world* that can hold items
+///
+/// Any file or chunk that cannot be read is an error, unlike when searching the maps.
+pub fn referenced_map_ids(world: &Path) -> Result<BTreeSet<u32>> {
+    let scan = scan_map_ids(world)?;
+    if let Some(error) = scan.errors.first() {
+        return Err(io::Error::other(format!("Could not read {error}")).into());
+    }
+    Ok(scan.found.into_iter().map(|(id, _)| id).collect())
+}
+
+/// Searches the filled map items in the region and entity files, player data and level.dat
+/// of the *world*
+///
+/// Items are found wherever they are, including dropped items, minecarts, mob inventories
+/// and bundles. The ids are returned with the files they were found in, sorted by the id.
+/// Files and chunks that cannot be read are skipped and returned as errors.
+pub fn scan_map_ids(world: &Path) -> Result<Scan<(u32, PathBuf)>> {
+    let mut found = BTreeSet::new();
+    let mut errors = scan_chunks(world, &["region", "entities"], |_, region, chunk| {
+        let mut ids = BTreeSet::new();
+        collect_map_ids(chunk, &mut ids);
+        found.extend(ids.into_iter().map(|id| (id, region.to_path_buf())));
+    })?;
+    for file in nbt_files(world)? {
+        let value = fs::read(&file)
+            .map_err(Error::from)
+            .and_then(|content| read_nbt(content.as_slice()));
+        match value {
+            Ok(value) => {
+                let mut ids = BTreeSet::new();
+                collect_map_ids(&value, &mut ids);
+                found.extend(ids.into_iter().map(|id| (id, file.clone())));
+            }
+            Err(err) => {
+                let error = ScanError {
+                    file,
+                    message: format!("Could not read NBT file: {err}"),
+                };
+                warn!("{error}");
+                errors.push(error);
+            }
+        }
+    }
+    Ok(Scan {
+        found: found.into_iter().collect(),
+        errors,
+    })
+}
+
+/// Patches the filled map items in all the files of the *world* that can hold items
+fn patch_world(world: &Path, renumbered: &mut Renumbered, sink: &Sink) -> Result<()> {
+    let ids = renumbered.ids.clone();
+    let mut patch = |value: &mut Value| {
+        let (patched, dangling) = patch_map_ids(value, &ids);
+        renumbered.patched_items += patched;
+        renumbered.dangling_items += dangling;
+        patched > 0
+    };
+
+    let mut patched_files = Vec::new();
+    for file in region_files(world)? {
+        if update_region_file(&file, sink, &mut patch)? {
+            patched_files.push(file);
+        }
+    }
+    for file in nbt_files(world)? {
+        if update_nbt_file(&file, sink, &mut patch)? {
+            patched_files.push(file);
+        }
+    }
+    renumbered.patched_files = patched_files;
+    Ok(())
+}
+
+/// Adds the ids of the filled map items in the *value* to the *ids*
+fn collect_map_ids(value: &Value, ids: &mut BTreeSet<u32>) {
+    match value {
+        Value::Compound(compound) => {
+            if matches!(compound.get("id"), Some(Value::String(id)) if id == "minecraft:filled_map")
+            {
+                // The id moved from the item tag to the item components in 1.20.5
+                let id_value = match compound.get("components") {
+                    Some(Value::Compound(components))
+                        if components.contains_key("minecraft:map_id") =>
+                    {
+                        components.get("minecraft:map_id")
+                    }
+                    _ => match compound.get("tag") {
+                        Some(Value::Compound(tag)) => tag.get("map"),
+                        _ => None,
+                    },
+                };
+                if let Some(id) = id_value.and_then(number) {
+                    ids.extend(u32::try_from(id).ok());
+                }
+            }
+            for child in compound.values() {
+                collect_map_ids(child, ids);
+            }
+        }
+        Value::List(list) => {
+            for child in list {
+                collect_map_ids(child, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Changes the ids of all filled map items in the *value* with the *ids*
+///
+/// Returns the number of changed items and the number of items with ids that are not in
+/// *ids*. Items keeping their id are not changed or counted.
+pub fn patch_map_ids(value: &mut Value, ids: &BTreeMap<u32, u32>) -> (usize, usize) {
+    let mut patched = 0;
+    let mut dangling = 0;
+    patch_value(value, ids, &mut patched, &mut dangling);
+    (patched, dangling)
+}
+
+fn patch_value(
+    value: &mut Value,
+    ids: &BTreeMap<u32, u32>,
+    patched: &mut usize,
+    dangling: &mut usize,
+) {
+    match value {
+        Value::Compound(compound) => {
+            if matches!(compound.get("id"), Some(Value::String(id)) if id == "minecraft:filled_map")
+            {
+                // The id moved from the item tag to the item components in 1.20.5
+                let id_value = match compound.get_mut("components") {
+                    Some(Value::Compound(components))
+                        if components.contains_key("minecraft:map_id") =>
+                    {
+                        components.get_mut("minecraft:map_id")
+                    }
+                    _ => match compound.get_mut("tag") {
+                        Some(Value::Compound(tag)) => tag.get_mut("map"),
+                        _ => None,
+                    },
+                };
+                if let Some(id_value) = id_value {
+                    let old_id = number(id_value).and_then(|id| u32::try_from(id).ok());
+                    match old_id.and_then(|old_id| Some((old_id, ids.get(&old_id)?))) {
+                        Some((old_id, &new_id)) if old_id != new_id => {
+                            *id_value = Value::Int(new_id as i32);
+                            *patched += 1;
+                        }
+                        Some(_) => {}
+                        None => *dangling += 1,
+                    }
+                }
+            }
+            for child in compound.values_mut() {
+                patch_value(child, ids, patched, dangling);
+            }
+        }
+        Value::List(list) => {
+            for child in list {
+                patch_value(child, ids, patched, dangling);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Lists the files with the *extension* in the *directory*, or nothing if it does not exist
+fn files_with_extension(directory: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|found| found == extension) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::containers::find_container_maps;
+    use crate::item_frames::find_map_frames;
+    use crate::nbt::{read_nbt, select, update_nbt_file};
+    use crate::renumber::renumber_maps;
+    use crate::sink::Sink;
+    use crate::synthetic::write_synthetic_world;
+    use fastnbt::Value;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_renumber_maps() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let data_dir = world.path().join("data");
+        for id in [1, 10, 11] {
+            fs::remove_file(data_dir.join(format!("map_{id}.dat"))).unwrap();
+        }
+
+        let sink = Sink::default();
+        let renumbered = renumber_maps(world.path(), true, &sink).unwrap();
+        assert_eq!(renumbered.ids.len(), 29);
+        // Maps 1 and 99 have items but no files, so their ids are not given to other maps
+        assert_eq!(renumbered.ids[&2], 2);
+        assert_eq!(renumbered.ids[&12], 10);
+        assert_eq!(renumbered.ids[&24], 22);
+        assert_eq!(renumbered.dangling_items, 2);
+        assert!(!data_dir.join("map_1.dat").exists());
+        assert!(data_dir.join("map_29.dat").is_file());
+        assert!(!data_dir.join("map_30.dat").exists());
+
+        let frames = find_map_frames(world.path(), None).unwrap();
+        let ids: Vec<_> = frames.iter().map(|frame| frame.map_id).collect();
+        assert_eq!(ids, [0, 5, 22]);
+        let maps = find_container_maps(world.path(), None).unwrap();
+        let ids: Vec<_> = maps.iter().map(|map| map.map_id).collect();
+        assert_eq!(ids, [4, 23, 99]);
+
+        // New maps continue after the dangling map 99
+        let id_counts = read_nbt(File::open(data_dir.join("idcounts.dat")).unwrap()).unwrap();
+        assert_eq!(select(&id_counts, "data.map").unwrap(), &Value::Int(99));
+
+        // Only the items of maps 24 and 25 got new ids
+        assert_eq!(renumbered.patched_items, 2);
+        let renumbered = renumber_maps(world.path(), true, &sink).unwrap();
+        assert!(renumbered
+            .ids
+            .iter()
+            .all(|(old_id, new_id)| old_id == new_id));
+        assert_eq!(renumbered.patched_items, 0);
+        assert!(renumbered.patched_files.is_empty());
+
+        // Nothing is renamed when a region file cannot be read
+        fs::remove_file(data_dir.join("map_0.dat")).unwrap();
+        fs::write(world.path().join("region").join("r.9.9.mca"), [1u8; 100]).unwrap();
+        assert!(renumber_maps(world.path(), true, &sink).is_err());
+        assert!(data_dir.join("map_29.dat").is_file());
+
+        // Worlds from before 1.13 are refused
+        update_nbt_file(&world.path().join("level.dat"), &sink, |level| {
+            if let Value::Compound(level) = level {
+                if let Some(Value::Compound(data)) = level.get_mut("Data") {
+                    data.insert("DataVersion".to_string(), Value::Int(1343));
+                }
+            }
+            true
+        })
+        .unwrap();
+        fs::remove_file(world.path().join("region").join("r.9.9.mca")).unwrap();
+        assert!(renumber_maps(world.path(), true, &sink).is_err());
+        assert!(data_dir.join("map_29.dat").is_file());
+    }
+}
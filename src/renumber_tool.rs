@@ -0,0 +1,56 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::renumber::renumber_maps;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+Close the world in the game or stop the server and take a backup before renumbering. \
+With --no-patch-references, the maps in item frames and inventories will show other maps.")]
+pub struct RenumberArgs {
+    /// The world directory
+    world: PathBuf,
+
+    /// Only rename the map files, leaving the filled map items in region, entity and player
+    /// files with their old ids
+    #[arg(long)]
+    no_patch_references: bool,
+}
+
+pub fn run(args: &RenumberArgs) -> ExitCode {
+    if !args.world.join("level.dat").is_file() {
+        error!("{} is not a world directory", args.world.display());
+        return ExitCode::FAILURE;
+    }
+    let renumbered = match renumber_maps(&args.world, !args.no_patch_references, sink()) {
+        Ok(renumbered) => renumbered,
+        Err(err) => {
+            error!("Could not renumber maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let changed = renumbered
+        .ids
+        .iter()
+        .filter(|(old_id, new_id)| old_id != new_id)
+        .count();
+    info!("{changed} of {} maps got a new id", renumbered.ids.len());
+    if !args.no_patch_references {
+        info!(
+            "{} map items patched in {} files",
+            renumbered.patched_items,
+            renumbered.patched_files.len()
+        );
+        if renumbered.dangling_items > 0 {
+            warn!(
+                "{} map items refer to maps without a file and were not changed",
+                renumbered.dangling_items
+            );
+        }
+    } else if changed > 0 {
+        warn!("References were not patched, so placed and carried maps now show other maps");
+    }
+    ExitCode::SUCCESS
+}
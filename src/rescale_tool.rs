@@ -0,0 +1,53 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info};
+use minecraft_map_tool::edit::rescale;
+use minecraft_map_tool::MapItem;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct RescaleArgs {
+    /// The map file to make the zoomed out map from
+    map_file: PathBuf,
+
+    /// Write the new map to this file
+    output_file: PathBuf,
+
+    /// Scale of the new map, from 1 to 4. Each step doubles the blocks per pixel.
+    #[arg(short, long, value_parser = clap::value_parser!(i8).range(1..=4))]
+    scale: i8,
+}
+
+pub fn run(args: &RescaleArgs) -> ExitCode {
+    let map_item = match MapItem::read_from(&args.map_file) {
+        Ok(map_item) => map_item,
+        Err(err) => {
+            error!("Could not read map file: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let data = match rescale(&map_item.data, args.scale) {
+        Ok(data) => data,
+        Err(err) => {
+            error!("Could not rescale the map: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    info!(
+        "New map with scale {} is centered at {}, {}",
+        data.scale_description(),
+        data.x_center,
+        data.z_center
+    );
+    let rescaled = MapItem {
+        file: args.output_file.clone(),
+        data,
+        data_version: map_item.data_version,
+    };
+    if let Err(err) = sink().write_with(&rescaled.file, |file| rescaled.write_to(file)) {
+        error!("Could not write {}: {err}", args.output_file.display());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
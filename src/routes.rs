@@ -0,0 +1,150 @@
+//! Routes between banners
+//!
+//! Ice roads and nether tunnels are easy to mark with banners at their ends and junctions.
+//! A [Route] connects named banners in a given order, so the network can be drawn on the
+//! stitched images and its length reported.
+
+use crate::annotations::{Annotation, Shape};
+use crate::error::Result;
+use crate::markers::{MarkerKind, PointMarker};
+use std::io;
+
+/// Line colors for the routes, used in turn
+pub const ROUTE_COLORS: [&str; 6] = ["gold", "aqua", "light_purple", "green", "yellow", "red"];
+
+/// Width of the route lines in pixels
+const ROUTE_WIDTH: u32 = 3;
+
+/// Banners connected in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Route {
+    pub stops: Vec<PointMarker>,
+}
+
+impl Route {
+    /// Finds the banners with the *names* from the *markers*, compared case-insensitively
+    ///
+    /// When many banners have the same name, the one closest to the previous stop is used.
+    pub fn find(markers: &[PointMarker], names: &[String]) -> Result<Route> {
+        let mut stops: Vec<PointMarker> = Vec::new();
+        for name in names {
+            let candidates = markers.iter().filter(|marker| {
+                marker.kind == MarkerKind::Banner && marker.label.eq_ignore_ascii_case(name)
+            });
+            let banner = match stops.last() {
+                Some(previous) => candidates
+                    .min_by(|a, b| distance(previous, a).total_cmp(&distance(previous, b))),
+                None => candidates.min_by_key(|marker| (marker.pos.x, marker.pos.z)),
+            };
+            match banner {
+                Some(banner) => stops.push(banner.clone()),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("No banner named {name:?}"),
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(Route { stops })
+    }
+
+    /// Distances between the stops in blocks, measured on the X-Z plane
+    pub fn legs(&self) -> Vec<f64> {
+        self.stops
+            .windows(2)
+            .map(|pair| distance(&pair[0], &pair[1]))
+            .collect()
+    }
+
+    /// Length of the whole route in blocks
+    pub fn length(&self) -> f64 {
+        self.legs().iter().sum()
+    }
+
+    /// Line through the stops and labeled points at them, drawn in the *color*
+    pub fn annotations(&self, color: &str) -> Vec<Annotation> {
+        let annotation = |shape: Shape, label: Option<String>| Annotation {
+            shape,
+            label,
+            color: Some(color.to_string()),
+            width: ROUTE_WIDTH,
+            fill: false,
+            opacity: None,
+            dimension: None,
+        };
+        let points = self
+            .stops
+            .iter()
+            .map(|stop| [stop.pos.x, stop.pos.z])
+            .collect();
+        let mut annotations = vec![annotation(Shape::Line { points }, None)];
+        for stop in &self.stops {
+            let point = Shape::Point {
+                x: stop.pos.x,
+                z: stop.pos.z,
+            };
+            annotations.push(annotation(point, Some(stop.label.clone())));
+        }
+        annotations
+    }
+}
+
+/// Parses a route from banner names separated by commas
+pub fn parse_route(text: &str) -> std::result::Result<Vec<String>, String> {
+    let names: Vec<String> = text
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .collect();
+    if names.len() < 2 || names.iter().any(String::is_empty) {
+        return Err(String::from(
+            "Route must have at least two banner names separated by commas",
+        ));
+    }
+    Ok(names)
+}
+
+/// Distance between the markers in blocks on the X-Z plane
+fn distance(a: &PointMarker, b: &PointMarker) -> f64 {
+    let dx = (a.pos.x - b.pos.x) as f64;
+    let dz = (a.pos.z - b.pos.z) as f64;
+    dx.hypot(dz)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markers::{MarkerKind, PointMarker};
+    use crate::routes::{parse_route, Route};
+    use crate::{BannerColor, Pos};
+
+    fn banner(label: &str, x: i32, z: i32) -> PointMarker {
+        PointMarker {
+            kind: MarkerKind::Banner,
+            label: label.to_string(),
+            pos: Pos { x, y: 64, z },
+            color: Some(BannerColor::Blue),
+        }
+    }
+
+    #[test]
+    fn test_route() {
+        let markers = vec![
+            banner("Hub", 0, 0),
+            banner("Farm", 300, 400),
+            banner("Farm", -30, -40),
+            banner("Base", -30, 0),
+        ];
+        let names = parse_route("hub, Farm,BASE").unwrap();
+        let route = Route::find(&markers, &names).unwrap();
+        // The closer farm is used
+        assert_eq!(route.stops[1].pos.x, -30);
+        assert_eq!(route.legs(), [50.0, 40.0]);
+        assert_eq!(route.length(), 90.0);
+        assert_eq!(route.annotations("gold").len(), 4);
+
+        assert!(Route::find(&markers, &parse_route("Hub,Mine").unwrap()).is_err());
+        assert!(parse_route("Hub").is_err());
+        assert!(parse_route("Hub,,Farm").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::serve_tool::MapInfo;
+use crate::transfer_tool::IdMapping;
+use clap::{Args, ValueEnum};
+use log::error;
+use minecraft_map_tool::diff::CollectionDiff;
+use minecraft_map_tool::thumbs::ThumbnailIndex;
+use schemars::schema_for;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct SchemaArgs {
+    /// The JSON output to print the schema for
+    output: JsonOutput,
+}
+
+/// Commands and endpoints that write JSON
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum JsonOutput {
+    /// Report of the diff command when comparing directories with --format json
+    Diff,
+
+    /// Id mapping printed by the transfer command
+    Transfer,
+
+    /// Map listing at /maps of the serve command
+    Maps,
+
+    /// Sprite sheet index written by the thumbs command
+    Thumbs,
+}
+
+pub fn run(args: &SchemaArgs) -> ExitCode {
+    let schema = match args.output {
+        JsonOutput::Diff => schema_for!(CollectionDiff),
+        JsonOutput::Transfer => schema_for!(Vec<IdMapping>),
+        JsonOutput::Maps => schema_for!(Vec<MapInfo>),
+        JsonOutput::Thumbs => schema_for!(ThumbnailIndex),
+    };
+    match serde_json::to_string_pretty(&schema) {
+        Ok(json) => println!("{json}"),
+        Err(err) => {
+            error!("Could not write the schema: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
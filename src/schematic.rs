@@ -0,0 +1,226 @@
+//! Sponge schematic export for map art
+//!
+//! The schematic contains blocks for one map with an extra row of blocks on the north side.
+//! The extra row is needed because the shade of the pixel depends on the block north of it.
+//! Blocks are stacked so that the map shows the dark, normal and light shades of the colors.
+//!
+//! The format is described in
+//! [https://github.com/SpongePowered/Schematic-Specification](https://github.com/SpongePowered/Schematic-Specification)
+
+use crate::error::{Error, Result};
+use crate::palette::{building_block, decompose};
+use fastnbt::{ByteArray, IntArray, SerOpts};
+use flate2::{write::GzEncoder, Compression};
+use heck::ToSnakeCase;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+/// Block used for the reference row and for base color 0
+const FILLER_BLOCK: &str = "minecraft:cobblestone";
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpongeSchematic {
+    version: i32,
+    data_version: i32,
+    width: i16,
+    height: i16,
+    length: i16,
+    offset: IntArray,
+    palette_max: i32,
+    palette: BTreeMap<String, i32>,
+    block_data: ByteArray,
+}
+
+/// Block placed in the schematic
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlacedBlock {
+    /// Block state, for example `minecraft:stone`
+    pub block: String,
+
+    /// Height of the block, where 0 is the lowest block
+    pub y: i32,
+}
+
+/// Block state for the given *base_color*
+///
+/// The block from [building_block] is used, and the filler block for base color 0. Leaves
+/// are made persistent so that they do not decay. Returns None for the colors that cannot be
+/// built with a solid block, like water.
+pub fn block_state(base_color: u8) -> Option<String> {
+    if base_color == 0 {
+        return Some(FILLER_BLOCK.to_string());
+    }
+    let id = format!("minecraft:{}", building_block(base_color)?.to_snake_case());
+    if id.ends_with("_leaves") {
+        Some(format!("{id}[persistent=true]"))
+    } else {
+        Some(id)
+    }
+}
+
+/// Calculates the blocks for the map *colors*
+///
+/// Returns 129 rows of 128 blocks, from north to south. The first row is the reference row.
+/// In each column, a light pixel is one block higher than the block north of it, a dark pixel
+/// is one block lower, and a normal pixel is at the same height. The darkest shade cannot be
+/// built and is handled like the dark shade. Colors without a block, like water, are an
+/// error.
+pub fn staircase(colors: &[u8]) -> Result<Vec<Vec<PlacedBlock>>> {
+    if colors.len() != 128 * 128 {
+        return Err(Error::map_item_error("Map art must have 128×128 pixels"));
+    }
+    let mut heights = vec![vec![0i32; 128]; 129];
+    for x in 0..128 {
+        for z in 0..128 {
+            let step = match decompose(colors[z * 128 + x]).1 {
+                2 => 1,
+                1 => 0,
+                _ => -1,
+            };
+            heights[z + 1][x] = heights[z][x] + step;
+        }
+        // Moving the column so that the lowest block is at zero
+        let lowest = (0..129).map(|z| heights[z][x]).min().unwrap_or_default();
+        for row in heights.iter_mut() {
+            row[x] -= lowest;
+        }
+    }
+    heights
+        .iter()
+        .enumerate()
+        .map(|(z, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, y)| {
+                    let block = match z {
+                        0 => Some(FILLER_BLOCK.to_string()),
+                        _ => block_state(decompose(colors[(z - 1) * 128 + x]).0),
+                    };
+                    match block {
+                        Some(block) => Ok(PlacedBlock { block, y: *y }),
+                        None => Err(Error::map_item_error(
+                            "Map art has colors that cannot be built with blocks, like water",
+                        )),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Writes map art *colors* to a Sponge schematic (version 2) file
+pub fn write_schematic(colors: &[u8], data_version: i32, file: &Path) -> Result<()> {
+    let rows = staircase(colors)?;
+    let width = 128usize;
+    let length = rows.len();
+    let height = rows
+        .iter()
+        .flatten()
+        .map(|block| block.y as usize + 1)
+        .max()
+        .unwrap_or(1);
+
+    let mut palette = BTreeMap::new();
+    palette.insert("minecraft:air".to_string(), 0);
+    let mut indices = vec![0i32; width * height * length];
+    for (z, row) in rows.iter().enumerate() {
+        for (x, block) in row.iter().enumerate() {
+            let next_index = palette.len() as i32;
+            let index = *palette.entry(block.block.clone()).or_insert(next_index);
+            indices[x + z * width + block.y as usize * width * length] = index;
+        }
+    }
+
+    let mut block_data = Vec::with_capacity(indices.len());
+    for index in indices {
+        write_varint(&mut block_data, index);
+    }
+
+    let schematic = SpongeSchematic {
+        version: 2,
+        data_version,
+        width: width as i16,
+        height: height as i16,
+        length: length as i16,
+        offset: IntArray::new(vec![0, 0, 0]),
+        palette_max: palette.len() as i32,
+        palette,
+        block_data: ByteArray::new(block_data),
+    };
+    let file_writer = File::create(file)?;
+    let encoder = GzEncoder::new(file_writer, Compression::default());
+    fastnbt::to_writer_with_opts(encoder, &schematic, SerOpts::new().root_name("Schematic"))?;
+    Ok(())
+}
+
+/// Block data is stored as variable length integers
+fn write_varint(buffer: &mut Vec<i8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            buffer.push(value as i8);
+            return;
+        }
+        buffer.push(((value & 0x7f) | 0x80) as i8);
+        value >>= 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schematic::{block_state, staircase};
+
+    #[test]
+    fn test_staircase_heights() {
+        // Light, normal and dark grass pixels in the first column
+        let mut colors = vec![5u8; 128 * 128];
+        colors[0] = 6;
+        colors[128] = 5;
+        colors[256] = 4;
+        colors[384] = 4;
+        let rows = staircase(&colors).unwrap();
+        let heights: Vec<i32> = rows.iter().take(5).map(|row| row[0].y).collect();
+        assert_eq!(heights, vec![1, 2, 2, 1, 0]);
+        assert_eq!(rows[1][0].block, "minecraft:grass_block");
+        assert_eq!(
+            block_state(7).unwrap(),
+            "minecraft:oak_leaves[persistent=true]"
+        );
+
+        // Water is not a block
+        colors[0] = 12 * 4 + 1;
+        assert!(staircase(&colors).is_err());
+    }
+
+    #[test]
+    fn test_blocks_stay_in_place() {
+        let unstable = [
+            "water",
+            "lava",
+            "fire",
+            "sand",
+            "red_sand",
+            "gravel",
+            "anvil",
+            "scaffolding",
+            "cobweb",
+            "glow_lichen",
+            "snow",
+            "powder_snow",
+            "pointed_dripstone",
+            "kelp",
+            "tnt",
+        ];
+        for base_color in 0..64 {
+            if let Some(block) = block_state(base_color) {
+                let name = block.trim_start_matches("minecraft:");
+                assert!(!unstable.contains(&name), "{block}");
+                assert!(!name.ends_with("concrete_powder"), "{block}");
+            }
+        }
+        assert_eq!(block_state(12), None);
+    }
+}
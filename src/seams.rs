@@ -0,0 +1,167 @@
+//! Finding seams in stitched images
+//!
+//! Maps explored at different times show the world as it was then, so where an old map
+//! meets a new one, the stitched image can have roads ending abruptly or forests cut in
+//! half. A seam is a boundary in the [Canvas] between the pixels of two maps with very
+//! different modification times. Re-exploring the older map in the game fixes it.
+
+use crate::canvas::{Canvas, CHUNK_SIZE};
+use crate::stitch::Area;
+use image::{Rgba, RgbaImage};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Color of the seams in the overlay
+pub const SEAM_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Boundary between the pixels of two maps
+#[derive(Clone, Debug, PartialEq)]
+pub struct Seam {
+    /// Map file that was modified earlier
+    pub older: PathBuf,
+
+    /// Map file that was modified later
+    pub newer: PathBuf,
+
+    /// Time between the modifications of the maps
+    pub age_difference: Duration,
+
+    /// Canvas pixels of the older map on the boundary
+    pub pixels: Vec<(u32, u32)>,
+
+    /// World area containing the seam
+    pub area: Area,
+}
+
+/// Finds the seams between the maps painted on the *canvas* that were modified at least
+/// *min_difference* apart
+///
+/// The modification times are asked from *modified*, and maps without a time have no seams.
+/// The seams with the largest age difference come first.
+pub fn find_seams<F>(canvas: &Canvas, modified: F, min_difference: Duration) -> Vec<Seam>
+where
+    F: Fn(&Path) -> Option<SystemTime>,
+{
+    let times: Vec<Option<SystemTime>> =
+        canvas.sources().iter().map(|file| modified(file)).collect();
+    // Seam pixels for each pair of sources, the older source first
+    let mut pairs: BTreeMap<(usize, usize), Vec<(u32, u32)>> = BTreeMap::new();
+    for (left, top) in canvas.chunks() {
+        let right = (left + CHUNK_SIZE).min(canvas.width());
+        let bottom = (top + CHUNK_SIZE).min(canvas.height());
+        for y in top..bottom {
+            for x in left..right {
+                let Some(source) = canvas.source_index(x, y) else {
+                    continue;
+                };
+                for (other_x, other_y) in [(x + 1, y), (x, y + 1)] {
+                    let Some(other) = canvas.source_index(other_x, other_y) else {
+                        continue;
+                    };
+                    let (Some(time), Some(other_time)) = (times[source], times[other]) else {
+                        continue;
+                    };
+                    if source == other || canvas.sources()[source] == canvas.sources()[other] {
+                        continue;
+                    }
+                    let pixel = match time <= other_time {
+                        true => ((source, other), (x, y)),
+                        false => ((other, source), (other_x, other_y)),
+                    };
+                    pairs.entry(pixel.0).or_default().push(pixel.1);
+                }
+            }
+        }
+    }
+
+    let transform = canvas.transform();
+    let mut seams: Vec<Seam> = pairs
+        .into_iter()
+        .filter_map(|((older, newer), mut pixels)| {
+            let (Some(older_time), Some(newer_time)) = (times[older], times[newer]) else {
+                return None;
+            };
+            let age_difference = newer_time.duration_since(older_time).unwrap_or_default();
+            if age_difference < min_difference {
+                return None;
+            }
+            pixels.sort_unstable();
+            pixels.dedup();
+            let area = pixels
+                .iter()
+                .map(|&(x, y)| transform.pixel_area(x as i32, y as i32))
+                .reduce(|area, pixel| area.union(&pixel))?;
+            Some(Seam {
+                older: canvas.sources()[older].clone(),
+                newer: canvas.sources()[newer].clone(),
+                age_difference,
+                pixels,
+                area,
+            })
+        })
+        .collect();
+    seams.sort_by(|a, b| {
+        b.age_difference
+            .cmp(&a.age_difference)
+            .then(b.pixels.len().cmp(&a.pixels.len()))
+    });
+    seams
+}
+
+/// Transparent image of the size of the *canvas* with the pixels of the *seams* marked
+pub fn draw_seams(canvas: &Canvas, seams: &[Seam]) -> RgbaImage {
+    let mut image = RgbaImage::new(canvas.width(), canvas.height());
+    for &(x, y) in seams.iter().flat_map(|seam| &seam.pixels) {
+        image.put_pixel(x, y, SEAM_COLOR);
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::seams::{draw_seams, find_seams, SEAM_COLOR};
+    use crate::stitch::Area;
+    use image::{Rgba, RgbaImage};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_find_seams() {
+        let area = Area {
+            left: 0,
+            top: 0,
+            right: 11,
+            bottom: 3,
+        };
+        let mut canvas = Canvas::new(&area);
+        let map = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        canvas.paint(&map, 0, 0, Path::new("old.dat"));
+        canvas.paint(&map, 4, 0, Path::new("new.dat"));
+        canvas.paint(&map, 8, 0, Path::new("newer.dat"));
+        let day = Duration::from_secs(24 * 60 * 60);
+        let modified = |file: &Path| {
+            let days = match file.to_str()? {
+                "old.dat" => 0,
+                "new.dat" => 100,
+                _ => 101,
+            };
+            Some(SystemTime::UNIX_EPOCH + day * days)
+        };
+
+        let seams = find_seams(&canvas, modified, day * 7);
+        assert_eq!(seams.len(), 1);
+        let seam = &seams[0];
+        assert_eq!(seam.older, Path::new("old.dat"));
+        assert_eq!(seam.newer, Path::new("new.dat"));
+        assert_eq!(seam.age_difference, day * 100);
+        assert_eq!(seam.pixels, [(3, 0), (3, 1), (3, 2), (3, 3)]);
+        assert_eq!((seam.area.left, seam.area.right), (3, 3));
+        assert_eq!(find_seams(&canvas, modified, Duration::ZERO).len(), 2);
+
+        let overlay = draw_seams(&canvas, &seams);
+        assert_eq!(*overlay.get_pixel(3, 2), SEAM_COLOR);
+        assert_eq!(overlay.get_pixel(4, 2)[3], 0);
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::{anyhow, Result};
+use clap::Args;
+use image::{ImageOutputFormat, RgbaImage};
+use log::{error, info, warn};
+use minecraft_map_tool::handle::MapHandle;
+use minecraft_map_tool::palette::{generate_palette, Palette, BASE_COLORS_2699};
+use minecraft_map_tool::source::{map_id, MapSource};
+use minecraft_map_tool::stats::MapStats;
+use minecraft_map_tool::stitch::{filter_maps, stitch_maps};
+use minecraft_map_tool::{read_maps, MapItem, SortingOrder};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// The directory or world backup archive (.zip, .tar.gz) from which map files are searched for.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    path: PathBuf,
+
+    /// Address and port for the server
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    address: String,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+}
+
+/// Map information for the JSON listing
+#[derive(JsonSchema, Serialize)]
+pub(crate) struct MapInfo {
+    /// Map id from the file name, null if the file is not named map_<#>.dat
+    id: Option<u32>,
+
+    /// Path to the map file
+    file: PathBuf,
+
+    /// How zoomed out the map is, from 0 to 4
+    scale: i8,
+
+    /// Pretty dimension name, like Overworld
+    dimension: String,
+
+    /// True if the map is locked in a cartography table
+    locked: bool,
+
+    /// Center of the map by X
+    x_center: i32,
+
+    /// Center of the map by Z
+    z_center: i32,
+
+    /// X coordinate of the left edge
+    left: i32,
+
+    /// Z coordinate of the top edge
+    top: i32,
+
+    /// X coordinate of the right edge
+    right: i32,
+
+    /// Z coordinate of the bottom edge
+    bottom: i32,
+
+    /// Number of banner markers
+    banners: usize,
+
+    /// Number of frame markers
+    frames: usize,
+
+    /// Data version of the Minecraft version that saved the map
+    data_version: i32,
+}
+
+impl From<&MapHandle> for MapInfo {
+    fn from(handle: &MapHandle) -> Self {
+        let metadata = handle.metadata();
+        let area = metadata.area();
+        MapInfo {
+            id: map_id(handle.path()),
+            file: handle.path().to_path_buf(),
+            scale: metadata.scale,
+            dimension: metadata.pretty_dimension(),
+            locked: metadata.locked != 0,
+            x_center: metadata.x_center,
+            z_center: metadata.z_center,
+            left: area.left,
+            top: area.top,
+            right: area.right,
+            bottom: area.bottom,
+            banners: metadata.banner_count,
+            frames: metadata.frame_count,
+            data_version: metadata.data_version,
+        }
+    }
+}
+
+/// Most images kept in the cache, the least recently used are dropped first
+const CACHE_SIZE: usize = 256;
+
+/// Rendered images are kept until the map files change
+///
+/// Each image is stored with a fingerprint of the map files it was drawn from, so adding,
+/// changing or removing any of them renders the image again.
+struct ImageCache {
+    images: HashMap<String, (u64, Vec<u8>)>,
+    order: VecDeque<String>,
+}
+
+impl ImageCache {
+    fn get_or_render<F>(&mut self, key: &str, fingerprint: u64, render: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce() -> Result<RgbaImage>,
+    {
+        self.order.retain(|used| used != key);
+        self.order.push_back(key.to_string());
+        if let Some((cached_fingerprint, png)) = self.images.get(key) {
+            if *cached_fingerprint == fingerprint {
+                return Ok(png.clone());
+            }
+        }
+        let mut png = Vec::new();
+        render()?.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+        self.images
+            .insert(key.to_string(), (fingerprint, png.clone()));
+        while self.order.len() > CACHE_SIZE {
+            if let Some(oldest) = self.order.pop_front() {
+                self.images.remove(&oldest);
+            }
+        }
+        Ok(png)
+    }
+}
+
+/// Fingerprint of the map *files* and their modification times in the *source*
+fn fingerprint<'a, I>(source: &dyn MapSource, files: I) -> u64
+where
+    I: IntoIterator<Item = &'a PathBuf>,
+{
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        file.hash(&mut hasher);
+        source.modified(file).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+struct MapServer {
+    path: PathBuf,
+    recursive: bool,
+    palette: Palette,
+    cache: ImageCache,
+}
+
+pub fn run(args: &ServeArgs) -> ExitCode {
+    let server = match Server::http(&args.address) {
+        Ok(server) => server,
+        Err(err) => {
+            error!("Could not start server: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    info!("Serving maps at: http://{}/maps", args.address);
+
+    let mut map_server = MapServer {
+        path: args.path.clone(),
+        recursive: args.recursive,
+        palette: generate_palette(&BASE_COLORS_2699),
+        cache: ImageCache {
+            images: HashMap::new(),
+            order: VecDeque::new(),
+        },
+    };
+    for request in server.incoming_requests() {
+        map_server.handle(request);
+    }
+    ExitCode::SUCCESS
+}
+
+impl MapServer {
+    fn handle(&mut self, request: Request) {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let result = if request.method() != &Method::Get {
+            Err((405, "Only GET requests are supported".to_string()))
+        } else if path == "/maps" {
+            self.maps_json().map(|json| ("application/json", json))
+        } else if path == "/metrics" {
+            self.metrics()
+                .map(|text| ("text/plain; version=0.0.4", text))
+        } else if path == "/stitched.png" {
+            self.stitched_png(query).map(|png| ("image/png", png))
+        } else if let Some(id) = path
+            .strip_prefix("/map/")
+            .and_then(|name| name.strip_suffix(".png"))
+        {
+            self.map_png(id).map(|png| ("image/png", png))
+        } else {
+            Err((404, "Not found".to_string()))
+        };
+
+        let response = match result {
+            Ok((content_type, body)) => {
+                let header = Header::from_bytes("Content-Type", content_type)
+                    .expect("valid content type header");
+                Response::from_data(body).with_header(header)
+            }
+            Err((status, message)) => Response::from_string(message).with_status_code(status),
+        };
+        if let Err(err) = request.respond(response) {
+            error!("Could not send response for {url}: {err}");
+        }
+    }
+
+    fn maps_json(&self) -> Result<Vec<u8>, (u16, String)> {
+        let maps = read_maps(&self.path, &Some(SortingOrder::Name), self.recursive)
+            .map_err(internal_error)?;
+        let infos: Vec<MapInfo> = maps
+            .handles()
+            .filter_map(|(file, handle)| match handle {
+                Ok(handle) => Some(MapInfo::from(&handle)),
+                Err(err) => {
+                    warn!("Could not read {}: {err}", file.display());
+                    None
+                }
+            })
+            .collect();
+        serde_json::to_vec_pretty(&infos).map_err(internal_error)
+    }
+
+    fn metrics(&self) -> Result<Vec<u8>, (u16, String)> {
+        let maps = read_maps(&self.path, &None, self.recursive).map_err(internal_error)?;
+        Ok(MapStats::collect(maps).to_prometheus().into_bytes())
+    }
+
+    fn map_png(&mut self, id: &str) -> Result<Vec<u8>, (u16, String)> {
+        let id = id
+            .parse::<u32>()
+            .map_err(|_| (400, format!("Invalid map id: {id}")))?;
+        let maps = read_maps(&self.path, &None, self.recursive).map_err(internal_error)?;
+        let source = maps.source();
+        let file = maps
+            .paths()
+            .find(|file| map_id(file) == Some(id))
+            .cloned()
+            .ok_or_else(|| (404, format!("Map {id} not found")))?;
+        let fingerprint = fingerprint(source.as_ref(), [&file]);
+        let palette = &self.palette;
+        self.cache
+            .get_or_render(&format!("map_{id}"), fingerprint, || {
+                let map_item =
+                    MapItem::read_from_reader(source.open(&file).map_err(to_anyhow)?, &file)
+                        .map_err(to_anyhow)?;
+                map_item.make_image(palette).map_err(to_anyhow)
+            })
+            .map_err(internal_error)
+    }
+
+    fn stitched_png(&mut self, query: &str) -> Result<Vec<u8>, (u16, String)> {
+        let mut dimension = "overworld".to_string();
+        let mut zoom = 0i8;
+        for (key, value) in query
+            .split('&')
+            .filter_map(|parameter| parameter.split_once('='))
+        {
+            match key {
+                "dimension" => dimension = decode_query_value(value),
+                "zoom" => {
+                    zoom = value
+                        .parse()
+                        .map_err(|_| (400, format!("Invalid zoom: {value}")))?
+                }
+                _ => return Err((400, format!("Unknown parameter: {key}"))),
+            }
+        }
+        if zoom != 0 {
+            return Err((400, "Only zoom step 0 is currently supported".to_string()));
+        }
+
+        let maps = read_maps(&self.path, &Some(SortingOrder::Time), self.recursive)
+            .map_err(internal_error)?;
+        let source = maps.source();
+        let fingerprint = fingerprint(source.as_ref(), maps.paths());
+        let (maps, area) =
+            filter_maps(maps, zoom, Some(&dimension)).map_err(|err| (404, err.to_string()))?;
+        let palette = &self.palette;
+        self.cache
+            .get_or_render(&format!("stitched_{dimension}_{zoom}"), fingerprint, || {
+                stitch_maps(maps, &area, palette, || {}).map_err(to_anyhow)
+            })
+            .map_err(internal_error)
+    }
+}
+
+/// Dimension names can be given like `the_nether` or `the%20nether`
+fn decode_query_value(value: &str) -> String {
+    let mut decoded = Vec::new();
+    let mut bytes = value.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'%' => {
+                let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+                let hex = String::from_utf8_lossy(&hex).to_string();
+                decoded.push(u8::from_str_radix(&hex, 16).unwrap_or(b'?'));
+            }
+            b'+' | b'_' => decoded.push(b' '),
+            _ => decoded.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn to_anyhow(err: minecraft_map_tool::error::Error) -> anyhow::Error {
+    anyhow!("{err}")
+}
+
+fn internal_error<E: std::fmt::Display>(err: E) -> (u16, String) {
+    (500, err.to_string())
+}
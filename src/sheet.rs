@@ -0,0 +1,127 @@
+//! Contact sheets of maps
+//!
+//! A contact sheet shows many maps side by side in a grid, each with a caption of its id and
+//! center, so a whole collection can be looked through at once. Unlike stitching, the maps
+//! are not placed by their location in the world.
+
+use crate::error::{Error, Result};
+use crate::font::{draw_text, GLYPH_HEIGHT};
+use crate::palette::Palette;
+use crate::source::map_id;
+use crate::stitch::paint_image;
+use crate::MapItem;
+use image::imageops::{resize, FilterType};
+use image::{Rgba, RgbaImage};
+
+/// Space around the maps and the captions in pixels
+const PADDING: u32 = 4;
+
+/// Color of the sheet behind the cells
+const BACKGROUND: Rgba<u8> = Rgba([43, 43, 43, 255]);
+
+/// Color of the unexplored pixels, so the map bounds can be seen
+const MAP_BACKGROUND: Rgba<u8> = Rgba([64, 64, 64, 255]);
+
+/// Color of the captions
+const TEXT_COLOR: Rgba<u8> = Rgba([238, 238, 238, 255]);
+
+/// Draws the *maps* into one image, *columns* maps per row, with captions under them
+///
+/// Each map is drawn *size* pixels wide. The first caption line is the map id, or the file
+/// name for files not named map_<#>.dat, and the second line is the center of the map.
+/// Maps are placed in the order they are given.
+pub fn make_contact_sheet(
+    maps: &[MapItem],
+    palette: &Palette,
+    size: u32,
+    columns: u32,
+) -> Result<RgbaImage> {
+    if size == 0 || columns == 0 {
+        return Err(Error::map_item_error(
+            "Map size and column count must be positive",
+        ));
+    }
+    let text_scale = (size / 64).max(1);
+    let line_height = (GLYPH_HEIGHT + 1) * text_scale;
+    let cell_width = size + 2 * PADDING;
+    let cell_height = size + 2 * PADDING + 2 * line_height;
+    let rows = (maps.len() as u32).div_ceil(columns);
+    let mut sheet = RgbaImage::from_pixel(
+        cell_width * columns.min(maps.len() as u32),
+        cell_height * rows,
+        BACKGROUND,
+    );
+    let map_background = RgbaImage::from_pixel(size, size, MAP_BACKGROUND);
+    // The same map image is reused for all maps
+    let mut map_image = RgbaImage::new(128, 128);
+    for (position, map_item) in maps.iter().enumerate() {
+        map_item.data.render_into(&mut map_image, palette)?;
+        let x = position as u32 % columns * cell_width + PADDING;
+        let y = position as u32 / columns * cell_height + PADDING;
+        paint_image(&map_background, &mut sheet, x as i32, y as i32);
+        if size == 128 {
+            paint_image(&map_image, &mut sheet, x as i32, y as i32);
+        } else {
+            let filter = if size > 128 {
+                FilterType::Nearest
+            } else {
+                FilterType::Triangle
+            };
+            paint_image(
+                &resize(&map_image, size, size, filter),
+                &mut sheet,
+                x as i32,
+                y as i32,
+            );
+        }
+
+        let name = match map_id(&map_item.file) {
+            Some(id) => format!("#{id}"),
+            None => map_item
+                .file
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        };
+        let center = format!("{}, {}", map_item.data.x_center, map_item.data.z_center);
+        let text_y = y + size + PADDING;
+        draw_text(&mut sheet, x, text_y, size, text_scale, &name, TEXT_COLOR);
+        draw_text(
+            &mut sheet,
+            x,
+            text_y + line_height,
+            size,
+            text_scale,
+            &center,
+            TEXT_COLOR,
+        );
+    }
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{generate_palette, BASE_COLORS_2699};
+    use crate::sheet::{make_contact_sheet, PADDING, TEXT_COLOR};
+    use crate::synthetic::synthetic_maps;
+    use std::path::Path;
+
+    #[test]
+    fn test_make_contact_sheet() {
+        let maps = synthetic_maps(Path::new("world"));
+        let palette = generate_palette(&BASE_COLORS_2699);
+        let sheet = make_contact_sheet(&maps[..5], &palette, 128, 4).unwrap();
+        // Cells are 136 pixels wide and 160 pixels high with two caption lines of 12 pixels
+        assert_eq!(sheet.dimensions(), (4 * 136, 2 * 160));
+
+        let image = maps[1].make_image(&palette).unwrap();
+        let (x, y) = (136 + PADDING, PADDING);
+        assert_eq!(sheet.get_pixel(x, y), image.get_pixel(0, 0));
+
+        // The caption of the first map starts with the # sign of "#0"
+        let caption_y = PADDING + 128 + PADDING;
+        assert_eq!(sheet.get_pixel(PADDING, caption_y), &TEXT_COLOR);
+        assert_ne!(sheet.get_pixel(PADDING + 2, caption_y), &TEXT_COLOR);
+    }
+}
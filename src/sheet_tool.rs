@@ -0,0 +1,101 @@
+use crate::changes::sink;
+use crate::image_tool::ColorArgs;
+use crate::input::read_input;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::sheet::make_contact_sheet;
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use minecraft_map_tool::SortSpec;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct SheetArgs {
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which map files are
+    /// searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path)
+    #[arg(required_unless_present = "files_from")]
+    paths: Vec<PathBuf>,
+
+    /// Read the map files from a list with one path on each line, or from standard input with -
+    #[arg(long, value_name = "FILE", conflicts_with = "paths")]
+    files_from: Option<PathBuf>,
+
+    /// The contact sheet image file
+    #[arg(short, long, default_value = "sheet.png")]
+    output_file: PathBuf,
+
+    /// Width and height of each map in pixels
+    #[arg(long, default_value_t = 128, value_parser = clap::value_parser!(u32).range(16..=512))]
+    size: u32,
+
+    /// Maps in each row of the image [default: about as many as rows]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    columns: Option<u32>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Only use the maps with these ids, for example 10..200,500,731. Ranges include both ends
+    #[arg(long, value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+
+    /// Order of the maps. Keys are name, time, id, data-version and scale.
+    /// Combine keys with commas and reverse them with :desc, for example scale,time:desc
+    #[arg(short, long, default_value = "id")]
+    sort: Option<SortSpec>,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+}
+
+pub fn run(args: &SheetArgs) -> ExitCode {
+    let mut maps = match read_input(
+        &args.paths,
+        args.files_from.as_deref(),
+        &args.sort,
+        args.recursive,
+    ) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not get maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(ids) = &args.ids {
+        maps.retain_ids(ids);
+    }
+    let mut map_items = Vec::new();
+    while let Some((path, map_item)) = maps.next_with_path() {
+        match map_item {
+            Ok(map_item) => map_items.push(map_item),
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+    if map_items.is_empty() {
+        error!("No map files found");
+        return ExitCode::FAILURE;
+    }
+
+    let columns = args
+        .columns
+        .unwrap_or_else(|| (map_items.len() as f64).sqrt().ceil() as u32);
+    let sheet = match make_contact_sheet(&map_items, &args.colors.palette(), args.size, columns) {
+        Ok(sheet) => sheet,
+        Err(err) => {
+            error!("Could not make the contact sheet: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = sink().write_with(&args.output_file, |file| sheet.save(file)) {
+        error!("Could not write {}: {err}", args.output_file.display());
+        return ExitCode::FAILURE;
+    }
+    info!(
+        "{} maps written to {}",
+        map_items.len(),
+        args.output_file.display()
+    );
+    ExitCode::SUCCESS
+}
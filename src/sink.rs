@@ -0,0 +1,185 @@
+//! Output sink for the changes to files
+//!
+//! Files written, moved or deleted by the library and the commands go through a [Sink].
+//! A sink made for a dry run changes nothing on the disk. It records the changes instead,
+//! so they can be listed with [Sink::changes], and logs each of them. Nothing is printed to
+//! the standard output, which can be carrying the actual output of a command. Directories
+//! are not created in a dry run, and they are not recorded either.
+
+use log::info;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Change to a file that was skipped in a dry run
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// The file is written
+    Write(PathBuf),
+
+    /// The first file is copied to the second
+    Copy(PathBuf, PathBuf),
+
+    /// The first file is moved to the second
+    Move(PathBuf, PathBuf),
+
+    /// The file is deleted
+    Delete(PathBuf),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Write(file) => write!(f, "write {}", file.display()),
+            Change::Copy(from, to) => write!(f, "copy {} -> {}", from.display(), to.display()),
+            Change::Move(from, to) => write!(f, "move {} -> {}", from.display(), to.display()),
+            Change::Delete(file) => write!(f, "delete {}", file.display()),
+        }
+    }
+}
+
+/// Where the changes to files go
+///
+/// The default sink changes the files on the disk.
+#[derive(Debug, Default)]
+pub struct Sink {
+    dry_run: bool,
+    changes: Mutex<Vec<Change>>,
+}
+
+impl Sink {
+    /// Sink changing the files on the disk, or only recording the changes if *dry_run* is set
+    pub fn new(dry_run: bool) -> Sink {
+        Sink {
+            dry_run,
+            ..Sink::default()
+        }
+    }
+
+    /// Tests if the changes are only recorded
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Changes recorded in a dry run, in the order they were made
+    pub fn changes(&self) -> Vec<Change> {
+        self.changes
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Logs and records the *change*
+    fn record(&self, change: Change) {
+        info!("{change}");
+        self.changes
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(change);
+    }
+
+    /// Writes the *file* with the *write* function, or records it in a dry run
+    pub fn write_with<P, E, F>(&self, file: P, write: F) -> Result<(), E>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Path) -> Result<(), E>,
+    {
+        let file = file.as_ref();
+        if self.dry_run {
+            self.record(Change::Write(file.to_path_buf()));
+            return Ok(());
+        }
+        write(file)
+    }
+
+    /// Writes the *contents* to the *file*
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, file: P, contents: C) -> io::Result<()> {
+        self.write_with(file, |file| fs::write(file, contents))
+    }
+
+    /// Creates the *file* for writing
+    ///
+    /// In a dry run, the returned writer discards everything written to it.
+    pub fn create<P: AsRef<Path>>(&self, file: P) -> io::Result<Box<dyn Write>> {
+        let file = file.as_ref();
+        if self.dry_run {
+            self.record(Change::Write(file.to_path_buf()));
+            return Ok(Box::new(io::sink()));
+        }
+        Ok(Box::new(File::create(file)?))
+    }
+
+    /// Copies the file *from* to the file *to*
+    pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> io::Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if self.dry_run {
+            self.record(Change::Copy(from.to_path_buf(), to.to_path_buf()));
+            return Ok(());
+        }
+        fs::copy(from, to).map(|_| ())
+    }
+
+    /// Renames the file *from* to *to*
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> io::Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if self.dry_run {
+            self.record(Change::Move(from.to_path_buf(), to.to_path_buf()));
+            return Ok(());
+        }
+        fs::rename(from, to)
+    }
+
+    /// Deletes the *file*
+    pub fn remove_file<P: AsRef<Path>>(&self, file: P) -> io::Result<()> {
+        let file = file.as_ref();
+        if self.dry_run {
+            self.record(Change::Delete(file.to_path_buf()));
+            return Ok(());
+        }
+        fs::remove_file(file)
+    }
+
+    /// Creates the *directory* and its missing parents, does nothing in a dry run
+    pub fn create_dir_all<P: AsRef<Path>>(&self, directory: P) -> io::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        fs::create_dir_all(directory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sink::{Change, Sink};
+
+    #[test]
+    fn test_dry_run() {
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("map_0.dat");
+        let moved = directory.path().join("map_1.dat");
+
+        let sink = Sink::new(true);
+        sink.write(&file, "map").unwrap();
+        sink.rename(&file, &moved).unwrap();
+        assert!(!file.exists());
+        assert_eq!(
+            sink.changes(),
+            [
+                Change::Write(file.clone()),
+                Change::Move(file.clone(), moved.clone())
+            ]
+        );
+        assert_eq!(
+            sink.changes()[1].to_string(),
+            format!("move {} -> {}", file.display(), moved.display())
+        );
+
+        let sink = Sink::default();
+        sink.write(&file, "map").unwrap();
+        sink.rename(&file, &moved).unwrap();
+        assert!(moved.is_file());
+        assert!(sink.changes().is_empty());
+    }
+}
@@ -0,0 +1,322 @@
+//! History of map files
+//!
+//! Snapshots are stored in a content-addressed store. Each map file is stored once in the
+//! `objects` directory under the SHA-256 hash of its content, so unchanged maps do not take
+//! more space when new snapshots are made. The `snapshots` directory has one JSON manifest for
+//! each snapshot, listing the map files and their hashes.
+
+use crate::error::{Error, Result};
+use crate::manifest::hash_content;
+use crate::sink::Sink;
+use crate::source::open_source;
+use crate::MapItem;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// Snapshot manifest
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// Identifier of the snapshot, made from the creation time
+    pub id: String,
+
+    /// Creation time as seconds since the Unix epoch
+    pub created: u64,
+
+    /// The directory or archive from which the maps were copied
+    pub source: PathBuf,
+
+    /// Hashes of the map files, keyed by the path relative to the source
+    pub maps: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    /// Creation time
+    pub fn created_time(&self) -> SystemTime {
+        UNIX_EPOCH + std::time::Duration::from_secs(self.created)
+    }
+
+    /// Finds the map file and its hash with the *name*
+    ///
+    /// The *name* is either the full path relative to the source or just the file name.
+    pub fn find(&self, name: &str) -> Option<(&String, &String)> {
+        self.maps.get_key_value(name).or_else(|| {
+            self.maps
+                .iter()
+                .find(|(path, _)| Path::new(path).file_name().unwrap_or_default() == name)
+        })
+    }
+
+    /// Checks that the paths stay inside the source and the hashes are SHA-256 hashes
+    ///
+    /// Manifests are read from the disk, so anything in them is checked before it is used
+    /// in a path.
+    fn validate(&self) -> Result<()> {
+        for (path, hash) in &self.maps {
+            let path = Path::new(path);
+            if path.as_os_str().is_empty()
+                || !path
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(Error::map_item_error(
+                    "Snapshot has a map file outside the source",
+                ));
+            }
+            if !is_hash(hash) {
+                return Err(Error::map_item_error("Snapshot has an invalid hash"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tests if the *hash* is a SHA-256 hash in lowercase hexadecimal, like [hash_content] makes
+fn is_hash(hash: &str) -> bool {
+    hash.len() == 64
+        && hash
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Store for the snapshots
+#[derive(Debug)]
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Opens the store in the *root* directory, which is created when the first snapshot is made
+    pub fn new(root: &Path) -> SnapshotStore {
+        SnapshotStore {
+            root: PathBuf::from(root),
+        }
+    }
+
+    /// Copies the map files from the *path* to a new snapshot
+    ///
+    /// The *path* can be anything supported by [open_source], and the store is written
+    /// through the *sink*. Returns the snapshot and the number of map files that were not in
+    /// the store before.
+    pub fn create(&self, path: &Path, recursive: bool, sink: &Sink) -> Result<(Snapshot, usize)> {
+        let source = open_source(path)?;
+        let mut maps = BTreeMap::new();
+        let mut new_objects = 0;
+        for file in source.find_map_files(recursive)? {
+            let mut content = Vec::new();
+            source.open(&file)?.read_to_end(&mut content)?;
+            let hash = hash_content(&content);
+            let object = self.object_path(&hash)?;
+            if !object.exists() {
+                sink.create_dir_all(object.parent().expect("objects have a parent"))?;
+                sink.write(&object, &content)?;
+                new_objects += 1;
+            }
+            let name = file.strip_prefix(path).unwrap_or(&file);
+            maps.insert(name.to_string_lossy().replace('\\', "/"), hash);
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let base_id = now
+            .format(format_description!(
+                "[year][month][day]-[hour][minute][second]"
+            ))
+            .map_err(|_| Error::map_item_error("Could not format snapshot time"))?;
+        let snapshots_dir = self.root.join("snapshots");
+        sink.create_dir_all(&snapshots_dir)?;
+        let mut id = base_id.clone();
+        let mut counter = 1;
+        while snapshots_dir.join(format!("{id}.json")).exists() {
+            counter += 1;
+            id = format!("{base_id}-{counter}");
+        }
+
+        let snapshot = Snapshot {
+            id,
+            created: now.unix_timestamp() as u64,
+            source: path.canonicalize().unwrap_or_else(|_| PathBuf::from(path)),
+            maps,
+        };
+        let manifest = serde_json::to_string_pretty(&snapshot).map_err(std::io::Error::from)?;
+        sink.write(
+            snapshots_dir.join(format!("{}.json", snapshot.id)),
+            manifest,
+        )?;
+        Ok((snapshot, new_objects))
+    }
+
+    /// All snapshots from oldest to newest
+    pub fn snapshots(&self) -> Result<Vec<Snapshot>> {
+        let snapshots_dir = self.root.join("snapshots");
+        let mut snapshots = Vec::new();
+        if !snapshots_dir.is_dir() {
+            return Ok(snapshots);
+        }
+        for entry in snapshots_dir.read_dir()?.flatten() {
+            let path = entry.path();
+            if path.extension().unwrap_or_default() != "json" {
+                continue;
+            }
+            let manifest = fs::read(&path)?;
+            let snapshot: Snapshot =
+                serde_json::from_slice(&manifest).map_err(std::io::Error::from)?;
+            snapshot.validate().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {err}", path.display()),
+                )
+            })?;
+            snapshots.push(snapshot);
+        }
+        snapshots.sort_by(|a, b| {
+            a.created
+                .cmp(&b.created)
+                .then_with(|| natord::compare(&a.id, &b.id))
+        });
+        Ok(snapshots)
+    }
+
+    /// Finds the snapshot with the *id*, or the newest snapshot if *id* is `latest`
+    pub fn snapshot(&self, id: &str) -> Result<Snapshot> {
+        let snapshots = self.snapshots()?;
+        let snapshot = if id == "latest" {
+            snapshots.into_iter().last()
+        } else {
+            snapshots.into_iter().find(|snapshot| snapshot.id == id)
+        };
+        snapshot.ok_or_else(|| Error::map_item_error("Snapshot not found"))
+    }
+
+    /// Reads the stored map file content for the *name* in the *snapshot*
+    ///
+    /// The content is checked against its hash, so a damaged store is noticed before the
+    /// content is used.
+    pub fn read_map_file(&self, snapshot: &Snapshot, name: &str) -> Result<Vec<u8>> {
+        let (_, hash) = snapshot
+            .find(name)
+            .ok_or_else(|| Error::map_item_error("Map file not found from the snapshot"))?;
+        let content = fs::read(self.object_path(hash)?)?;
+        if hash_content(&content) != *hash {
+            return Err(Error::map_item_error(
+                "Stored map file does not match its hash",
+            ));
+        }
+        Ok(content)
+    }
+
+    /// Reads the map item for the *name* in the *snapshot*
+    pub fn read_map(&self, snapshot: &Snapshot, name: &str) -> Result<MapItem> {
+        let (path, _) = snapshot
+            .find(name)
+            .ok_or_else(|| Error::map_item_error("Map file not found from the snapshot"))?;
+        let content = self.read_map_file(snapshot, name)?;
+        MapItem::read_from_reader(content.as_slice(), Path::new(path))
+    }
+
+    /// Writes the map file *name* from the *snapshot* to the *target* file through the *sink*
+    pub fn restore(
+        &self,
+        snapshot: &Snapshot,
+        name: &str,
+        target: &Path,
+        sink: &Sink,
+    ) -> Result<()> {
+        let content = self.read_map_file(snapshot, name)?;
+        Ok(sink.write(target, content)?)
+    }
+
+    /// Revisions of the map file *name*, from oldest to newest
+    ///
+    /// Only the snapshots where the map file has changed are returned.
+    pub fn history(&self, name: &str) -> Result<Vec<Snapshot>> {
+        let mut history: Vec<Snapshot> = Vec::new();
+        let mut previous_hash: Option<String> = None;
+        for snapshot in self.snapshots()? {
+            let hash = match snapshot.find(name) {
+                Some((_, hash)) => hash.clone(),
+                None => continue,
+            };
+            if previous_hash.as_ref() != Some(&hash) {
+                previous_hash = Some(hash);
+                history.push(snapshot);
+            }
+        }
+        Ok(history)
+    }
+
+    /// Path to the object with the *hash*, which must be a SHA-256 hash
+    fn object_path(&self, hash: &str) -> Result<PathBuf> {
+        if !is_hash(hash) {
+            return Err(Error::map_item_error("Invalid hash for a stored map file"));
+        }
+        Ok(self.root.join("objects").join(&hash[..2]).join(hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sink::Sink;
+    use crate::snapshot::SnapshotStore;
+    use crate::synthetic::write_synthetic_world;
+
+    #[test]
+    fn test_unchanged_maps_are_stored_once() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let store = SnapshotStore::new(&world.path().join("snapshots"));
+        let data = world.path().join("data");
+
+        let (first, new_objects) = store.create(&data, false, &Sink::default()).unwrap();
+        assert_eq!(first.maps.len(), 32);
+        assert_eq!(new_objects, 32);
+
+        std::fs::copy(data.join("map_1.dat"), data.join("map_0.dat")).unwrap();
+        let (second, new_objects) = store.create(&data, false, &Sink::default()).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(new_objects, 0);
+        assert_eq!(store.history("map_0.dat").unwrap().len(), 2);
+        assert_eq!(store.history("map_1.dat").unwrap().len(), 1);
+
+        let restored = world.path().join("restored.dat");
+        store
+            .restore(&first, "map_0.dat", &restored, &Sink::default())
+            .unwrap();
+        assert_ne!(
+            std::fs::read(&restored).unwrap(),
+            std::fs::read(data.join("map_0.dat")).unwrap()
+        );
+
+        // Damaged objects are not restored
+        let hash = &first.maps["map_0.dat"];
+        let object = world
+            .path()
+            .join("snapshots/objects")
+            .join(&hash[..2])
+            .join(hash);
+        std::fs::write(object, "damaged").unwrap();
+        assert!(store
+            .restore(&first, "map_0.dat", &restored, &Sink::default())
+            .is_err());
+
+        // Manifests pointing outside the store or the source are refused
+        let manifest = world
+            .path()
+            .join("snapshots/snapshots")
+            .join(format!("{}.json", first.id));
+        let mut tampered = first.clone();
+        tampered
+            .maps
+            .insert("map_0.dat".to_string(), "../../../outside".to_string());
+        std::fs::write(&manifest, serde_json::to_string(&tampered).unwrap()).unwrap();
+        assert!(store.snapshots().is_err());
+        let mut tampered = first.clone();
+        tampered.maps = [("../map_0.dat".to_string(), hash.clone())].into();
+        std::fs::write(&manifest, serde_json::to_string(&tampered).unwrap()).unwrap();
+        assert!(store.snapshot(&first.id).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::changes::sink;
+use crate::charset::preset;
+use clap::{Args, Subcommand};
+use comfy_table::{ContentArrangement, Table};
+use log::{error, info};
+use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
+use minecraft_map_tool::snapshot::{Snapshot, SnapshotStore};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+#[derive(Args, Debug)]
+pub struct SnapshotArgs {
+    /// Directory where the snapshots are stored
+    #[arg(short, long, global = true, default_value = "map_snapshots")]
+    store: PathBuf,
+
+    #[command(subcommand)]
+    command: SnapshotCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum SnapshotCommands {
+    /// Copy the current map files to a new snapshot
+    Create {
+        /// The directory or world backup archive (.zip, .tar.gz) from which map files are
+        /// searched for
+        path: PathBuf,
+
+        /// Search map files recursively in subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+    },
+
+    /// List snapshots, or the revisions of one map file
+    List {
+        /// Only show the snapshots where this map file has changed
+        #[arg(short, long)]
+        map: Option<String>,
+    },
+
+    /// Restore a map file from a snapshot
+    Restore {
+        /// Snapshot id, or "latest" for the newest snapshot
+        id: String,
+
+        /// Name of the map file, for example map_5.dat
+        map: String,
+
+        /// Write the map file here instead of its original location
+        #[arg(short, long)]
+        output_file: Option<PathBuf>,
+    },
+
+    /// Create an image of a map file in a snapshot
+    Render {
+        /// Snapshot id, or "latest" for the newest snapshot
+        id: String,
+
+        /// Name of the map file, for example map_5.dat
+        map: String,
+
+        /// Write the map image to the file. Standard file formats are supported.
+        output_file: PathBuf,
+    },
+}
+
+pub fn run(args: &SnapshotArgs) -> ExitCode {
+    let store = SnapshotStore::new(&args.store);
+    let result = match &args.command {
+        SnapshotCommands::Create { path, recursive } => create(&store, path, *recursive),
+        SnapshotCommands::List { map } => list(&store, map.as_deref()),
+        SnapshotCommands::Restore {
+            id,
+            map,
+            output_file,
+        } => restore(&store, id, map, output_file.as_ref()),
+        SnapshotCommands::Render {
+            id,
+            map,
+            output_file,
+        } => render(&store, id, map, output_file),
+    };
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn create(store: &SnapshotStore, path: &Path, recursive: bool) -> Result<(), String> {
+    let (snapshot, new_objects) = store
+        .create(path, recursive, sink())
+        .map_err(|err| format!("Could not create snapshot: {err}"))?;
+    info!(
+        "Snapshot {} created with {} maps, {} of them new or changed",
+        snapshot.id,
+        snapshot.maps.len(),
+        new_objects
+    );
+    Ok(())
+}
+
+fn list(store: &SnapshotStore, map: Option<&str>) -> Result<(), String> {
+    let snapshots = match map {
+        Some(map) => store.history(map),
+        None => store.snapshots(),
+    }
+    .map_err(|err| format!("Could not read snapshots: {err}"))?;
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Id", "Created", "Maps", "Source"]);
+    for snapshot in &snapshots {
+        table.add_row(vec![
+            snapshot.id.clone(),
+            format_created(snapshot),
+            snapshot.maps.len().to_string(),
+            snapshot.source.display().to_string(),
+        ]);
+    }
+    println!("{table}");
+    Ok(())
+}
+
+fn restore(
+    store: &SnapshotStore,
+    id: &str,
+    map: &str,
+    output_file: Option<&PathBuf>,
+) -> Result<(), String> {
+    let snapshot = store.snapshot(id).map_err(|err| format!("{err}: {id}"))?;
+    let target = match output_file {
+        Some(output_file) => output_file.clone(),
+        None if snapshot.source.is_dir() => match snapshot.find(map) {
+            Some((path, _)) => snapshot.source.join(path),
+            None => return Err(format!("Map file not found from the snapshot: {map}")),
+        },
+        None => {
+            return Err(String::from(
+                "The snapshot was not made from a directory, use --output-file",
+            ))
+        }
+    };
+    store
+        .restore(&snapshot, map, &target, sink())
+        .map_err(|err| format!("Could not restore map file: {err}"))?;
+    info!("Map file restored to: {target:?}");
+    Ok(())
+}
+
+fn render(store: &SnapshotStore, id: &str, map: &str, output_file: &Path) -> Result<(), String> {
+    let snapshot = store.snapshot(id).map_err(|err| format!("{err}: {id}"))?;
+    let map_item = store
+        .read_map(&snapshot, map)
+        .map_err(|err| format!("Could not read map item: {err}"))?;
+    let image = map_item
+        .make_image(&generate_palette(&BASE_COLORS_2699))
+        .map_err(|err| format!("Could not create image: {err}"))?;
+    sink()
+        .write_with(output_file, |file| image.save(file))
+        .map_err(|err| format!("Could not write image: {err}"))?;
+    info!("Image written to: {output_file:?}");
+    Ok(())
+}
+
+fn format_created(snapshot: &Snapshot) -> String {
+    OffsetDateTime::from(snapshot.created_time())
+        .format(format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second] UTC"
+        ))
+        .unwrap_or_default()
+}
@@ -0,0 +1,803 @@
+//! Sources for map files
+//!
+//! Map files can be read from a directory or from a world backup archive. Each source
+//! lists map files with paths relative to the source and opens them for reading. Archives,
+//! glob patterns and memory mapping need the `fs` feature.
+
+use crate::error::{Error, Result};
+use crate::limits::{read_options, ReadOptions};
+#[cfg(feature = "fs")]
+use flate2::read::GzDecoder;
+use log::warn;
+#[cfg(feature = "fs")]
+use memmap2::Mmap;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::Cursor;
+use std::io::Read;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+#[cfg(feature = "fs")]
+use std::time::{Duration, UNIX_EPOCH};
+
+/// A place where map files can be read from
+pub trait MapSource: Debug + Send + Sync {
+    /// Finds map files from the source
+    ///
+    /// When *recursive* is false, only the map files at the top level of the source are returned.
+    /// Archives are always searched completely because the map files are usually in the `data`
+    /// directory of the packed world.
+    fn find_map_files(&self, recursive: bool) -> Result<VecDeque<PathBuf>>;
+
+    /// Opens the map file for reading
+    ///
+    /// The reader returns the file content as it is stored, so it is still compressed.
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + '_>>;
+
+    /// Modification time of the map file if it is known
+    fn modified(&self, path: &Path) -> Option<SystemTime>;
+
+    /// Size of the stored map file in bytes if it is known
+    fn size(&self, _path: &Path) -> Option<u64> {
+        None
+    }
+}
+
+/// Opens a source for the given *path*
+///
+/// With the `fs` feature, files ending with `.zip`, `.tar.gz` or `.tgz` are opened as
+/// archives. With the `sftp` feature, paths like `sftp://user@host/path` are opened as remote
+/// directories. Other paths are handled as directories.
+pub fn open_source(path: &Path) -> Result<Box<dyn MapSource>> {
+    #[cfg(feature = "sftp")]
+    if let Some(url) = path.to_str().filter(|url| url.starts_with("sftp://")) {
+        return Ok(Box::new(SftpSource::connect(url)?));
+    }
+    #[cfg(not(feature = "sftp"))]
+    if path.to_str().is_some_and(|url| url.starts_with("sftp://")) {
+        return Err(Error::map_item_error("SFTP sources need the sftp feature"));
+    }
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    let is_zip = name.ends_with(".zip");
+    let is_tar_gz = name.ends_with(".tar.gz") || name.ends_with(".tgz");
+    if path.is_file() && (is_zip || is_tar_gz) {
+        #[cfg(feature = "fs")]
+        return match is_zip {
+            true => Ok(Box::new(ZipSource::open(path)?)),
+            false => Ok(Box::new(TarGzSource::open(path)?)),
+        };
+        #[cfg(not(feature = "fs"))]
+        return Err(Error::map_item_error("Archive sources need the fs feature"));
+    }
+    Ok(Box::new(DirectorySource::new(path)))
+}
+
+/// Opens the *file* for reading, memory mapped if the *options* ask for it
+///
+/// Without the `fs` feature the file is always read normally.
+pub fn open_file(file: &Path, options: &ReadOptions) -> Result<Box<dyn Read>> {
+    let handle = File::open(file)?;
+    // Empty files cannot be mapped on every platform
+    #[cfg(feature = "fs")]
+    if options.memory_mapping && handle.metadata()?.len() > 0 {
+        // SAFETY: Whoever turned memory_mapping on promises that the file is not changed or
+        // truncated while it is mapped, see ReadOptions::memory_mapping
+        let mapped = unsafe { Mmap::map(&handle)? };
+        return Ok(Box::new(Cursor::new(mapped)));
+    }
+    #[cfg(not(feature = "fs"))]
+    let _ = options;
+    Ok(Box::new(handle))
+}
+
+/// Reads a map file stored in an archive or on a server into memory
+///
+/// The stored file is still compressed, so a map file within the [ReadOptions::max_size]
+/// limit is smaller than that. Larger files are refused before they are read completely,
+/// so a crafted archive cannot fill the memory.
+#[cfg(feature = "fs")]
+fn read_stored<R: Read>(reader: R) -> Result<Vec<u8>> {
+    let max_size = read_options().max_size;
+    let mut content = Vec::new();
+    reader
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut content)?;
+    if content.len() as u64 > max_size {
+        return Err(Error::map_item_error(
+            "Map file is larger than the size limit",
+        ));
+    }
+    Ok(content)
+}
+
+/// Expands glob patterns in the *paths*
+///
+/// Paths that exist or do not contain any of the `*?[` characters are kept as they are, so
+/// patterns already expanded by the shell are not expanded again. Matches of a pattern are
+/// returned in the order given by [glob::glob].
+#[cfg(feature = "fs")]
+pub fn expand_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if path.exists() || !pattern.contains(['*', '?', '[']) {
+            expanded.push(path.clone());
+            continue;
+        }
+        let matches = glob::glob(&pattern).map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid glob pattern: {err}"),
+            )
+        })?;
+        let count = expanded.len();
+        expanded.extend(matches.flatten());
+        if expanded.len() == count {
+            warn!("No files match the pattern: {pattern}");
+        }
+    }
+    Ok(expanded)
+}
+
+/// Tests if the *path* looks like a map file (map_<#>.dat)
+pub fn is_map_file(path: &Path) -> bool {
+    path.extension().unwrap_or_default() == "dat"
+        && path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .starts_with("map_")
+}
+
+/// Parses the map id from the map file name (map_<#>.dat)
+pub fn map_id(path: &Path) -> Option<u32> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix("map_")?
+        .parse::<u32>()
+        .ok()
+}
+
+/// Selection of map ids, parsed with [parse_id_ranges]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdRanges {
+    ranges: Vec<RangeInclusive<u32>>,
+}
+
+impl IdRanges {
+    /// Tests if the *id* is selected
+    pub fn contains(&self, id: u32) -> bool {
+        self.ranges.iter().any(|range| range.contains(&id))
+    }
+}
+
+/// Parses a comma-separated list of map ids and ranges, for example `10..200,500,731`
+///
+/// Ranges include both ends, and either end can be left out (`..50`, `100..`).
+pub fn parse_id_ranges(text: &str) -> std::result::Result<IdRanges, String> {
+    let parse_id = |id: &str, default: u32| -> std::result::Result<u32, String> {
+        let id = id.trim();
+        if id.is_empty() {
+            return Ok(default);
+        }
+        id.parse::<u32>()
+            .map_err(|err| format!("Invalid map id {id:?}: {err}"))
+    };
+    let mut ranges = Vec::new();
+    for part in text.split(',') {
+        let range = match part.split_once("..") {
+            Some((start, end)) => parse_id(start, 0)?..=parse_id(end, u32::MAX)?,
+            None if part.trim().is_empty() => return Err(String::from("Empty map id")),
+            None => {
+                let id = parse_id(part, 0)?;
+                id..=id
+            }
+        };
+        if range.is_empty() {
+            return Err(format!("Empty map id range: {}", part.trim()));
+        }
+        ranges.push(range);
+    }
+    Ok(IdRanges { ranges })
+}
+
+/// Map files in a directory
+///
+/// The modification time and size of each map file are stored when the directory is searched,
+/// so sorting does not need more file system calls. This matters on network file systems.
+#[derive(Debug)]
+pub struct DirectorySource {
+    root: PathBuf,
+    metadata: Mutex<BTreeMap<PathBuf, (Option<SystemTime>, u64)>>,
+}
+
+impl DirectorySource {
+    pub fn new(root: &Path) -> DirectorySource {
+        DirectorySource {
+            root: PathBuf::from(root),
+            metadata: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> Option<(Option<SystemTime>, u64)> {
+        let mut cache = self.metadata.lock().expect("poisoned lock");
+        if let Some(metadata) = cache.get(path) {
+            return Some(*metadata);
+        }
+        let metadata = path.metadata().ok()?;
+        let metadata = (metadata.modified().ok(), metadata.len());
+        cache.insert(path.to_path_buf(), metadata);
+        Some(metadata)
+    }
+}
+
+impl MapSource for DirectorySource {
+    fn find_map_files(&self, recursive: bool) -> Result<VecDeque<PathBuf>> {
+        let mut cache = self.metadata.lock().expect("poisoned lock");
+        let mut directory_stack = VecDeque::new();
+        let mut map_files = VecDeque::new();
+        directory_stack.push_back(self.root.clone());
+        while let Some(dir) = directory_stack.pop_front() {
+            let read_dir = match dir.read_dir() {
+                Ok(read_dir) => read_dir,
+                Err(err) => {
+                    warn!("Could not read: {dir:?}, {err}");
+                    continue;
+                }
+            };
+            for dir_entry in read_dir.flatten() {
+                let path = dir_entry.path();
+                if path.is_symlink() {
+                    // We do not follow symlinks for now, could cause forever loop
+                    continue;
+                } else if path.is_file() && is_map_file(&path) {
+                    if let Ok(metadata) = dir_entry.metadata() {
+                        cache.insert(path.clone(), (metadata.modified().ok(), metadata.len()));
+                    }
+                    map_files.push_back(path);
+                } else if path.is_dir() && recursive {
+                    directory_stack.push_back(path);
+                }
+            }
+        }
+        Ok(map_files)
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + '_>> {
+        open_file(path, &read_options())
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.metadata(path)?.0
+    }
+
+    fn size(&self, path: &Path) -> Option<u64> {
+        Some(self.metadata(path)?.1)
+    }
+}
+
+/// Map files combined from several paths
+///
+/// Each path can be a map file or anything supported by [open_source]. Map files inside
+/// archives are listed under the archive path, so maps with the same name in different
+/// archives are kept apart. A map file found through several paths is listed once.
+#[derive(Debug)]
+pub struct MultiSource {
+    sources: Vec<Box<dyn MapSource>>,
+    files: BTreeMap<PathBuf, (usize, PathBuf)>,
+    order: VecDeque<PathBuf>,
+}
+
+impl MultiSource {
+    pub fn new() -> MultiSource {
+        MultiSource {
+            // Map files given directly are read through the first source
+            sources: vec![Box::new(DirectorySource::new(Path::new(".")))],
+            files: BTreeMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Adds the map files found from the *path*
+    pub fn add_path(&mut self, path: &Path, recursive: bool) -> Result<()> {
+        if path.is_file() && is_map_file(path) {
+            self.insert(path.to_path_buf(), 0, path.to_path_buf());
+            return Ok(());
+        }
+        let source = open_source(path)?;
+        let is_archive = path.is_file();
+        let index = self.sources.len();
+        for file in source.find_map_files(recursive)? {
+            let listed = if is_archive {
+                path.join(&file)
+            } else {
+                file.clone()
+            };
+            self.insert(listed, index, file);
+        }
+        self.sources.push(source);
+        Ok(())
+    }
+
+    fn insert(&mut self, listed: PathBuf, index: usize, path: PathBuf) {
+        if !self.files.contains_key(&listed) {
+            self.order.push_back(listed.clone());
+            self.files.insert(listed, (index, path));
+        }
+    }
+}
+
+impl Default for MultiSource {
+    fn default() -> Self {
+        MultiSource::new()
+    }
+}
+
+impl MapSource for MultiSource {
+    /// Returns the map files found when the paths were added
+    fn find_map_files(&self, _recursive: bool) -> Result<VecDeque<PathBuf>> {
+        Ok(self.order.clone())
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + '_>> {
+        match self.files.get(path) {
+            Some((index, path)) => self.sources[*index].open(path),
+            None => Err(Error::map_item_error("Map file not found from the sources")),
+        }
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        let (index, path) = self.files.get(path)?;
+        self.sources[*index].modified(path)
+    }
+
+    fn size(&self, path: &Path) -> Option<u64> {
+        let (index, path) = self.files.get(path)?;
+        self.sources[*index].size(path)
+    }
+}
+
+/// Map files inside a zip archive
+///
+/// The archive is kept open and the map files are read from it one at a time, so only the
+/// central directory is kept in memory.
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct ZipSource {
+    zip: Mutex<zip::ZipArchive<File>>,
+    entries: BTreeMap<PathBuf, ZipEntry>,
+}
+
+/// Map file inside a zip archive
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+struct ZipEntry {
+    index: usize,
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+#[cfg(feature = "fs")]
+impl ZipSource {
+    pub fn open(archive: &Path) -> Result<ZipSource> {
+        let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+        let mut entries = BTreeMap::new();
+        for index in 0..zip.len() {
+            let entry = zip.by_index_raw(index)?;
+            if !entry.is_file() {
+                continue;
+            }
+            let path = match entry.enclosed_name() {
+                Some(path) => path.to_path_buf(),
+                None => continue,
+            };
+            if is_map_file(&path) {
+                let modified = entry.last_modified().to_time().ok().map(SystemTime::from);
+                let size = entry.size();
+                entries.insert(
+                    path,
+                    ZipEntry {
+                        index,
+                        modified,
+                        size,
+                    },
+                );
+            }
+        }
+        Ok(ZipSource {
+            zip: Mutex::new(zip),
+            entries,
+        })
+    }
+}
+
+#[cfg(feature = "fs")]
+impl MapSource for ZipSource {
+    fn find_map_files(&self, _recursive: bool) -> Result<VecDeque<PathBuf>> {
+        Ok(self.entries.keys().cloned().collect())
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + '_>> {
+        let entry = self.entries.get(path).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Map file not in the archive")
+        })?;
+        let mut zip = self.zip.lock().unwrap_or_else(|err| err.into_inner());
+        let content = read_stored(zip.by_index(entry.index)?)?;
+        Ok(Box::new(Cursor::new(content)))
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.entries.get(path).and_then(|entry| entry.modified)
+    }
+
+    fn size(&self, path: &Path) -> Option<u64> {
+        self.entries.get(path).map(|entry| entry.size)
+    }
+}
+
+/// Map files inside a gzip-compressed tar archive
+///
+/// Tar archives cannot be read in random order, so the map files are read into memory
+/// while the archive is scanned. Other files are skipped without storing them, as are map
+/// files over the size limit.
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct TarGzSource {
+    files: BTreeMap<PathBuf, (Vec<u8>, Option<SystemTime>)>,
+}
+
+#[cfg(feature = "fs")]
+impl TarGzSource {
+    pub fn open(archive: &Path) -> Result<TarGzSource> {
+        let mut tar = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+        let mut files = BTreeMap::new();
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_path_buf();
+            if !is_map_file(&path) {
+                continue;
+            }
+            let modified = entry
+                .header()
+                .mtime()
+                .ok()
+                .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+            match read_stored(&mut entry) {
+                Ok(content) => {
+                    files.insert(path, (content, modified));
+                }
+                Err(err) => warn!("Skipping {}: {err}", path.display()),
+            }
+        }
+        Ok(TarGzSource { files })
+    }
+}
+
+#[cfg(feature = "fs")]
+impl MapSource for TarGzSource {
+    fn find_map_files(&self, _recursive: bool) -> Result<VecDeque<PathBuf>> {
+        Ok(self.files.keys().cloned().collect())
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + '_>> {
+        match self.files.get(path) {
+            Some((content, _)) => Ok(Box::new(content.as_slice())),
+            None => Err(Error::map_item_error("Map file not found from the archive")),
+        }
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.files.get(path).and_then(|(_, modified)| *modified)
+    }
+
+    fn size(&self, path: &Path) -> Option<u64> {
+        self.files
+            .get(path)
+            .map(|(content, _)| content.len() as u64)
+    }
+}
+
+/// Map files in a directory on a remote server
+///
+/// Authentication is tried with the password given in the URL, the SSH agent, and the
+/// default private keys in `~/.ssh`, in that order.
+#[cfg(feature = "sftp")]
+pub struct SftpSource {
+    url: SftpUrl,
+    sftp: std::sync::Mutex<ssh2::Sftp>,
+    root: PathBuf,
+    modified: std::sync::Mutex<BTreeMap<PathBuf, Option<SystemTime>>>,
+}
+
+#[cfg(feature = "sftp")]
+impl Debug for SftpSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SftpSource")
+            .field("url", &self.url.redacted())
+            .finish()
+    }
+}
+
+#[cfg(feature = "sftp")]
+impl SftpSource {
+    /// Connects to the server given in the *url* (`sftp://[user[:password]@]host[:port]/path`)
+    pub fn connect(url: &str) -> Result<SftpSource> {
+        let url = SftpUrl::parse(url)?;
+        let user = &url.user;
+        let mut session = ssh2::Session::new()?;
+        session.set_tcp_stream(std::net::TcpStream::connect((url.host.as_str(), url.port))?);
+        session.handshake()?;
+        if let Some(password) = &url.password {
+            session.userauth_password(user, password)?;
+        }
+        if !session.authenticated() {
+            let _ = session.userauth_agent(user);
+        }
+        if !session.authenticated() {
+            let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+            for key in ["id_ed25519", "id_ecdsa", "id_rsa"] {
+                let private_key = home.join(".ssh").join(key);
+                if private_key.is_file()
+                    && session
+                        .userauth_pubkey_file(user, None, &private_key, None)
+                        .is_ok()
+                {
+                    break;
+                }
+            }
+        }
+        if !session.authenticated() {
+            return Err(Error::map_item_error("SFTP authentication failed"));
+        }
+
+        Ok(SftpSource {
+            sftp: std::sync::Mutex::new(session.sftp()?),
+            root: url.root.clone(),
+            url,
+            modified: std::sync::Mutex::new(BTreeMap::new()),
+        })
+    }
+}
+
+/// Parts of an SFTP URL (`sftp://[user[:password]@]host[:port][/path]`)
+#[cfg(feature = "sftp")]
+#[derive(Clone, PartialEq, Eq)]
+struct SftpUrl {
+    user: String,
+    password: Option<String>,
+    host: String,
+    port: u16,
+    root: PathBuf,
+}
+
+#[cfg(feature = "sftp")]
+impl SftpUrl {
+    /// Splits the *url* into its parts
+    ///
+    /// The user defaults to the `USER` environment variable, the port to 22 and the path to
+    /// the directory the server starts in. IPv6 addresses are written in brackets.
+    fn parse(url: &str) -> Result<SftpUrl> {
+        let invalid_url = || Error::map_item_error("Invalid SFTP URL");
+        let rest = url.strip_prefix("sftp://").ok_or_else(invalid_url)?;
+        let (authority, root) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, "."),
+        };
+        // Passwords can have @ characters, host names cannot
+        let (user_info, host_port) = match authority.rsplit_once('@') {
+            Some((user_info, host_port)) => (Some(user_info), host_port),
+            None => (None, authority),
+        };
+        let (user, password) = match user_info {
+            Some(user_info) => match user_info.split_once(':') {
+                Some((user, password)) => (user.to_string(), Some(password.to_string())),
+                None => (user_info.to_string(), None),
+            },
+            None => (std::env::var("USER").unwrap_or_default(), None),
+        };
+        let (host, port) = match host_port.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, port) = bracketed.split_once(']').ok_or_else(invalid_url)?;
+                match port {
+                    "" => (host, None),
+                    port => (host, Some(port.strip_prefix(':').ok_or_else(invalid_url)?)),
+                }
+            }
+            None => match host_port.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| invalid_url())?,
+            None => 22,
+        };
+        if host.is_empty() {
+            return Err(invalid_url());
+        }
+        Ok(SftpUrl {
+            user,
+            password,
+            host: host.to_string(),
+            port,
+            root: PathBuf::from(root),
+        })
+    }
+
+    /// The URL without the password, for messages and logs
+    fn redacted(&self) -> String {
+        let host = match self.host.contains(':') {
+            true => format!("[{}]", self.host),
+            false => self.host.clone(),
+        };
+        let root = match self.root == Path::new(".") {
+            true => String::new(),
+            false => self.root.display().to_string(),
+        };
+        format!("sftp://{}@{host}:{}{root}", self.user, self.port)
+    }
+}
+
+#[cfg(feature = "sftp")]
+impl MapSource for SftpSource {
+    fn find_map_files(&self, recursive: bool) -> Result<VecDeque<PathBuf>> {
+        let sftp = self.sftp.lock().expect("poisoned lock");
+        let mut modified = self.modified.lock().expect("poisoned lock");
+        let mut directory_stack = VecDeque::new();
+        let mut map_files = VecDeque::new();
+        directory_stack.push_back(self.root.clone());
+        while let Some(dir) = directory_stack.pop_front() {
+            let entries = match sftp.readdir(&dir) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    warn!("Could not read: {dir:?}, {err}");
+                    continue;
+                }
+            };
+            for (path, stat) in entries {
+                if stat.file_type().is_symlink() {
+                    // We do not follow symlinks for now, could cause forever loop
+                    continue;
+                } else if stat.is_file() && is_map_file(&path) {
+                    let mtime = stat
+                        .mtime
+                        .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+                    modified.insert(path.clone(), mtime);
+                    map_files.push_back(path);
+                } else if stat.is_dir() && recursive {
+                    directory_stack.push_back(path);
+                }
+            }
+        }
+        Ok(map_files)
+    }
+
+    fn open(&self, path: &Path) -> Result<Box<dyn Read + '_>> {
+        // Map files are small, so they are read at once to release the connection
+        let sftp = self.sftp.lock().expect("poisoned lock");
+        let content = read_stored(sftp.open(path)?)?;
+        Ok(Box::new(Cursor::new(content)))
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        let modified = self.modified.lock().expect("poisoned lock");
+        modified.get(path).copied().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::limits::ReadOptions;
+    use crate::source::{parse_id_ranges, MapSource, TarGzSource, ZipSource};
+    use crate::synthetic::write_synthetic_world;
+    use crate::{read_maps, MapItem};
+    use flate2::write::GzEncoder;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_id_ranges() {
+        let ids = parse_id_ranges("10..200, 500,731..").unwrap();
+        assert!(ids.contains(10));
+        assert!(ids.contains(200));
+        assert!(!ids.contains(201));
+        assert!(ids.contains(500));
+        assert!(!ids.contains(501));
+        assert!(ids.contains(100_000));
+        assert!(parse_id_ranges("5..3").is_err());
+        assert!(parse_id_ranges("1,,2").is_err());
+        assert!(parse_id_ranges("map_1").is_err());
+    }
+
+    #[test]
+    fn test_archive_size_limit() {
+        let directory = tempfile::tempdir().unwrap();
+        // Zeros compress well, so the archives stay small while the entries are not
+        let huge = vec![0u8; ReadOptions::DEFAULT.max_size as usize + 1];
+
+        let archive = directory.path().join("backup.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        for (name, content) in [("map_0.dat", &b"map"[..]), ("map_1.dat", &huge)] {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+        let source = ZipSource::open(&archive).unwrap();
+        assert!(source.open(Path::new("map_0.dat")).is_ok());
+        assert!(source.open(Path::new("map_1.dat")).is_err());
+
+        let archive = directory.path().join("backup.tar.gz");
+        let gzip = GzEncoder::new(File::create(&archive).unwrap(), Default::default());
+        let mut tar = tar::Builder::new(gzip);
+        for (name, content) in [("map_0.dat", &b"map"[..]), ("map_1.dat", &huge)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_cksum();
+            tar.append_data(&mut header, name, content).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+        let source = TarGzSource::open(&archive).unwrap();
+        let files = source.find_map_files(true).unwrap();
+        assert_eq!(files, [Path::new("map_0.dat")]);
+    }
+
+    #[cfg(feature = "sftp")]
+    #[test]
+    fn test_parse_sftp_url() {
+        use crate::source::SftpUrl;
+
+        let url = SftpUrl::parse("sftp://steve:p@ss:word@example.com:2222/srv/world/data").unwrap();
+        assert_eq!(url.user, "steve");
+        assert_eq!(url.password.as_deref(), Some("p@ss:word"));
+        assert_eq!((url.host.as_str(), url.port), ("example.com", 2222));
+        assert_eq!(url.root, Path::new("/srv/world/data"));
+        assert_eq!(
+            url.redacted(),
+            "sftp://steve@example.com:2222/srv/world/data"
+        );
+
+        let url = SftpUrl::parse("sftp://alex@[::1]/data").unwrap();
+        assert_eq!(url.password, None);
+        assert_eq!((url.host.as_str(), url.port), ("::1", 22));
+        assert_eq!(url.redacted(), "sftp://alex@[::1]:22/data");
+
+        let url = SftpUrl::parse("sftp://alex@example.com").unwrap();
+        assert_eq!(url.root, Path::new("."));
+        assert_eq!(url.redacted(), "sftp://alex@example.com:22");
+
+        assert!(SftpUrl::parse("ssh://alex@example.com/data").is_err());
+        assert!(SftpUrl::parse("sftp://alex@/data").is_err());
+        assert!(SftpUrl::parse("sftp://alex@example.com:port/data").is_err());
+        assert!(SftpUrl::parse("sftp://alex@[::1/data").is_err());
+    }
+
+    #[test]
+    fn test_memory_mapping() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let data = world.path().join("data");
+        let mapping = ReadOptions {
+            memory_mapping: true,
+            ..ReadOptions::default()
+        };
+        for read in read_maps(&data, &None, false).unwrap() {
+            let read = read.unwrap();
+            let mapped = MapItem::read_from_with(&read.file, &mapping).unwrap();
+            assert_eq!(read.data.colors[..], mapped.data.colors[..]);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Statistics over a set of maps
+
+use crate::ReadMap;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Summary of the maps in a world
+#[derive(Debug, Default)]
+pub struct MapStats {
+    /// Number of maps that could be read
+    pub total_maps: usize,
+
+    /// Number of map files that could not be read
+    pub unreadable_maps: usize,
+
+    /// Number of maps for each dimension, using [pretty dimension](crate::MapData::pretty_dimension)
+    pub maps_per_dimension: BTreeMap<String, usize>,
+
+    /// Number of maps for each scale
+    pub maps_per_scale: BTreeMap<i8, usize>,
+
+    /// Number of pixels that are not unexplored, summed over all maps
+    pub explored_pixels: u64,
+
+    /// Modification time of the newest map file
+    pub newest_map: Option<SystemTime>,
+}
+
+impl MapStats {
+    /// Reads all the *maps* and collects statistics from them
+    pub fn collect(maps: ReadMap) -> MapStats {
+        let source = maps.source();
+        let mut stats = MapStats {
+            newest_map: maps.paths().filter_map(|path| source.modified(path)).max(),
+            ..Default::default()
+        };
+        for map_item in maps {
+            let map_item = match map_item {
+                Ok(map_item) => map_item,
+                Err(_) => {
+                    stats.unreadable_maps += 1;
+                    continue;
+                }
+            };
+            stats.total_maps += 1;
+            *stats
+                .maps_per_dimension
+                .entry(map_item.data.pretty_dimension())
+                .or_default() += 1;
+            *stats.maps_per_scale.entry(map_item.data.scale).or_default() += 1;
+            stats.explored_pixels += map_item
+                .data
+                .colors
+                .iter()
+                .filter(|color| **color as u8 >= 4)
+                .count() as u64;
+        }
+        stats
+    }
+
+    /// Formats the statistics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        let mut gauge = |name: &str, help: &str, values: &[(String, String)]| {
+            let _ = writeln!(text, "# HELP minecraft_map_{name} {help}");
+            let _ = writeln!(text, "# TYPE minecraft_map_{name} gauge");
+            for (labels, value) in values {
+                let _ = writeln!(text, "minecraft_map_{name}{labels} {value}");
+            }
+        };
+        gauge(
+            "total",
+            "Number of map files that could be read.",
+            &[(String::new(), self.total_maps.to_string())],
+        );
+        gauge(
+            "unreadable",
+            "Number of map files that could not be read.",
+            &[(String::new(), self.unreadable_maps.to_string())],
+        );
+        gauge(
+            "dimension_total",
+            "Number of maps in each dimension.",
+            &self
+                .maps_per_dimension
+                .iter()
+                .map(|(dimension, count)| {
+                    (
+                        format!("{{dimension=\"{}\"}}", escape_label(dimension)),
+                        count.to_string(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+        gauge(
+            "scale_total",
+            "Number of maps with each scale.",
+            &self
+                .maps_per_scale
+                .iter()
+                .map(|(scale, count)| (format!("{{scale=\"{scale}\"}}"), count.to_string()))
+                .collect::<Vec<_>>(),
+        );
+        gauge(
+            "explored_pixels",
+            "Number of explored pixels in all maps.",
+            &[(String::new(), self.explored_pixels.to_string())],
+        );
+        if let Some(seconds) = self
+            .newest_map
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        {
+            gauge(
+                "newest_timestamp_seconds",
+                "Modification time of the newest map file.",
+                &[(String::new(), seconds.as_secs().to_string())],
+            );
+        }
+        text
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
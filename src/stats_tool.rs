@@ -0,0 +1,70 @@
+use clap::{Args, ValueEnum};
+use log::error;
+use minecraft_map_tool::read_maps;
+use minecraft_map_tool::stats::MapStats;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::UNIX_EPOCH;
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// The directory or world backup archive (.zip, .tar.gz) from which map files are searched for.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    path: PathBuf,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Output format
+    #[arg(short, long, default_value = "text")]
+    format: StatsFormat,
+}
+
+/// Output formats for the statistics
+#[derive(Clone, Debug, ValueEnum)]
+pub enum StatsFormat {
+    /// Human-readable summary
+    Text,
+
+    /// Prometheus text exposition format for monitoring systems
+    Prometheus,
+}
+
+pub fn run(args: &StatsArgs) -> ExitCode {
+    let maps = match read_maps(&args.path, &None, args.recursive) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not get maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let stats = MapStats::collect(maps);
+    match args.format {
+        StatsFormat::Text => print_text(&stats),
+        StatsFormat::Prometheus => print!("{}", stats.to_prometheus()),
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_text(stats: &MapStats) {
+    println!("Maps            : {}", stats.total_maps);
+    if stats.unreadable_maps > 0 {
+        println!("Unreadable maps : {}", stats.unreadable_maps);
+    }
+    println!("Explored pixels : {}", stats.explored_pixels);
+    if let Some(seconds) = stats
+        .newest_map
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+    {
+        println!("Newest map      : {} (Unix time)", seconds.as_secs());
+    }
+    println!("Dimensions");
+    for (dimension, count) in &stats.maps_per_dimension {
+        println!("  {dimension:<14}: {count}");
+    }
+    println!("Scales");
+    for (scale, count) in &stats.maps_per_scale {
+        println!("  {scale:<14}: {count}");
+    }
+}
@@ -0,0 +1,221 @@
+//! Drawing multiple maps into a single image
+
+use crate::canvas::Canvas;
+use crate::error::{Error, Result};
+use crate::handle::MapHandle;
+use crate::palette::Palette;
+use crate::{MapData, ReadMap};
+use image::RgbaImage;
+use std::path::Path;
+
+/// Rectangular area in the world coordinates
+///
+/// All edges are inclusive, so a single map with scale 0 covers an area where
+/// `right - left + 1` is 128.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Area {
+    /// Left coordinate (Smaller X)
+    pub left: i32,
+
+    /// Top coordinate (Smaller Z)
+    pub top: i32,
+
+    /// Right coordinate (Larger X)
+    pub right: i32,
+
+    /// Bottom coordinate (Larger Z)
+    pub bottom: i32,
+}
+
+impl Area {
+    /// Area covered by the map
+    pub fn of_map(map_data: &MapData) -> Area {
+        Area {
+            left: map_data.left(),
+            top: map_data.top(),
+            right: map_data.right(),
+            bottom: map_data.bottom(),
+        }
+    }
+
+    /// Width of the area in blocks
+    pub fn width(&self) -> u32 {
+        (self.right - self.left + 1) as u32
+    }
+
+    /// Height of the area in blocks
+    pub fn height(&self) -> u32 {
+        (self.bottom - self.top + 1) as u32
+    }
+
+    /// Smallest area containing both *self* and *other*
+    pub fn union(&self, other: &Area) -> Area {
+        Area {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    /// Tests if the areas have common blocks
+    pub fn overlaps(&self, other: &Area) -> bool {
+        other.left <= self.right
+            && other.top <= self.bottom
+            && other.right >= self.left
+            && other.bottom >= self.top
+    }
+}
+
+/// Parses an area from four comma-separated world coordinates: left, top, right and bottom
+pub fn parse_area(text: &str) -> std::result::Result<Area, String> {
+    let values = text
+        .split(',')
+        .map(|value| value.trim().parse::<i32>())
+        .collect::<std::result::Result<Vec<i32>, _>>()
+        .map_err(|err| format!("Invalid area: {err}"))?;
+    let [left, top, right, bottom] = values[..] else {
+        return Err(String::from(
+            "Area must have four coordinates: left, top, right, bottom",
+        ));
+    };
+    if left > right || top > bottom {
+        return Err(String::from(
+            "Area must have left <= right and top <= bottom",
+        ));
+    }
+    Ok(Area {
+        left,
+        top,
+        right,
+        bottom,
+    })
+}
+
+/// Parses a point from two comma-separated world coordinates: X and Z
+pub fn parse_point(text: &str) -> std::result::Result<(i32, i32), String> {
+    let values = text
+        .split(',')
+        .map(|value| value.trim().parse::<i32>())
+        .collect::<std::result::Result<Vec<i32>, _>>()
+        .map_err(|err| format!("Invalid point: {err}"))?;
+    match values[..] {
+        [x, z] => Ok((x, z)),
+        _ => Err(String::from("Point must have two coordinates: X, Z")),
+    }
+}
+
+/// Keeps the maps with the given *scale* and *dimension*, and finds the area they cover
+///
+/// Dimension is compared case-insensitively against [MapData::pretty_dimension].
+/// Returns an error if no maps are left after filtering.
+pub fn filter_maps(maps: ReadMap, scale: i8, dimension: Option<&str>) -> Result<(ReadMap, Area)> {
+    filter_maps_reporting(maps, scale, dimension, |_, _| {})
+}
+
+/// Same as [filter_maps], but calls *on_error* for each map file that could not be read
+pub fn filter_maps_reporting<F: FnMut(&Path, &Error)>(
+    mut maps: ReadMap,
+    scale: i8,
+    dimension: Option<&str>,
+    mut on_error: F,
+) -> Result<(ReadMap, Area)> {
+    let source = maps.source();
+
+    // Making dimension to lowercase for case-insensitive comparison
+    let dimension = dimension.map(|s| s.to_lowercase());
+
+    // The map area
+    let mut area: Option<Area> = None;
+
+    maps.retain(|file| {
+        let handle = match MapHandle::open(source.clone(), file) {
+            Ok(handle) => handle,
+            Err(err) => {
+                on_error(file, &err);
+                return false;
+            }
+        };
+        let metadata = handle.metadata();
+
+        // Filtering with scale
+        if metadata.scale != scale {
+            return false;
+        }
+
+        // Filtering with dimension
+        if let Some(dimension) = &dimension {
+            if &metadata.pretty_dimension().to_lowercase() != dimension {
+                return false;
+            }
+        }
+
+        // Update map area
+        let map_area = metadata.area();
+        area = Some(area.map_or(map_area, |area| area.union(&map_area)));
+        true
+    });
+
+    match area {
+        None => Err(Error::map_item_error("No map files after filtering")),
+        Some(area) => Ok((maps, area)),
+    }
+}
+
+/// Paints non-transparent pixels of the *source* image to the *target* image at *x*, *y*
+pub fn paint_image(source: &RgbaImage, target: &mut RgbaImage, x: i32, y: i32) {
+    for in_y in 0..source.height() {
+        for in_x in 0..source.width() {
+            let out_x = in_x as i32 + x;
+            let out_y = in_y as i32 + y;
+            if out_x < 0
+                || out_y < 0
+                || out_x as u32 >= target.width()
+                || out_y as u32 >= target.height()
+            {
+                continue; // Outside of the target image
+            }
+            let pixel = source.get_pixel(in_x, in_y);
+            if pixel[3] == 0 {
+                continue; // Transparent
+            }
+            target.put_pixel(out_x as u32, out_y as u32, *pixel);
+        }
+    }
+}
+
+/// Draws the *maps* overlapping the *area* into a new image
+///
+/// Maps are painted in the order they come from the *maps*, so later maps are drawn on top.
+/// The *on_map* callback is called after each map, which is useful for showing progress.
+pub fn stitch_maps<F: FnMut()>(
+    maps: ReadMap,
+    area: &Area,
+    palette: &Palette,
+    on_map: F,
+) -> Result<RgbaImage> {
+    Ok(stitch_canvas(maps, area, palette, on_map)?.to_image())
+}
+
+/// Same as [stitch_maps], but the maps are painted into a [Canvas] that knows which map
+/// painted each pixel
+pub fn stitch_canvas<F: FnMut()>(
+    maps: ReadMap,
+    area: &Area,
+    palette: &Palette,
+    mut on_map: F,
+) -> Result<Canvas> {
+    let mut canvas = Canvas::new(area);
+    // The same map image is reused for all maps
+    let mut map_image = RgbaImage::new(128, 128);
+    for map_item in maps.flatten() {
+        if area.overlaps(&Area::of_map(&map_item.data)) {
+            // Map overlaps the target image, paint it
+            map_item.data.render_into(&mut map_image, palette)?;
+            let (left, top) = (map_item.data.left(), map_item.data.top());
+            canvas.paint(&map_image, left, top, &map_item.file);
+        }
+        on_map();
+    }
+    Ok(canvas)
+}
@@ -1,28 +1,61 @@
+use crate::batch::{fatal, Failures};
+use crate::changes::sink;
+use crate::charset::preset;
+use crate::image_tool::{ColorArgs, MaskArgs, PostProcessArgs};
+use crate::list_tool::format_time;
 use anyhow::{anyhow, Result};
-use clap::{arg, Args};
-use image::RgbaImage;
-use indicatif::{ProgressBar, ProgressStyle};
-use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
-use minecraft_map_tool::{read_maps, ReadMap, SortingOrder};
-use std::collections::VecDeque;
-use std::fs;
+use clap::Args;
+use comfy_table::{CellAlignment, ContentArrangement, Table};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use log::{error, info, log_enabled, Level};
+use minecraft_map_tool::annotations::{draw_annotations, read_annotations, Annotation, Shape};
+use minecraft_map_tool::canvas::Canvas;
+use minecraft_map_tool::legend::{banner_groups, draw_legend};
+use minecraft_map_tool::markers::{collect_markers, MarkerKind, PointMarker};
+use minecraft_map_tool::naming::{check_template, fill_template};
+use minecraft_map_tool::palette::Palette;
+use minecraft_map_tool::regions::read_regions;
+use minecraft_map_tool::routes::{parse_route, Route, ROUTE_COLORS};
+use minecraft_map_tool::seams::{draw_seams, find_seams, Seam};
+use minecraft_map_tool::source::MapSource;
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use minecraft_map_tool::stitch::{filter_maps_reporting, parse_point, stitch_canvas, Area};
+use minecraft_map_tool::transform::{
+    nether_area_to_overworld, MapTransform, NETHER_NAME, OVERWORLD_NAME,
+};
+use minecraft_map_tool::{read_maps_from, ReadMap, SortSpec};
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Seconds in a day, for the seam age limits
+const DAY: u64 = 24 * 60 * 60;
+
 #[derive(Args, Debug)]
 pub struct StitchingArgs {
     /// Only draw maps with matching dimensions name
     #[arg(short, long, default_value = "Overworld")]
     dimension: Option<String>,
 
+    /// Write one image for each dimension found. The filename must contain {dimension},
+    /// which is replaced with the dimension name, for example stitched/{dimension}.png
+    #[arg(long, conflicts_with = "dimension")]
+    per_dimension: bool,
+
     /// Search map files recursively in subdirectories
     #[arg(long)]
     recursive: bool,
 
-    /// Image drawing order
+    /// Only use the maps with these ids, for example 10..200,500,731. Ranges include both ends
+    #[arg(long, value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+
+    /// Image drawing order. Keys are name, time, id, data-version and scale.
+    /// Combine keys with commas and reverse them with :desc, for example scale,time:desc
     #[arg(short, long, default_value = "time")]
-    sort: Option<SortingOrder>,
+    sort: Option<SortSpec>,
 
     /// Draw only maps with this zoom level
     #[arg(short, long, default_value_t = 0)]
@@ -44,211 +77,483 @@ pub struct StitchingArgs {
     #[arg(short, long)]
     bottom: Option<i32>,
 
-    /// The directory from which map files are searched for
-    path: PathBuf,
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which map files are
+    /// searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp feature,
+    /// this can also be a remote directory (sftp://user@host/path)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
 
-    /// Filename for the output image
+    /// Filename for the output image. Placeholders {dimension} and {zoom} are replaced with
+    /// the dimension and the zoom level of the maps.
+    #[arg(value_parser = parse_filename)]
     filename: String,
-}
 
-struct ImageProject {
-    maps: ReadMap,
-    left: i32,
-    top: i32,
-    right: i32,
-    bottom: i32,
-}
+    #[command(flatten)]
+    colors: ColorArgs,
 
-fn filter_and_area(
-    maps: ReadMap,
-    scale: i8,
-    dimension: &Option<String>,
-) -> anyhow::Result<ImageProject> {
-    // Making dimension to lowercase for case-insensitive comparison
-    let dimension = dimension.clone().map(|s| s.to_lowercase());
-
-    // Container for filtered map paths
-    let mut filtered_map_files: VecDeque<PathBuf> = VecDeque::new();
-
-    // Variables for finding the map area
-    let mut left = i32::MAX;
-    let mut top = i32::MAX;
-    let mut right = i32::MIN;
-    let mut bottom = i32::MIN;
-
-    for map_item in maps.flatten() {
-        // Filtering with scale
-        if map_item.data.scale != scale {
-            continue;
-        }
+    #[command(flatten)]
+    adjustments: PostProcessArgs,
 
-        // Filtering with dimension
-        if let Some(dimension) = &dimension {
-            if &map_item.data.pretty_dimension().to_lowercase() != dimension {
-                continue;
-            }
-        }
+    #[command(flatten)]
+    mask: MaskArgs,
 
-        // Update map area
-        left = left.min(map_item.data.left());
-        top = top.min(map_item.data.top());
-        right = right.max(map_item.data.right());
-        bottom = bottom.max(map_item.data.bottom());
+    /// Draw the labeled points, lines and rectangles from this JSON file on the image
+    #[arg(long, value_name = "FILE")]
+    annotations: Option<PathBuf>,
 
-        // Keep this map item in new list
-        filtered_map_files.push_back(map_item.file);
-    }
+    /// Shade the polygons of this GeoJSON file, like town and claim borders, on the image.
+    /// Coordinates are world X and Z, and the fill, stroke and name properties are used
+    #[arg(long, value_name = "FILE")]
+    regions: Option<PathBuf>,
 
-    if filtered_map_files.is_empty() {
-        return Err(anyhow!("No map files after filtering"));
-    }
+    /// Draw a legend of the banner names grouped by color into the upper left corner
+    #[arg(long)]
+    legend: bool,
 
-    let maps = ReadMap::from_paths(filtered_map_files);
-    Ok(ImageProject {
-        maps,
-        left,
-        top,
-        right,
-        bottom,
-    })
+    /// Draw the outlines of the Nether maps and the Nether banners on the Overworld image at
+    /// the matching coordinates, multiplied by 8, for planning nether portals
+    #[arg(long)]
+    project_nether: bool,
+
+    /// Connect the banners with these names, separated by commas, with a line in the given
+    /// order and print the distances. Can be repeated for more routes
+    #[arg(long, value_name = "NAMES", value_parser = parse_route)]
+    route: Vec<Vec<String>>,
+
+    /// Print the map file that painted the pixel at the world coordinates X,Z. Can be repeated
+    #[arg(
+        long,
+        value_name = "X,Z",
+        value_parser = parse_point,
+        allow_hyphen_values = true
+    )]
+    inspect: Vec<(i32, i32)>,
+
+    /// Print the seams where maps modified far apart in time meet, so the older maps can be
+    /// explored again
+    #[arg(long)]
+    report_seams: bool,
+
+    /// Smallest difference in the modification times of the maps on a reported seam
+    #[arg(
+        long,
+        value_name = "DAYS",
+        default_value_t = 7,
+        requires = "report_seams"
+    )]
+    seam_days: u64,
+
+    /// Also save an image with only the seams drawn, the same size as the stitched image.
+    /// Placeholders are replaced like in the output filename
+    #[arg(long, value_name = "FILENAME", value_parser = parse_filename, requires = "report_seams")]
+    seam_overlay: Option<String>,
+}
+
+struct ImageProject {
+    maps: ReadMap,
+    area: Area,
+}
+
+fn parse_filename(filename: &str) -> std::result::Result<String, String> {
+    check_template(filename, &["dimension", "zoom"]).map(|_| filename.to_string())
 }
 
-fn prepare(args: &StitchingArgs) -> Result<ImageProject> {
+/// Finds the map files to stitch
+fn find_maps(args: &StitchingArgs) -> Result<ReadMap> {
     if args.zoom != 0 {
         return Err(anyhow!("Only zoom step 0 is currently supported"));
     }
-
-    // Get maps
-    let maps = read_maps(&args.path, &args.sort, args.recursive)
+    let mut maps = read_maps_from(&args.paths, &args.sort, args.recursive)
         .map_err(|err| anyhow!(format!("Could not read maps: {err}")))?;
+    if let Some(ids) = &args.ids {
+        maps.retain_ids(ids);
+    }
     if maps.is_empty() {
         return Err(anyhow!("No map files found"));
     }
-    println!("Found {} map files.", maps.file_count());
+    info!("Found {} map files.", maps.file_count());
+    Ok(maps)
+}
 
+fn prepare(
+    args: &StitchingArgs,
+    maps: ReadMap,
+    dimension: Option<&str>,
+    failures: &mut Failures,
+) -> Result<ImageProject> {
     // Filtering and finding the area
-    let ImageProject {
-        maps,
-        mut left,
-        mut top,
-        mut right,
-        mut bottom,
-    } = filter_and_area(maps, args.zoom, &args.dimension)?;
-    println!("After filtering we have {} map files.", maps.file_count());
-    println!("Map area");
-    println!("  Upper Left  : {left} {top}");
-    println!("  Lower Right : {right} {bottom}");
-    println!("  Size        : {}×{}", right - left + 1, bottom - top + 1);
+    let (maps, mut area) = filter_maps_reporting(maps, args.zoom, dimension, |file, err| {
+        failures.add(file, format!("Could not read map item: {err}"))
+    })
+    .map_err(|err| anyhow!("{err}"))?;
+    info!("After filtering we have {} map files.", maps.file_count());
+    info!("Map area");
+    info!("  Upper Left  : {} {}", area.left, area.top);
+    info!("  Lower Right : {} {}", area.right, area.bottom);
+    info!("  Size        : {}×{}", area.width(), area.height());
 
     // Apply users area limits if given
     if let Some(value) = args.left {
-        left = value;
+        area.left = value;
     }
     if let Some(value) = args.top {
-        top = value;
+        area.top = value;
     }
     if let Some(value) = args.right {
-        right = value;
+        area.right = value;
     }
     if let Some(value) = args.bottom {
-        bottom = value;
-    }
-    println!("Map area for image");
-    println!("  Upper Left  : {left} {top}");
-    println!("  Lower Right : {right} {bottom}");
-    println!("  Size        : {}×{}", right - left + 1, bottom - top + 1);
-
-    Ok(ImageProject {
-        maps,
-        left,
-        top,
-        right,
-        bottom,
-    })
-}
-
-fn paint_image(source: &RgbaImage, target: &mut RgbaImage, x: i32, y: i32) {
-    for in_y in 0..source.height() {
-        for in_x in 0..source.width() {
-            let out_x = in_x as i32 + x;
-            let out_y = in_y as i32 + y;
-            if out_x < 0
-                || out_y < 0
-                || out_x as u32 >= target.width()
-                || out_y as u32 >= target.height()
-            {
-                continue; // Outside of the target image
-            }
-            let pixel = source.get_pixel(in_x, in_y);
-            if pixel[3] == 0 {
-                continue; // Transparent
-            }
-            target.put_pixel(out_x as u32, out_y as u32, *pixel);
-        }
+        area.bottom = value;
     }
-}
+    info!("Map area for image");
+    info!("  Upper Left  : {} {}", area.left, area.top);
+    info!("  Lower Right : {} {}", area.right, area.bottom);
+    info!("  Size        : {}×{}", area.width(), area.height());
 
-fn make_image(project: ImageProject) -> Result<RgbaImage> {
-    // Create Image
-    let width = (project.right - project.left + 1) as u32;
-    let height = (project.bottom - project.top + 1) as u32;
-    println!("Making image with size: {width}×{height}");
-    let mut image = RgbaImage::new(width, height);
+    Ok(ImageProject { maps, area })
+}
 
-    // Prepare palette
-    let palette = generate_palette(&BASE_COLORS_2699);
+fn make_canvas(project: ImageProject, palette: &Palette) -> Result<Canvas> {
+    info!(
+        "Making image with size: {}×{}",
+        project.area.width(),
+        project.area.height()
+    );
 
     // Painting maps
     let progress_bar = ProgressBar::new(project.maps.file_count() as u64);
+    hide_when_quiet(&progress_bar);
     progress_bar.set_style(ProgressStyle::with_template(
         "{spinner:.green} {msg} [{bar:40.green}] {pos}/{len} ({eta})",
     )?);
     progress_bar.set_message("Drawing maps");
+    let canvas = stitch_canvas(project.maps, &project.area, palette, || progress_bar.inc(1))
+        .map_err(|err| anyhow!("Could not paint image: {err}"))?;
+    progress_bar.finish();
 
-    for map_item in project.maps.flatten() {
-        if map_item.data.left() <= project.right
-            && map_item.data.top() <= project.bottom
-            && map_item.data.right() >= project.left
-            && map_item.data.bottom() >= project.top
-        {
-            // Map overlaps the target image, paint it
-            let map_image = map_item
-                .make_image(&palette)
-                .map_err(|err| anyhow!("Could not paint image: {err}"))?;
-            paint_image(
-                &map_image,
-                &mut image,
-                map_item.data.left() - project.left,
-                map_item.data.top() - project.top,
-            );
+    Ok(canvas)
+}
+
+/// Prints the map files that painted the pixels at the *points*
+fn inspect(canvas: &Canvas, points: &[(i32, i32)]) {
+    for &(x, z) in points {
+        match canvas.source_at(x, z) {
+            Some(file) => println!("{x},{z}: {}", file.display()),
+            None => println!("{x},{z}: not painted"),
         }
-        progress_bar.inc(1);
     }
-    progress_bar.finish();
+}
 
-    Ok(image)
+/// Prints the seams on the *canvas* between the maps modified at least *days* apart
+///
+/// The modification times of the maps are read from the *source*.
+fn report_seams(canvas: &Canvas, source: &dyn MapSource, days: u64) -> Vec<Seam> {
+    let seams = find_seams(
+        canvas,
+        |file| source.modified(file),
+        Duration::from_secs(days * DAY),
+    );
+    info!("Found {} seams", seams.len());
+    if seams.is_empty() {
+        return seams;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header([
+            "Older map",
+            "Modified",
+            "Newer map",
+            "Modified",
+            "Days apart",
+            "Length",
+            "Area",
+        ]);
+    let time = |file| {
+        source
+            .modified(file)
+            .map_or_else(|| "-".to_string(), format_time)
+    };
+    for seam in &seams {
+        let area = &seam.area;
+        table.add_row([
+            seam.older.display().to_string(),
+            time(&seam.older),
+            seam.newer.display().to_string(),
+            time(&seam.newer),
+            (seam.age_difference.as_secs() / DAY).to_string(),
+            seam.pixels.len().to_string(),
+            format!(
+                "{},{} to {},{}",
+                area.left, area.top, area.right, area.bottom
+            ),
+        ]);
+    }
+    for column in 4..=5 {
+        if let Some(column) = table.column_mut(column) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+    }
+    println!("{table}");
+    seams
+}
+
+/// Output file name for the maps in the *dimension*, made from the *template*
+fn filename(args: &StitchingArgs, template: &str, dimension: &str) -> String {
+    fill_template(
+        template,
+        &[
+            ("dimension", dimension.to_string()),
+            ("zoom", args.zoom.to_string()),
+        ],
+    )
 }
 
-fn process(args: &StitchingArgs) -> Result<()> {
-    if let Some(output_path) = PathBuf::from(&args.filename).parent() {
-        fs::create_dir_all(output_path)?;
+/// Draws the image of the maps in the *dimension* and saves it
+fn save(
+    args: &StitchingArgs,
+    project: ImageProject,
+    dimension: &str,
+    nether: &[Annotation],
+) -> Result<()> {
+    let image_filename = filename(args, &args.filename, dimension);
+    if let Some(output_path) = PathBuf::from(&image_filename).parent() {
+        sink().create_dir_all(output_path)?;
+    }
+    let area = project.area;
+    // Only the mask needs to know which areas the maps cover
+    let bounds: Vec<Area> = match args.mask.is_enabled() {
+        true => project
+            .maps
+            .handles()
+            .filter_map(|(_, handle)| handle.ok())
+            .map(|handle| handle.metadata().area())
+            .collect(),
+        false => Vec::new(),
+    };
+    // Regions are drawn first, so the annotations are on top of them
+    let mut annotations = match &args.regions {
+        Some(file) => read_regions(file)
+            .map_err(|err| anyhow!("Could not read regions {}: {err}", file.display()))?,
+        None => Vec::new(),
+    };
+    if let Some(file) = &args.annotations {
+        annotations.extend(
+            read_annotations(file)
+                .map_err(|err| anyhow!("Could not read annotations {}: {err}", file.display()))?,
+        );
+    }
+    let mut annotations: Vec<Annotation> = annotations
+        .into_iter()
+        .filter(|annotation| dimension.is_empty() || annotation.is_in(dimension))
+        .collect();
+    if dimension.eq_ignore_ascii_case(OVERWORLD_NAME) {
+        annotations.extend_from_slice(nether);
+    }
+    let markers = match args.legend || !args.route.is_empty() {
+        true => banner_markers(&project.maps),
+        false => Vec::new(),
+    };
+    let groups = match args.legend {
+        true => {
+            let groups = banner_groups(&markers);
+            info!("Legend has {} banner colors", groups.len());
+            groups
+        }
+        false => Vec::new(),
+    };
+    let routes = args
+        .route
+        .iter()
+        .map(|names| Route::find(&markers, names).map_err(|err| anyhow!("{err}")))
+        .collect::<Result<Vec<Route>>>()?;
+    if !routes.is_empty() {
+        report_routes(&routes, dimension);
     }
-    let project = prepare(args)?;
-    let image = make_image(project)?;
+    for (route, color) in routes.iter().zip(ROUTE_COLORS.iter().cycle()) {
+        annotations.extend(route.annotations(color));
+    }
+    // The maps are consumed when painting, the source still knows their times
+    let source: Option<Arc<dyn MapSource>> = args.report_seams.then(|| project.maps.source());
+    let canvas = make_canvas(project, &args.colors.palette())?;
+    inspect(&canvas, &args.inspect);
+    if let Some(source) = source {
+        let seams = report_seams(&canvas, source.as_ref(), args.seam_days);
+        if let Some(template) = &args.seam_overlay {
+            let overlay_filename = filename(args, template, dimension);
+            info!("Saving seam overlay as {overlay_filename:?}");
+            let overlay = draw_seams(&canvas, &seams);
+            sink().write_with(&overlay_filename, |file| overlay.save(file))?;
+        }
+    }
+    let mut image = canvas.to_image();
+    args.adjustments.post_process().apply(&mut image);
+    let mut image = args.mask.apply(image, &area, &bounds);
+    // Annotations and the legend are drawn last, so the mask does not hide them
+    let scale = (image.width() / 512).clamp(1, 4);
+    draw_annotations(
+        &mut image,
+        &MapTransform::of_area(&area, 0),
+        &annotations,
+        scale,
+    );
+    draw_legend(&mut image, &groups, scale);
     let progress_bar = ProgressBar::new_spinner();
+    hide_when_quiet(&progress_bar);
     progress_bar.set_style(ProgressStyle::with_template("{spinner:.green} {msg}")?);
-    progress_bar.set_message(format!("Saving image as {:?}", args.filename));
+    progress_bar.set_message(format!("Saving image as {image_filename:?}"));
     progress_bar.enable_steady_tick(Duration::from_millis(50));
-    image.save(&args.filename)?;
+    sink().write_with(&image_filename, |file| image.save(file))?;
     progress_bar.finish();
     Ok(())
 }
 
+/// Banners and frames marked on the *maps*
+fn banner_markers(maps: &ReadMap) -> Vec<PointMarker> {
+    let map_items: Vec<_> = maps
+        .handles()
+        .filter_map(|(_, handle)| handle.ok()?.load().ok())
+        .collect();
+    collect_markers(&map_items)
+}
+
+/// Prints the legs and the lengths of the *routes* in the *dimension*
+///
+/// Nether routes are also given in overworld blocks, since they are usually built as
+/// shortcuts.
+fn report_routes(routes: &[Route], dimension: &str) {
+    let nether = dimension.eq_ignore_ascii_case(NETHER_NAME);
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    let mut header = vec!["Route", "From", "To", "Blocks"];
+    if nether {
+        header.push("Overworld");
+    }
+    table.set_header(header);
+    let row = |route: String, from: String, to: String, blocks: f64| {
+        let mut row = vec![route, from, to, format!("{blocks:.0}")];
+        if nether {
+            row.push(format!("{:.0}", blocks * 8.0));
+        }
+        row
+    };
+    for (index, route) in routes.iter().enumerate() {
+        let number = (index + 1).to_string();
+        for (pair, blocks) in route.stops.windows(2).zip(route.legs()) {
+            let (from, to) = (pair[0].label.clone(), pair[1].label.clone());
+            table.add_row(row(number.clone(), from, to, blocks));
+        }
+        let total = String::from("Total");
+        table.add_row(row(number, total, String::new(), route.length()));
+    }
+    for column in 3..=4 {
+        if let Some(column) = table.column_mut(column) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+    }
+    println!("{table}");
+}
+
+/// Outlines of the Nether maps and the Nether banners projected to the Overworld
+fn nether_annotations(maps: &ReadMap) -> Vec<Annotation> {
+    let annotation = |shape: Shape, label: Option<String>| Annotation {
+        shape,
+        label,
+        color: Some(String::from("dark_red")),
+        width: 1,
+        fill: true,
+        opacity: Some(0.15),
+        dimension: None,
+    };
+    let map_items: Vec<_> = maps
+        .handles()
+        .filter_map(|(_, handle)| handle.ok())
+        .filter(|handle| handle.metadata().pretty_dimension() == NETHER_NAME)
+        .filter_map(|handle| handle.load().ok())
+        .collect();
+    let mut annotations: Vec<Annotation> = map_items
+        .iter()
+        .map(|map_item| {
+            let area = nether_area_to_overworld(&Area::of_map(&map_item.data));
+            let shape = Shape::Rectangle {
+                left: area.left,
+                top: area.top,
+                right: area.right,
+                bottom: area.bottom,
+            };
+            annotation(shape, None)
+        })
+        .collect();
+    let markers = collect_markers(&map_items);
+    let banners = markers
+        .iter()
+        .filter(|marker| marker.kind == MarkerKind::Banner);
+    for marker in banners {
+        let marker = marker.projected_to_overworld();
+        let point = Shape::Point {
+            x: marker.pos.x,
+            z: marker.pos.z,
+        };
+        annotations.push(annotation(point, Some(marker.label)));
+    }
+    info!(
+        "Projecting {} Nether maps to the Overworld",
+        map_items.len()
+    );
+    annotations
+}
+
+fn process(args: &StitchingArgs, failures: &mut Failures) -> Result<()> {
+    let maps = find_maps(args)?;
+    let nether = match args.project_nether {
+        true => nether_annotations(&maps),
+        false => Vec::new(),
+    };
+    if !args.per_dimension {
+        let project = prepare(args, maps, args.dimension.as_deref(), failures)?;
+        let dimension = args.dimension.clone().unwrap_or_default();
+        return save(args, project, &dimension, &nether);
+    }
+    if !args.filename.contains("{dimension}") {
+        return Err(anyhow!(
+            "Filename must contain {{dimension}} when writing an image for each dimension"
+        ));
+    }
+
+    // Unreadable maps and maps with other zoom levels are dropped and reported only once
+    let (maps, _) = filter_maps_reporting(maps, args.zoom, None, |file, err| {
+        failures.add(file, format!("Could not read map item: {err}"))
+    })
+    .map_err(|err| anyhow!("{err}"))?;
+    let dimensions: BTreeSet<String> = maps
+        .handles()
+        .filter_map(|(_, handle)| handle.ok())
+        .map(|handle| handle.metadata().pretty_dimension())
+        .collect();
+    for dimension in dimensions {
+        info!("Stitching {dimension}");
+        let project = prepare(args, maps.clone(), Some(&dimension), failures)?;
+        save(args, project, &dimension, &nether)?;
+    }
+    Ok(())
+}
+
 pub fn run(args: &StitchingArgs) -> ExitCode {
-    // Try to make the image
-    if let Err(err) = process(args) {
-        eprintln!("{err}");
-        return ExitCode::FAILURE;
+    // Try to make the image, unreadable map files are left out and reported at the end
+    let mut failures = Failures::new();
+    if let Err(err) = process(args, &mut failures) {
+        error!("{err}");
+        return fatal();
+    }
+    failures.finish()
+}
+
+/// Progress is shown with the info messages
+fn hide_when_quiet(progress_bar: &ProgressBar) {
+    if !log_enabled!(Level::Info) {
+        progress_bar.set_draw_target(ProgressDrawTarget::hidden());
     }
-    ExitCode::SUCCESS
 }
@@ -0,0 +1,75 @@
+//! Styling of the printed text
+//!
+//! Bold text and other attributes are only written when the standard output is a terminal
+//! and the `NO_COLOR` environment variable is not set, so pipes, files and logs get plain
+//! text. The `--color` option overrides this for the whole program.
+
+use clap::ValueEnum;
+use crossterm::style::{Attribute, SetAttribute};
+use crossterm::Command;
+use std::env;
+use std::fmt;
+use std::io::{stdout, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the attributes are written, see [init]
+static STYLED: AtomicBool = AtomicBool::new(false);
+
+/// When the printed text is styled
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    /// Style the text on terminals unless NO_COLOR is set
+    #[default]
+    Auto,
+
+    /// Always style the text
+    Always,
+
+    /// Never style the text
+    Never,
+}
+
+impl ColorChoice {
+    /// Tests if the text should be styled with this choice
+    pub fn is_styled(&self) -> bool {
+        match self {
+            ColorChoice::Auto => no_color_unset() && stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Sets the styling for the whole program
+pub fn init(choice: ColorChoice) {
+    STYLED.store(choice.is_styled(), Ordering::Relaxed);
+}
+
+/// Tests if the printed text is styled
+pub fn is_styled() -> bool {
+    STYLED.load(Ordering::Relaxed)
+}
+
+/// Command setting the attribute when the text is styled, and doing nothing otherwise
+#[derive(Clone, Copy, Debug)]
+pub struct Styled(pub Attribute);
+
+impl Command for Styled {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match is_styled() {
+            true => SetAttribute(self.0).write_ansi(f),
+            false => Ok(()),
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        // Attributes are not supported by WinAPI
+        Ok(())
+    }
+}
+
+/// NO_COLOR turns the styling off when it is set to anything but an empty string
+fn no_color_unset() -> bool {
+    env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
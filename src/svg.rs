@@ -0,0 +1,191 @@
+//! SVG export for the map images
+//!
+//! Pixels are merged into rectangles, and all rectangles with the same color are written as
+//! a single path. This keeps the files small enough for posters and wiki pages.
+
+use crate::text::StyledText;
+use crate::transform::MapTransform;
+use crate::MapItem;
+use image::{Rgba, RgbaImage};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Rectangle of pixels with the same color
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub color: Rgba<u8>,
+}
+
+/// Merges the non-transparent pixels of the *image* into rectangles
+///
+/// Each row is split into runs of the same color, and runs continuing at the same columns on the
+/// following rows are merged.
+pub fn vectorize(image: &RgbaImage) -> Vec<Rect> {
+    let mut rects = Vec::new();
+    // Open rectangles keyed by the columns and the color of their runs
+    let mut open: BTreeMap<(u32, u32, [u8; 4]), Rect> = BTreeMap::new();
+    for y in 0..image.height() {
+        let mut continued = BTreeMap::new();
+        let mut x = 0;
+        while x < image.width() {
+            let color = *image.get_pixel(x, y);
+            let start = x;
+            while x < image.width() && *image.get_pixel(x, y) == color {
+                x += 1;
+            }
+            if color[3] == 0 {
+                continue;
+            }
+            let key = (start, x, color.0);
+            let rect = match open.remove(&key) {
+                Some(rect) => Rect {
+                    height: rect.height + 1,
+                    ..rect
+                },
+                None => Rect {
+                    x: start,
+                    y,
+                    width: x - start,
+                    height: 1,
+                    color,
+                },
+            };
+            continued.insert(key, rect);
+        }
+        rects.extend(open.into_values());
+        open = continued;
+    }
+    rects.extend(open.into_values());
+    rects
+}
+
+/// Writes the *image* as an SVG document
+///
+/// The banners of the *map_item* are added as labeled markers.
+pub fn map_svg(map_item: &MapItem, image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" shape-rendering="crispEdges">"#
+    );
+
+    // One path for each color
+    let mut paths: BTreeMap<[u8; 4], String> = BTreeMap::new();
+    for rect in vectorize(image) {
+        let path = paths.entry(rect.color.0).or_default();
+        let _ = write!(
+            path,
+            "M{} {}h{}v{}h-{}z",
+            rect.x, rect.y, rect.width, rect.height, rect.width
+        );
+    }
+    for ([r, g, b, a], path) in paths {
+        let opacity = if a == 255 {
+            String::new()
+        } else {
+            format!(r#" fill-opacity="{:.3}""#, a as f32 / 255.0)
+        };
+        let _ = writeln!(
+            svg,
+            r##"  <path fill="#{r:02x}{g:02x}{b:02x}"{opacity} d="{path}"/>"##
+        );
+    }
+
+    // Banner markers
+    let transform = MapTransform::of_map(&map_item.data);
+    for banner in &map_item.data.banners {
+        let (x, y) = transform.position(banner.pos.x as f64, banner.pos.z as f64);
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        let [r, g, b] = banner.color.rgb();
+        let name = escape(&banner.extract_name());
+        let _ = writeln!(svg, r#"  <g class="banner">"#);
+        let _ = writeln!(svg, "    <title>{name}</title>");
+        let _ = writeln!(
+            svg,
+            r##"    <circle cx="{x}" cy="{y}" r="2" fill="#{r:02x}{g:02x}{b:02x}" stroke="#000" stroke-width="0.5"/>"##
+        );
+        if let Some(styled_name) = banner.styled_name() {
+            let _ = writeln!(
+                svg,
+                r##"    <text x="{x}" y="{}" font-family="sans-serif" font-size="4" text-anchor="middle" fill="#000">{}</text>"##,
+                y - 3.0,
+                tspans(&styled_name)
+            );
+        }
+        let _ = writeln!(svg, "  </g>");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The spans of the *text* as tspan elements, plain spans are written without an element
+fn tspans(text: &StyledText) -> String {
+    let mut tspans = String::new();
+    for span in &text.spans {
+        let style = &span.style;
+        let mut attributes = String::new();
+        if let Some([r, g, b]) = style.rgb() {
+            let _ = write!(attributes, r##" fill="#{r:02x}{g:02x}{b:02x}""##);
+        }
+        if style.bold {
+            attributes.push_str(r#" font-weight="bold""#);
+        }
+        if style.italic {
+            attributes.push_str(r#" font-style="italic""#);
+        }
+        let decorations: Vec<&str> = [
+            (style.underlined, "underline"),
+            (style.strikethrough, "line-through"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, decoration)| *decoration)
+        .collect();
+        if !decorations.is_empty() {
+            let _ = write!(
+                attributes,
+                r#" text-decoration="{}""#,
+                decorations.join(" ")
+            );
+        }
+        match attributes.is_empty() {
+            true => tspans.push_str(&escape(&span.text)),
+            false => {
+                let _ = write!(tspans, "<tspan{attributes}>{}</tspan>", escape(&span.text));
+            }
+        }
+    }
+    tspans
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::svg::vectorize;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_vectorize_merges_pixels() {
+        let mut image = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        for x in 0..4 {
+            image.put_pixel(x, 3, Rgba([0, 0, 0, 0]));
+        }
+        image.put_pixel(1, 1, Rgba([200, 0, 0, 255]));
+        let rects = vectorize(&image);
+        // Top row, left and right sides of the red pixel, the red pixel and the row under it
+        assert_eq!(rects.len(), 5);
+        let area: u32 = rects.iter().map(|rect| rect.width * rect.height).sum();
+        assert_eq!(area, 12);
+    }
+}
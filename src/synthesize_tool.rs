@@ -0,0 +1,140 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::edit::synthesize;
+use minecraft_map_tool::stitch::Area;
+use minecraft_map_tool::{read_maps_from, MapData, MapItem};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+Works like zooming out maps in a cartography table, but the new map is filled with the \
+exploration of all the maps covering its area, for example several maps with zoom 0 \
+make one fully explored map with zoom 1.")]
+pub struct SynthesizeArgs {
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which the maps are
+    /// sampled. Glob patterns like data/map_1*.dat are expanded. With the sftp feature, this
+    /// can also be a remote directory (sftp://user@host/path)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Write the new map to this file
+    #[arg(short, long)]
+    output_file: PathBuf,
+
+    /// Zoom level of the new map, from 0 to 4. Maps with a larger zoom are not used.
+    #[arg(short, long, value_parser = clap::value_parser!(i8).range(0..=4))]
+    zoom: i8,
+
+    /// A block the new map should cover as X,Z. The map is aligned like the game does.
+    #[arg(
+        short,
+        long,
+        value_name = "X,Z",
+        value_parser = parse_center,
+        allow_hyphen_values = true
+    )]
+    center: (i32, i32),
+
+    /// Only use maps with matching dimensions name
+    #[arg(short, long, default_value = "Overworld")]
+    dimension: String,
+
+    /// Search map files recursively in subdirectories
+    #[arg(long)]
+    recursive: bool,
+}
+
+fn parse_center(text: &str) -> Result<(i32, i32), String> {
+    let values: Vec<_> = text.split(',').map(|value| value.trim().parse()).collect();
+    match values.as_slice() {
+        [Ok(x), Ok(z)] => Ok((*x, *z)),
+        _ => Err(String::from("Expected two integers separated by a comma")),
+    }
+}
+
+pub fn run(args: &SynthesizeArgs) -> ExitCode {
+    let maps = match read_maps_from(&args.paths, &None, args.recursive) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not read maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let half_width = 64 * 2i32.pow(args.zoom as u32);
+    let (x, z) = args.center;
+    let (x_center, z_center) = MapData::aligned_center_for(x, z, args.zoom);
+    let area = Area {
+        left: x_center - half_width,
+        top: z_center - half_width,
+        right: x_center + half_width - 1,
+        bottom: z_center + half_width - 1,
+    };
+    let dimension = args.dimension.to_lowercase();
+    let mut sources = Vec::new();
+    for (path, handle) in maps.handles() {
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(err) => {
+                warn!("Skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+        let metadata = handle.metadata();
+        if metadata.scale > args.zoom
+            || metadata.pretty_dimension().to_lowercase() != dimension
+            || !metadata.area().overlaps(&area)
+        {
+            continue;
+        }
+        match handle.load() {
+            Ok(map_item) => sources.push(map_item),
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+    if sources.is_empty() {
+        error!(
+            "No maps cover the area {}, {} to {}, {}",
+            area.left, area.top, area.right, area.bottom
+        );
+        return ExitCode::FAILURE;
+    }
+
+    let data = match synthesize(
+        sources.iter().map(|map_item| &map_item.data),
+        args.zoom,
+        x_center,
+        z_center,
+    ) {
+        Ok(data) => data,
+        Err(err) => {
+            error!("Could not make the map: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let explored = data.colors.iter().filter(|&&c| (c as u8) >= 4).count();
+    info!(
+        "New map with scale {} is centered at {}, {} and made from {} maps, {} of 16384 pixels explored",
+        data.scale_description(),
+        data.x_center,
+        data.z_center,
+        sources.len(),
+        explored
+    );
+    let map_item = MapItem {
+        file: args.output_file.clone(),
+        data,
+        data_version: sources
+            .iter()
+            .map(|map_item| map_item.data_version)
+            .max()
+            .unwrap_or_default(),
+    };
+    if let Err(err) = sink().write_with(&map_item.file, |file| map_item.write_to(file)) {
+        error!("Could not write {}: {err}", args.output_file.display());
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
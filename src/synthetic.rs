@@ -0,0 +1,371 @@
+//! Synthetic worlds for testing
+//!
+//! The functions in this module generate a world directory with the same layout as
+//! the game uses. The world only contains the files this tool is interested in, so there
+//! are no chunks, but the map items look like they could have been filled in the game.
+//! A few maps are placed in item frames and chests, stored in entity and region files, and
+//! a player carries some maps.
+
+use crate::error::Result;
+use crate::region::write_region;
+use crate::{Banner, BannerColor, MapData, MapItem, Pos};
+use fastnbt::{nbt, ByteArray, Value};
+use flate2::{write::GzEncoder, Compression};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// Data version used for the generated files (Java Edition 1.20.4)
+pub const SYNTHETIC_DATA_VERSION: i32 = 3700;
+
+/// Description of a group of maps in the synthetic world
+struct MapGroup {
+    dimension: &'static str,
+    scale: i8,
+    columns: i32,
+    rows: i32,
+}
+
+/// Maps generated to the synthetic world
+///
+/// The first group is a square of detailed Overworld maps, and the rest cover other
+/// scales and dimensions.
+const MAP_GROUPS: [MapGroup; 7] = [
+    MapGroup {
+        dimension: "minecraft:overworld",
+        scale: 0,
+        columns: 4,
+        rows: 4,
+    },
+    MapGroup {
+        dimension: "minecraft:overworld",
+        scale: 1,
+        columns: 2,
+        rows: 2,
+    },
+    MapGroup {
+        dimension: "minecraft:overworld",
+        scale: 2,
+        columns: 2,
+        rows: 1,
+    },
+    MapGroup {
+        dimension: "minecraft:overworld",
+        scale: 3,
+        columns: 1,
+        rows: 1,
+    },
+    MapGroup {
+        dimension: "minecraft:overworld",
+        scale: 4,
+        columns: 1,
+        rows: 1,
+    },
+    MapGroup {
+        dimension: "minecraft:the_nether",
+        scale: 0,
+        columns: 3,
+        rows: 2,
+    },
+    MapGroup {
+        dimension: "minecraft:the_end",
+        scale: 0,
+        columns: 2,
+        rows: 1,
+    },
+];
+
+/// Content of the level.dat stub
+#[derive(Serialize)]
+struct Level {
+    #[serde(rename = "Data")]
+    data: LevelData,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct LevelData {
+    level_name: String,
+    data_version: i32,
+    version: LevelVersion,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct LevelVersion {
+    id: i32,
+    name: String,
+    snapshot: i8,
+}
+
+/// Content of the idcounts.dat file
+#[derive(Serialize)]
+struct IdCounts {
+    data: IdCountsData,
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+}
+
+#[derive(Serialize)]
+struct IdCountsData {
+    map: i32,
+}
+
+/// Generates map items for the synthetic world
+///
+/// The maps are numbered from zero, and their files are located in the `data` directory
+/// under the given *world* directory. Nothing is written to the disk.
+pub fn synthetic_maps(world: &Path) -> Vec<MapItem> {
+    let data_dir = world.join("data");
+    let mut maps = Vec::new();
+    for group in &MAP_GROUPS {
+        let size = 128 * 2i32.pow(group.scale as u32);
+        for row in 0..group.rows {
+            for column in 0..group.columns {
+                let id = maps.len();
+                maps.push(MapItem {
+                    file: data_dir.join(format!("map_{id}.dat")),
+                    data: MapData {
+                        scale: group.scale,
+                        dimension: group.dimension.to_string(),
+                        tracking_position: 1,
+                        unlimited_tracking: 0,
+                        locked: 0,
+                        // Centers are aligned like the game does
+                        x_center: column * size + size / 2 - 64,
+                        z_center: row * size + size / 2 - 64,
+                        banners: synthetic_banners(id, column * size, row * size),
+                        frames: vec![],
+                        colors: ByteArray::new(synthetic_colors(id)),
+                    },
+                    data_version: SYNTHETIC_DATA_VERSION,
+                });
+            }
+        }
+    }
+    maps
+}
+
+/// Every third map gets a banner at the upper left corner
+fn synthetic_banners(id: usize, left: i32, top: i32) -> Vec<Banner> {
+    if !id.is_multiple_of(3) {
+        return vec![];
+    }
+    vec![Banner {
+        color: BannerColor::Red,
+        name: Some(format!(r#"{{"text":"Banner {id}"}}"#)),
+        pos: Pos {
+            x: left + 1,
+            y: 64,
+            z: top + 1,
+        },
+    }]
+}
+
+/// Each map uses its own base color with horizontal bands of all shades, and the
+/// bottom rows of every other map are left unexplored.
+fn synthetic_colors(id: usize) -> Vec<i8> {
+    let base_color = (id % 61 + 1) as u8;
+    let mut colors = Vec::with_capacity(128 * 128);
+    for y in 0..128 {
+        for _ in 0..128 {
+            let color = if !id.is_multiple_of(2) && y >= 112 {
+                0
+            } else {
+                base_color * 4 + (y / 32) as u8
+            };
+            colors.push(color as i8);
+        }
+    }
+    colors
+}
+
+/// Writes a synthetic world to the given *world* directory
+///
+/// Creates the `data`, `DIM-1` and `DIM1` directories, the `level.dat` stub, the
+/// `data/idcounts.dat`, the map files returned by [synthetic_maps] and region files
+/// with item frames holding maps 0, 5 and 24 and chests holding maps 4, 25 and the missing
+/// map 99. The only player carries maps 1, 2 and 3.
+/// Returns paths to the written map files.
+pub fn write_synthetic_world(world: &Path) -> Result<Vec<PathBuf>> {
+    let data_dir = world.join("data");
+    fs::create_dir_all(&data_dir)?;
+    fs::create_dir_all(world.join("DIM-1").join("data"))?;
+    fs::create_dir_all(world.join("DIM1").join("data"))?;
+
+    let level = Level {
+        data: LevelData {
+            level_name: "Synthetic World".to_string(),
+            data_version: SYNTHETIC_DATA_VERSION,
+            version: LevelVersion {
+                id: SYNTHETIC_DATA_VERSION,
+                name: "1.20.4".to_string(),
+                snapshot: 0,
+            },
+        },
+    };
+    write_nbt(&world.join("level.dat"), &level)?;
+
+    let maps = synthetic_maps(world);
+    let id_counts = IdCounts {
+        data: IdCountsData {
+            map: maps.len() as i32 - 1,
+        },
+        data_version: SYNTHETIC_DATA_VERSION,
+    };
+    write_nbt(&data_dir.join("idcounts.dat"), &id_counts)?;
+
+    let mut files = Vec::with_capacity(maps.len());
+    for map in maps {
+        map.write()?;
+        files.push(map.file);
+    }
+    write_region_files(world)?;
+    write_player(world)?;
+    Ok(files)
+}
+
+/// UUID of the player in the synthetic world
+pub const SYNTHETIC_PLAYER: &str = "00000000-0000-0000-0000-000000000001";
+
+/// Writes a player with two copies of map 1 in the inventory, map 2 in a shulker box and
+/// map 3 in the ender chest
+fn write_player(world: &Path) -> Result<()> {
+    let playerdata_dir = world.join("playerdata");
+    fs::create_dir_all(&playerdata_dir)?;
+    let player = nbt!({
+        "DataVersion": SYNTHETIC_DATA_VERSION,
+        "Inventory": [
+            {
+                "id": "minecraft:filled_map",
+                "count": 2,
+                "Slot": 0i8,
+                "components": { "minecraft:map_id": 1 },
+            },
+            {
+                "id": "minecraft:shulker_box",
+                "count": 1,
+                "Slot": 1i8,
+                "components": {
+                    "minecraft:container": [{ "slot": 0, "item": map_item(2) }],
+                },
+            },
+        ],
+        "EnderItems": [map_item(3)],
+    });
+    write_nbt(
+        &playerdata_dir.join(format!("{SYNTHETIC_PLAYER}.dat")),
+        &player,
+    )
+}
+
+/// Writes item frames and chests in the format of 1.20.5 and in the older region chunk format
+fn write_region_files(world: &Path) -> Result<()> {
+    let entities_dir = world.join("entities");
+    fs::create_dir_all(&entities_dir)?;
+    let chunk = nbt!({
+        "DataVersion": SYNTHETIC_DATA_VERSION,
+        "Entities": [
+            item_frame("minecraft:item_frame", [1, 64, 1], 1, map_item(0)),
+        ],
+    });
+    write_region(File::create(entities_dir.join("r.0.0.mca"))?, &[(0, chunk)])?;
+    let chunk = nbt!({
+        "DataVersion": SYNTHETIC_DATA_VERSION,
+        "Entities": [
+            item_frame("minecraft:glow_item_frame", [20, 65, -3], 2, map_item(5)),
+            item_frame("minecraft:item_frame", [21, 65, -3], 2, nbt!({
+                "id": "minecraft:compass",
+                "count": 1,
+            })),
+        ],
+    });
+    // Chunk (1, -1) is the second chunk on the last row of region (0, -1)
+    write_region(
+        File::create(entities_dir.join("r.0.-1.mca"))?,
+        &[(1 + 31 * 32, chunk)],
+    )?;
+
+    let region_dir = world.join("region");
+    fs::create_dir_all(&region_dir)?;
+    let chunk = nbt!({
+        "DataVersion": SYNTHETIC_DATA_VERSION,
+        "block_entities": [
+            {
+                "id": "minecraft:chest",
+                "x": 2,
+                "y": 64,
+                "z": 1,
+                "Items": [
+                    map_item(4),
+                    {
+                        "id": "minecraft:shulker_box",
+                        "count": 1,
+                        "components": {
+                            "minecraft:container": [{ "slot": 0, "item": map_item(99) }],
+                        },
+                    },
+                ],
+            },
+        ],
+    });
+    write_region(File::create(region_dir.join("r.0.0.mca"))?, &[(0, chunk)])?;
+
+    let region_dir = world.join("DIM-1").join("region");
+    fs::create_dir_all(&region_dir)?;
+    let chunk = nbt!({
+        "DataVersion": 2586,
+        "Level": {
+            "TileEntities": [
+                {
+                    "id": "minecraft:chest",
+                    "x": 4,
+                    "y": 40,
+                    "z": 5,
+                    "Items": [
+                        {
+                            "id": "minecraft:filled_map",
+                            "Count": 1i8,
+                            "Slot": 0i8,
+                            "tag": { "map": 25 },
+                        },
+                    ],
+                },
+            ],
+            "Entities": [
+                item_frame("minecraft:item_frame", [3, 40, 5], 4, nbt!({
+                    "id": "minecraft:filled_map",
+                    "Count": 1i8,
+                    "tag": { "map": 24 },
+                })),
+            ],
+        },
+    });
+    write_region(File::create(region_dir.join("r.0.0.mca"))?, &[(0, chunk)])
+}
+
+fn item_frame(id: &str, [x, y, z]: [i32; 3], facing: i8, item: Value) -> Value {
+    nbt!({
+        "id": id,
+        "TileX": x,
+        "TileY": y,
+        "TileZ": z,
+        "Facing": facing,
+        "Item": item,
+    })
+}
+
+fn map_item(id: i32) -> Value {
+    nbt!({
+        "id": "minecraft:filled_map",
+        "count": 1,
+        "components": { "minecraft:map_id": id },
+    })
+}
+
+fn write_nbt<T: Serialize>(file: &Path, value: &T) -> Result<()> {
+    let file_writer = File::create(file)?;
+    let encoder = GzEncoder::new(file_writer, Compression::default());
+    fastnbt::to_writer(encoder, value)?;
+    Ok(())
+}
@@ -1,8 +1,13 @@
-use clap::{arg, Args};
+use crate::changes::sink;
+use anyhow::anyhow;
+use clap::Args;
 use fastnbt::ByteArray;
+use log::{error, info};
+use minecraft_map_tool::palette::{generate_palette, BASE_COLORS_2699};
 use minecraft_map_tool::versions::MINECRAFT_VERSIONS;
-use minecraft_map_tool::{MapData, MapItem};
-use std::path::PathBuf;
+use minecraft_map_tool::{Banner, BannerColor, MapData, MapItem, Marker, Pos};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 #[derive(Args, Debug)]
@@ -14,6 +19,19 @@ pub struct TestMapArgs {
     /// Set data version to [default: latest known version]
     #[arg(short, long, value_name = "VERSION")]
     data_version: Option<i32>,
+
+    /// Write the whole suite of fixture maps with reference images and an index.json into
+    /// this directory instead of the single test map
+    #[arg(long, value_name = "DIR", conflicts_with = "output_file")]
+    suite: Option<PathBuf>,
+}
+
+/// Entry of the index.json in the fixture suite
+#[derive(Serialize)]
+struct Fixture {
+    map: String,
+    image: Option<String>,
+    description: &'static str,
 }
 
 pub fn run(args: &TestMapArgs) -> ExitCode {
@@ -28,7 +46,39 @@ pub fn run(args: &TestMapArgs) -> ExitCode {
         Some(version) => version,
     };
 
-    // Generating map with all colors, where each color have 8x8 pixels
+    if let Some(directory) = &args.suite {
+        return match write_suite(directory, data_version) {
+            Ok(count) => {
+                info!("{count} fixture maps written to: {:?}", directory);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                error!("Could not write the fixture suite: {err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let test_map = MapItem {
+        file: args.output_file.clone(),
+        data: overworld_map(0, 0, 0, all_colors()),
+        data_version,
+    };
+    let result = sink().write_with(&test_map.file, |file| test_map.write_to(file));
+    match result.map_err(|err| err.to_string()) {
+        Ok(_) => {
+            info!("Test map written to: {:?}", args.output_file);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            error!("Could not write test map: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Map with all colors, where each color have 8x8 pixels
+fn all_colors() -> Vec<i8> {
     let mut colors: Vec<i8> = Vec::with_capacity(128 * 128);
     let mut color = 0u8;
     for _ in 0..16 {
@@ -43,31 +93,151 @@ pub fn run(args: &TestMapArgs) -> ExitCode {
             colors.extend(&line);
         }
     }
+    colors
+}
 
-    let test_map = MapItem {
-        file: args.output_file.clone(),
-        data: MapData {
-            scale: 0,
-            dimension: "minecraft:overworld".to_string(),
-            tracking_position: 1,
-            unlimited_tracking: 0,
-            locked: 1,
-            x_center: 0,
-            z_center: 0,
-            banners: vec![],
-            frames: vec![],
-            colors: ByteArray::new(colors),
+/// Locked Overworld map without markers
+fn overworld_map(scale: i8, x_center: i32, z_center: i32, colors: Vec<i8>) -> MapData {
+    MapData {
+        scale,
+        dimension: "minecraft:overworld".to_string(),
+        tracking_position: 1,
+        unlimited_tracking: 0,
+        locked: 1,
+        x_center,
+        z_center,
+        banners: vec![],
+        frames: vec![],
+        colors: ByteArray::new(colors),
+    }
+}
+
+/// Maps of the fixture suite with their descriptions
+///
+/// The first map is the same as the single test map, so the suite can replace it.
+fn fixtures() -> Vec<(&'static str, MapData)> {
+    let mut fixtures = vec![("All colors", overworld_map(0, 0, 0, all_colors()))];
+    for scale in 1..=4 {
+        let (x_center, z_center) = MapData::aligned_center_for(0, 0, scale);
+        let map = overworld_map(scale, x_center, z_center, all_colors());
+        let description = match scale {
+            1 => "All colors at scale 1:2",
+            2 => "All colors at scale 1:4",
+            3 => "All colors at scale 1:8",
+            _ => "All colors at scale 1:16",
+        };
+        fixtures.push((description, map));
+    }
+
+    let mut nether = overworld_map(0, -128, 128, all_colors());
+    nether.dimension = "minecraft:the_nether".to_string();
+    fixtures.push(("All colors in the Nether", nether));
+    let mut end = overworld_map(0, 1024, -1024, all_colors());
+    end.dimension = "minecraft:the_end".to_string();
+    fixtures.push(("All colors in the End", end));
+    let mut modded = overworld_map(0, 0, 0, all_colors());
+    modded.dimension = "example:mining_world".to_string();
+    fixtures.push(("All colors in a dimension from a mod", modded));
+
+    let mut markers = overworld_map(0, 0, 0, all_colors());
+    markers.banners = vec![
+        Banner {
+            color: BannerColor::Red,
+            name: Some(r#"{"text":"JSON name"}"#.to_string()),
+            pos: Pos {
+                x: -64,
+                y: 64,
+                z: -64,
+            },
         },
-        data_version,
-    };
-    match test_map.write().map_err(|err| err.to_string()) {
-        Ok(_) => {
-            println!("Test map written to: {:?}", args.output_file);
-            ExitCode::SUCCESS
-        }
-        Err(err) => {
-            eprintln!("Could not write test map: {err}");
-            ExitCode::FAILURE
-        }
+        Banner {
+            color: BannerColor::LightBlue,
+            name: Some(r#""Plain name""#.to_string()),
+            pos: Pos {
+                x: 63,
+                y: 64,
+                z: 63,
+            },
+        },
+        Banner {
+            color: BannerColor::Black,
+            name: None,
+            pos: Pos { x: 0, y: -60, z: 0 },
+        },
+    ];
+    markers.frames = vec![
+        Marker {
+            entity_id: 1,
+            rotation: 0,
+            pos: Pos {
+                x: 10,
+                y: 70,
+                z: 10,
+            },
+        },
+        Marker {
+            entity_id: 2,
+            rotation: 270,
+            pos: Pos {
+                x: -10,
+                y: 70,
+                z: 20,
+            },
+        },
+    ];
+    fixtures.push(("Banners and item frame markers", markers));
+
+    fixtures.push(("Unexplored map", overworld_map(0, 0, 0, vec![0; 128 * 128])));
+    // Only the unused fourth shade of each base color
+    let shade_3 = (0..128 * 128).map(|i| ((i % 64) * 4 + 3) as u8 as i8);
+    fixtures.push((
+        "Fourth shades of the base colors",
+        overworld_map(0, 0, 0, shade_3.collect()),
+    ));
+    fixtures.push((
+        "Incomplete color array",
+        overworld_map(0, 0, 0, all_colors()[..1000].to_vec()),
+    ));
+    fixtures
+}
+
+/// Writes the fixture maps, their reference images and the index.json into the *directory*
+///
+/// The reference images are drawn with the palette of this version of the tool, so they can
+/// be regenerated when the palette changes. Maps that cannot be drawn get no image. Returns
+/// the number of maps written.
+fn write_suite(directory: &Path, data_version: i32) -> anyhow::Result<usize> {
+    sink().create_dir_all(directory)?;
+    let palette = generate_palette(&BASE_COLORS_2699);
+    let mut index = Vec::new();
+    for (id, (description, data)) in fixtures().into_iter().enumerate() {
+        let map = format!("map_{id}.dat");
+        let map_item = MapItem {
+            file: directory.join(&map),
+            data,
+            data_version,
+        };
+        sink()
+            .write_with(&map_item.file, |file| map_item.write_to(file))
+            .map_err(|err| anyhow!("{err}"))?;
+        let image = match map_item.make_image(&palette) {
+            Ok(image) => {
+                let name = format!("map_{id}.png");
+                sink().write_with(directory.join(&name), |file| image.save(file))?;
+                Some(name)
+            }
+            Err(_) => None,
+        };
+        info!("{map}: {description}");
+        index.push(Fixture {
+            map,
+            image,
+            description,
+        });
     }
+    sink().write(
+        directory.join("index.json"),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(index.len())
 }
@@ -0,0 +1,36 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info};
+use minecraft_map_tool::error::Error;
+use minecraft_map_tool::synthetic::write_synthetic_world;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct TestWorldArgs {
+    /// Directory for the world
+    #[arg(value_name = "DIR")]
+    output_dir: PathBuf,
+}
+
+pub fn run(args: &TestWorldArgs) -> ExitCode {
+    let mut files = Vec::new();
+    let result = sink().write_with(&args.output_dir, |directory| {
+        files = write_synthetic_world(directory)?;
+        Ok::<_, Error>(())
+    });
+    match result {
+        Ok(()) => {
+            info!(
+                "Test world with {} maps written to: {:?}",
+                files.len(),
+                args.output_dir
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            error!("Could not write test world: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
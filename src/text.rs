@@ -0,0 +1,316 @@
+//! Minecraft text components
+//!
+//! Custom names, like the names of banners, are stored as text components. A component can
+//! be a plain JSON string, an object with `text`, `translate`, `keybind`, `selector`,
+//! `score` or `nbt` content, or an array of components, and each component can have more
+//! components in its `extra` array. Styles are inherited from the parent component.
+//!
+//! Without the game's language files the translations cannot be looked up, so translated
+//! components show their `fallback` text, or the translation key, with the `with`
+//! arguments filled in.
+
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+/// Named text colors and their RGB values
+const TEXT_COLORS: [(&str, [u8; 3]); 16] = [
+    ("black", [0x00, 0x00, 0x00]),
+    ("dark_blue", [0x00, 0x00, 0xaa]),
+    ("dark_green", [0x00, 0xaa, 0x00]),
+    ("dark_aqua", [0x00, 0xaa, 0xaa]),
+    ("dark_red", [0xaa, 0x00, 0x00]),
+    ("dark_purple", [0xaa, 0x00, 0xaa]),
+    ("gold", [0xff, 0xaa, 0x00]),
+    ("gray", [0xaa, 0xaa, 0xaa]),
+    ("dark_gray", [0x55, 0x55, 0x55]),
+    ("blue", [0x55, 0x55, 0xff]),
+    ("green", [0x55, 0xff, 0x55]),
+    ("aqua", [0x55, 0xff, 0xff]),
+    ("red", [0xff, 0x55, 0x55]),
+    ("light_purple", [0xff, 0x55, 0xff]),
+    ("yellow", [0xff, 0xff, 0x55]),
+    ("white", [0xff, 0xff, 0xff]),
+];
+
+/// Deepest nesting of components that is followed, deeper components are left out
+const MAX_DEPTH: usize = 32;
+
+/// RGB value of a text *color*, a name like `dark_red` or a hex color like `#ff8800`
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some([(value >> 16) as u8, (value >> 8) as u8, value as u8]);
+    }
+    TEXT_COLORS
+        .iter()
+        .find(|(name, _)| *name == color)
+        .map(|(_, rgb)| *rgb)
+}
+
+/// Style of a piece of text
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TextStyle {
+    /// Color name, like `red`, or a hex color like `#ff8800`
+    pub color: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    pub obfuscated: bool,
+}
+
+impl TextStyle {
+    /// RGB value of the color, if the color is known
+    pub fn rgb(&self) -> Option<[u8; 3]> {
+        parse_color(self.color.as_deref()?)
+    }
+
+    /// Style of a child component, with the fields the *component* sets overriding these
+    fn inherit(&self, component: &serde_json::Map<String, Value>) -> TextStyle {
+        let flag = |key: &str, inherited: bool| match component.get(key) {
+            Some(Value::Bool(value)) => *value,
+            // Older versions also wrote the flags as strings
+            Some(Value::String(value)) => value == "true",
+            _ => inherited,
+        };
+        TextStyle {
+            color: match component.get("color") {
+                Some(Value::String(color)) => Some(color.clone()),
+                _ => self.color.clone(),
+            },
+            bold: flag("bold", self.bold),
+            italic: flag("italic", self.italic),
+            underlined: flag("underlined", self.underlined),
+            strikethrough: flag("strikethrough", self.strikethrough),
+            obfuscated: flag("obfuscated", self.obfuscated),
+        }
+    }
+}
+
+/// A piece of text with one style
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+/// Text made of styled spans, flattened from a text component
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StyledText {
+    pub spans: Vec<TextSpan>,
+}
+
+impl StyledText {
+    /// Reads the text component from the *json*
+    ///
+    /// Text that is not valid JSON is taken as it is, like the game does with names that are
+    /// not components.
+    pub fn parse(json: &str) -> StyledText {
+        match serde_json::from_str::<Value>(json) {
+            Ok(value) => StyledText::from_value(&value),
+            Err(_) => StyledText {
+                spans: vec![TextSpan {
+                    text: json.to_string(),
+                    style: TextStyle::default(),
+                }],
+            },
+        }
+    }
+
+    /// Flattens the text component in the *value*
+    pub fn from_value(value: &Value) -> StyledText {
+        let mut text = StyledText::default();
+        text.add(value, &TextStyle::default(), 0);
+        text
+    }
+
+    /// Text without the styles
+    pub fn plain(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Adds the spans of the component in the *value* with the *style* of its parent
+    fn add(&mut self, value: &Value, style: &TextStyle, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        match value {
+            Value::Null => {}
+            Value::String(text) => self.push(text.clone(), style),
+            Value::Bool(_) | Value::Number(_) => self.push(value.to_string(), style),
+            // The first component is the parent of the others
+            Value::Array(components) => {
+                if let Some((first, rest)) = components.split_first() {
+                    let style = match first {
+                        Value::Object(component) => style.inherit(component),
+                        _ => style.clone(),
+                    };
+                    self.add(first, &style, depth + 1);
+                    for component in rest {
+                        self.add(component, &style, depth + 1);
+                    }
+                }
+            }
+            Value::Object(component) => {
+                let style = style.inherit(component);
+                self.add_content(component, &style, depth);
+                if let Some(Value::Array(extra)) = component.get("extra") {
+                    for component in extra {
+                        self.add(component, &style, depth + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds the content of the *component* itself, without its extra components
+    fn add_content(
+        &mut self,
+        component: &serde_json::Map<String, Value>,
+        style: &TextStyle,
+        depth: usize,
+    ) {
+        let string = |key: &str| component.get(key).and_then(Value::as_str);
+        if let Some(text) = component.get("text") {
+            self.add(text, style, depth + 1);
+        } else if let Some(key) = string("translate") {
+            let format = string("fallback").unwrap_or(key);
+            let arguments = match component.get("with") {
+                Some(Value::Array(arguments)) => arguments.as_slice(),
+                _ => &[],
+            };
+            self.add_translation(format, arguments, style, depth);
+        } else if let Some(key) = string("keybind") {
+            self.push(key.to_string(), style);
+        } else if let Some(selector) = string("selector") {
+            self.push(selector.to_string(), style);
+        } else if let Some(Value::Object(score)) = component.get("score") {
+            // The score is not known outside the game, so the stored value or the holder is shown
+            if let Some(value) = score.get("value").and_then(Value::as_str) {
+                self.push(value.to_string(), style);
+            } else if let Some(name) = score.get("name").and_then(Value::as_str) {
+                self.push(name.to_string(), style);
+            }
+        } else if let Some(path) = string("nbt") {
+            self.push(path.to_string(), style);
+        }
+    }
+
+    /// Adds the translation *format* with the `%s` and `%1$s` placeholders replaced with the
+    /// *arguments*
+    fn add_translation(
+        &mut self,
+        format: &str,
+        arguments: &[Value],
+        style: &TextStyle,
+        depth: usize,
+    ) {
+        let mut next = 0;
+        let mut rest = format;
+        while let Some(start) = rest.find('%') {
+            self.push(rest[..start].to_string(), style);
+            rest = &rest[start + 1..];
+            if let Some(after) = rest.strip_prefix('%') {
+                self.push(String::from("%"), style);
+                rest = after;
+                continue;
+            }
+            let digits = rest.chars().take_while(char::is_ascii_digit).count();
+            let index = match rest[digits..].strip_prefix("$s") {
+                Some(after) if digits > 0 => {
+                    let index = rest[..digits].parse::<usize>().ok().map(|n| n - 1);
+                    rest = after;
+                    index
+                }
+                _ => match rest.strip_prefix('s') {
+                    Some(after) => {
+                        rest = after;
+                        next += 1;
+                        Some(next - 1)
+                    }
+                    None => {
+                        self.push(String::from("%"), style);
+                        continue;
+                    }
+                },
+            };
+            if let Some(argument) = index.and_then(|index| arguments.get(index)) {
+                self.add(argument, style, depth + 1);
+            }
+        }
+        self.push(rest.to_string(), style);
+    }
+
+    /// Adds the *text*, joining it with the last span if the style is the same
+    fn push(&mut self, text: String, style: &TextStyle) {
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            Some(span) if &span.style == style => span.text.push_str(&text),
+            _ => self.spans.push(TextSpan {
+                text,
+                style: style.clone(),
+            }),
+        }
+    }
+}
+
+impl Display for StyledText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for span in &self.spans {
+            f.write_str(&span.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::{StyledText, TextStyle};
+
+    #[test]
+    fn test_text_components() {
+        assert_eq!(StyledText::parse(r#"{"text":"Base"}"#).plain(), "Base");
+        assert_eq!(StyledText::parse(r#""Base""#).plain(), "Base");
+        assert_eq!(StyledText::parse("Base").plain(), "Base");
+        assert_eq!(
+            StyledText::parse(r#"["Ba", {"text":"se"}]"#).plain(),
+            "Base"
+        );
+        assert_eq!(
+            StyledText::parse(r#"{"text":"Ba","extra":["s",{"text":"e","extra":[1]}]}"#).plain(),
+            "Base1"
+        );
+        assert_eq!(
+            StyledText::parse(r#"{"translate":"%2$s of %s","with":["Tower",{"text":"Base"}]}"#)
+                .plain(),
+            "Base of Tower"
+        );
+        assert_eq!(
+            StyledText::parse(r#"{"translate":"unknown.key","fallback":"100%% %s","with":[5]}"#)
+                .plain(),
+            "100% 5"
+        );
+        assert_eq!(
+            StyledText::parse(r#"{"keybind":"key.jump"}"#).plain(),
+            "key.jump"
+        );
+
+        let text = StyledText::parse(
+            r#"{"text":"A","color":"red","bold":true,"extra":[{"text":"B","bold":false},"C"]}"#,
+        );
+        let styles: Vec<_> = text.spans.iter().map(|span| span.style.clone()).collect();
+        assert_eq!(text.plain(), "ABC");
+        assert_eq!(text.spans.len(), 3);
+        assert!(styles[0].bold && !styles[1].bold && styles[2].bold);
+        assert_eq!(styles[1].rgb(), Some([0xff, 0x55, 0x55]));
+        let hex = TextStyle {
+            color: Some(String::from("#12ab34")),
+            ..TextStyle::default()
+        };
+        assert_eq!(hex.rgb(), Some([0x12, 0xab, 0x34]));
+    }
+}
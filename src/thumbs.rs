@@ -0,0 +1,151 @@
+//! Thumbnail sprite sheets
+//!
+//! Web pages showing thousands of maps load slowly when each map is a separate image. A
+//! sprite sheet has the thumbnails of all maps in one image, and an index tells where each
+//! map is in the sheet and which area of the world it shows.
+
+use crate::error::{Error, Result};
+use crate::palette::Palette;
+use crate::source::map_id;
+use crate::stitch::paint_image;
+use crate::MapItem;
+use image::imageops::{resize, FilterType};
+use image::RgbaImage;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Where a map is in the sprite sheet and what it shows
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Thumbnail {
+    /// Map id from the file name, null if the file is not named map_<#>.dat
+    pub id: Option<u32>,
+
+    /// X coordinate of the thumbnail in the sprite sheet
+    pub x: u32,
+
+    /// Y coordinate of the thumbnail in the sprite sheet
+    pub y: u32,
+
+    /// Pretty dimension name, like Overworld
+    pub dimension: String,
+
+    /// How zoomed out the map is, from 0 to 4
+    pub scale: i8,
+
+    /// X coordinate of the left edge of the map in the world
+    pub left: i32,
+
+    /// Z coordinate of the top edge of the map in the world
+    pub top: i32,
+
+    /// X coordinate of the right edge of the map in the world
+    pub right: i32,
+
+    /// Z coordinate of the bottom edge of the map in the world
+    pub bottom: i32,
+}
+
+/// Index of a sprite sheet
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ThumbnailIndex {
+    /// File name of the sprite sheet image
+    pub image: String,
+
+    /// Width and height of each thumbnail in pixels
+    pub size: u32,
+
+    /// Thumbnails by the file name of the map without the extension, like map_12
+    pub maps: BTreeMap<String, Thumbnail>,
+}
+
+/// Draws thumbnails of the *maps* into one image, *columns* thumbnails per row
+///
+/// Each map is drawn *size* pixels wide, and the index refers to the sheet by the *image*
+/// file name. Maps are placed in the order they are given.
+pub fn make_sprite_sheet(
+    maps: &[MapItem],
+    palette: &Palette,
+    size: u32,
+    columns: u32,
+    image: &str,
+) -> Result<(RgbaImage, ThumbnailIndex)> {
+    if size == 0 || columns == 0 {
+        return Err(Error::map_item_error(
+            "Thumbnail size and column count must be positive",
+        ));
+    }
+    let rows = (maps.len() as u32).div_ceil(columns);
+    let mut sheet = RgbaImage::new(size * columns.min(maps.len() as u32), size * rows);
+    let mut index = ThumbnailIndex {
+        image: image.to_string(),
+        size,
+        maps: BTreeMap::new(),
+    };
+    // The same map image is reused for all maps
+    let mut map_image = RgbaImage::new(128, 128);
+    for (position, map_item) in maps.iter().enumerate() {
+        map_item.data.render_into(&mut map_image, palette)?;
+        let x = position as u32 % columns * size;
+        let y = position as u32 / columns * size;
+        if size == 128 {
+            paint_image(&map_image, &mut sheet, x as i32, y as i32);
+        } else {
+            let filter = if size > 128 {
+                FilterType::Nearest
+            } else {
+                FilterType::Triangle
+            };
+            let thumbnail = resize(&map_image, size, size, filter);
+            paint_image(&thumbnail, &mut sheet, x as i32, y as i32);
+        }
+        let name = map_item
+            .file
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        index.maps.insert(
+            name,
+            Thumbnail {
+                id: map_id(&map_item.file),
+                x,
+                y,
+                dimension: map_item.data.pretty_dimension(),
+                scale: map_item.data.scale,
+                left: map_item.data.left(),
+                top: map_item.data.top(),
+                right: map_item.data.right(),
+                bottom: map_item.data.bottom(),
+            },
+        );
+    }
+    Ok((sheet, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{generate_palette, BASE_COLORS_2699};
+    use crate::synthetic::synthetic_maps;
+    use crate::thumbs::make_sprite_sheet;
+    use std::path::Path;
+
+    #[test]
+    fn test_make_sprite_sheet() {
+        let maps = synthetic_maps(Path::new("world"));
+        let palette = generate_palette(&BASE_COLORS_2699);
+        let (sheet, index) = make_sprite_sheet(&maps, &palette, 32, 10, "thumbs.png").unwrap();
+        assert_eq!(sheet.dimensions(), (320, 32 * 4));
+        assert_eq!(index.maps.len(), maps.len());
+        let thumbnail = &index.maps["map_12"];
+        assert_eq!((thumbnail.x, thumbnail.y), (64, 32));
+        assert_eq!(thumbnail.id, Some(12));
+        assert_eq!(thumbnail.left, maps[12].data.left());
+
+        // Thumbnails in full size are copied as they are
+        let (sheet, _) = make_sprite_sheet(&maps[..3], &palette, 128, 10, "thumbs.png").unwrap();
+        let image = maps[1].make_image(&palette).unwrap();
+        assert_eq!(sheet.get_pixel(128, 0), image.get_pixel(0, 0));
+    }
+}
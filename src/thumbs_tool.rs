@@ -0,0 +1,109 @@
+use crate::changes::sink;
+use crate::image_tool::ColorArgs;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::thumbs::make_sprite_sheet;
+use minecraft_map_tool::{read_maps_sorted, SortSpec};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+The index is written next to the image with the .json extension. It has the thumbnail \
+size and, for each map by its file name, the position in the image, the dimension, the \
+zoom and the area of the world the map shows.")]
+pub struct ThumbsArgs {
+    /// The directory or world backup archive (.zip, .tar.gz) from which map files are searched for.
+    /// With the sftp feature, this can also be a remote directory (sftp://user@host/path)
+    path: PathBuf,
+
+    /// The sprite sheet image file
+    #[arg(short, long, default_value = "thumbs.png")]
+    output_file: PathBuf,
+
+    /// Width and height of each thumbnail in pixels
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..=512))]
+    size: u32,
+
+    /// Thumbnails in each row of the image [default: about as many as rows]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    columns: Option<u32>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Order of the thumbnails. Keys are name, time, id, data-version and scale.
+    /// Combine keys with commas and reverse them with :desc, for example scale,time:desc
+    #[arg(short, long, default_value = "id")]
+    sort: Option<SortSpec>,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+}
+
+pub fn run(args: &ThumbsArgs) -> ExitCode {
+    let mut maps = match read_maps_sorted(&args.path, &args.sort, args.recursive) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not get maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut map_items = Vec::new();
+    while let Some((path, map_item)) = maps.next_with_path() {
+        match map_item {
+            Ok(map_item) => map_items.push(map_item),
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+    if map_items.is_empty() {
+        error!("No map files found");
+        return ExitCode::FAILURE;
+    }
+
+    let columns = args
+        .columns
+        .unwrap_or_else(|| (map_items.len() as f64).sqrt().ceil() as u32);
+    let image_name = args
+        .output_file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let (sheet, index) = match make_sprite_sheet(
+        &map_items,
+        &args.colors.palette(),
+        args.size,
+        columns,
+        &image_name,
+    ) {
+        Ok(result) => result,
+        Err(err) => {
+            error!("Could not make the sprite sheet: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = sink().write_with(&args.output_file, |file| sheet.save(file)) {
+        error!("Could not write {}: {err}", args.output_file.display());
+        return ExitCode::FAILURE;
+    }
+    let index_file = args.output_file.with_extension("json");
+    let json = match serde_json::to_string_pretty(&index) {
+        Ok(json) => json,
+        Err(err) => {
+            error!("Could not make the index: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = sink().write(&index_file, json) {
+        error!("Could not write {}: {err}", index_file.display());
+        return ExitCode::FAILURE;
+    }
+    info!(
+        "{} thumbnails written to {} with the index {}",
+        index.maps.len(),
+        args.output_file.display(),
+        index_file.display()
+    );
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,128 @@
+//! Copying maps between worlds
+//!
+//! Map ids are only unique within a world, so the copied maps get new ids after the last
+//! map of the destination world. The destination `idcounts.dat` is updated, so the game
+//! does not give the same ids to new maps.
+
+use crate::compression::NbtCompression;
+use crate::error::{Error, Result};
+use crate::handle::MapMetadata;
+use crate::nbt::write_nbt;
+use crate::read_maps;
+use crate::renumber::{last_map_id, update_last_map_id};
+use crate::sink::Sink;
+use crate::source::{map_id, IdRanges};
+use fastnbt::nbt;
+use log::info;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Copies the map files from the *source* world to the *destination* world
+///
+/// Only maps with ids in *ids* are copied, or all of them when *ids* is None.
+/// The destination is written through the *sink*. Returns the new ids in the destination by
+/// the ids in the source.
+pub fn transfer_maps(
+    source: &Path,
+    destination: &Path,
+    ids: Option<&IdRanges>,
+    sink: &Sink,
+) -> Result<BTreeMap<u32, u32>> {
+    let source_data = source.join("data");
+    let destination_data = destination.join("data");
+    let mut maps: Vec<_> = read_maps(&source_data, &None, false)?
+        .paths()
+        .filter_map(|path| Some((map_id(path)?, path.to_path_buf())))
+        .filter(|(id, _)| ids.is_none_or(|ids| ids.contains(*id)))
+        .collect();
+    maps.sort();
+
+    sink.create_dir_all(&destination_data)?;
+    let mut next_id = first_free_id(&destination_data)?;
+    let mut transferred = BTreeMap::new();
+    for (old_id, file) in &maps {
+        let target = destination_data.join(format!("map_{next_id}.dat"));
+        if target.exists() {
+            let message = format!("{} already exists", target.display());
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, message).into());
+        }
+        info!("Copying map_{old_id}.dat to {}", target.display());
+        sink.copy(file, &target)?;
+        transferred.insert(*old_id, next_id);
+        next_id += 1;
+    }
+
+    if let Some((_, file)) = maps.first() {
+        let last_id = next_id as i32 - 1;
+        if !update_last_map_id(&destination_data, last_id, sink)? {
+            let data_version = MapMetadata::read_from(file)?.data_version;
+            let id_counts = nbt!({
+                "data": { "map": last_id },
+                "DataVersion": data_version,
+            });
+            let file = sink.create(destination_data.join("idcounts.dat"))?;
+            write_nbt(file, &id_counts, NbtCompression::Gzip)?;
+        }
+    }
+    Ok(transferred)
+}
+
+/// The first id after the maps counted in idcounts.dat and the map files in the *data_dir*
+///
+/// A missing *data_dir* has no maps, which happens in a dry run to a new world.
+fn first_free_id(data_dir: &Path) -> Result<u32> {
+    let counted = last_map_id(data_dir)?.map_or(0, |id| id + 1);
+    let mut first_free = u32::try_from(counted)
+        .map_err(|_| Error::map_item_error("Negative map id in idcounts.dat"))?;
+    if !data_dir.is_dir() {
+        return Ok(first_free);
+    }
+    for entry in fs::read_dir(data_dir)? {
+        if let Some(id) = map_id(&entry?.path()) {
+            first_free = first_free.max(id + 1);
+        }
+    }
+    Ok(first_free)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renumber::last_map_id;
+    use crate::sink::Sink;
+    use crate::source::parse_id_ranges;
+    use crate::synthetic::write_synthetic_world;
+    use crate::transfer::transfer_maps;
+
+    #[test]
+    fn test_transfer_maps() {
+        let source = tempfile::tempdir().unwrap();
+        write_synthetic_world(source.path()).unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        write_synthetic_world(destination.path()).unwrap();
+
+        let ids = parse_id_ranges("3,5..6").unwrap();
+        let sink = Sink::default();
+        let transferred =
+            transfer_maps(source.path(), destination.path(), Some(&ids), &sink).unwrap();
+        let pairs: Vec<_> = transferred.into_iter().collect();
+        assert_eq!(pairs, [(3, 32), (5, 33), (6, 34)]);
+        let data_dir = destination.path().join("data");
+        assert!(data_dir.join("map_34.dat").is_file());
+        assert_eq!(last_map_id(&data_dir).unwrap(), Some(34));
+
+        let empty = tempfile::tempdir().unwrap();
+        let transferred = transfer_maps(source.path(), empty.path(), Some(&ids), &sink).unwrap();
+        assert_eq!(transferred[&6], 2);
+        assert_eq!(last_map_id(&empty.path().join("data")).unwrap(), Some(2));
+
+        // Nothing is written in a dry run, not even the data directory
+        let empty = tempfile::tempdir().unwrap();
+        let dry_run = Sink::new(true);
+        let transferred = transfer_maps(source.path(), empty.path(), Some(&ids), &dry_run).unwrap();
+        assert_eq!(transferred[&6], 2);
+        assert_eq!(dry_run.changes().len(), 4);
+        assert!(!empty.path().join("data").exists());
+    }
+}
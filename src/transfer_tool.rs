@@ -0,0 +1,59 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info};
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use minecraft_map_tool::transfer::transfer_maps;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+The old and new ids are printed as JSON, for example [{\"old\": 3, \"new\": 40}], \
+so the maps can be given back to players with commands or a datapack.")]
+pub struct TransferArgs {
+    /// The world directory the maps are copied from
+    source: PathBuf,
+
+    /// The world directory the maps are copied to
+    destination: PathBuf,
+
+    /// Copy only these map ids, for example 0..10,20,100.. (all maps by default)
+    #[arg(long, value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+}
+
+/// Old and new id of a copied map
+#[derive(JsonSchema, Serialize)]
+pub(crate) struct IdMapping {
+    /// Map id in the source world
+    old: u32,
+
+    /// Map id in the destination world
+    new: u32,
+}
+
+pub fn run(args: &TransferArgs) -> ExitCode {
+    let transferred =
+        match transfer_maps(&args.source, &args.destination, args.ids.as_ref(), sink()) {
+            Ok(transferred) => transferred,
+            Err(err) => {
+                error!("Could not transfer maps: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+    info!("{} maps copied", transferred.len());
+    let mapping: Vec<_> = transferred
+        .iter()
+        .map(|(&old, &new)| IdMapping { old, new })
+        .collect();
+    match serde_json::to_string_pretty(&mapping) {
+        Ok(json) => println!("{json}"),
+        Err(err) => {
+            error!("Could not write the id mapping: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,180 @@
+//! Converting between world coordinates and image pixels
+//!
+//! A map image, or a stitched canvas, starts from a world coordinate at its upper left
+//! corner, and each pixel covers a square of 2<sup>scale</sup> blocks. [MapTransform] keeps
+//! these together, so drawing maps, overlays and masks does the arithmetic the same way.
+
+use crate::stitch::Area;
+use crate::MapData;
+
+/// Name of the Overworld, see [MapData::pretty_dimension]
+pub const OVERWORLD_NAME: &str = "Overworld";
+
+/// Name of the Nether, see [MapData::pretty_dimension]
+pub const NETHER_NAME: &str = "The Nether";
+
+/// Each block in the Nether is this many blocks in the Overworld
+pub const NETHER_SCALE: i32 = 8;
+
+/// Overworld coordinates matching the Nether coordinates *x*, *z*, like nether portals
+/// are linked in the game
+pub fn nether_to_overworld(x: i32, z: i32) -> (i32, i32) {
+    (x * NETHER_SCALE, z * NETHER_SCALE)
+}
+
+/// Overworld blocks covered by the *area* in the Nether
+pub fn nether_area_to_overworld(area: &Area) -> Area {
+    let (left, top) = nether_to_overworld(area.left, area.top);
+    let (right, bottom) = nether_to_overworld(area.right + 1, area.bottom + 1);
+    Area {
+        left,
+        top,
+        right: right - 1,
+        bottom: bottom - 1,
+    }
+}
+
+/// Mapping between the world coordinates and the pixels of an image
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MapTransform {
+    /// X coordinate of the blocks on the left edge of the image
+    pub left: i32,
+
+    /// Z coordinate of the blocks on the top edge of the image
+    pub top: i32,
+
+    /// Each pixel is 2<sup>scale</sup> blocks wide, like in the maps
+    pub scale: i8,
+}
+
+impl MapTransform {
+    /// Transform of the 128×128 image of the *map*
+    pub fn of_map(map: &MapData) -> MapTransform {
+        MapTransform {
+            left: map.left(),
+            top: map.top(),
+            scale: map.scale,
+        }
+    }
+
+    /// Transform of an image showing the *area* with the *scale*
+    pub fn of_area(area: &Area, scale: i8) -> MapTransform {
+        MapTransform {
+            left: area.left,
+            top: area.top,
+            scale,
+        }
+    }
+
+    /// Width of a pixel in blocks
+    pub fn block_size(&self) -> i32 {
+        2i32.pow(self.scale as u32)
+    }
+
+    /// Pixel containing the block at the world coordinates *x*, *z*
+    ///
+    /// The pixel can be outside the image, even with negative coordinates.
+    pub fn pixel_at(&self, x: i32, z: i32) -> (i32, i32) {
+        let size = self.block_size();
+        (
+            (x - self.left).div_euclid(size),
+            (z - self.top).div_euclid(size),
+        )
+    }
+
+    /// Position of the world coordinates *x*, *z* in pixels, with the fractions kept
+    ///
+    /// The upper left corner of the image is 0, 0, so the middle of the first pixel is at
+    /// 0.5, 0.5. This is the place for drawing overlays, like banner markers.
+    pub fn position(&self, x: f64, z: f64) -> (f64, f64) {
+        let size = self.block_size() as f64;
+        ((x - self.left as f64) / size, (z - self.top as f64) / size)
+    }
+
+    /// World coordinates of the upper left block of the *pixel_x*, *pixel_y* pixel
+    pub fn world_at(&self, pixel_x: i32, pixel_y: i32) -> (i32, i32) {
+        let size = self.block_size();
+        (self.left + pixel_x * size, self.top + pixel_y * size)
+    }
+
+    /// Blocks covered by the *pixel_x*, *pixel_y* pixel
+    pub fn pixel_area(&self, pixel_x: i32, pixel_y: i32) -> Area {
+        let (left, top) = self.world_at(pixel_x, pixel_y);
+        let size = self.block_size();
+        Area {
+            left,
+            top,
+            right: left + size - 1,
+            bottom: top + size - 1,
+        }
+    }
+
+    /// Blocks covered by an image of *width* × *height* pixels
+    pub fn area(&self, width: u32, height: u32) -> Area {
+        let size = self.block_size();
+        Area {
+            left: self.left,
+            top: self.top,
+            right: self.left + width as i32 * size - 1,
+            bottom: self.top + height as i32 * size - 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stitch::Area;
+    use crate::synthetic::synthetic_maps;
+    use crate::transform::{nether_area_to_overworld, MapTransform};
+    use std::path::Path;
+
+    #[test]
+    fn test_transform() {
+        for map_item in synthetic_maps(Path::new("world")) {
+            let transform = MapTransform::of_map(&map_item.data);
+            let data = &map_item.data;
+            assert_eq!(transform.area(128, 128), Area::of_map(data));
+            assert_eq!(transform.pixel_at(data.left(), data.top()), (0, 0));
+            assert_eq!(transform.pixel_at(data.right(), data.bottom()), (127, 127));
+            assert_eq!(transform.pixel_at(data.left() - 1, data.top()), (-1, 0));
+            assert_eq!(
+                transform.world_at(127, 0),
+                (data.right() + 1 - transform.block_size(), data.top())
+            );
+            let (x, z) = transform.position(data.x_center as f64, data.z_center as f64);
+            assert_eq!((x, z), (64.0, 64.0));
+        }
+
+        let transform = MapTransform {
+            left: -10,
+            top: 20,
+            scale: 2,
+        };
+        assert_eq!(
+            transform.pixel_area(1, 1),
+            Area {
+                left: -6,
+                top: 24,
+                right: -3,
+                bottom: 27
+            }
+        );
+        assert_eq!(transform.pixel_at(-7, 19), (0, -1));
+
+        let nether = Area {
+            left: -64,
+            top: 0,
+            right: 63,
+            bottom: 127,
+        };
+        assert_eq!(
+            nether_area_to_overworld(&nether),
+            Area {
+                left: -512,
+                top: 0,
+                right: 511,
+                bottom: 1023
+            }
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Texture for the uncharted parts of explorer maps
+//!
+//! Explorer maps, like buried treasure and woodland mansion maps, show the terrain in orange
+//! and brown. Their pixels that are not drawn yet can be filled with diagonal stripes, so the
+//! maps look like the old charts from the game instead of having transparent holes.
+
+use crate::palette::Palette;
+use crate::{MapItem, MapKind};
+use image::RgbaImage;
+
+/// Width of one stripe in pixels
+const STRIPE_WIDTH: u32 = 4;
+
+/// Palette colors of the stripes, the light and the normal shade of sand
+const STRIPE_COLORS: [usize; 2] = [2 * 4 + 2, 2 * 4 + 1];
+
+/// How the uncharted pixels of explorer maps are drawn
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum UnchartedStyle {
+    /// Transparent like the unexplored pixels of other maps
+    #[default]
+    Transparent,
+
+    /// Diagonal stripes in the palette colors
+    Striped,
+}
+
+/// Fills the uncharted pixels of the *image* of the *map_item* in the *style*
+///
+/// Nothing is changed unless the map is an [explorer map](MapKind::Explorer). The stripe
+/// colors are taken from the *palette*, so they follow the color mode of the map colors.
+pub fn fill_uncharted(
+    image: &mut RgbaImage,
+    map_item: &MapItem,
+    palette: &Palette,
+    style: UnchartedStyle,
+) {
+    if style == UnchartedStyle::Transparent || map_item.kind() != MapKind::Explorer {
+        return;
+    }
+    for (z, row) in map_item.data.rows().enumerate() {
+        for (x, &color) in row.iter().enumerate() {
+            if color as u8 >= 4 {
+                continue;
+            }
+            let (x, z) = (x as u32, z as u32);
+            let stripe = ((x + z) / STRIPE_WIDTH % 2) as usize;
+            if let Some(pixel) = image.get_pixel_mut_checked(x, z) {
+                *pixel = palette[STRIPE_COLORS[stripe]];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{generate_palette, BASE_COLORS_2699};
+    use crate::synthetic::synthetic_maps;
+    use crate::uncharted::{fill_uncharted, UnchartedStyle};
+    use fastnbt::ByteArray;
+    use std::path::Path;
+
+    #[test]
+    fn test_fill_uncharted() {
+        let palette = generate_palette(&BASE_COLORS_2699);
+        let mut map_item = synthetic_maps(Path::new("world")).remove(0);
+        // Orange land on the left half and uncharted pixels on the right half
+        let colors = (0..128 * 128).map(|i| if i % 128 < 64 { 61 } else { 0 });
+        map_item.data.colors = ByteArray::new(colors.collect());
+        let image = map_item.make_image(&palette).unwrap();
+
+        let mut transparent = image.clone();
+        fill_uncharted(
+            &mut transparent,
+            &map_item,
+            &palette,
+            UnchartedStyle::Transparent,
+        );
+        assert_eq!(transparent, image);
+
+        let mut striped = image.clone();
+        fill_uncharted(&mut striped, &map_item, &palette, UnchartedStyle::Striped);
+        assert_eq!(striped.get_pixel(0, 0), image.get_pixel(0, 0));
+        assert_eq!(striped.get_pixel(64, 0), &palette[10]);
+        assert_eq!(striped.get_pixel(68, 0), &palette[9]);
+        assert_eq!(striped.get_pixel(64, 4), &palette[9]);
+    }
+}
@@ -1,9 +1,11 @@
-use clap::{arg, Args};
-use quick_xml::events::Event;
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use quick_xml::events::{BytesStart, BytesText, Event};
 use quick_xml::Reader;
+use serde_json::json;
 use std::collections::BTreeMap;
 use std::fmt::Display;
-use std::fs;
 use std::path::PathBuf;
 use std::process::ExitCode;
 
@@ -18,17 +20,39 @@ pub struct UpdateVersionsArgs {
     )]
     source_url: String,
 
+    /// Read the versions from a saved copy of the page instead of downloading it
+    #[arg(short, long, value_name = "FILE", conflicts_with = "source_url")]
+    input: Option<PathBuf>,
+
     /// Output file name
-    #[arg(short, long, value_name = "FILE", default_value = "src/versions.rs")]
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        default_value = "src/versions/table.rs"
+    )]
     output_file: PathBuf,
+
+    /// Write the versions also as JSON to the file
+    #[arg(short, long, value_name = "FILE")]
+    json_file: Option<PathBuf>,
 }
 
 pub fn run(args: &UpdateVersionsArgs) -> ExitCode {
-    println!("Loading: {}", args.source_url);
-    let body = match load(&args.source_url) {
+    let body = match &args.input {
+        Some(input) => {
+            info!("Reading: {}", input.display());
+            std::fs::read_to_string(input).map_err(err_to_string)
+        }
+        None => {
+            info!("Loading: {}", args.source_url);
+            load(&args.source_url)
+        }
+    };
+    let body = match body {
         Ok(body) => body,
         Err(err) => {
-            eprintln!("Loading error: {err}");
+            error!("Loading error: {err}");
             return ExitCode::FAILURE;
         }
     };
@@ -38,50 +62,25 @@ pub fn run(args: &UpdateVersionsArgs) -> ExitCode {
     let versions_table = match find_version_table(&body) {
         Ok(table) => table,
         Err(err) => {
-            eprintln!("Could not find version table: {err}");
+            error!("Could not find version table: {err}");
             return ExitCode::FAILURE;
         }
     };
-
-    // Parsing versions from the XML
-    let mut buf = Vec::new();
-    let mut versions_tree = BTreeMap::new();
-    let mut table_row = TableRow::new();
-    let mut reader = Reader::from_str(versions_table);
-    loop {
-        match reader.read_event_into(&mut buf) {
-            // Stop at error
-            Err(err) => {
-                eprintln!("XML error: {err}");
-                return ExitCode::FAILURE;
-            }
-
-            // Parse rows when start of 'tr' is found
-            Ok(Event::Start(event)) => {
-                if event.name().as_ref() == b"tr" {
-                    match table_row.read(&mut reader, &mut buf) {
-                        Ok(_) => {
-                            if let Ok(version_info) = table_row.parse_line() {
-                                versions_tree
-                                    .entry(version_info.data_version)
-                                    .or_insert(version_info.client_version);
-                            }
-                        }
-                        Err(err) => {
-                            eprintln!("Error while parsing table row: {err}");
-                            return ExitCode::FAILURE;
-                        }
-                    }
-                }
-            }
-
-            // Exits the loop when reaching "end of file"
-            Ok(Event::Eof) => break,
-
-            // Continue loop for rest of the events
-            _ => (),
+    let rows = match read_table(versions_table) {
+        Ok(rows) => rows,
+        Err(err) => {
+            error!("Could not read the version table: {err}");
+            return ExitCode::FAILURE;
         }
-    }
+    };
+    let versions_tree = match parse_versions(&rows) {
+        Ok(versions_tree) => versions_tree,
+        Err(err) => {
+            error!("Could not parse the version table: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    info!("Found {} data versions", versions_tree.len());
 
     let mut versions_code = r#"use phf::{phf_map, Map};
 
@@ -91,21 +90,36 @@ pub fn run(args: &UpdateVersionsArgs) -> ExitCode {
 /// [https://minecraft.fandom.com/wiki/Data_version](https://minecraft.fandom.com/wiki/Data_version#List_of_data_versions)
 pub const MINECRAFT_VERSIONS: Map<i32, &'static str> = phf_map! {
 "#.to_string();
-    for (data_version, client_version) in versions_tree {
+    for (data_version, client_version) in &versions_tree {
         versions_code.push_str(&format!("    {data_version}i32 => \"{client_version}\",\n"));
     }
     versions_code.push_str("};\n");
 
-    match fs::write(&args.output_file, versions_code) {
-        Ok(_) => {
-            println!("Source code written to: {:?}", args.output_file);
-            ExitCode::SUCCESS
-        }
+    match sink().write(&args.output_file, versions_code) {
+        Ok(_) => info!("Source code written to: {:?}", args.output_file),
         Err(err) => {
-            eprintln!("Error while writing source code: {err}");
-            ExitCode::FAILURE
+            error!("Error while writing source code: {err}");
+            return ExitCode::FAILURE;
         }
     }
+
+    if let Some(json_file) = &args.json_file {
+        let versions: Vec<_> = versions_tree
+            .iter()
+            .map(|(data_version, client_version)| {
+                json!({"data_version": data_version, "client_version": client_version})
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&versions).expect("JSON from values");
+        match sink().write(json_file, json + "\n") {
+            Ok(_) => info!("JSON written to: {json_file:?}"),
+            Err(err) => {
+                error!("Error while writing JSON: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
 }
 
 fn err_to_string<E>(err: E) -> String
@@ -137,119 +151,231 @@ fn find_version_table(body: &str) -> Result<&str, &str> {
     Ok(&body[start_pos..end_pos])
 }
 
-struct VersionInfo {
-    client_version: String,
-    data_version: i32,
-}
-
-#[derive(Debug)]
+/// Row of the table with the texts of its cells
 struct TableRow {
-    cells: Vec<TableCell>,
-}
+    /// Number of the row in the table, starting from 1
+    number: usize,
 
-impl TableRow {
-    fn new() -> TableRow {
-        TableRow {
-            cells: vec![TableCell::new(), TableCell::new(), TableCell::new()],
-        }
-    }
+    /// True if all cells of the row are th cells
+    header: bool,
 
-    fn read(&mut self, reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Result<(), String> {
-        for cell in self.cells.iter_mut() {
-            cell.read(reader, buf)?;
-        }
-        Ok(())
-    }
+    cells: Vec<String>,
+}
 
-    fn parse_line(&self) -> Result<VersionInfo, &'static str> {
-        if self.cells[0].text.is_empty() {
-            return Err("Client version is empty");
-        }
-        let client_version = self.cells[0].text.clone();
-        let data_version = self.cells[2]
-            .text
-            .parse::<i32>()
-            .map_err(|_| "Could not parse data version")?;
-        Ok(VersionInfo {
-            client_version,
-            data_version,
-        })
-    }
+/// Cell spanning over the following rows
+struct RowSpan {
+    text: String,
+    rows: u32,
 }
 
-#[derive(Debug)]
+/// Cell being read
 struct TableCell {
     text: String,
+    header: bool,
     rowspan: u32,
+    colspan: u32,
 }
 
 impl TableCell {
-    fn new() -> TableCell {
-        TableCell {
-            text: "".to_string(),
-            rowspan: 0,
+    fn new(event: &BytesStart, reader: &Reader<&[u8]>) -> Result<TableCell, String> {
+        let mut cell = TableCell {
+            text: String::new(),
+            header: event.name().as_ref() == b"th",
+            rowspan: 1,
+            colspan: 1,
+        };
+        for attribute in event.html_attributes() {
+            let attribute = attribute.map_err(err_to_string)?;
+            let span = match attribute.key.as_ref() {
+                b"rowspan" => &mut cell.rowspan,
+                b"colspan" => &mut cell.colspan,
+                _ => continue,
+            };
+            let value = attribute
+                .decode_and_unescape_value(reader)
+                .map_err(err_to_string)?;
+            *span = value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid span {value:?}"))?
+                .max(1);
         }
+        Ok(cell)
     }
+}
 
-    fn read(&mut self, reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Result<(), String> {
-        if self.rowspan > 0 {
-            self.rowspan -= 1;
-            return Ok(());
-        }
-        let mut td_found = false;
-        loop {
-            match reader.read_event_into(buf) {
-                // Stop at error
-                Err(err) => return Err(format!("XML error: {err}")),
-
-                // We should not run to the "End of File"
-                Ok(Event::Eof) => return Err("Unexpected end of file".to_string()),
-
-                Ok(Event::Start(event)) => {
-                    if event.name().as_ref() == b"td" {
-                        if td_found {
-                            return Err("Unexpected second td tag".to_string());
-                        } else {
-                            td_found = true;
-                            for attribute_result in event.attributes() {
-                                let attribute = attribute_result.map_err(err_to_string)?;
-                                if attribute.key.as_ref() == b"rowspan" {
-                                    self.rowspan = attribute
-                                        .decode_and_unescape_value(reader)
-                                        .map_err(err_to_string)?
-                                        .parse::<u32>()
-                                        .map_err(err_to_string)?
-                                        - 1;
-                                }
-                            }
-                        }
-                    }
-                }
+/// Reads the rows of the HTML *table*
+///
+/// Cells spanning several rows or columns are repeated in each of them, so that the columns
+/// line up. The text of a cell is the text of the tags inside it, without footnote references
+/// in sup tags and with the whitespace collapsed.
+fn read_table(table: &str) -> Result<Vec<TableRow>, String> {
+    let mut reader = Reader::from_str(table);
+    // HTML has tags without end tags, like br and img
+    reader.check_end_names(false);
 
-                Ok(Event::Text(event)) => {
-                    if td_found {
-                        self.text = event.unescape().map_err(err_to_string)?.to_string();
+    let mut rows = Vec::new();
+    let mut spans: Vec<Option<RowSpan>> = Vec::new();
+    let mut row: Option<TableRow> = None;
+    let mut cell: Option<TableCell> = None;
+    let mut sup_depth = 0;
+    loop {
+        let position = reader.buffer_position();
+        let event = reader
+            .read_event()
+            .map_err(|err| format!("XML error at position {position} of the table: {err}"))?;
+        match event {
+            Event::Start(event) | Event::Empty(event) if event.name().as_ref() == b"tr" => {
+                if let Some(row) = row.take() {
+                    rows.push(end_row(row, &mut spans));
+                }
+                row = Some(TableRow {
+                    number: rows.len() + 1,
+                    header: true,
+                    cells: Vec::new(),
+                });
+            }
+            Event::Start(event) if matches!(event.name().as_ref(), b"td" | b"th") => {
+                let Some(row) = row.as_mut() else {
+                    return Err(format!("Cell outside of a row at position {position}"));
+                };
+                if let Some(cell) = cell.take() {
+                    end_cell(row, cell, &mut spans);
+                }
+                cell = Some(TableCell::new(&event, &reader)?);
+            }
+            Event::Start(event) if event.name().as_ref() == b"sup" => sup_depth += 1,
+            Event::Start(event) | Event::Empty(event) if event.name().as_ref() == b"br" => {
+                if let Some(cell) = cell.as_mut() {
+                    cell.text.push(' ');
+                }
+            }
+            Event::Text(text) if sup_depth == 0 => {
+                if let Some(cell) = cell.as_mut() {
+                    cell.text.push_str(&unescape(&text));
+                }
+            }
+            Event::End(event) => match event.name().as_ref() {
+                b"sup" => sup_depth = (sup_depth - 1).max(0),
+                b"td" | b"th" => {
+                    if let (Some(row), Some(cell)) = (row.as_mut(), cell.take()) {
+                        end_cell(row, cell, &mut spans);
                     }
                 }
-
-                Ok(Event::End(event)) => match event.name().as_ref() {
-                    b"td" => {
-                        return if td_found {
-                            Ok(())
-                        } else {
-                            Err("Unexpected end td tag found".to_string())
+                b"tr" => {
+                    if let Some(mut row) = row.take() {
+                        if let Some(cell) = cell.take() {
+                            end_cell(&mut row, cell, &mut spans);
                         }
+                        rows.push(end_row(row, &mut spans));
                     }
-                    b"th" => {
-                        self.text.clear();
-                        return Ok(());
-                    }
-                    b"tr" => return Err("Unexpected tr tag".to_string()),
-                    _ => (),
-                },
-
+                }
                 _ => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    if let Some(row) = row {
+        rows.push(end_row(row, &mut spans));
+    }
+    Ok(rows)
+}
+
+/// Unescapes the *text*, keeping unknown HTML entities as they are
+fn unescape(text: &BytesText) -> String {
+    text.unescape_with(|entity| match entity {
+        "nbsp" => Some(" "),
+        _ => None,
+    })
+    .map_or_else(
+        |_| String::from_utf8_lossy(text).to_string(),
+        |text| text.to_string(),
+    )
+}
+
+/// Adds the cells spanning from the rows above at the end of the *row*
+fn fill_spans(row: &mut TableRow, spans: &mut [Option<RowSpan>]) {
+    while let Some(Some(span)) = spans.get_mut(row.cells.len()) {
+        row.cells.push(span.text.clone());
+        span.rows -= 1;
+        if span.rows == 0 {
+            spans[row.cells.len() - 1] = None;
+        }
+    }
+}
+
+/// Adds the *cell* to the *row* and remembers it for the rows below if it spans them
+fn end_cell(row: &mut TableRow, cell: TableCell, spans: &mut Vec<Option<RowSpan>>) {
+    fill_spans(row, spans);
+    let text = cell.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    row.header &= cell.header;
+    for _ in 0..cell.colspan {
+        let column = row.cells.len();
+        if cell.rowspan > 1 {
+            if spans.len() <= column {
+                spans.resize_with(column + 1, || None);
             }
+            spans[column] = Some(RowSpan {
+                text: text.clone(),
+                rows: cell.rowspan - 1,
+            });
         }
+        row.cells.push(text.clone());
+    }
+}
+
+fn end_row(mut row: TableRow, spans: &mut [Option<RowSpan>]) -> TableRow {
+    fill_spans(&mut row, spans);
+    row.header &= !row.cells.is_empty();
+    row
+}
+
+/// Parses the data versions and their client versions from the table *rows*
+///
+/// The columns are found from the header row by their titles. Rows that cannot be parsed are
+/// reported and skipped, and only a table without any versions is an error.
+fn parse_versions(rows: &[TableRow]) -> Result<BTreeMap<i32, String>, String> {
+    let column = |row: &TableRow, title: &str| {
+        row.cells
+            .iter()
+            .position(|cell| cell.to_lowercase().contains(title))
+    };
+    let (client_column, data_column) = rows
+        .iter()
+        .filter(|row| row.header)
+        .find_map(|row| Some((column(row, "client version")?, column(row, "data version")?)))
+        .ok_or("Could not find the 'Client version' and 'Data version' columns in the header")?;
+
+    let mut versions_tree = BTreeMap::new();
+    for row in rows.iter().filter(|row| !row.header) {
+        let (Some(client_version), Some(data_version)) =
+            (row.cells.get(client_column), row.cells.get(data_column))
+        else {
+            warn!(
+                "Skipping row {}: it has only {} cells",
+                row.number,
+                row.cells.len()
+            );
+            continue;
+        };
+        if client_version.is_empty() {
+            warn!("Skipping row {}: client version is empty", row.number);
+            continue;
+        }
+        let Ok(data_version) = data_version.parse::<i32>() else {
+            warn!(
+                "Skipping row {}: could not parse data version {data_version:?} of {client_version}",
+                row.number
+            );
+            continue;
+        };
+        versions_tree
+            .entry(data_version)
+            .or_insert(client_version.clone());
+    }
+    if versions_tree.is_empty() {
+        return Err(String::from("No versions in the table"));
     }
+    Ok(versions_tree)
 }
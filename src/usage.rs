@@ -0,0 +1,264 @@
+//! Where the maps of a world are used
+//!
+//! Combines the map files with the references found in item frames, containers and player
+//! inventories, and with the filled map items anywhere else in the world. Map files without
+//! any references are orphaned and can be cleaned up, and references without a map file
+//! point to lost maps.
+
+use crate::containers::{scan_container_maps, ContainerMap};
+use crate::error::Result;
+use crate::item_frames::{scan_map_frames, MapFrame};
+use crate::player::{player_names, scan_players, CarriedMap};
+use crate::read_maps;
+use crate::region::{Scan, ScanError};
+use crate::renumber::scan_map_ids;
+use crate::source::map_id;
+use heck::ToTitleCase;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Place in the world where a map is found
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapReference {
+    /// Map in an item frame
+    ItemFrame(MapFrame),
+
+    /// Map in a chest or another container block
+    Container(ContainerMap),
+
+    /// Map carried by a player, with the name of the player or the UUID if the name is not known
+    Player(String, CarriedMap),
+
+    /// Map item elsewhere in a file of the world, like in the level.dat of a single player
+    /// world, a dropped item or a minecart, with the file relative to the world directory
+    Item(u32, PathBuf),
+}
+
+impl MapReference {
+    /// Id of the referenced map
+    pub fn map_id(&self) -> u32 {
+        match self {
+            MapReference::ItemFrame(frame) => frame.map_id,
+            MapReference::Container(map) => map.map_id,
+            MapReference::Player(_, map) => map.map_id,
+            MapReference::Item(map_id, _) => *map_id,
+        }
+    }
+
+    /// Describes the place, for example "Item frame at 20, 65, -3 in Overworld"
+    pub fn describe(&self) -> String {
+        match self {
+            MapReference::ItemFrame(frame) => format!(
+                "{} at {}, {}, {} in {}",
+                if frame.glowing {
+                    "Glow item frame"
+                } else {
+                    "Item frame"
+                },
+                frame.x,
+                frame.y,
+                frame.z,
+                frame.dimension
+            ),
+            MapReference::Container(map) => {
+                let mut description = format!(
+                    "{} at {}, {}, {} in {}",
+                    pretty_id(&map.block),
+                    map.x,
+                    map.y,
+                    map.z,
+                    map.dimension
+                );
+                if let Some(item) = &map.item {
+                    description.push_str(&format!(", inside {}", pretty_id(item)));
+                }
+                description
+            }
+            MapReference::Player(player, map) => {
+                let mut description = if map.ender_chest {
+                    format!("Ender chest of {player}")
+                } else {
+                    format!("Inventory of {player}")
+                };
+                if let Some(item) = &map.container {
+                    description.push_str(&format!(", inside {}", pretty_id(item)));
+                }
+                description
+            }
+            MapReference::Item(_, file) => format!("Item in {}", file.display()),
+        }
+    }
+}
+
+/// Map id with its file and references
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapUsage {
+    /// Id of the map
+    pub map_id: u32,
+
+    /// The map file, or None if the file does not exist
+    pub file: Option<PathBuf>,
+
+    /// Places in the world where the map is found
+    pub references: Vec<MapReference>,
+}
+
+impl MapUsage {
+    /// True if the map file exists but nothing refers to it
+    pub fn is_orphaned(&self) -> bool {
+        self.file.is_some() && self.references.is_empty()
+    }
+
+    /// True if the map is referenced but the map file does not exist
+    pub fn is_missing(&self) -> bool {
+        self.file.is_none()
+    }
+}
+
+/// Collects the map files and references of the *world*, sorted by the map id
+///
+/// Map files are searched from the `data` directory. References are searched from the
+/// item frames, containers and the player data. Players are named with the usercache.json
+/// of the server when it is found next to the world. Maps that none of these refer to are
+/// also searched from all the files that can hold items, see [scan_map_ids].
+///
+/// The parts of the world that could not be read are returned with the usage. Maps used
+/// only there look orphaned.
+pub fn map_usage(world: &Path) -> Result<Scan<MapUsage>> {
+    let mut usage = BTreeMap::new();
+    let maps = read_maps(&world.join("data"), &None, false)?;
+    for file in maps.paths() {
+        if let Some(map_id) = map_id(file) {
+            usage.insert(
+                map_id,
+                MapUsage {
+                    map_id,
+                    file: Some(PathBuf::from(file)),
+                    references: vec![],
+                },
+            );
+        }
+    }
+
+    let frames = scan_map_frames(world, None)?;
+    let containers = scan_container_maps(world, None)?;
+    let mut errors = frames.errors;
+    // Region files are read by several scans, so the same file can fail more than once
+    let mut add_errors = |found: Vec<ScanError>| {
+        for error in found {
+            if !errors.iter().any(|known| known.file == error.file) {
+                errors.push(error);
+            }
+        }
+    };
+    add_errors(containers.errors);
+    let mut references: Vec<MapReference> = frames
+        .found
+        .into_iter()
+        .map(MapReference::ItemFrame)
+        .collect();
+    references.extend(containers.found.into_iter().map(MapReference::Container));
+    if world.join("playerdata").is_dir() {
+        let names = player_names(world);
+        let players = scan_players(world)?;
+        add_errors(players.errors);
+        for player in players.found {
+            let uuid = player.uuid();
+            let name = names.get(&uuid).cloned().unwrap_or(uuid);
+            for map in player.maps() {
+                references.push(MapReference::Player(name.clone(), map));
+            }
+        }
+    }
+    let items = scan_map_ids(world)?;
+    add_errors(items.errors);
+    let known: BTreeSet<u32> = references.iter().map(|r| r.map_id()).collect();
+    for (map_id, file) in items.found {
+        if !known.contains(&map_id) {
+            let file = file.strip_prefix(world).unwrap_or(&file).to_path_buf();
+            references.push(MapReference::Item(map_id, file));
+        }
+    }
+    for reference in references {
+        let map_id = reference.map_id();
+        usage
+            .entry(map_id)
+            .or_insert_with(|| MapUsage {
+                map_id,
+                file: None,
+                references: vec![],
+            })
+            .references
+            .push(reference);
+    }
+    Ok(Scan {
+        found: usage.into_values().collect(),
+        errors,
+    })
+}
+
+/// Resource location in title case without the minecraft namespace, like Shulker Box
+fn pretty_id(id: &str) -> String {
+    id.strip_prefix("minecraft:").unwrap_or(id).to_title_case()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nbt::update_nbt_file;
+    use crate::sink::Sink;
+    use crate::synthetic::write_synthetic_world;
+    use crate::usage::map_usage;
+    use fastnbt::{nbt, Value};
+    use std::fs;
+
+    #[test]
+    fn test_map_usage() {
+        let world = tempfile::tempdir().unwrap();
+        write_synthetic_world(world.path()).unwrap();
+        let scan = map_usage(world.path()).unwrap();
+        assert!(scan.errors.is_empty());
+        let usage = scan.found;
+        let referenced: Vec<_> = usage
+            .iter()
+            .filter(|map| !map.references.is_empty())
+            .map(|map| map.map_id)
+            .collect();
+        assert_eq!(referenced, [0, 1, 2, 3, 4, 5, 24, 25, 99]);
+        let missing: Vec<_> = usage.iter().filter(|map| map.is_missing()).collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(
+            missing[0].references[0].describe(),
+            "Chest at 2, 64, 1 in Overworld, inside Shulker Box"
+        );
+        assert_eq!(usage.iter().filter(|map| map.is_orphaned()).count(), 32 - 8);
+
+        // Map 30 is only in the inventory of the single player world
+        let sink = Sink::default();
+        update_nbt_file(&world.path().join("level.dat"), &sink, |level| {
+            let map = nbt!({
+                "id": "minecraft:filled_map",
+                "count": 1,
+                "components": {"minecraft:map_id": 30},
+            });
+            let player = nbt!({"Inventory": [map]});
+            if let Value::Compound(level) = level {
+                if let Some(Value::Compound(data)) = level.get_mut("Data") {
+                    data.insert("Player".to_string(), player);
+                }
+            }
+            true
+        })
+        .unwrap();
+        let usage = map_usage(world.path()).unwrap().found;
+        let map = usage.iter().find(|map| map.map_id == 30).unwrap();
+        assert!(!map.is_orphaned());
+        assert_eq!(map.references[0].describe(), "Item in level.dat");
+        assert_eq!(usage.iter().filter(|map| map.is_orphaned()).count(), 32 - 9);
+
+        fs::write(world.path().join("region").join("r.9.9.mca"), [1u8; 100]).unwrap();
+        fs::write(world.path().join("playerdata").join("bad.dat"), [1u8; 100]).unwrap();
+        let scan = map_usage(world.path()).unwrap();
+        assert_eq!(scan.errors.len(), 2);
+        assert!(scan.errors[1].file.ends_with("bad.dat"));
+    }
+}
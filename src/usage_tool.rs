@@ -0,0 +1,68 @@
+use crate::charset::preset;
+use clap::Args;
+use comfy_table::{Cell, ContentArrangement, Table};
+use log::{error, info, warn};
+use minecraft_map_tool::usage::map_usage;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct UsageArgs {
+    /// The world directory
+    world: PathBuf,
+
+    /// Print only the paths of orphaned map files that nothing in the world refers to
+    #[arg(long)]
+    orphaned: bool,
+}
+
+pub fn run(args: &UsageArgs) -> ExitCode {
+    let (usage, errors) = match map_usage(&args.world) {
+        Ok(scan) => (scan.found, scan.errors),
+        Err(err) => {
+            error!("Could not collect map usage: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.orphaned {
+        for map in usage.iter().filter(|map| map.is_orphaned()) {
+            if let Some(file) = &map.file {
+                println!("{}", file.display());
+            }
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Map", "Status", "References"]);
+    for map in &usage {
+        let status = if map.is_missing() {
+            "Missing file"
+        } else if map.is_orphaned() {
+            "Orphaned"
+        } else {
+            "In use"
+        };
+        let references: Vec<_> = map.references.iter().map(|r| r.describe()).collect();
+        table.add_row(vec![
+            Cell::new(map.map_id),
+            Cell::new(status),
+            Cell::new(references.join("\n")),
+        ]);
+    }
+    println!("{table}");
+    let orphaned = usage.iter().filter(|map| map.is_orphaned()).count();
+    let missing = usage.iter().filter(|map| map.is_missing()).count();
+    info!("{orphaned} orphaned map files, {missing} referenced maps without a file");
+    if !errors.is_empty() {
+        warn!(
+            "{} parts of the world could not be read, maps used there look orphaned",
+            errors.len()
+        );
+    }
+    ExitCode::SUCCESS
+}
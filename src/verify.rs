@@ -0,0 +1,112 @@
+//! Checking the palette against the game
+//!
+//! Map colors change between Minecraft versions now and then. Comparing a rendered map with
+//! a screenshot of the same map in the game shows which colors of the palette are off.
+
+use crate::palette::Palette;
+use crate::MapData;
+use image::RgbaImage;
+
+/// Palette color that differs from the screenshot
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorMismatch {
+    /// Color value of the map pixels, [decompose](crate::palette::decompose) gives its base color
+    /// and shade
+    pub color: u8,
+
+    /// Color in the palette
+    pub expected: [u8; 3],
+
+    /// Average color of the pixels in the screenshot
+    pub found: [u8; 3],
+
+    /// Number of map pixels with the color
+    pub pixels: usize,
+}
+
+impl ColorMismatch {
+    /// Largest difference in the red, green and blue channels
+    pub fn difference(&self) -> u8 {
+        (0..3)
+            .map(|channel| self.expected[channel].abs_diff(self.found[channel]))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Compares the *palette* colors of the *map* with a *screenshot* of the map in the game
+///
+/// The screenshot must be cropped to the map area, but it can be larger than 128×128 pixels.
+/// Each map pixel is compared with the screenshot pixel at its center. Colors whose average
+/// in the screenshot differs more than the *tolerance* in any channel are returned, sorted
+/// by the color value. Unexplored pixels are skipped.
+pub fn compare_screenshot(
+    map: &MapData,
+    screenshot: &RgbaImage,
+    palette: &Palette,
+    tolerance: u8,
+) -> Vec<ColorMismatch> {
+    let (width, height) = screenshot.dimensions();
+    // Sums of the red, green and blue channels and the pixel count for each color value
+    let mut sums = [[0u64; 4]; 256];
+    for (z, row) in map.rows().enumerate() {
+        for (x, &color) in row.iter().enumerate() {
+            let color = color as u8;
+            if color < 4 {
+                continue;
+            }
+            let screen_x = ((2 * x as u32 + 1) * width / 256).min(width - 1);
+            let screen_y = ((2 * z as u32 + 1) * height / 256).min(height - 1);
+            let pixel = screenshot.get_pixel(screen_x, screen_y);
+            let sum = &mut sums[color as usize];
+            for channel in 0..3 {
+                sum[channel] += pixel[channel] as u64;
+            }
+            sum[3] += 1;
+        }
+    }
+
+    let mut mismatches = Vec::new();
+    for (color, sum) in sums.iter().enumerate() {
+        if sum[3] == 0 {
+            continue;
+        }
+        let expected = palette[color];
+        let mismatch = ColorMismatch {
+            color: color as u8,
+            expected: [expected[0], expected[1], expected[2]],
+            found: [0, 1, 2].map(|channel| (sum[channel] / sum[3]) as u8),
+            pixels: sum[3] as usize,
+        };
+        if mismatch.difference() > tolerance {
+            mismatches.push(mismatch);
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{generate_palette, BASE_COLORS_2699};
+    use crate::verify::compare_screenshot;
+    use crate::MapItem;
+    use image::imageops::{resize, FilterType};
+    use image::Rgba;
+    use std::path::Path;
+
+    #[test]
+    fn test_compare_screenshot() {
+        let tests_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+        let map_item = MapItem::read_from(&tests_dir.join("map_0.dat")).unwrap();
+        let reference = image::open(tests_dir.join("map_0.png")).unwrap();
+        let screenshot = resize(&reference.to_rgba8(), 512, 512, FilterType::Nearest);
+        let mut palette = generate_palette(&BASE_COLORS_2699);
+        assert!(compare_screenshot(&map_item.data, &screenshot, &palette, 0).is_empty());
+
+        palette[42] = Rgba([0, 0, 0, 255]);
+        let mismatches = compare_screenshot(&map_item.data, &screenshot, &palette, 4);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].color, 42);
+        assert_eq!(mismatches[0].pixels, 64);
+    }
+}
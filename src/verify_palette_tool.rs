@@ -0,0 +1,80 @@
+use crate::charset::preset;
+use clap::Args;
+use comfy_table::{Cell, CellAlignment, ContentArrangement, Table};
+use log::{error, info};
+use minecraft_map_tool::palette::{
+    base_color_name, decompose, generate_palette, BASE_COLORS_2699, SHADE_NAMES,
+};
+use minecraft_map_tool::verify::compare_screenshot;
+use minecraft_map_tool::MapItem;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+Take the screenshot of the map held in hand or in an item frame in bright light, and crop \
+it to the map pixels without the border. The exit code is 1 if some colors differ.")]
+pub struct VerifyPaletteArgs {
+    /// The map_#.dat file shown in the screenshot
+    map_file: PathBuf,
+
+    /// Screenshot of the map in the game, cropped to the map area
+    screenshot: PathBuf,
+
+    /// Largest difference in a color channel that is not reported
+    #[arg(short, long, default_value_t = 4)]
+    tolerance: u8,
+}
+
+pub fn run(args: &VerifyPaletteArgs) -> ExitCode {
+    let map_item = match MapItem::read_from(&args.map_file) {
+        Ok(map_item) => map_item,
+        Err(err) => {
+            error!("Could not read map item: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let screenshot = match image::open(&args.screenshot) {
+        Ok(screenshot) => screenshot.to_rgba8(),
+        Err(err) => {
+            error!("Could not read the screenshot: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let palette = generate_palette(&BASE_COLORS_2699);
+    let mismatches = compare_screenshot(&map_item.data, &screenshot, &palette, args.tolerance);
+    if mismatches.is_empty() {
+        info!("All colors match the screenshot");
+        return ExitCode::SUCCESS;
+    }
+
+    let hex = |rgb: [u8; 3]| format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]);
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    table.set_header(vec![
+        "Color",
+        "Base color",
+        "Shade",
+        "Palette",
+        "Screenshot",
+        "Difference",
+        "Pixels",
+    ]);
+    for mismatch in &mismatches {
+        let (base_color, shade) = decompose(mismatch.color);
+        table.add_row(vec![
+            Cell::new(mismatch.color).set_alignment(CellAlignment::Right),
+            Cell::new(base_color_name(base_color)),
+            Cell::new(SHADE_NAMES[shade as usize]),
+            Cell::new(hex(mismatch.expected)),
+            Cell::new(hex(mismatch.found)),
+            Cell::new(mismatch.difference()).set_alignment(CellAlignment::Right),
+            Cell::new(mismatch.pixels).set_alignment(CellAlignment::Right),
+        ]);
+    }
+    println!("{table}");
+    info!("{} colors differ from the screenshot", mismatches.len());
+    ExitCode::FAILURE
+}
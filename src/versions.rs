@@ -1,520 +1,194 @@
+//! Minecraft versions by their data versions
+//!
+//! The [MINECRAFT_VERSIONS] table is generated with the update-versions command, the functions
+//! here give it an order and find the versions near data versions missing from the table.
+
 use phf::{phf_map, Map};
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+
+mod table;
+
+pub use table::MINECRAFT_VERSIONS;
 
-/// Mapping data versions to known client versions
+/// Release dates of the Java Edition releases
 ///
-/// The table was made from the content available at
-/// [https://minecraft.wiki/w/Data_version](https://minecraft.wiki/w/Data_version)
-pub const MINECRAFT_VERSIONS: Map<i32, &'static str> = phf_map! {
-    100i32 => "Java Edition 15w32a",
-    103i32 => "Java Edition 15w32b",
-    104i32 => "Java Edition 15w32c",
-    111i32 => "Java Edition 15w33b",
-    112i32 => "Java Edition 15w33c",
-    114i32 => "Java Edition 15w34a",
-    115i32 => "Java Edition 15w34b",
-    116i32 => "Java Edition 15w34c",
-    117i32 => "Java Edition 15w34d",
-    118i32 => "Java Edition 15w35a",
-    119i32 => "Java Edition 15w35b",
-    120i32 => "Java Edition 15w35c",
-    121i32 => "Java Edition 15w35d",
-    122i32 => "Java Edition 15w35e",
-    123i32 => "Java Edition 15w36a",
-    124i32 => "Java Edition 15w36b",
-    125i32 => "Java Edition 15w36c",
-    126i32 => "Java Edition 15w36d",
-    127i32 => "Java Edition 15w37a",
-    128i32 => "Java Edition 15w38a",
-    129i32 => "Java Edition 15w38b",
-    130i32 => "Java Edition 15w39a",
-    131i32 => "Java Edition 15w39b",
-    132i32 => "Java Edition 15w39c",
-    133i32 => "Java Edition 15w40a",
-    134i32 => "Java Edition 15w40b",
-    136i32 => "Java Edition 15w41a",
-    137i32 => "Java Edition 15w41b",
-    138i32 => "Java Edition 15w42a",
-    139i32 => "Java Edition 15w43a",
-    140i32 => "Java Edition 15w43b",
-    141i32 => "Java Edition 15w43c",
-    142i32 => "Java Edition 15w44a",
-    143i32 => "Java Edition 15w44b",
-    145i32 => "Java Edition 15w45a",
-    146i32 => "Java Edition 15w46a",
-    148i32 => "Java Edition 15w47a",
-    149i32 => "Java Edition 15w47b",
-    150i32 => "Java Edition 15w47c",
-    151i32 => "Java Edition 15w49a",
-    152i32 => "Java Edition 15w49b",
-    153i32 => "Java Edition 15w50a",
-    154i32 => "Java Edition 15w51a",
-    155i32 => "Java Edition 15w51b",
-    156i32 => "Java Edition 16w02a",
-    157i32 => "Java Edition 16w03a",
-    158i32 => "Java Edition 16w04a",
-    159i32 => "Java Edition 16w05a",
-    160i32 => "Java Edition 16w05b",
-    161i32 => "Java Edition 16w06a",
-    162i32 => "Java Edition 16w07a",
-    163i32 => "Java Edition 16w07b",
-    164i32 => "Java Edition 1.9-pre1",
-    165i32 => "Java Edition 1.9-pre2",
-    167i32 => "Java Edition 1.9-pre3",
-    168i32 => "Java Edition 1.9-pre4",
-    169i32 => "Java Edition 1.9",
-    170i32 => "Java Edition 1.9.1-pre1",
-    171i32 => "Java Edition 1.9.1-pre2",
-    172i32 => "Java Edition 1.9.1-pre3",
-    175i32 => "Java Edition 1.9.1",
-    176i32 => "Java Edition 1.9.2",
-    177i32 => "Java Edition 16w14a",
-    178i32 => "Java Edition 16w15a",
-    179i32 => "Java Edition 16w15b",
-    180i32 => "Java Edition 1.9.3-pre1",
-    181i32 => "Java Edition 1.9.3-pre2",
-    182i32 => "Java Edition 1.9.3-pre3",
-    183i32 => "Java Edition 1.9.3",
-    184i32 => "Java Edition 1.9.4",
-    501i32 => "Java Edition 16w20a",
-    503i32 => "Java Edition 16w21a",
-    504i32 => "Java Edition 16w21b",
-    506i32 => "Java Edition 1.10-pre1",
-    507i32 => "Java Edition 1.10-pre2",
-    510i32 => "Java Edition 1.10",
-    511i32 => "Java Edition 1.10.1",
-    512i32 => "Java Edition 1.10.2",
-    800i32 => "Java Edition 16w32a",
-    801i32 => "Java Edition 16w32b",
-    802i32 => "Java Edition 16w33a",
-    803i32 => "Java Edition 16w35a",
-    805i32 => "Java Edition 16w36a",
-    807i32 => "Java Edition 16w38a",
-    809i32 => "Java Edition 16w39a",
-    811i32 => "Java Edition 16w39b",
-    812i32 => "Java Edition 16w39c",
-    813i32 => "Java Edition 16w40a",
-    814i32 => "Java Edition 16w41a",
-    815i32 => "Java Edition 16w42a",
-    816i32 => "Java Edition 16w43a",
-    817i32 => "Java Edition 16w44a",
-    818i32 => "Java Edition 1.11-pre1",
-    819i32 => "Java Edition 1.11",
-    920i32 => "Java Edition 16w50a",
-    921i32 => "Java Edition 1.11.1",
-    922i32 => "Java Edition 1.11.2",
-    1022i32 => "Java Edition 17w06a",
-    1122i32 => "Java Edition 17w13a",
-    1123i32 => "Java Edition 17w13b",
-    1124i32 => "Java Edition 17w14a",
-    1125i32 => "Java Edition 17w15a",
-    1126i32 => "Java Edition 17w16a",
-    1127i32 => "Java Edition 17w16b",
-    1128i32 => "Java Edition 17w17a",
-    1129i32 => "Java Edition 17w17b",
-    1130i32 => "Java Edition 17w18a",
-    1131i32 => "Java Edition 17w18b",
-    1132i32 => "Java Edition 1.12-pre1",
-    1133i32 => "Java Edition 1.12-pre2",
-    1134i32 => "Java Edition 1.12-pre3",
-    1135i32 => "Java Edition 1.12-pre4",
-    1136i32 => "Java Edition 1.12-pre5",
-    1137i32 => "Java Edition 1.12-pre6",
-    1138i32 => "Java Edition 1.12-pre7",
-    1139i32 => "Java Edition 1.12",
-    1239i32 => "Java Edition 17w31a",
-    1240i32 => "Java Edition 1.12.1-pre1",
-    1241i32 => "Java Edition 1.12.1",
-    1341i32 => "Java Edition 1.12.2-pre1",
-    1342i32 => "Java Edition 1.12.2-pre2",
-    1343i32 => "Java Edition 1.12.2",
-    1444i32 => "Java Edition 17w43a",
-    1445i32 => "Java Edition 17w43b",
-    1447i32 => "Java Edition 17w45a",
-    1448i32 => "Java Edition 17w45b",
-    1449i32 => "Java Edition 17w46a",
-    1451i32 => "Java Edition 17w47a",
-    1452i32 => "Java Edition 17w47b",
-    1453i32 => "Java Edition 17w48a",
-    1454i32 => "Java Edition 17w49a",
-    1455i32 => "Java Edition 17w49b",
-    1457i32 => "Java Edition 17w50a",
-    1459i32 => "Java Edition 18w01a",
-    1461i32 => "Java Edition 18w02a",
-    1462i32 => "Java Edition 18w03a",
-    1463i32 => "Java Edition 18w03b",
-    1464i32 => "Java Edition 18w05a",
-    1466i32 => "Java Edition 18w06a",
-    1467i32 => "Java Edition 18w07a",
-    1468i32 => "Java Edition 18w07b",
-    1469i32 => "Java Edition 18w07c",
-    1470i32 => "Java Edition 18w08a",
-    1471i32 => "Java Edition 18w08b",
-    1472i32 => "Java Edition 18w09a",
-    1473i32 => "Java Edition 18w10a",
-    1474i32 => "Java Edition 18w10b",
-    1476i32 => "Java Edition 18w10c",
-    1477i32 => "Java Edition 18w10d",
-    1478i32 => "Java Edition 18w11a",
-    1479i32 => "Java Edition 18w14a",
-    1481i32 => "Java Edition 18w14b",
-    1482i32 => "Java Edition 18w15a",
-    1483i32 => "Java Edition 18w16a",
-    1484i32 => "Java Edition 18w19a",
-    1485i32 => "Java Edition 18w19b",
-    1489i32 => "Java Edition 18w20a",
-    1491i32 => "Java Edition 18w20b",
-    1493i32 => "Java Edition 18w20c",
-    1495i32 => "Java Edition 18w21a",
-    1496i32 => "Java Edition 18w21b",
-    1497i32 => "Java Edition 18w22a",
-    1498i32 => "Java Edition 18w22b",
-    1499i32 => "Java Edition 18w22c",
-    1501i32 => "Java Edition 1.13-pre1",
-    1502i32 => "Java Edition 1.13-pre2",
-    1503i32 => "Java Edition 1.13-pre3",
-    1504i32 => "Java Edition 1.13-pre4",
-    1511i32 => "Java Edition 1.13-pre5",
-    1512i32 => "Java Edition 1.13-pre6",
-    1513i32 => "Java Edition 1.13-pre7",
-    1516i32 => "Java Edition 1.13-pre8",
-    1517i32 => "Java Edition 1.13-pre9",
-    1518i32 => "Java Edition 1.13-pre10",
-    1519i32 => "Java Edition 1.13",
-    1620i32 => "Java Edition 18w30a",
-    1621i32 => "Java Edition 18w30b",
-    1622i32 => "Java Edition 18w31a",
-    1623i32 => "Java Edition 18w32a",
-    1625i32 => "Java Edition 18w33a",
-    1626i32 => "Java Edition 1.13.1-pre1",
-    1627i32 => "Java Edition 1.13.1-pre2",
-    1628i32 => "Java Edition 1.13.1",
-    1629i32 => "Java Edition 1.13.2-pre1",
-    1630i32 => "Java Edition 1.13.2-pre2",
-    1631i32 => "Java Edition 1.13.2",
-    1901i32 => "Java Edition 18w43a",
-    1902i32 => "Java Edition 18w43b",
-    1903i32 => "Java Edition 18w43c",
-    1907i32 => "Java Edition 18w44a",
-    1908i32 => "Java Edition 18w45a",
-    1910i32 => "Java Edition 18w46a",
-    1912i32 => "Java Edition 18w47a",
-    1913i32 => "Java Edition 18w47b",
-    1914i32 => "Java Edition 18w48a",
-    1915i32 => "Java Edition 18w48b",
-    1916i32 => "Java Edition 18w49a",
-    1919i32 => "Java Edition 18w50a",
-    1921i32 => "Java Edition 19w02a",
-    1922i32 => "Java Edition 19w03a",
-    1923i32 => "Java Edition 19w03b",
-    1924i32 => "Java Edition 19w03c",
-    1926i32 => "Java Edition 19w04a",
-    1927i32 => "Java Edition 19w04b",
-    1930i32 => "Java Edition 19w05a",
-    1931i32 => "Java Edition 19w06a",
-    1932i32 => "Java Edition 19w07a",
-    1933i32 => "Java Edition 19w08a",
-    1934i32 => "Java Edition 19w08b",
-    1935i32 => "Java Edition 19w09a",
-    1937i32 => "Java Edition 19w11a",
-    1938i32 => "Java Edition 19w11b",
-    1940i32 => "Java Edition 19w12a",
-    1941i32 => "Java Edition 19w12b",
-    1942i32 => "Java Edition 19w13a",
-    1943i32 => "Java Edition 19w13b",
-    1944i32 => "Java Edition 19w14a",
-    1945i32 => "Java Edition 19w14b",
-    1947i32 => "Java Edition 1.14 Pre-Release 1",
-    1948i32 => "Java Edition 1.14 Pre-Release 2",
-    1949i32 => "Java Edition 1.14 Pre-Release 3",
-    1950i32 => "Java Edition 1.14 Pre-Release 4",
-    1951i32 => "Java Edition 1.14 Pre-Release 5",
-    1952i32 => "Java Edition 1.14",
-    1955i32 => "Java Edition 1.14.1 Pre-Release 1",
-    1956i32 => "Java Edition 1.14.1 Pre-Release 2",
-    1957i32 => "Java Edition 1.14.1",
-    1958i32 => "Java Edition 1.14.2 Pre-Release 1",
-    1959i32 => "Java Edition 1.14.2 Pre-Release 2",
-    1960i32 => "Java Edition 1.14.2 Pre-Release 3",
-    1962i32 => "Java Edition 1.14.2 Pre-Release 4",
-    1963i32 => "Java Edition 1.14.2",
-    1964i32 => "Java Edition 1.14.3 Pre-Release 1",
-    1965i32 => "Java Edition 1.14.3 Pre-Release 2",
-    1966i32 => "Java Edition 1.14.3 Pre-Release 3",
-    1967i32 => "Java Edition 1.14.3 Pre-Release 4",
-    1968i32 => "Java Edition 1.14.3",
-    1969i32 => "Java Edition 1.14.4 Pre-Release 1",
-    1970i32 => "Java Edition 1.14.4 Pre-Release 2",
-    1971i32 => "Java Edition 1.14.4 Pre-Release 3",
-    1972i32 => "Java Edition 1.14.4 Pre-Release 4",
-    1973i32 => "Java Edition 1.14.4 Pre-Release 5",
-    1974i32 => "Java Edition 1.14.4 Pre-Release 6",
-    1975i32 => "Java Edition 1.14.4 Pre-Release 7",
-    1976i32 => "Java Edition 1.14.4",
-    2067i32 => "Java Edition 1.14.3 - Combat Test",
-    2068i32 => "Java Edition Combat Test 2",
-    2069i32 => "Java Edition Combat Test 3",
-    2200i32 => "Java Edition 19w34a",
-    2201i32 => "Java Edition 19w35a",
-    2203i32 => "Java Edition 19w36a",
-    2204i32 => "Java Edition 19w37a",
-    2205i32 => "Java Edition 19w38a",
-    2206i32 => "Java Edition 19w38b",
-    2207i32 => "Java Edition 19w39a",
-    2208i32 => "Java Edition 19w40a",
-    2210i32 => "Java Edition 19w41a",
-    2212i32 => "Java Edition 19w42a",
-    2213i32 => "Java Edition 19w44a",
-    2214i32 => "Java Edition 19w45a",
-    2215i32 => "Java Edition 19w45b",
-    2216i32 => "Java Edition 19w46a",
-    2217i32 => "Java Edition 19w46b",
-    2218i32 => "Java Edition 1.15 Pre-release 1",
-    2219i32 => "Java Edition 1.15 Pre-Release 2",
-    2220i32 => "Java Edition 1.15 Pre-release 3",
-    2221i32 => "Java Edition 1.15 Pre-release 4",
-    2222i32 => "Java Edition 1.15 Pre-release 5",
-    2223i32 => "Java Edition 1.15 Pre-release 6",
-    2224i32 => "Java Edition 1.15 Pre-release 7",
-    2225i32 => "Java Edition 1.15",
-    2226i32 => "Java Edition 1.15.1 Pre-release 1",
-    2227i32 => "Java Edition 1.15.1",
-    2228i32 => "Java Edition 1.15.2 Pre-Release 1",
-    2229i32 => "Java Edition 1.15.2 Pre-release 2",
-    2230i32 => "Java Edition 1.15.2",
-    2320i32 => "Java Edition Combat Test 4",
-    2321i32 => "Java Edition Combat Test 5",
-    2504i32 => "Java Edition Snapshot 20w06a",
-    2506i32 => "Java Edition 20w07a",
-    2507i32 => "Java Edition 20w08a",
-    2510i32 => "Java Edition 20w09a",
-    2512i32 => "Java Edition 20w10a",
-    2513i32 => "Java Edition 20w11a",
-    2515i32 => "Java Edition 20w12a",
-    2520i32 => "Java Edition 20w13a",
-    2521i32 => "Java Edition 20w13b",
-    2524i32 => "Java Edition 20w14a",
-    2525i32 => "Java Edition 20w15a",
-    2526i32 => "Java Edition 20w16a",
-    2529i32 => "Java Edition 20w17a",
-    2532i32 => "Java Edition 20w18a",
-    2534i32 => "Java Edition 20w19a",
-    2536i32 => "Java Edition 20w20a",
-    2537i32 => "Java Edition 20w20b",
-    2554i32 => "Java Edition 20w21a",
-    2555i32 => "Java Edition 20w22a",
-    2556i32 => "Java Edition 1.16 Pre-release 1",
-    2557i32 => "Java Edition 1.16 Pre-release 2",
-    2559i32 => "Java Edition 1.16 Pre-release 3",
-    2560i32 => "Java Edition 1.16 Pre-release 4",
-    2561i32 => "Java Edition 1.16 Pre-release 5",
-    2562i32 => "Java Edition 1.16 Pre-release 6",
-    2563i32 => "Java Edition 1.16 Pre-release 7",
-    2564i32 => "Java Edition 1.16 Pre-release 8",
-    2565i32 => "Java Edition 1.16 Release Candidate 1",
-    2566i32 => "Java Edition 1.16",
-    2567i32 => "Java Edition 1.16.1",
-    2569i32 => "Java Edition 20w27a",
-    2570i32 => "Java Edition 20w28a",
-    2571i32 => "Java Edition 20w29a",
-    2572i32 => "Java Edition 20w30a",
-    2573i32 => "Java Edition 1.16.2 Pre-release 1",
-    2574i32 => "Java Edition 1.16.2 Pre-release 2",
-    2575i32 => "Java Edition 1.16.2 Pre-release 3",
-    2576i32 => "Java Edition 1.16.2 Release Candidate 1",
-    2577i32 => "Java Edition 1.16.2 Release Candidate 2",
-    2578i32 => "Java Edition 1.16.2",
-    2579i32 => "Java Edition 1.16.3 Release Candidate 1",
-    2580i32 => "Java Edition 1.16.3",
-    2581i32 => "Java Edition 1.16.4 Pre-release 1",
-    2582i32 => "Java Edition 1.16.4 Pre-release 2",
-    2583i32 => "Java Edition 1.16.4 Release Candidate 1",
-    2584i32 => "Java Edition 1.16.4",
-    2585i32 => "Java Edition 1.16.5 Release Candidate 1",
-    2586i32 => "Java Edition 1.16.5",
-    2681i32 => "Java Edition 20w45a",
-    2682i32 => "Java Edition 20w46a",
-    2683i32 => "Java Edition 20w48a",
-    2685i32 => "Java Edition 20w49a",
-    2687i32 => "Java Edition 20w51a",
-    2689i32 => "Java Edition 21w03a",
-    2690i32 => "Java Edition 21w05a",
-    2692i32 => "Java Edition 21w05b",
-    2694i32 => "Java Edition 21w06a",
-    2695i32 => "Java Edition 21w07a",
-    2697i32 => "Java Edition 21w08a",
-    2698i32 => "Java Edition 21w08b",
-    2699i32 => "Java Edition 21w10a",
-    2701i32 => "Java Edition Combat Test 6",
-    2702i32 => "Java Edition Combat Test 7",
-    2703i32 => "Java Edition 21w11a",
-    2704i32 => "Java Edition Combat Test 7c",
-    2705i32 => "Java Edition 21w13a",
-    2706i32 => "Java Edition 21w14a",
-    2707i32 => "Java Edition Combat Test 8c",
-    2709i32 => "Java Edition 21w15a",
-    2711i32 => "Java Edition 21w16a",
-    2712i32 => "Java Edition 21w17a",
-    2713i32 => "Java Edition 21w18a",
-    2714i32 => "Java Edition 21w19a",
-    2715i32 => "Java Edition 21w20a",
-    2716i32 => "Java Edition 1.17 Pre-release 1",
-    2718i32 => "Java Edition 1.17 Pre-release 2",
-    2719i32 => "Java Edition 1.17 Pre-release 3",
-    2720i32 => "Java Edition 1.17 Pre-release 4",
-    2721i32 => "Java Edition 1.17 Pre-release 5",
-    2722i32 => "Java Edition 1.17 Release Candidate 1",
-    2723i32 => "Java Edition 1.17 Release Candidate 2",
-    2724i32 => "Java Edition 1.17",
-    2725i32 => "Java Edition 1.17.1 Pre-release 1",
-    2726i32 => "Java Edition 1.17.1 Pre-release 2",
-    2727i32 => "Java Edition 1.17.1 Pre-release 3",
-    2728i32 => "Java Edition 1.17.1 Release Candidate 1",
-    2729i32 => "Java Edition 1.17.1 Release Candidate 2",
-    2730i32 => "Java Edition 1.17.1",
-    2825i32 => "Java Edition 1.18 Experimental Snapshot 1",
-    2826i32 => "Java Edition 1.18 experimental snapshot 2",
-    2827i32 => "Java Edition 1.18 experimental snapshot 3",
-    2828i32 => "Java Edition 1.18 experimental snapshot 4",
-    2829i32 => "Java Edition 1.18 experimental snapshot 5",
-    2830i32 => "Java Edition 1.18 experimental snapshot 6",
-    2831i32 => "Java Edition 1.18 experimental snapshot 7",
-    2834i32 => "Java Edition 21w37a",
-    2835i32 => "Java Edition 21w38a",
-    2836i32 => "Java Edition 21w39a",
-    2838i32 => "Java Edition 21w40a",
-    2839i32 => "Java Edition 21w41a",
-    2840i32 => "Java Edition 21w42a",
-    2844i32 => "Java Edition 21w43a",
-    2845i32 => "Java Edition 21w44a",
-    2847i32 => "Java Edition 1.18 Pre-release 1",
-    2848i32 => "Java Edition 1.18 Pre-release 2",
-    2849i32 => "Java Edition 1.18 Pre-release 3",
-    2850i32 => "Java Edition 1.18 Pre-release 4",
-    2851i32 => "Java Edition 1.18 Pre-release 5",
-    2853i32 => "Java Edition 1.18 Pre-release 6",
-    2854i32 => "Java Edition 1.18 Pre-release 7",
-    2855i32 => "Java Edition 1.18 Pre-release 8",
-    2856i32 => "Java Edition 1.18 Release Candidate 1",
-    2857i32 => "Java Edition 1.18 Release Candidate 2",
-    2858i32 => "Java Edition 1.18 Release Candidate 3",
-    2859i32 => "Java Edition 1.18 Release Candidate 4",
-    2860i32 => "Java Edition 1.18",
-    2861i32 => "Java Edition 1.18.1 Pre-release 1",
-    2862i32 => "Java Edition 1.18.1 Release Candidate 1",
-    2863i32 => "Java Edition 1.18.1 Release Candidate 2",
-    2864i32 => "Java Edition 1.18.1 Release Candidate 3",
-    2865i32 => "Java Edition 1.18.1",
-    2966i32 => "Java Edition 22w03a",
-    2967i32 => "Java Edition 22w05a",
-    2968i32 => "Java Edition 22w06a",
-    2969i32 => "Java Edition 22w07a",
-    2971i32 => "Java Edition 1.18.2 Pre-release 1",
-    2972i32 => "Java Edition 1.18.2 Pre-release 2",
-    2973i32 => "Java Edition 1.18.2 Pre-release 3",
-    2974i32 => "Java Edition 1.18.2 Release Candidate 1",
-    2975i32 => "Java Edition 1.18.2",
-    3066i32 => "Java Edition Deep Dark Experimental Snapshot 1",
-    3080i32 => "Java Edition 22w11a",
-    3082i32 => "Java Edition 22w12a",
-    3085i32 => "Java Edition 22w13a",
-    3088i32 => "Java Edition 22w14a",
-    3089i32 => "Java Edition 22w15a",
-    3091i32 => "Java Edition 22w16a",
-    3092i32 => "Java Edition 22w16b",
-    3093i32 => "Java Edition 22w17a",
-    3095i32 => "Java Edition 22w18a",
-    3096i32 => "Java Edition 22w19a",
-    3098i32 => "Java Edition 1.19 Pre-release 1",
-    3099i32 => "Java Edition 1.19 Pre-release 2",
-    3100i32 => "Java Edition 1.19 Pre-release 3",
-    3101i32 => "Java Edition 1.19 Pre-release 4",
-    3102i32 => "Java Edition 1.19 Pre-release 5",
-    3103i32 => "Java Edition 1.19 Release Candidate 1",
-    3104i32 => "Java Edition 1.19 Release Candidate 2",
-    3105i32 => "Java Edition 1.19",
-    3106i32 => "Java Edition 22w24a",
-    3107i32 => "Java Edition 1.19.1 Pre-release 1",
-    3109i32 => "Java Edition 1.19.1 Release Candidate 1",
-    3110i32 => "Java Edition 1.19.1 Pre-release 2",
-    3111i32 => "Java Edition 1.19.1 Pre-release 3",
-    3112i32 => "Java Edition 1.19.1 Pre-release 4",
-    3113i32 => "Java Edition 1.19.1 Pre-release 5",
-    3114i32 => "Java Edition 1.19.1 Pre-release 6",
-    3115i32 => "Java Edition 1.19.1 Release Candidate 2",
-    3116i32 => "Java Edition 1.19.1 Release Candidate 3",
-    3117i32 => "Java Edition 1.19.1",
-    3118i32 => "Java Edition 1.19.2 Release Candidate 1",
-    3119i32 => "Java Edition 1.19.2 Release Candidate 2",
-    3120i32 => "Java Edition 1.19.2",
-    3205i32 => "Java Edition 22w42a",
-    3206i32 => "Java Edition 22w43a",
-    3207i32 => "Java Edition 22w44a",
-    3208i32 => "Java Edition 22w45a",
-    3210i32 => "Java Edition 22w46a",
-    3211i32 => "Java Edition 1.19.3 Pre-release 1",
-    3212i32 => "Java Edition 1.19.3 Pre-release 2",
-    3213i32 => "Java Edition 1.19.3 Pre-release 3",
-    3215i32 => "Java Edition 1.19.3 Release Candidate 1",
-    3216i32 => "Java Edition 1.19.3 Release Candidate 2",
-    3217i32 => "Java Edition 1.19.3 Release Candidate 3",
-    3218i32 => "Java Edition 1.19.3",
-    3320i32 => "Java Edition 23w03a",
-    3321i32 => "Java Edition 23w04a",
-    3323i32 => "Java Edition 23w05a",
-    3326i32 => "Java Edition 23w06a",
-    3329i32 => "Java Edition 23w07a",
-    3330i32 => "Java Edition 1.19.4 Pre-release 1",
-    3331i32 => "Java Edition 1.19.4 Pre-release 2",
-    3332i32 => "Java Edition 1.19.4 Pre-release 3",
-    3333i32 => "Java Edition 1.19.4 Pre-release 4",
-    3334i32 => "Java Edition 1.19.4 Release Candidate 1",
-    3335i32 => "Java Edition 1.19.4 Release Candidate 2",
-    3336i32 => "Java Edition 1.19.4 Release Candidate 3",
-    3337i32 => "Java Edition 1.19.4",
-    3442i32 => "Java Edition 23w12a",
-    3443i32 => "Java Edition 23w13a",
-    3445i32 => "Java Edition 23w14a",
-    3449i32 => "Java Edition 23w16a",
-    3452i32 => "Java Edition 23w17a",
-    3453i32 => "Java Edition 23w18a",
-    3454i32 => "Java Edition 1.20 Pre-release 1",
-    3455i32 => "Java Edition 1.20 Pre-release 2",
-    3456i32 => "Java Edition 1.20 Pre-release 3",
-    3457i32 => "Java Edition 1.20 Pre-release 4",
-    3458i32 => "Java Edition 1.20 Pre-release 5",
-    3460i32 => "Java Edition 1.20 Pre-release 6",
-    3461i32 => "Java Edition 1.20 Pre-release 7",
-    3462i32 => "Java Edition 1.20 Release Candidate 1",
-    3463i32 => "Java Edition 1.20",
-    3464i32 => "Java Edition 1.20.1 Release Candidate 1",
-    3465i32 => "Java Edition 1.20.1",
-    3567i32 => "Java Edition 23w31a",
-    3569i32 => "Java Edition 23w32a",
-    3570i32 => "Java Edition 23w33a",
-    3571i32 => "Java Edition 23w35a",
-    3572i32 => "Java Edition 1.20.2 Pre-release 1",
-    3573i32 => "Java Edition 1.20.2 Pre-release 2",
-    3574i32 => "Java Edition 1.20.2 Pre-Release 3",
-    3575i32 => "Java Edition 1.20.2 Pre-Release 4",
-    3576i32 => "Java Edition 1.20.2 Release Candidate 1",
-    3577i32 => "Java Edition 1.20.2 Release Candidate 2",
-    3578i32 => "Java Edition 1.20.2",
-    3679i32 => "Java Edition 23w40a",
-    3681i32 => "Java Edition 23w41a",
-    3684i32 => "Java Edition 23w42a",
-    3686i32 => "Java Edition 23w43a",
-    3687i32 => "Java Edition 23w43b",
-    3688i32 => "Java Edition 23w44a",
-    3690i32 => "Java Edition 23w45a",
-    3691i32 => "Java Edition 23w46a",
-    3693i32 => "Java Edition 1.20.3 Pre-Release 1",
-    3694i32 => "Java Edition 1.20.3 Pre-Release 2",
-    3695i32 => "Java Edition 1.20.3 Pre-Release 3",
-    3696i32 => "Java Edition 1.20.3 Pre-Release 4",
-    3697i32 => "Java Edition 1.20.3 Release Candidate 1",
-    3698i32 => "Java Edition 1.20.3",
-    3699i32 => "Java Edition 1.20.4 Release Candidate 1",
-    3700i32 => "Java Edition 1.20.4",
-    3801i32 => "Java Edition 23w51a",
-    3802i32 => "Java Edition 23w51b",
+/// Source: [https://minecraft.wiki/w/Java_Edition_version_history](https://minecraft.wiki/w/Java_Edition_version_history)
+const RELEASE_DATES: Map<&'static str, &'static str> = phf_map! {
+    "1.9" => "2016-02-29",
+    "1.9.1" => "2016-03-30",
+    "1.9.2" => "2016-03-30",
+    "1.9.3" => "2016-05-10",
+    "1.9.4" => "2016-05-10",
+    "1.10" => "2016-06-08",
+    "1.10.1" => "2016-06-22",
+    "1.10.2" => "2016-06-23",
+    "1.11" => "2016-11-14",
+    "1.11.1" => "2016-12-20",
+    "1.11.2" => "2016-12-21",
+    "1.12" => "2017-06-07",
+    "1.12.1" => "2017-08-03",
+    "1.12.2" => "2017-09-18",
+    "1.13" => "2018-07-18",
+    "1.13.1" => "2018-08-22",
+    "1.13.2" => "2018-10-22",
+    "1.14" => "2019-04-23",
+    "1.14.1" => "2019-05-13",
+    "1.14.2" => "2019-05-27",
+    "1.14.3" => "2019-06-24",
+    "1.14.4" => "2019-07-19",
+    "1.15" => "2019-12-10",
+    "1.15.1" => "2019-12-17",
+    "1.15.2" => "2020-01-21",
+    "1.16" => "2020-06-23",
+    "1.16.1" => "2020-06-24",
+    "1.16.2" => "2020-08-11",
+    "1.16.3" => "2020-09-10",
+    "1.16.4" => "2020-11-02",
+    "1.16.5" => "2021-01-15",
+    "1.17" => "2021-06-08",
+    "1.17.1" => "2021-07-06",
+    "1.18" => "2021-11-30",
+    "1.18.1" => "2021-12-10",
+    "1.18.2" => "2022-02-28",
+    "1.19" => "2022-06-07",
+    "1.19.1" => "2022-07-27",
+    "1.19.2" => "2022-08-05",
+    "1.19.3" => "2022-12-07",
+    "1.19.4" => "2023-03-14",
+    "1.20" => "2023-06-07",
+    "1.20.1" => "2023-06-12",
+    "1.20.2" => "2023-09-21",
+    "1.20.3" => "2023-12-05",
+    "1.20.4" => "2023-12-07",
 };
+
+/// Known Minecraft version
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MinecraftVersion {
+    pub data_version: i32,
+
+    /// Name in the [MINECRAFT_VERSIONS] table, like "Java Edition 1.20"
+    pub name: &'static str,
+}
+
+impl MinecraftVersion {
+    /// Name without the "Java Edition" prefix, like "1.20" or "23w12a"
+    pub fn short_name(&self) -> &'static str {
+        self.name.strip_prefix("Java Edition ").unwrap_or(self.name)
+    }
+
+    /// Tests if the version is a snapshot, pre-release, release candidate or other test version
+    ///
+    /// Releases have only numbers and dots in their names.
+    pub fn is_snapshot(&self) -> bool {
+        let name = self.short_name();
+        name.is_empty() || !name.chars().all(|c| c.is_ascii_digit() || c == '.')
+    }
+
+    /// Release date as YYYY-MM-DD, known only for the releases
+    pub fn release_date(&self) -> Option<&'static str> {
+        RELEASE_DATES.get(self.short_name()).copied()
+    }
+}
+
+impl Display for MinecraftVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.short_name())
+    }
+}
+
+/// All known versions ordered by their data versions
+pub fn all_versions() -> &'static [MinecraftVersion] {
+    static VERSIONS: OnceLock<Vec<MinecraftVersion>> = OnceLock::new();
+    VERSIONS.get_or_init(|| {
+        let mut versions: Vec<_> = MINECRAFT_VERSIONS
+            .entries()
+            .map(|(&data_version, &name)| MinecraftVersion { data_version, name })
+            .collect();
+        versions.sort_by_key(|version| version.data_version);
+        versions
+    })
+}
+
+/// Version with the *data_version*
+pub fn version(data_version: i32) -> Option<MinecraftVersion> {
+    MINECRAFT_VERSIONS
+        .get(&data_version)
+        .map(|&name| MinecraftVersion { data_version, name })
+}
+
+/// Known version with the data version nearest to the *data_version*
+///
+/// The older version is returned when two versions are equally near.
+pub fn closest_version(data_version: i32) -> Option<MinecraftVersion> {
+    all_versions()
+        .iter()
+        .min_by_key(|version| version.data_version.abs_diff(data_version))
+        .copied()
+}
+
+/// Latest release older than the *data_version*
+pub fn previous_release(data_version: i32) -> Option<MinecraftVersion> {
+    all_versions()
+        .iter()
+        .rev()
+        .find(|version| version.data_version < data_version && !version.is_snapshot())
+        .copied()
+}
+
+/// First release newer than the *data_version*
+pub fn next_release(data_version: i32) -> Option<MinecraftVersion> {
+    all_versions()
+        .iter()
+        .find(|version| version.data_version > data_version && !version.is_snapshot())
+        .copied()
+}
+
+/// Describes the *data_version*
+///
+/// Known versions are described with their names from the [MINECRAFT_VERSIONS] table. Others
+/// are placed between the releases around them, like "between 1.19.4 and 1.20 (snapshot
+/// 23w12a)", where the snapshot is the known version with the closest data version.
+pub fn describe(data_version: i32) -> String {
+    if let Some(version) = version(data_version) {
+        return version.name.to_string();
+    }
+    let mut description = match (previous_release(data_version), next_release(data_version)) {
+        (Some(previous), Some(next)) => format!("between {previous} and {next}"),
+        (Some(previous), None) => format!("after {previous}"),
+        (None, Some(next)) => format!("before {next}"),
+        (None, None) => return String::from("Unknown"),
+    };
+    if let Some(closest) = closest_version(data_version).filter(|v| v.is_snapshot()) {
+        description.push_str(&format!(" (snapshot {closest})"));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::versions::{all_versions, closest_version, describe, version};
+
+    #[test]
+    fn test_versions() {
+        let versions = all_versions();
+        assert!(versions
+            .windows(2)
+            .all(|pair| pair[0].data_version < pair[1].data_version));
+
+        let release = version(3463).unwrap();
+        assert_eq!(release.short_name(), "1.20");
+        assert!(!release.is_snapshot());
+        assert_eq!(release.release_date(), Some("2023-06-07"));
+        let snapshot = version(3801).unwrap();
+        assert!(snapshot.is_snapshot());
+        assert_eq!(snapshot.release_date(), None);
+
+        assert_eq!(describe(3463), "Java Edition 1.20");
+        assert!(version(3441).is_none());
+        assert_eq!(closest_version(3441), version(3442));
+        assert_eq!(describe(3441), "between 1.19.4 and 1.20 (snapshot 23w12a)");
+        assert_eq!(describe(4000), "after 1.20.4 (snapshot 23w51b)");
+    }
+}
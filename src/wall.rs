@@ -0,0 +1,195 @@
+//! Planning walls of maps in item frames
+//!
+//! The game aligns the maps of each scale to a grid, so a wall showing an area of the world
+//! needs the maps centered on the grid points around the area. A plan tells the centers of
+//! these maps, and a mockup shows how the finished wall looks with the maps already made.
+
+use crate::error::{Error, Result};
+use crate::frame::{apply_frame, FrameStyle};
+use crate::palette::Palette;
+use crate::stitch::{paint_image, Area};
+use crate::{MapData, MapItem};
+use image::{Rgba, RgbaImage};
+
+/// Most maps in a planned wall
+pub const MAX_WALL_MAPS: usize = 4096;
+
+/// Color of the wall behind the item frames
+const WALL_COLOR: Rgba<u8> = Rgba([58, 58, 58, 255]);
+
+/// Map needed for a wall
+#[derive(Clone, Debug, PartialEq)]
+pub struct WallCell {
+    /// Column of the map on the wall, from the left
+    pub column: u32,
+
+    /// Row of the map on the wall, from the top
+    pub row: u32,
+
+    /// Center of the map by X
+    pub x_center: i32,
+
+    /// Center of the map by Z
+    pub z_center: i32,
+
+    /// Area of the world shown by the map
+    pub area: Area,
+}
+
+/// Maps of the same scale tiling an area of the world
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wall {
+    pub scale: i8,
+    pub columns: u32,
+    pub rows: u32,
+
+    /// Maps row by row from the top left corner
+    pub cells: Vec<WallCell>,
+}
+
+impl Wall {
+    /// Plans the maps with the *scale* that are needed to show the *area*
+    ///
+    /// The maps are aligned like the game does, so the wall can cover more than the area.
+    /// Walls with more than [MAX_WALL_MAPS] maps are an error.
+    pub fn plan(area: &Area, scale: i8) -> Result<Wall> {
+        if !(0..=4).contains(&scale) {
+            return Err(Error::map_item_error("Scale must be from 0 to 4"));
+        }
+        let size = 128 * 2i32.pow(scale as u32);
+        let (left, top) = MapData::aligned_center_for(area.left, area.top, scale);
+        let (right, bottom) = MapData::aligned_center_for(area.right, area.bottom, scale);
+        let columns = ((right - left) / size + 1) as u32;
+        let rows = ((bottom - top) / size + 1) as u32;
+        if columns as usize * rows as usize > MAX_WALL_MAPS {
+            return Err(Error::map_item_error(
+                "Wall would need too many maps, use a larger zoom or a smaller area",
+            ));
+        }
+        let mut cells = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let x_center = left + column as i32 * size;
+                let z_center = top + row as i32 * size;
+                cells.push(WallCell {
+                    column,
+                    row,
+                    x_center,
+                    z_center,
+                    area: Area {
+                        left: x_center - size / 2,
+                        top: z_center - size / 2,
+                        right: x_center + size / 2 - 1,
+                        bottom: z_center + size / 2 - 1,
+                    },
+                });
+            }
+        }
+        Ok(Wall {
+            scale,
+            columns,
+            rows,
+            cells,
+        })
+    }
+
+    /// Area of the world covered by the whole wall
+    pub fn area(&self) -> Area {
+        self.cells
+            .iter()
+            .skip(1)
+            .fold(self.cells[0].area, |area, cell| area.union(&cell.area))
+    }
+
+    /// Tests if the *map* belongs to the *cell* of this wall
+    pub fn fits(&self, cell: &WallCell, map: &MapItem) -> bool {
+        map.data.scale == self.scale
+            && map.data.x_center == cell.x_center
+            && map.data.z_center == cell.z_center
+    }
+
+    /// Draws the wall with the *maps* in item frames
+    ///
+    /// The *maps* are given for each cell in the same order as the cells. Cells without a map
+    /// are left empty, so the missing maps show as holes in the wall.
+    pub fn draw_mockup(&self, maps: &[Option<&MapItem>], palette: &Palette) -> Result<RgbaImage> {
+        let mut map_image = RgbaImage::new(128, 128);
+        let frame_size = apply_frame(&map_image, FrameStyle::Framed).width();
+        let mut wall = RgbaImage::from_pixel(
+            self.columns * frame_size,
+            self.rows * frame_size,
+            WALL_COLOR,
+        );
+        for (cell, map) in self.cells.iter().zip(maps) {
+            let Some(map) = map else {
+                continue;
+            };
+            map.data.render_into(&mut map_image, palette)?;
+            paint_image(
+                &apply_frame(&map_image, FrameStyle::Framed),
+                &mut wall,
+                (cell.column * frame_size) as i32,
+                (cell.row * frame_size) as i32,
+            );
+        }
+        Ok(wall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::{generate_palette, BASE_COLORS_2699};
+    use crate::stitch::Area;
+    use crate::synthetic::synthetic_maps;
+    use crate::wall::{Wall, WALL_COLOR};
+    use std::path::Path;
+
+    #[test]
+    fn test_plan_wall() {
+        let area = Area {
+            left: -100,
+            top: 0,
+            right: 100,
+            bottom: 10,
+        };
+        let wall = Wall::plan(&area, 0).unwrap();
+        assert_eq!((wall.columns, wall.rows), (3, 1));
+        let centers: Vec<_> = wall.cells.iter().map(|cell| cell.x_center).collect();
+        assert_eq!(centers, [-128, 0, 128]);
+        assert_eq!(wall.cells[0].z_center, 0);
+        assert_eq!(wall.area().left, -192);
+        assert_eq!(wall.area().right, 191);
+
+        let wall = Wall::plan(&area, 1).unwrap();
+        assert_eq!((wall.columns, wall.rows), (2, 1));
+        assert_eq!(wall.cells[0].x_center, -192);
+        assert_eq!(wall.cells[0].area.left, -320);
+
+        let huge = Area {
+            left: -100_000,
+            top: -100_000,
+            right: 100_000,
+            bottom: 100_000,
+        };
+        assert!(Wall::plan(&huge, 0).is_err());
+    }
+
+    #[test]
+    fn test_draw_mockup() {
+        let maps = synthetic_maps(Path::new("world"));
+        let area = Area::of_map(&maps[0].data).union(&Area::of_map(&maps[1].data));
+        let wall = Wall::plan(&area, 0).unwrap();
+        assert!(wall.fits(&wall.cells[0], &maps[0]));
+        assert!(!wall.fits(&wall.cells[1], &maps[0]));
+
+        let palette = generate_palette(&BASE_COLORS_2699);
+        let mockup = wall.draw_mockup(&[Some(&maps[0]), None], &palette).unwrap();
+        let (width, height) = mockup.dimensions();
+        assert_eq!(width, 2 * height);
+        assert_ne!(mockup.get_pixel(height / 2, height / 2), &WALL_COLOR);
+        assert_eq!(
+            mockup.get_pixel(height + height / 2, height / 2),
+            &WALL_COLOR
+        );
+    }
+}
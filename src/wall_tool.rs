@@ -0,0 +1,162 @@
+use crate::changes::sink;
+use crate::charset::preset;
+use crate::image_tool::ColorArgs;
+use crate::input::read_input;
+use clap::Args;
+use comfy_table::{Cell, ContentArrangement, Table};
+use log::{error, info, warn};
+use minecraft_map_tool::source::map_id;
+use minecraft_map_tool::stitch::{parse_area, Area};
+use minecraft_map_tool::wall::Wall;
+use minecraft_map_tool::MapItem;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct WallArgs {
+    /// Area of the world to show on the wall as LEFT,TOP,RIGHT,BOTTOM
+    #[arg(long, value_parser = parse_area, allow_hyphen_values = true)]
+    area: Area,
+
+    /// Zoom level of the maps on the wall
+    #[arg(short, long, default_value_t = 0, value_parser = clap::value_parser!(i8).range(0..=4))]
+    zoom: i8,
+
+    /// Dimension of the maps on the wall
+    #[arg(short, long, default_value = "Overworld")]
+    dimension: String,
+
+    /// Directories, world backup archives (.zip, .tar.gz), or map files from which the existing
+    /// maps are searched for. Glob patterns like data/map_1*.dat are expanded. With the sftp
+    /// feature, this can also be a remote directory (sftp://user@host/path)
+    paths: Vec<PathBuf>,
+
+    /// Read the map files from a list with one path on each line, or from standard input with -
+    #[arg(long, value_name = "FILE", conflicts_with = "paths")]
+    files_from: Option<PathBuf>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Draw the finished wall with the existing maps into this image file
+    #[arg(long, value_name = "FILE")]
+    mockup: Option<PathBuf>,
+
+    #[command(flatten)]
+    colors: ColorArgs,
+}
+
+pub fn run(args: &WallArgs) -> ExitCode {
+    let wall = match Wall::plan(&args.area, args.zoom) {
+        Ok(wall) => wall,
+        Err(err) => {
+            error!("Could not plan the wall: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let map_items = if args.paths.is_empty() && args.files_from.is_none() {
+        Vec::new()
+    } else {
+        match read_maps(args) {
+            Some(map_items) => map_items,
+            None => return ExitCode::FAILURE,
+        }
+    };
+
+    // Existing maps for each cell, in the order of the cells
+    let cell_maps: Vec<Vec<&MapItem>> = wall
+        .cells
+        .iter()
+        .map(|cell| {
+            map_items
+                .iter()
+                .filter(|map_item| wall.fits(cell, map_item))
+                .collect()
+        })
+        .collect();
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Row", "Column", "Center", "Area", "Maps"]);
+    for (cell, maps) in wall.cells.iter().zip(&cell_maps) {
+        let names = if maps.is_empty() {
+            String::from("missing")
+        } else {
+            maps.iter()
+                .map(|map_item| match map_id(&map_item.file) {
+                    Some(id) => format!("#{id}"),
+                    None => map_item.file.display().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let area = cell.area;
+        table.add_row(vec![
+            Cell::new(cell.row + 1),
+            Cell::new(cell.column + 1),
+            Cell::new(format!("{}, {}", cell.x_center, cell.z_center)),
+            Cell::new(format!(
+                "{}, {} – {}, {}",
+                area.left, area.top, area.right, area.bottom
+            )),
+            Cell::new(names),
+        ]);
+    }
+    println!("{table}");
+
+    let area = wall.area();
+    info!(
+        "Wall of {}×{} maps covers {}, {} – {}, {}",
+        wall.columns, wall.rows, area.left, area.top, area.right, area.bottom
+    );
+    let existing = cell_maps.iter().filter(|maps| !maps.is_empty()).count();
+    info!("{existing} of {} maps exist", wall.cells.len());
+
+    if let Some(mockup_file) = &args.mockup {
+        let maps: Vec<_> = cell_maps.iter().map(|maps| maps.first().copied()).collect();
+        let mockup = match wall.draw_mockup(&maps, &args.colors.palette()) {
+            Ok(mockup) => mockup,
+            Err(err) => {
+                error!("Could not draw the mockup: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+        if let Err(err) = sink().write_with(mockup_file, |file| mockup.save(file)) {
+            error!("Could not write {}: {err}", mockup_file.display());
+            return ExitCode::FAILURE;
+        }
+        info!("Mockup written to {}", mockup_file.display());
+    }
+    ExitCode::SUCCESS
+}
+
+/// Reads the maps in the dimension of the wall
+fn read_maps(args: &WallArgs) -> Option<Vec<MapItem>> {
+    let mut maps = match read_input(
+        &args.paths,
+        args.files_from.as_deref(),
+        &None,
+        args.recursive,
+    ) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not get maps: {err}");
+            return None;
+        }
+    };
+    let dimension = args.dimension.to_lowercase();
+    let mut map_items = Vec::new();
+    while let Some((path, map_item)) = maps.next_with_path() {
+        match map_item {
+            Ok(map_item) if map_item.data.pretty_dimension().to_lowercase() == dimension => {
+                map_items.push(map_item)
+            }
+            Ok(_) => {}
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+    Some(map_items)
+}
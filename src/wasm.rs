@@ -0,0 +1,49 @@
+//! Functions for rendering maps in web browsers
+//!
+//! The library is built for `wasm32-unknown-unknown` with the `wasm` feature and without
+//! the default features, for example with
+//! `wasm-pack build --target web --no-default-features --features wasm`. Functions here
+//! work on the bytes of map files, so a web page can render files the user drops on it
+//! without uploading them anywhere.
+
+use crate::error::Error;
+use crate::palette::{generate_palette, BASE_COLORS_2699};
+use crate::MapItem;
+use std::path::Path;
+use wasm_bindgen::prelude::*;
+
+/// Decodes the *data* of a map file into 128×128 RGBA pixels
+///
+/// The data is the content of a map_<#>.dat file, compressed or not, given as a Uint8Array.
+/// The pixels are returned row by row as a Uint8Array that can be used for an ImageData.
+#[wasm_bindgen(js_name = decodeMapRgba)]
+pub fn decode_map_rgba(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    let map_item = MapItem::read_from_reader(data, Path::new("map.dat")).map_err(js_error)?;
+    let image = map_item
+        .make_image(&generate_palette(&BASE_COLORS_2699))
+        .map_err(js_error)?;
+    Ok(image.into_raw())
+}
+
+/// Reads the metadata of the map file *data* as a JSON string
+///
+/// The JSON has the scale, dimension, center, data version and banners of the map.
+#[wasm_bindgen(js_name = decodeMapInfo)]
+pub fn decode_map_info(data: &[u8]) -> Result<String, JsError> {
+    let map_item = MapItem::read_from_reader(data, Path::new("map.dat")).map_err(js_error)?;
+    let info = serde_json::json!({
+        "scale": map_item.data.scale,
+        "dimension": map_item.data.pretty_dimension(),
+        "xCenter": map_item.data.x_center,
+        "zCenter": map_item.data.z_center,
+        "locked": map_item.data.locked != 0,
+        "dataVersion": map_item.data_version,
+        "banners": map_item.data.banners,
+    });
+    Ok(info.to_string())
+}
+
+/// JavaScript error with the message of the *err*
+fn js_error(err: Error) -> JsError {
+    JsError::new(&err.to_string())
+}
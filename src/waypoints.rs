@@ -0,0 +1,284 @@
+//! Minimap waypoints
+//!
+//! Client side minimap mods keep their own waypoints that the game does not know about.
+//! This module reads the waypoint files of JourneyMap and Xaero's Minimap, and turns the
+//! waypoints into banner markers on the maps that cover them.
+
+use crate::error::{Error, Result};
+use crate::{Banner, BannerColor, MapData, Pos};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A waypoint read from a minimap mod
+#[derive(Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    /// Name shown for the waypoint
+    pub name: String,
+
+    /// Block position of the waypoint
+    pub pos: Pos,
+
+    /// Dimensions where the waypoint is shown, for example minecraft:overworld
+    pub dimensions: Vec<String>,
+
+    /// Color of the waypoint as RGB
+    pub color: [u8; 3],
+}
+
+impl Waypoint {
+    /// Tests if the waypoint is shown in the *dimension*, for example minecraft:the_nether
+    pub fn is_in(&self, dimension: &str) -> bool {
+        self.dimensions.iter().any(|d| d == dimension)
+    }
+
+    /// Makes a banner with the name of the waypoint and the dye color closest to its color
+    pub fn to_banner(&self) -> Banner {
+        Banner {
+            color: closest_banner_color(self.color),
+            name: Some(serde_json::json!({ "text": self.name }).to_string()),
+            pos: self.pos.clone(),
+        }
+    }
+}
+
+/// Reads waypoints from the JourneyMap or Xaero's Minimap waypoint *file*
+///
+/// JourneyMap stores each waypoint in its own .json file, and Xaero's Minimap stores a
+/// waypoint set in a .txt file inside a directory named after the dimension.
+pub fn read_waypoints(file: &Path) -> Result<Vec<Waypoint>> {
+    let text = fs::read_to_string(file)?;
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => Ok(vec![parse_journeymap(&text)?]),
+        Some("txt") => {
+            let dimension = file
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .and_then(|name| xaero_dimension(&name.to_string_lossy()))
+                .unwrap_or_else(|| String::from("minecraft:overworld"));
+            parse_xaero(&text, &dimension)
+        }
+        _ => Err(Error::map_item_error(
+            "Unknown waypoint file, expected JourneyMap .json or Xaero's Minimap .txt file",
+        )),
+    }
+}
+
+/// JourneyMap waypoint, only the fields needed for banners
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JourneyMapWaypoint {
+    name: String,
+    x: i32,
+    y: i32,
+    z: i32,
+    #[serde(default)]
+    r: u8,
+    #[serde(default)]
+    g: u8,
+    #[serde(default)]
+    b: u8,
+    #[serde(default)]
+    dimensions: Vec<JourneyMapDimension>,
+    primary_dimension: Option<String>,
+}
+
+/// Older JourneyMap versions use dimension numbers instead of names
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JourneyMapDimension {
+    Number(i32),
+    Name(String),
+}
+
+/// Parses a JourneyMap waypoint from the JSON *text*
+pub fn parse_journeymap(text: &str) -> Result<Waypoint> {
+    let waypoint: JourneyMapWaypoint = serde_json::from_str(text)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let mut dimensions: Vec<String> = waypoint
+        .dimensions
+        .into_iter()
+        .filter_map(|dimension| match dimension {
+            JourneyMapDimension::Number(number) => dimension_from_number(number),
+            JourneyMapDimension::Name(name) => Some(name),
+        })
+        .collect();
+    if let Some(primary) = waypoint.primary_dimension {
+        if !dimensions.contains(&primary) {
+            dimensions.push(primary);
+        }
+    }
+    Ok(Waypoint {
+        name: waypoint.name,
+        pos: Pos {
+            x: waypoint.x,
+            y: waypoint.y,
+            z: waypoint.z,
+        },
+        dimensions,
+        color: [waypoint.r, waypoint.g, waypoint.b],
+    })
+}
+
+/// Parses the waypoints of a Xaero's Minimap waypoint set *text* from the *dimension*
+///
+/// Waypoint lines look like `waypoint:name:initials:x:y:z:color:disabled:...`, where the
+/// color is an index to the chat colors. Lines that are not waypoints are skipped.
+pub fn parse_xaero(text: &str, dimension: &str) -> Result<Vec<Waypoint>> {
+    let mut waypoints = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.first() != Some(&"waypoint") {
+            continue;
+        }
+        let [_, name, _, x, y, z, color, ..] = fields.as_slice() else {
+            return Err(Error::map_item_error("Waypoint line has too few fields"));
+        };
+        let (Ok(x), Ok(z)) = (x.parse(), z.parse()) else {
+            return Err(Error::map_item_error("Waypoint has invalid coordinates"));
+        };
+        // The height is ~ when it is not known
+        let y = y.parse().unwrap_or(64);
+        let color = color.parse::<usize>().unwrap_or(0);
+        waypoints.push(Waypoint {
+            // Colons in names are written as §§
+            name: name.replace("§§", ":"),
+            pos: Pos { x, y, z },
+            dimensions: vec![dimension.to_string()],
+            color: XAERO_COLORS[color % XAERO_COLORS.len()],
+        });
+    }
+    Ok(waypoints)
+}
+
+/// Adds banners for the *waypoints* that are inside the *map* and in its dimension
+///
+/// Waypoints that already have a banner at the same position are skipped. Returns the
+/// number of added banners.
+pub fn add_waypoint_banners(map: &mut MapData, waypoints: &[Waypoint]) -> usize {
+    let mut added = 0;
+    for waypoint in waypoints {
+        let inside = (map.left()..=map.right()).contains(&waypoint.pos.x)
+            && (map.top()..=map.bottom()).contains(&waypoint.pos.z);
+        if !inside
+            || !waypoint.is_in(&map.dimension)
+            || map.banners.iter().any(|banner| banner.pos == waypoint.pos)
+        {
+            continue;
+        }
+        map.banners.push(waypoint.to_banner());
+        added += 1;
+    }
+    added
+}
+
+/// Dimension name for the old dimension *number*
+fn dimension_from_number(number: i32) -> Option<String> {
+    match number {
+        0 => Some(String::from("minecraft:overworld")),
+        -1 => Some(String::from("minecraft:the_nether")),
+        1 => Some(String::from("minecraft:the_end")),
+        _ => None,
+    }
+}
+
+/// Dimension name from a Xaero's Minimap dimension directory, like dim%-1 or
+/// dim%minecraft$the_nether
+fn xaero_dimension(directory: &str) -> Option<String> {
+    let dimension = directory.strip_prefix("dim%")?;
+    match dimension.parse() {
+        Ok(number) => dimension_from_number(number),
+        Err(_) => Some(dimension.replace('$', ":")),
+    }
+}
+
+/// Colors used by Xaero's Minimap, which are the chat formatting colors
+const XAERO_COLORS: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0xaa],
+    [0x00, 0xaa, 0x00],
+    [0x00, 0xaa, 0xaa],
+    [0xaa, 0x00, 0x00],
+    [0xaa, 0x00, 0xaa],
+    [0xff, 0xaa, 0x00],
+    [0xaa, 0xaa, 0xaa],
+    [0x55, 0x55, 0x55],
+    [0x55, 0x55, 0xff],
+    [0x55, 0xff, 0x55],
+    [0x55, 0xff, 0xff],
+    [0xff, 0x55, 0x55],
+    [0xff, 0x55, 0xff],
+    [0xff, 0xff, 0x55],
+    [0xff, 0xff, 0xff],
+];
+
+/// Banner color with the dye color closest to the *rgb* color
+fn closest_banner_color(rgb: [u8; 3]) -> BannerColor {
+    let distance = |color: &BannerColor| {
+        color
+            .rgb()
+            .iter()
+            .zip(rgb)
+            .map(|(&a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    [
+        BannerColor::Black,
+        BannerColor::Blue,
+        BannerColor::Brown,
+        BannerColor::Cyan,
+        BannerColor::Gray,
+        BannerColor::Green,
+        BannerColor::LightBlue,
+        BannerColor::LightGray,
+        BannerColor::Lime,
+        BannerColor::Magenta,
+        BannerColor::Orange,
+        BannerColor::Pink,
+        BannerColor::Purple,
+        BannerColor::Red,
+        BannerColor::White,
+        BannerColor::Yellow,
+    ]
+    .into_iter()
+    .min_by_key(distance)
+    .unwrap_or(BannerColor::White)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::synthetic::synthetic_maps;
+    use crate::waypoints::{add_waypoint_banners, parse_journeymap, parse_xaero};
+    use crate::BannerColor;
+    use std::path::Path;
+
+    #[test]
+    fn test_waypoint_banners() {
+        let journeymap = parse_journeymap(
+            r#"{"id":"Home_10,70,-20","name":"Home","x":10,"y":70,"z":-20,
+                "r":255,"g":0,"b":0,"enable":true,"dimensions":[0,-1]}"#,
+        )
+        .unwrap();
+        assert_eq!(journeymap.dimensions.len(), 2);
+        let xaero = parse_xaero(
+            "#\n#waypoint:name:initials:x:y:z:color:disabled:type:set\n\
+             waypoint:Base§§1:B:20:~:30:14:false:0:gui.xaero_default:false:0:0:false\n\
+             waypoint:Far:F:5000:64:5000:2:false:0:gui.xaero_default:false:0:0:false\n",
+            "minecraft:overworld",
+        )
+        .unwrap();
+        assert_eq!(xaero.len(), 2);
+        assert_eq!(xaero[0].name, "Base:1");
+
+        let mut maps = synthetic_maps(Path::new("world"));
+        let map = &mut maps[0].data;
+        let banners = map.banners.len();
+        let waypoints = [journeymap, xaero[0].clone(), xaero[1].clone()];
+        assert_eq!(add_waypoint_banners(map, &waypoints), 2);
+        assert_eq!(add_waypoint_banners(map, &waypoints), 0);
+        assert_eq!(map.banners[banners].color, BannerColor::Red);
+        assert_eq!(map.banners[banners + 1].color, BannerColor::Yellow);
+        assert_eq!(map.banners[banners + 1].extract_name(), "Base:1");
+    }
+}
@@ -0,0 +1,74 @@
+use crate::changes::sink;
+use clap::Args;
+use log::{error, info, warn};
+use minecraft_map_tool::read_maps;
+use minecraft_map_tool::waypoints::{add_waypoint_banners, read_waypoints};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+#[command(after_help = "\
+JourneyMap keeps each waypoint in a .json file under journeymap/data/*/*/waypoints, and \
+Xaero's Minimap keeps waypoint sets in .txt files under XaeroWaypoints/*/dim%*, where the \
+directory name tells the dimension.")]
+pub struct WaypointsArgs {
+    /// Directory with the map files to add the banners to, usually the data directory of a world
+    maps: PathBuf,
+
+    /// JourneyMap (.json) or Xaero's Minimap (.txt) waypoint files
+    #[arg(required = true)]
+    waypoint_files: Vec<PathBuf>,
+
+    /// Search map files recursively in subdirectories
+    #[arg(long)]
+    recursive: bool,
+}
+
+pub fn run(args: &WaypointsArgs) -> ExitCode {
+    let mut waypoints = Vec::new();
+    for file in &args.waypoint_files {
+        match read_waypoints(file) {
+            Ok(read) => waypoints.extend(read),
+            Err(err) => {
+                error!("Could not read waypoints from {}: {err}", file.display());
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    info!("{} waypoints read", waypoints.len());
+
+    let mut maps = match read_maps(&args.maps, &None, args.recursive) {
+        Ok(maps) => maps,
+        Err(err) => {
+            error!("Could not read maps: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut total = 0;
+    let mut exit_code = ExitCode::SUCCESS;
+    while let Some((path, map_item)) = maps.next_with_path() {
+        let mut map_item = match map_item {
+            Ok(map_item) => map_item,
+            Err(err) => {
+                warn!("Skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+        let added = add_waypoint_banners(&mut map_item.data, &waypoints);
+        if added == 0 {
+            continue;
+        }
+        println!("{}: {added} banners", path.display());
+        total += added;
+        if let Err(err) = sink().write_with(&map_item.file, |file| map_item.write_to(file)) {
+            error!("Could not write {}: {err}", path.display());
+            exit_code = ExitCode::FAILURE;
+        }
+    }
+    if sink().is_dry_run() {
+        info!("{total} banners would be added");
+    } else {
+        info!("{total} banners added");
+    }
+    exit_code
+}
@@ -0,0 +1,69 @@
+use crate::charset::preset;
+use clap::Args;
+use comfy_table::{Cell, ContentArrangement, Table};
+use log::{error, info};
+use minecraft_map_tool::player::{player_names, read_players};
+use minecraft_map_tool::source::{parse_id_ranges, IdRanges};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Args, Debug)]
+pub struct WhoHasArgs {
+    /// The world directory with the playerdata directory
+    world: PathBuf,
+
+    /// Map ids to look for, for example 212 or 0..10,20,100.. (all maps by default)
+    #[arg(value_parser = parse_id_ranges)]
+    ids: Option<IdRanges>,
+}
+
+pub fn run(args: &WhoHasArgs) -> ExitCode {
+    let players = match read_players(&args.world) {
+        Ok(players) => players,
+        Err(err) => {
+            error!("Could not read player data: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let names = player_names(&args.world);
+
+    let mut table = Table::new();
+    table
+        .load_preset(preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Player", "Map", "Count", "Location"]);
+    for player in &players {
+        let uuid = player.uuid();
+        let name = names.get(&uuid).unwrap_or(&uuid);
+        for map in player.maps() {
+            if args
+                .ids
+                .as_ref()
+                .is_some_and(|ids| !ids.contains(map.map_id))
+            {
+                continue;
+            }
+            let mut location = String::from(if map.ender_chest {
+                "Ender chest"
+            } else {
+                "Inventory"
+            });
+            if let Some(container) = &map.container {
+                location.push_str(", in ");
+                location.push_str(container.strip_prefix("minecraft:").unwrap_or(container));
+            }
+            table.add_row(vec![
+                Cell::new(name),
+                Cell::new(map.map_id),
+                Cell::new(map.count),
+                Cell::new(location),
+            ]);
+        }
+    }
+    if table.row_count() == 0 {
+        info!("No players carry the maps");
+        return ExitCode::SUCCESS;
+    }
+    println!("{table}");
+    ExitCode::SUCCESS
+}
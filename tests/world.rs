@@ -0,0 +1,159 @@
+//! Integration tests running the tool against a synthetic world
+
+use minecraft_map_tool::synthetic::write_synthetic_world;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn make_world() -> TempDir {
+    let world = tempfile::tempdir().unwrap();
+    write_synthetic_world(world.path()).unwrap();
+    world
+}
+
+fn run_tool(args: &[&str], current_dir: &Path) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_minecraft_map_tool"))
+        .args(args)
+        .current_dir(current_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "Command {args:?} failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn list_finds_all_maps() {
+    let world = make_world();
+    let output = run_tool(&["list", "data"], world.path());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for id in 0..32 {
+        assert!(stdout.contains(&format!("map_{id}.dat")), "map_{id}.dat");
+    }
+    assert!(!stdout.contains("idcounts.dat"));
+    assert!(stdout.contains("The Nether"));
+    assert!(stdout.contains("The End"));
+}
+
+#[test]
+fn list_recursive_from_world_root() {
+    let world = make_world();
+    let output = run_tool(&["list", "--recursive", "."], world.path());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("map_31.dat"));
+}
+
+#[test]
+fn images_are_grouped_by_dimension() {
+    let world = make_world();
+    run_tool(&["images", "--output-dir", "images", "data"], world.path());
+    assert!(world.path().join("images/Overworld/map_0.png").is_file());
+    assert!(world.path().join("images/The Nether/map_24.png").is_file());
+    assert!(world.path().join("images/The End/map_31.png").is_file());
+}
+
+#[test]
+fn stitch_covers_detailed_overworld_maps() {
+    let world = make_world();
+    run_tool(&["stitch", "data", "stitched.png"], world.path());
+    let image = image::open(world.path().join("stitched.png")).unwrap();
+    assert_eq!((image.width(), image.height()), (512, 512));
+}
+
+#[test]
+fn stitch_nether_maps() {
+    let world = make_world();
+    run_tool(
+        &["stitch", "--dimension", "the nether", "data", "nether.png"],
+        world.path(),
+    );
+    let image = image::open(world.path().join("nether.png")).unwrap();
+    assert_eq!((image.width(), image.height()), (384, 256));
+}
+
+#[test]
+fn list_maps_from_zip_archive() {
+    let world = make_world();
+    let archive = world.path().join("backup.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+    for id in 0..32 {
+        let name = format!("world/data/map_{id}.dat");
+        zip.start_file(&name, zip::write::FileOptions::default())
+            .unwrap();
+        let content = std::fs::read(world.path().join(format!("data/map_{id}.dat"))).unwrap();
+        std::io::Write::write_all(&mut zip, &content).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let output = run_tool(&["list", "backup.zip"], world.path());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("map_0.dat"));
+    assert!(stdout.contains("map_31.dat"));
+}
+
+#[test]
+fn stats_in_prometheus_format() {
+    let world = make_world();
+    let output = run_tool(&["stats", "--format", "prometheus", "data"], world.path());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("minecraft_map_total 32\n"));
+    assert!(stdout.contains("minecraft_map_dimension_total{dimension=\"The Nether\"} 6\n"));
+    assert!(stdout.contains("minecraft_map_scale_total{scale=\"4\"} 1\n"));
+}
+
+#[test]
+fn diff_between_backups() {
+    let old = make_world();
+    let new = make_world();
+    std::fs::remove_file(new.path().join("data/map_5.dat")).unwrap();
+    let output = run_tool(
+        &[
+            "diff",
+            "--format",
+            "json",
+            old.path().join("data").to_str().unwrap(),
+            "data",
+        ],
+        new.path(),
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["removed"], serde_json::json!(["map_5.dat"]));
+    assert_eq!(report["unchanged"], 31);
+}
+
+#[test]
+fn list_merges_paths_and_globs() {
+    let world = make_world();
+    let output = run_tool(&["list", "data/map_1*.dat", "data/map_2.dat"], world.path());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("map_1.dat"));
+    assert!(stdout.contains("map_19.dat"));
+    assert!(stdout.contains("map_2.dat"));
+    assert!(!stdout.contains("map_20.dat"));
+}
+
+#[test]
+fn list_files_from_stdin() {
+    let world = make_world();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_minecraft_map_tool"))
+        .args(["list", "--files-from", "-"])
+        .current_dir(world.path())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::io::Write::write_all(
+        child.stdin.as_mut().unwrap(),
+        b"data/map_3.dat\ndata/map_7.dat\n",
+    )
+    .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("map_3.dat"));
+    assert!(stdout.contains("map_7.dat"));
+    assert!(!stdout.contains("map_0.dat"));
+}